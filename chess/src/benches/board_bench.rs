@@ -4,7 +4,7 @@ use chess::board::Board;
 
 fn benchmark_new_board(c: &mut Criterion) {
     c.bench_function("new board", |bencher| {
-        bencher.iter(|| Board::new());
+        bencher.iter(Board::new);
     });
 }
 
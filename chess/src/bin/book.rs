@@ -0,0 +1,63 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use chess::book::builder::{BookBuilder, BookBuilderOptions};
+use chess::pgn::parse_pgn;
+
+fn usage() -> ! {
+  eprintln!("Usage: chess-book <games.pgn> <output.bin> [--native <file>] [--max-ply <n>] [--min-games <n>]");
+  process::exit(1);
+}
+
+fn parse_or_usage<T: std::str::FromStr>(value: Option<String>) -> T {
+  value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() {
+  let mut args = env::args().skip(1);
+  let pgn_path = args.next().unwrap_or_else(|| usage());
+  let output_path = args.next().unwrap_or_else(|| usage());
+
+  let mut options = BookBuilderOptions::default();
+  let mut native_path = None;
+  while let Some(flag) = args.next() {
+    match flag.as_str() {
+      "--native" => native_path = Some(parse_or_usage::<String>(args.next())),
+      "--max-ply" => options.max_ply = parse_or_usage(args.next()),
+      "--min-games" => options.min_games = parse_or_usage(args.next()),
+      _ => usage(),
+    }
+  }
+
+  let text = fs::read_to_string(&pgn_path).unwrap_or_else(|err| {
+    eprintln!("Failed to read {}: {}", pgn_path, err);
+    process::exit(1);
+  });
+
+  let mut builder = BookBuilder::new(options);
+  let mut skipped = 0;
+  for game in parse_pgn(&text) {
+    match game {
+      Ok(game) if builder.add_game(&game) => {}
+      _ => skipped += 1,
+    }
+  }
+  println!("Read {} games ({} skipped), {} positions", builder.get_games(), skipped, builder.get_positions());
+
+  let book = builder.build();
+  let polyglot = book.to_polyglot();
+  println!("Writing {} moves to {}", polyglot.get_entries().len(), output_path);
+  if let Err(err) = polyglot.save(&output_path) {
+    eprintln!("Failed to write {}: {:?}", output_path, err);
+    process::exit(1);
+  }
+
+  if let Some(native_path) = native_path {
+    println!("Writing {} moves to {}", book.get_entries().len(), native_path);
+    if let Err(err) = book.save(&native_path) {
+      eprintln!("Failed to write {}: {:?}", native_path, err);
+      process::exit(1);
+    }
+  }
+}
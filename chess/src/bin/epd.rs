@@ -0,0 +1,70 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+use chess::epd::parse_epd;
+use chess::epd::suite::{run_suite, SuiteLimit};
+use chess::eval::EvalParams;
+use chess::search::{SearchOptions, Searcher};
+
+fn usage() -> ! {
+  eprintln!("Usage: chess-epd <suite.epd> [--depth <n> | --time <ms>] [--threads <n>] [--hash <mb>]");
+  process::exit(1);
+}
+
+fn parse_or_usage<T: std::str::FromStr>(value: Option<String>) -> T {
+  value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() {
+  let mut args = env::args().skip(1);
+  let suite_path = args.next().unwrap_or_else(|| usage());
+
+  let mut limit = SuiteLimit::Time(Duration::from_secs(1));
+  let mut options = SearchOptions::default();
+  while let Some(flag) = args.next() {
+    match flag.as_str() {
+      "--depth" => limit = SuiteLimit::Depth(parse_or_usage(args.next())),
+      "--time" => limit = SuiteLimit::Time(Duration::from_millis(parse_or_usage(args.next()))),
+      "--threads" => options.threads = parse_or_usage(args.next()),
+      "--hash" => options.hash_size_mb = parse_or_usage(args.next()),
+      _ => usage(),
+    }
+  }
+
+  let text = fs::read_to_string(&suite_path).unwrap_or_else(|err| {
+    eprintln!("Failed to read {}: {}", suite_path, err);
+    process::exit(1);
+  });
+
+  let mut records = vec![];
+  for (i, record) in parse_epd(&text).into_iter().enumerate() {
+    match record {
+      Ok(record) => records.push(record),
+      Err(err) => eprintln!("Skipping record {}: {:?}", i + 1, err),
+    }
+  }
+
+  let mut searcher = Searcher::with_options(EvalParams::default(), options);
+  let summary = run_suite(&mut searcher, &records, limit, |result| {
+    let best_move = result.best_move.map(|mv| mv.to_string()).unwrap_or_else(|| "none".to_string());
+    println!(
+      "{:<16} {} {:<6} score {:>6} depth {:>2} nodes {:>10}",
+      result.id.as_deref().unwrap_or("-"),
+      if result.passed { "pass" } else { "FAIL" },
+      best_move,
+      result.score,
+      result.depth,
+      result.nodes,
+    );
+  });
+
+  println!("Passed {}/{}", summary.get_passed(), summary.get_total());
+  let (points, max_points) = summary.get_points();
+  if max_points > 0 {
+    println!("Points {}/{}", points, max_points);
+  }
+  let elapsed = summary.get_elapsed();
+  println!("Nodes {} in {:.1}s ({} nps)", summary.get_nodes(), elapsed.as_secs_f64(), (summary.get_nodes() as f64 / elapsed.as_secs_f64().max(0.001)) as u64);
+}
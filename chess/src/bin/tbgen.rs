@@ -0,0 +1,41 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use chess::tablebase::dtm::DtmTablebase;
+
+fn usage() -> ! {
+  eprintln!("Usage: chess-tbgen <output dir> <material>...  (e.g. chess-tbgen tables KQvK KRvK KBNvK KPvK)");
+  process::exit(1);
+}
+
+fn main() {
+  let mut args = env::args().skip(1);
+  let output_dir = args.next().unwrap_or_else(|| usage());
+  let materials: Vec<String> = args.collect();
+  if materials.is_empty() {
+    usage();
+  }
+
+  if let Err(err) = fs::create_dir_all(&output_dir) {
+    eprintln!("Failed to create {}: {}", output_dir, err);
+    process::exit(1);
+  }
+
+  let mut tablebase = DtmTablebase::open(&output_dir).unwrap_or_default();
+  for material in materials.iter() {
+    println!("Generating {}", material);
+    if let Err(err) = tablebase.generate(material) {
+      eprintln!("Failed to generate {}: {:?}", material, err);
+      process::exit(1);
+    }
+  }
+
+  for table in tablebase.get_tables() {
+    println!("{}: longest mate {} plies", table.get_material(), table.get_longest_mate());
+  }
+  if let Err(err) = tablebase.save(&output_dir) {
+    eprintln!("Failed to write {}: {:?}", output_dir, err);
+    process::exit(1);
+  }
+}
@@ -0,0 +1,514 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::{self, Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chess::board::Board;
+use chess::board::coord::Coordinate;
+use chess::board::display::DisplayOptions;
+use chess::board::moves::Move;
+use chess::clock::{SystemTimeSource, TimeControl, TimeSource};
+use chess::eval::EvalParams;
+use chess::game::{Game, GameResult};
+use chess::pgn::{full_moves, parse_pgn};
+use chess::piece::{Color, PieceKind};
+use chess::search::{Searcher, MAX_PLY};
+use chess::search::time::{ClockState, TimeManager};
+use chess::variant::{self, Variant};
+
+const DEFAULT_MOVETIME_MS: u64 = 1000;
+
+const HELP: &str = "Type a move (e4, Nf3, e7e8q) or a command; with an empty line, Enter picks the square under the cursor.
+  undo            take back a move (your last move, against the engine)
+  flip            turn the board around
+  go              let the engine play the side to move
+  engine <side>   let the engine play white, black or none
+  new             start a new game
+  fen [<fen>]     show the position, or start from another one
+  load <file>     load the first game of a PGN file
+  save <file>     save the game as PGN
+  quit            leave";
+
+fn usage() -> ! {
+  eprintln!("Usage: chess-tui [--engine white|black|none] [--movetime <ms>] [--time-control <tag>] [--variant <name>] [--fen <fen>] [--pgn <file>]");
+  process::exit(1);
+}
+
+fn parse_or_usage<T: std::str::FromStr>(value: Option<String>) -> T {
+  value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn parse_side(side: &str) -> Result<Option<Color>, String> {
+  match side {
+    "white" => Ok(Some(Color::White)),
+    "black" => Ok(Some(Color::Black)),
+    "none" | "off" => Ok(None),
+    _ => Err(format!("Unknown side: {}", side)),
+  }
+}
+
+fn format_clock(time: Duration) -> String {
+  let seconds = time.as_secs();
+  if seconds >= 3600 {
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+  } else {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+  }
+}
+
+fn square_name(coord: Coordinate) -> String {
+  coord.to_string().to_lowercase()
+}
+
+/// Puts the terminal in raw mode while alive, so keys arrive as they're pressed. When stdin
+/// isn't a terminal this does nothing, and moves and commands are read a line at a time.
+struct RawMode {
+  saved: Option<String>,
+}
+
+impl RawMode {
+  fn enable() -> RawMode {
+    let saved = Command::new("stty").arg("-g").stdin(Stdio::inherit()).output().ok()
+      .filter(|output| output.status.success())
+      .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    let enabled = saved.is_some() && Command::new("stty").args(["raw", "-echo"]).stdin(Stdio::inherit())
+      .status().map(|status| status.success()).unwrap_or(false);
+    RawMode { saved: if enabled { saved } else { None } }
+  }
+
+  fn is_enabled(&self) -> bool {
+    self.saved.is_some()
+  }
+}
+
+impl Drop for RawMode {
+  fn drop(&mut self) {
+    if let Some(saved) = self.saved.as_ref() {
+      let _ = Command::new("stty").arg(saved).stdin(Stdio::inherit()).status();
+    }
+  }
+}
+
+enum Key {
+  Char(char),
+  Enter,
+  Backspace,
+  Up,
+  Down,
+  Left,
+  Right,
+  Quit,
+}
+
+struct Tui {
+  game: Game,
+  /// The variant new games are played in.
+  variant: Arc<dyn Variant>,
+  control: Option<TimeControl>,
+  time_source: Arc<dyn TimeSource>,
+  /// The side the engine plays, if any.
+  engine: Option<Color>,
+  searcher: Searcher,
+  movetime: Duration,
+  flipped: bool,
+  cursor: Coordinate,
+  selected: Option<Coordinate>,
+  input: String,
+  message: String,
+  raw: bool,
+}
+
+impl Tui {
+  /// Switches to another game, putting it on the clock if games are timed.
+  fn start_game(&mut self, mut game: Game) {
+    if let Some(control) = self.control.clone() {
+      game.set_clock(control, Arc::clone(&self.time_source));
+    }
+    self.game = game;
+    self.selected = None;
+    self.searcher.clear();
+  }
+
+  /// The moves so far in SAN, numbered two to a row.
+  fn get_move_rows(&self) -> Vec<String> {
+    full_moves(self.game.get_start(), self.game.get_san_moves()).iter()
+      .map(|full_move| format!("{:>3}. {:<8} {}", full_move.number, full_move.white.unwrap_or("..."), full_move.black.unwrap_or("")))
+      .collect()
+  }
+
+  fn get_status(&self) -> String {
+    let board = self.game.get_board();
+    match self.game.get_result() {
+      Some(GameResult::WhiteWins) => String::from("1-0, white wins"),
+      Some(GameResult::BlackWins) => String::from("0-1, black wins"),
+      Some(GameResult::Draw) => String::from("1/2-1/2, drawn"),
+      None => {
+        let color = board.get_active_color();
+        let side = if color == Color::White { "White" } else { "Black" };
+        let check = if board.is_in_check(&color) { ", in check" } else { "" };
+        match self.selected {
+          Some(selected) => format!("{} to move{}; moving from {}", side, check, square_name(selected)),
+          None => format!("{} to move{}", side, check),
+        }
+      }
+    }
+  }
+
+  fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+    let board = self.game.get_board();
+    let options = DisplayOptions {
+      unicode: true,
+      flipped: self.flipped,
+      last_move: self.game.get_last_move(),
+      cursor: Some(self.cursor),
+      ansi_colors: true,
+      ..Default::default()
+    };
+    let drawing = board.display(options).to_string();
+    let board_lines: Vec<&str> = drawing.lines().collect();
+
+    // The move list sits beside the board, scrolled to the latest moves
+    let rows = self.get_move_rows();
+    let shown = &rows[rows.len().saturating_sub(board_lines.len())..];
+    let mut screen = String::from("\x1b[2J\x1b[H");
+    for (i, line) in board_lines.iter().enumerate() {
+      screen += line;
+      if let Some(row) = shown.get(i) {
+        screen += "   ";
+        screen += row;
+      }
+      screen += "\n";
+    }
+
+    screen += "\n";
+    if let Some(clock) = self.game.get_clock() {
+      let running = |color| if clock.get_running() == Some(color) { "*" } else { " " };
+      screen += &format!("White {}{}   Black {}{}\n", format_clock(clock.get_remaining(Color::White)), running(Color::White),
+        format_clock(clock.get_remaining(Color::Black)), running(Color::Black));
+    }
+    screen += &self.get_status();
+    screen += "\n";
+    for line in self.message.lines() {
+      screen += line;
+      screen += "\n";
+    }
+    screen += "> ";
+    screen += &self.input;
+
+    if self.raw {
+      screen = screen.replace('\n', "\r\n");
+    }
+    out.write_all(screen.as_bytes())?;
+    out.flush()
+  }
+
+  fn play(&mut self, mv: Move) {
+    match self.game.make_move(mv) {
+      Ok(()) => self.message.clear(),
+      Err(err) => self.message = format!("Can't play {}: {:?}", mv, err),
+    }
+  }
+
+  /// Plays the move typed, in SAN or UCI notation.
+  fn play_typed(&mut self, text: &str) {
+    let board = self.game.get_board();
+    match board.move_from_san(text).or_else(|_| Move::from_uci(text)) {
+      Ok(mv) => self.play(mv),
+      Err(_) => self.message = format!("Not a move or command: {} (type help for help)", text),
+    }
+  }
+
+  /// Picks the square under the cursor: first the piece to move, then where it goes.
+  fn select(&mut self) {
+    let board = self.game.get_board();
+    let selected = match self.selected {
+      Some(selected) if selected != self.cursor => selected,
+      Some(_) => {
+        self.selected = None;
+        return;
+      }
+      None => {
+        let own_piece = board.get_square(self.cursor).ok()
+          .and_then(|square| square.get_piece().as_ref())
+          .is_some_and(|piece| *piece.get_color() == board.get_active_color());
+        if own_piece {
+          self.selected = Some(self.cursor);
+          self.message.clear();
+        } else {
+          self.message = format!("No piece to move on {}", square_name(self.cursor));
+        }
+        return;
+      }
+    };
+
+    let moves: Vec<Move> = board.get_legal_moves().into_iter()
+      .filter(|mv| mv.drop.is_none() && mv.from == selected && mv.to == self.cursor)
+      .collect();
+    // Promotions go to a queen when picked by cursor; type the move to underpromote
+    let mv = moves.iter().find(|mv| mv.promotion.is_none() || mv.promotion == Some(PieceKind::Queen)).or(moves.first()).copied();
+    self.selected = None;
+    match mv {
+      Some(mv) => self.play(mv),
+      None => self.message = format!("{} to {} isn't legal", square_name(selected), square_name(self.cursor)),
+    }
+  }
+
+  fn move_cursor(&mut self, dx: i8, dy: i8) {
+    let board = self.game.get_board();
+    let (dx, dy) = if self.flipped { (-dx, -dy) } else { (dx, dy) };
+    let x = (Into::<i8>::into(self.cursor.file) - 1 + dx).clamp(0, board.get_width() - 1);
+    let y = (Into::<i8>::into(self.cursor.rank) - 1 + dy).clamp(0, board.get_height() - 1);
+    self.cursor = Coordinate::make_coordinate(x, y);
+  }
+
+  fn take_back(&mut self) {
+    if self.game.take_back().is_none() {
+      self.message = String::from("No moves to take back");
+      return;
+    }
+    // Against the engine, go back to a position where it's the player's move
+    if self.engine == Some(self.game.current_player()) {
+      self.game.take_back();
+    }
+    self.selected = None;
+    self.message.clear();
+  }
+
+  fn load_pgn(&mut self, path: &str) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {}", path, err))?;
+    let pgn = parse_pgn(&text).next()
+      .ok_or_else(|| format!("No games in {}", path))?
+      .map_err(|err| format!("Failed to read the game in {}: {:?}", path, err))?;
+    let game = Game::from_pgn(&pgn).map_err(|err| format!("Failed to replay the game in {}: {:?}", path, err))?;
+    self.start_game(game);
+    Ok(())
+  }
+
+  fn save_pgn(&self, path: &str) -> Result<(), String> {
+    let player = |color| if self.engine == Some(color) { "rust-chess" } else { "Human" };
+    let tags = vec![
+      (String::from("Event"), String::from("chess-tui game")),
+      (String::from("White"), String::from(player(Color::White))),
+      (String::from("Black"), String::from(player(Color::Black))),
+    ];
+    fs::write(path, self.game.to_pgn(tags).to_pgn_string()).map_err(|err| format!("Failed to write {}: {}", path, err))
+  }
+
+  /// Runs a typed command or move. Returns false to quit.
+  fn run(&mut self, line: &str) -> bool {
+    let line = line.trim();
+    let (command, args) = match line.find(' ') {
+      Some(i) => (&line[..i], line[i + 1..].trim()),
+      None => (line, ""),
+    };
+    match command {
+      "quit" | "exit" => return false,
+      "help" | "?" => self.message = String::from(HELP),
+      "undo" | "takeback" => self.take_back(),
+      "flip" => {
+        self.flipped = !self.flipped;
+        self.message.clear();
+      }
+      "go" => match self.game.get_result() {
+        Some(_) => self.message = String::from("The game is over"),
+        None => self.engine_move(),
+      },
+      "engine" => match parse_side(args) {
+        Ok(side) => {
+          self.engine = side;
+          self.message.clear();
+        }
+        Err(message) => self.message = message,
+      },
+      "new" => {
+        match Board::new_variant(Arc::clone(&self.variant)) {
+          Ok(board) => {
+            self.start_game(Game::from_board(board));
+            self.message.clear();
+          }
+          Err(err) => self.message = format!("Failed to start a game: {:?}", err),
+        }
+      }
+      "fen" if args.is_empty() => self.message = self.game.get_board().to_fen_string(),
+      "fen" => match Board::from_variant_fen(Arc::clone(&self.variant), args) {
+        Ok(board) => {
+          self.start_game(Game::from_board(board));
+          self.message.clear();
+        }
+        Err(err) => self.message = format!("Invalid FEN: {:?}", err),
+      },
+      "load" => {
+        self.message = match self.load_pgn(args) {
+          Ok(()) => format!("Loaded {}", args),
+          Err(message) => message,
+        }
+      }
+      "save" => {
+        self.message = match self.save_pgn(args) {
+          Ok(()) => format!("Saved {}", args),
+          Err(message) => message,
+        }
+      }
+      _ => self.play_typed(line),
+    }
+    true
+  }
+
+  /// Lets the engine play the side to move, thinking for the set time, or from its clock.
+  fn engine_move(&mut self) {
+    let color = self.game.current_player();
+    let time = match self.game.get_clock() {
+      Some(clock) => {
+        let state = ClockState {
+          time_left: clock.get_remaining(color),
+          increment: clock.get_increment(color),
+          moves_to_go: clock.get_moves_to_go(color),
+        };
+        TimeManager::new(&state, Arc::clone(&self.time_source))
+      }
+      None => TimeManager::fixed(self.movetime, Arc::clone(&self.time_source)),
+    };
+    let result = self.searcher.search_with_time(self.game.get_board(), MAX_PLY as u32 / 2, time);
+    match result.best_move {
+      Some(mv) => self.play(mv),
+      None => self.message = String::from("The engine has no move"),
+    }
+  }
+
+  fn read_key(&self, input: &mut impl Iterator<Item = io::Result<u8>>) -> Option<Key> {
+    loop {
+      let byte = input.next()?.ok()?;
+      return Some(match byte {
+        b'\r' | b'\n' => Key::Enter,
+        // Backspace and delete
+        8 | 127 => Key::Backspace,
+        // Ctrl-C and Ctrl-D, which raw mode delivers as keys
+        3 | 4 => Key::Quit,
+        0x1b => {
+          if input.next()?.ok()? != b'[' {
+            continue;
+          }
+          match input.next()?.ok()? {
+            b'A' => Key::Up,
+            b'B' => Key::Down,
+            b'C' => Key::Right,
+            b'D' => Key::Left,
+            _ => continue,
+          }
+        }
+        byte if byte.is_ascii_graphic() || byte == b' ' => Key::Char(byte as char),
+        _ => continue,
+      });
+    }
+  }
+
+  fn run_loop(&mut self) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock().bytes();
+    let mut out = io::stdout();
+    let mut redraw = true;
+    loop {
+      if redraw {
+        self.draw(&mut out)?;
+      }
+      if self.game.get_result().is_none() && self.engine == Some(self.game.current_player()) {
+        self.message = String::from("Thinking...");
+        self.draw(&mut out)?;
+        self.engine_move();
+        redraw = true;
+        continue;
+      }
+
+      // Without raw mode the terminal echoes typing itself, so only whole lines need a redraw
+      let key = self.read_key(&mut input);
+      redraw = self.raw || !matches!(key, Some(Key::Char(_)) | Some(Key::Backspace));
+      match key {
+        None | Some(Key::Quit) => break,
+        Some(Key::Char(c)) => self.input.push(c),
+        Some(Key::Backspace) => {
+          self.input.pop();
+        }
+        Some(Key::Up) => self.move_cursor(0, 1),
+        Some(Key::Down) => self.move_cursor(0, -1),
+        Some(Key::Left) => self.move_cursor(-1, 0),
+        Some(Key::Right) => self.move_cursor(1, 0),
+        Some(Key::Enter) => {
+          let line = std::mem::take(&mut self.input);
+          if line.trim().is_empty() {
+            self.select();
+          } else if !self.run(&line) {
+            break;
+          }
+        }
+      }
+    }
+    let end = if self.raw { "\r\n" } else { "\n" };
+    out.write_all(end.as_bytes())?;
+    out.flush()
+  }
+}
+
+fn main() {
+  let mut args = env::args().skip(1);
+  let mut engine = Some(Color::Black);
+  let mut movetime = DEFAULT_MOVETIME_MS;
+  let mut control = None;
+  let mut variant: Arc<dyn Variant> = Arc::new(variant::Standard);
+  let mut fen = None;
+  let mut pgn_path = None;
+  while let Some(flag) = args.next() {
+    match flag.as_str() {
+      "--engine" => engine = parse_side(&parse_or_usage::<String>(args.next())).unwrap_or_else(|_| usage()),
+      "--movetime" => movetime = parse_or_usage(args.next()),
+      "--time-control" => {
+        control = Some(TimeControl::from_pgn_tag(&parse_or_usage::<String>(args.next())).unwrap_or_else(|_| usage()));
+      }
+      "--variant" => variant = variant::from_name(&parse_or_usage::<String>(args.next())).unwrap_or_else(|| usage()),
+      "--fen" => fen = Some(parse_or_usage::<String>(args.next())),
+      "--pgn" => pgn_path = Some(parse_or_usage::<String>(args.next())),
+      _ => usage(),
+    }
+  }
+
+  let board = match fen {
+    Some(fen) => Board::from_variant_fen(Arc::clone(&variant), &fen),
+    None => Board::new_variant(Arc::clone(&variant)),
+  };
+  let board = board.unwrap_or_else(|err| {
+    eprintln!("Failed to set up the board: {:?}", err);
+    process::exit(1);
+  });
+
+  let mut tui = Tui {
+    game: Game::from_board(board.clone()),
+    variant,
+    control,
+    time_source: Arc::new(SystemTimeSource::default()),
+    engine,
+    searcher: Searcher::new(EvalParams::default()),
+    movetime: Duration::from_millis(movetime),
+    flipped: engine == Some(Color::White),
+    cursor: Coordinate::make_coordinate(board.get_width() / 2, 1),
+    selected: None,
+    input: String::new(),
+    message: String::from("Type help for help"),
+    raw: false,
+  };
+  tui.start_game(Game::from_board(board));
+  if let Some(path) = pgn_path {
+    if let Err(message) = tui.load_pgn(&path) {
+      eprintln!("{}", message);
+      process::exit(1);
+    }
+  }
+
+  let raw_mode = RawMode::enable();
+  tui.raw = raw_mode.is_enabled();
+  if let Err(err) = tui.run_loop() {
+    drop(raw_mode);
+    eprintln!("Terminal error: {}", err);
+    process::exit(1);
+  }
+}
@@ -0,0 +1,63 @@
+use std::env;
+use std::process;
+
+use chess::eval::EvalParams;
+use chess::eval::tuner::{load_labeled_positions, Tuner, TunerOptions};
+
+fn usage() -> ! {
+  eprintln!("Usage: chess-tune <positions> <output> [--params <file>] [--iterations <n>] [--learning-rate <f>] [--threads <n>] [--k <f>]");
+  process::exit(1);
+}
+
+fn parse_or_usage<T: std::str::FromStr>(value: Option<String>) -> T {
+  value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() {
+  let mut args = env::args().skip(1);
+  let positions_path = args.next().unwrap_or_else(|| usage());
+  let output_path = args.next().unwrap_or_else(|| usage());
+
+  let mut params = EvalParams::default();
+  let mut options = TunerOptions::default();
+  while let Some(flag) = args.next() {
+    match flag.as_str() {
+      "--params" => {
+        let path: String = parse_or_usage(args.next());
+        params = EvalParams::load(&path).unwrap_or_else(|err| {
+          eprintln!("Failed to load parameters from {}: {:?}", path, err);
+          process::exit(1);
+        });
+      }
+      "--iterations" => options.iterations = parse_or_usage(args.next()),
+      "--learning-rate" => options.learning_rate = parse_or_usage(args.next()),
+      "--threads" => options.threads = parse_or_usage(args.next()),
+      "--k" => options.k = Some(parse_or_usage(args.next())),
+      _ => usage(),
+    }
+  }
+
+  let positions = load_labeled_positions(&positions_path).unwrap_or_else(|err| {
+    eprintln!("Failed to load positions from {}: {:?}", positions_path, err);
+    process::exit(1);
+  });
+  println!("Loaded {} positions", positions.len());
+
+  let mut tuner = Tuner::new(&positions, &params, options).unwrap_or_else(|err| {
+    eprintln!("Failed to prepare positions: {:?}", err);
+    process::exit(1);
+  });
+  println!("K = {:.4}, initial error = {:.6}", tuner.k(), tuner.error());
+
+  let tuned = tuner.run(|iteration, error| {
+    if iteration % 50 == 0 {
+      println!("Iteration {}: error = {:.6}", iteration, error);
+    }
+  });
+
+  if let Err(err) = tuned.save(&output_path) {
+    eprintln!("Failed to write {}: {:?}", output_path, err);
+    process::exit(1);
+  }
+  println!("Wrote tuned parameters to {}", output_path);
+}
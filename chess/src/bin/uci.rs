@@ -0,0 +1,17 @@
+use std::io::{self, BufRead};
+
+use chess::uci::UciEngine;
+
+fn main() {
+  let mut engine = UciEngine::new(io::stdout());
+  for line in io::stdin().lock().lines() {
+    let line = match line {
+      Ok(line) => line,
+      Err(_) => break,
+    };
+    match engine.handle_command(&line) {
+      Ok(true) => {}
+      _ => break,
+    }
+  }
+}
@@ -0,0 +1,202 @@
+//! Chess960 (Fischer Random) starting positions, numbered 0 to 959 as Reinhard Scharnagl did.
+//! Number 518 is the usual starting position.
+
+use crate::board::{Board, CastleAvailability, DEFAULT_BOARD_SIZE};
+use crate::board::coord::Coordinate;
+use crate::board::file::File;
+use crate::board::rank::Rank;
+use crate::errors::Error;
+use crate::piece::{make_piece, Color, PieceKind};
+
+/// How many starting positions there are.
+pub const CHESS960_POSITIONS: u16 = 960;
+/// The number of the usual starting position.
+pub const STANDARD_POSITION: u16 = 518;
+
+/// Where the two knights go among the five squares left after the bishops and queen.
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+/// The back rank of a starting position, from the a-file to the h-file, or `None` if the
+/// number is 960 or more.
+pub fn get_back_rank(index: u16) -> Option<[PieceKind; 8]> {
+  if index >= CHESS960_POSITIONS {
+    return None;
+  }
+
+  let mut rank: [Option<PieceKind>; 8] = [None; 8];
+  let mut n = index as usize;
+  // One bishop on a light square (b, d, f or h), then one on a dark square (a, c, e or g)
+  rank[(n % 4) * 2 + 1] = Some(PieceKind::Bishop);
+  n /= 4;
+  rank[(n % 4) * 2] = Some(PieceKind::Bishop);
+  n /= 4;
+
+  let place = |rank: &mut [Option<PieceKind>; 8], nth_empty: usize, kind: PieceKind| {
+    let file = (0..8).filter(|file| rank[*file].is_none()).nth(nth_empty).expect("there are enough empty files");
+    rank[file] = Some(kind);
+  };
+  place(&mut rank, n % 6, PieceKind::Queen);
+  n /= 6;
+  let (first, second) = KNIGHT_PLACEMENTS[n];
+  // Placing the second knight first keeps the first one's count of empty files right
+  place(&mut rank, second, PieceKind::Knight);
+  place(&mut rank, first, PieceKind::Knight);
+  // The king always stands between the rooks
+  for kind in [PieceKind::Rook, PieceKind::King, PieceKind::Rook].iter() {
+    place(&mut rank, 0, *kind);
+  }
+
+  let mut back_rank = [PieceKind::Pawn; 8];
+  for (file, kind) in rank.iter().enumerate() {
+    back_rank[file] = kind.expect("every file is filled");
+  }
+  Some(back_rank)
+}
+
+/// The number of a starting position's back rank, or `None` if it isn't a Chess960 position.
+pub fn get_index(back_rank: &[PieceKind; 8]) -> Option<u16> {
+  (0..CHESS960_POSITIONS).find(|index| get_back_rank(*index).as_ref() == Some(back_rank))
+}
+
+impl Board {
+  /// Sets up the Chess960 starting position with the given number, with every castling right.
+  /// The board uses [Chess960 conventions](`crate::board::Board::is_chess960`), even for the
+  /// usual starting position.
+  ///
+  /// Returns [`Error::InvalidChess960Index`](`crate::errors::Error::InvalidChess960Index`) for
+  /// numbers of 960 and above.
+  pub fn new_chess960(index: u16) -> Result<Board, Error> {
+    let back_rank = get_back_rank(index).ok_or(Error::InvalidChess960Index)?;
+    let mut board = Board::with_pieces(DEFAULT_BOARD_SIZE, DEFAULT_BOARD_SIZE, |coord| {
+      let kind = back_rank[Into::<i8>::into(coord.file) as usize - 1];
+      match coord.rank {
+        Rank::One => Some(make_piece(kind, Color::White, coord)),
+        Rank::Two => Some(make_piece(PieceKind::Pawn, Color::White, coord)),
+        Rank::Seven => Some(make_piece(PieceKind::Pawn, Color::Black, coord)),
+        Rank::Eight => Some(make_piece(kind, Color::Black, coord)),
+        _ => None,
+      }
+    });
+
+    let file_of = |wanted: &dyn Fn(usize, &PieceKind) -> bool| {
+      back_rank.iter().enumerate().position(|(file, kind)| wanted(file, kind)).map(|file| File::from(file as i8 + 1))
+    };
+    let king = back_rank.iter().position(|kind| *kind == PieceKind::King).expect("every position has a king");
+    let king_file = File::from(king as i8 + 1);
+    let queenside_rook = file_of(&|file, kind| *kind == PieceKind::Rook && file < king).expect("a rook is left of the king");
+    let kingside_rook = file_of(&|file, kind| *kind == PieceKind::Rook && file > king).expect("a rook is right of the king");
+    for castle in CastleAvailability::ALL.iter() {
+      let rook_file = if castle.is_kingside() { kingside_rook } else { queenside_rook };
+      board.castling_files[castle.index()] = (king_file, rook_file);
+    }
+    board.chess960 = true;
+    Ok(board)
+  }
+
+  /// The number of the Chess960 starting position, if the board is one.
+  pub fn get_chess960_index(&self) -> Option<u16> {
+    let mut back_rank = [PieceKind::Pawn; 8];
+    for (file, kind) in back_rank.iter_mut().enumerate() {
+      let coord = Coordinate { file: File::from(file as i8 + 1), rank: Rank::One };
+      *kind = self.piece_at(coord).and_then(|piece| piece.get_kind())?;
+    }
+    let index = get_index(&back_rank)?;
+    let fen = Board::new_chess960(index).ok()?.to_fen_string();
+    let placement_matches = fen.split(' ').take(3).eq(self.to_fen_string().split(' ').take(3));
+    if placement_matches && self.full_move == 1 { Some(index) } else { None }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::board::moves::Move;
+
+  #[test]
+  fn test_scharnagl_numbering() {
+    use PieceKind::*;
+    assert_eq!(get_back_rank(STANDARD_POSITION), Some([Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook]));
+    assert_eq!(get_back_rank(0), Some([Bishop, Bishop, Queen, Knight, Knight, Rook, King, Rook]));
+    assert_eq!(get_back_rank(959), Some([Rook, King, Rook, Knight, Knight, Queen, Bishop, Bishop]));
+    assert_eq!(get_back_rank(960), None);
+
+    let mut seen = std::collections::HashSet::new();
+    for index in 0..CHESS960_POSITIONS {
+      let back_rank = get_back_rank(index).unwrap();
+      let files = |wanted: PieceKind| back_rank.iter().enumerate().filter(|(_, kind)| **kind == wanted).map(|(file, _)| file).collect::<Vec<_>>();
+      let (bishops, rooks, king) = (files(Bishop), files(Rook), files(King)[0]);
+      assert!(bishops[0] % 2 != bishops[1] % 2);
+      assert!(rooks[0] < king && king < rooks[1]);
+      assert!(seen.insert(back_rank));
+    }
+    assert_eq!(get_index(&get_back_rank(321).unwrap()), Some(321));
+  }
+
+  #[test]
+  fn test_new_chess960() {
+    let board = Board::new_chess960(STANDARD_POSITION).unwrap();
+    assert_eq!(board.to_fen_string(), Board::new().to_fen_string());
+    assert!(board.is_chess960());
+    assert_eq!(board.get_chess960_index(), Some(STANDARD_POSITION));
+    assert_eq!(Board::new().get_chess960_index(), Some(STANDARD_POSITION));
+
+    let board = Board::new_chess960(0).unwrap();
+    assert_eq!(board.to_fen_string(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+    assert_eq!(board.get_castling_files(CastleAvailability::WhiteQueenside), (File::G, File::F));
+    assert_eq!(board.perft(2), 400);
+    assert!(Board::new_chess960(960).is_err());
+  }
+
+  #[test]
+  fn test_chess960_castling() {
+    // King on b1 with rooks on a1 and h1; queenside castling puts the king on c1 and the rook on d1
+    let mut board = Board::from_fen_string("r3k2r/8/8/8/8/8/8/RK5R w KQkq - 0 1").unwrap();
+    assert!(board.is_chess960());
+    assert_eq!(board.get_castling_files(CastleAvailability::WhiteQueenside), (File::B, File::A));
+    assert_eq!(board.get_castling_files(CastleAvailability::BlackKingside), (File::E, File::H));
+    let legal = board.get_legal_moves();
+    let queenside = Move::from_uci("b1a1").unwrap();
+    let kingside = Move::from_uci("b1h1").unwrap();
+    assert!(legal.contains(&queenside) && legal.contains(&kingside));
+
+    let mut after = board.clone();
+    after.make_move(queenside).unwrap();
+    assert_eq!(after.to_fen_string(), "r3k2r/8/8/8/8/8/8/2KR3R b kq - 1 1");
+    after = board.clone();
+    after.make_move(kingside).unwrap();
+    assert_eq!(after.to_fen_string(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+
+    let undo = board.make_move(kingside).unwrap();
+    board.unmake_move(kingside, undo);
+    assert_eq!(board.to_fen_string(), "r3k2r/8/8/8/8/8/8/RK5R w KQkq - 0 1");
+
+    // The king may not pass through e1, which the rook on e8 attacks
+    let attacked = Board::from_fen_string("4r1k1/8/8/8/8/8/8/RK5R w KQ - 0 1").unwrap();
+    assert!(!attacked.get_legal_moves().contains(&kingside));
+    assert!(attacked.get_legal_moves().contains(&queenside));
+  }
+
+  #[test]
+  fn test_chess960_perft() {
+    let perft = |fen: &str| Board::from_fen_string(fen).unwrap().perft(3);
+    assert_eq!(perft("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"), 12189);
+    assert_eq!(perft("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9"), 18002);
+  }
+
+  #[test]
+  fn test_castling_fields() {
+    // Shredder-FEN names every rook by file; X-FEN only the inner one of two on the same side
+    let board = Board::from_fen_string("rk2r3/8/8/8/8/8/8/RK2R2R w HAea - 0 1").unwrap();
+    assert_eq!(board.get_castling_files(CastleAvailability::WhiteKingside), (File::B, File::H));
+    assert_eq!(board.get_castling_files(CastleAvailability::BlackKingside), (File::B, File::E));
+    assert_eq!(board.to_fen_string(), "rk2r3/8/8/8/8/8/8/RK2R2R w KQkq - 0 1");
+
+    let inner = Board::from_fen_string("4k3/8/8/8/8/8/8/RK2R2R w E - 0 1").unwrap();
+    assert_eq!(inner.get_castling_files(CastleAvailability::WhiteKingside), (File::B, File::E));
+    assert_eq!(inner.to_fen_string(), "4k3/8/8/8/8/8/8/RK2R2R w E - 0 1");
+
+    let standard = Board::from_fen_string("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    assert!(!standard.is_chess960());
+    assert!(standard.get_legal_moves().contains(&Move::from_uci("e1g1").unwrap()));
+  }
+}
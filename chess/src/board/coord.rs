@@ -5,7 +5,7 @@ use std::ops::Add;
 
 use crate::errors::Error;
 
-#[derive(Clone, Copy, Debug, PartialOrd, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialOrd, PartialEq)]
 pub struct Coordinate {
   pub file: File,
   pub rank: Rank,
@@ -17,10 +17,10 @@ impl fmt::Display for Coordinate {
   }
 }
 
-impl Into<String> for Coordinate {
-  fn into(self) -> String {
-    let file_str: &str = self.file.into();
-    let rank_str: &str = self.rank.into();
+impl From<Coordinate> for String {
+  fn from(coord: Coordinate) -> String {
+    let file_str: &str = coord.file.into();
+    let rank_str: &str = coord.rank.into();
 
     String::from(file_str) + rank_str
   }
@@ -51,7 +51,7 @@ impl Coordinate {
   /// A [`Error`](`crate::board::Error`) will be returned if the position fails
   /// to parse.
   pub fn get_coordinate(position_str: &str) -> Result<Coordinate, Error> {
-    if position_str.len() != 2 {
+    if position_str.len() != 2 && position_str.len() != 3 || !position_str.is_ascii() {
       return Err(Error::InvalidPositionString);
     }
 
@@ -64,18 +64,22 @@ impl Coordinate {
       Some('f') | Some('F') => Ok(File::F),
       Some('g') | Some('G') => Ok(File::G),
       Some('h') | Some('H') => Ok(File::H),
+      Some('i') | Some('I') => Ok(File::I),
+      Some('j') | Some('J') => Ok(File::J),
       _ => Err(Error::InvalidPositionString)
     }?;
 
-    let rank = match position_str.chars().nth(1) {
-      Some('1') => Ok(Rank::One),
-      Some('2') => Ok(Rank::Two),
-      Some('3') => Ok(Rank::Three),
-      Some('4') => Ok(Rank::Four),
-      Some('5') => Ok(Rank::Five),
-      Some('6') => Ok(Rank::Six),
-      Some('7') => Ok(Rank::Seven),
-      Some('8') => Ok(Rank::Eight),
+    let rank = match &position_str[1..] {
+      "1" => Ok(Rank::One),
+      "2" => Ok(Rank::Two),
+      "3" => Ok(Rank::Three),
+      "4" => Ok(Rank::Four),
+      "5" => Ok(Rank::Five),
+      "6" => Ok(Rank::Six),
+      "7" => Ok(Rank::Seven),
+      "8" => Ok(Rank::Eight),
+      "9" => Ok(Rank::Nine),
+      "10" => Ok(Rank::Ten),
       _ => Err(Error::InvalidPositionString)
     }?;
 
@@ -112,8 +116,17 @@ mod tests {
   }
 
   #[test]
-  fn test_get_coordinate_a9_invalid() {
-    assert_eq!(Coordinate::get_coordinate("a9").err().unwrap(), Error::InvalidPositionString);
+  fn test_get_coordinate_j10_success() {
+    let coord = Coordinate::get_coordinate("j10").unwrap();
+    assert_eq!(coord.file, File::J);
+    assert_eq!(coord.rank, Rank::Ten);
+    assert_eq!(coord.to_string(), "J10");
+  }
+
+  #[test]
+  fn test_get_coordinate_a11_invalid() {
+    assert_eq!(Coordinate::get_coordinate("a11").err().unwrap(), Error::InvalidPositionString);
+    assert_eq!(Coordinate::get_coordinate("k1").err().unwrap(), Error::InvalidPositionString);
   }
 
   #[test]
@@ -0,0 +1,224 @@
+//! Drawing a [`Board`](`crate::board::Board`) as text, for terminals and debugging.
+
+use std::fmt;
+
+use crate::board::Board;
+use crate::board::coord::Coordinate;
+use crate::board::moves::Move;
+use crate::board::square::{Square, SquareColor};
+use crate::piece::{Color, PieceKind};
+
+const RESET: &str = "\x1b[0m";
+const LIGHT_SQUARE: &str = "\x1b[48;5;180m";
+const DARK_SQUARE: &str = "\x1b[48;5;137m";
+const LIGHT_LAST_MOVE: &str = "\x1b[48;5;186m";
+const DARK_LAST_MOVE: &str = "\x1b[48;5;143m";
+const CHECK_SQUARE: &str = "\x1b[48;5;167m";
+const CURSOR_SQUARE: &str = "\x1b[48;5;75m";
+const WHITE_PIECE: &str = "\x1b[1;97m";
+const BLACK_PIECE: &str = "\x1b[1;30m";
+
+/// How [`Board::display`](`crate::board::Board::display`) draws a board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayOptions {
+  /// Draw chess pieces as Unicode figurines rather than FEN letters. Fairy pieces keep their letters.
+  pub unicode: bool,
+  /// Label the ranks down the left and the files along the bottom.
+  pub labels: bool,
+  /// Draw the board from black's side, with the eighth rank at the bottom.
+  pub flipped: bool,
+  /// The move that was just played, whose squares are highlighted.
+  pub last_move: Option<Move>,
+  /// Highlight the king of the side to move when it is in check.
+  pub highlight_check: bool,
+  /// A square to mark as under the cursor, for choosing squares from the keyboard.
+  pub cursor: Option<Coordinate>,
+  /// Shade the squares with ANSI colors. Without them, highlighted squares are bracketed:
+  /// `[P]` for the last move, `!K!` for a king in check and `<P>` for the cursor.
+  pub ansi_colors: bool,
+}
+
+impl Default for DisplayOptions {
+  fn default() -> Self {
+    DisplayOptions {
+      unicode: false,
+      labels: true,
+      flipped: false,
+      last_move: None,
+      highlight_check: true,
+      cursor: None,
+      ansi_colors: false,
+    }
+  }
+}
+
+/// A board ready to be drawn with the given options; see [`Board::display`](`crate::board::Board::display`).
+pub struct BoardDisplay<'a> {
+  board: &'a Board,
+  options: DisplayOptions,
+}
+
+fn figurine(kind: PieceKind, color: Color) -> char {
+  match (color, kind) {
+    (Color::White, PieceKind::King) => '♔',
+    (Color::White, PieceKind::Queen) => '♕',
+    (Color::White, PieceKind::Rook) => '♖',
+    (Color::White, PieceKind::Bishop) => '♗',
+    (Color::White, PieceKind::Knight) => '♘',
+    (Color::White, PieceKind::Pawn) => '♙',
+    (Color::Black, PieceKind::King) => '♚',
+    (Color::Black, PieceKind::Queen) => '♛',
+    (Color::Black, PieceKind::Rook) => '♜',
+    (Color::Black, PieceKind::Bishop) => '♝',
+    (Color::Black, PieceKind::Knight) => '♞',
+    (Color::Black, PieceKind::Pawn) => '♟',
+  }
+}
+
+impl BoardDisplay<'_> {
+  fn get_symbol(&self, square: &Square) -> String {
+    match square.get_piece() {
+      Some(piece) => match (self.options.unicode, piece.get_kind()) {
+        (true, Some(kind)) => figurine(kind, *piece.get_color()).to_string(),
+        _ if *piece.get_color() == Color::White => piece.get_short_name().to_uppercase(),
+        _ => piece.get_short_name().to_lowercase(),
+      },
+      None if self.options.ansi_colors => String::from(" "),
+      None if self.options.unicode => String::from("·"),
+      None => String::from("."),
+    }
+  }
+
+  fn write_square(&self, f: &mut fmt::Formatter<'_>, square: &Square, checked_king: Option<Coordinate>) -> fmt::Result {
+    let coord = *square.get_coord();
+    let in_check = checked_king == Some(coord);
+    let last_move = self.options.last_move.is_some_and(|mv| mv.from == coord || mv.to == coord);
+    let symbol = self.get_symbol(square);
+    let cursor = self.options.cursor == Some(coord);
+
+    if !self.options.ansi_colors {
+      return match (in_check, last_move) {
+        _ if cursor => write!(f, "<{}>", symbol),
+        (true, _) => write!(f, "!{}!", symbol),
+        (false, true) => write!(f, "[{}]", symbol),
+        (false, false) => write!(f, " {} ", symbol),
+      };
+    }
+
+    let light = *square.get_color() == SquareColor::Light;
+    let background = match (in_check, last_move, light) {
+      _ if cursor => CURSOR_SQUARE,
+      (true, _, _) => CHECK_SQUARE,
+      (false, true, true) => LIGHT_LAST_MOVE,
+      (false, true, false) => DARK_LAST_MOVE,
+      (false, false, true) => LIGHT_SQUARE,
+      (false, false, false) => DARK_SQUARE,
+    };
+    let foreground = match square.get_piece() {
+      Some(piece) if *piece.get_color() == Color::White => WHITE_PIECE,
+      _ => BLACK_PIECE,
+    };
+    write!(f, "{}{} {} {}", background, foreground, symbol, RESET)
+  }
+}
+
+impl fmt::Display for BoardDisplay<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let board = self.board;
+    let (width, height) = (board.get_width(), board.get_height());
+    let color = board.get_active_color();
+    let checked_king = if self.options.highlight_check && board.is_in_check(&color) { board.find_king(&color) } else { None };
+
+    let ranks: Vec<i8> = if self.options.flipped { (0..height).collect() } else { (0..height).rev().collect() };
+    let files: Vec<i8> = if self.options.flipped { (0..width).rev().collect() } else { (0..width).collect() };
+    for y in ranks.iter() {
+      if self.options.labels {
+        write!(f, "{:>2} ", y + 1)?;
+      }
+      for x in files.iter() {
+        let square = board.get_square_by_coords(*x, *y).map_err(|_| fmt::Error)?;
+        self.write_square(f, square, checked_king)?;
+      }
+      writeln!(f)?;
+    }
+
+    if self.options.labels {
+      write!(f, "   ")?;
+      for x in files.iter() {
+        write!(f, " {} ", (b'a' + *x as u8) as char)?;
+      }
+      writeln!(f)?;
+    }
+    Ok(())
+  }
+}
+
+impl Board {
+  /// Returns something that draws the board with the given options when formatted, e.g.
+  /// `println!("{}", board.display(options))`.
+  pub fn display(&self, options: DisplayOptions) -> BoardDisplay<'_> {
+    BoardDisplay { board: self, options }
+  }
+}
+
+/// Draws the board in ASCII with the default [`DisplayOptions`](`crate::board::display::DisplayOptions`).
+impl fmt::Display for Board {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.display(DisplayOptions::default()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_display_starting_position() {
+    let expected = [
+      " 8  r  n  b  q  k  b  n  r ",
+      " 7  p  p  p  p  p  p  p  p ",
+      " 6  .  .  .  .  .  .  .  . ",
+      " 5  .  .  .  .  .  .  .  . ",
+      " 4  .  .  .  .  .  .  .  . ",
+      " 3  .  .  .  .  .  .  .  . ",
+      " 2  P  P  P  P  P  P  P  P ",
+      " 1  R  N  B  Q  K  B  N  R ",
+      "    a  b  c  d  e  f  g  h ",
+    ];
+    assert_eq!(Board::new().to_string(), expected.join("\n") + "\n");
+
+    let flipped = Board::new().display(DisplayOptions { flipped: true, unicode: true, labels: false, ..Default::default() }).to_string();
+    let lines: Vec<&str> = flipped.lines().collect();
+    assert_eq!(lines.len(), 8);
+    assert_eq!(lines[0], " ♖  ♘  ♗  ♔  ♕  ♗  ♘  ♖ ");
+    assert_eq!(lines[2], " ·  ·  ·  ·  ·  ·  ·  · ");
+  }
+
+  #[test]
+  fn test_display_highlights() {
+    let mut board = Board::from_fen_string("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let mv = Move::from_uci("a1a8").unwrap();
+    board.make_move(mv).unwrap();
+    let options = DisplayOptions { last_move: Some(mv), labels: false, ..Default::default() };
+    let text = board.display(options).to_string();
+    assert!(text.starts_with("[R] .  .  . !k! .  .  . \n"));
+    assert!(text.ends_with("[.] .  .  .  K  .  .  . \n"));
+
+    let shaded = board.display(DisplayOptions { ansi_colors: true, ..options }).to_string();
+    assert!(shaded.contains(CHECK_SQUARE) && shaded.contains(DARK_LAST_MOVE) && shaded.contains(LIGHT_SQUARE));
+    assert!(!shaded.contains("[R]"));
+
+    let cursor = Coordinate::get_coordinate("e1").ok();
+    let text = board.display(DisplayOptions { cursor, ..options }).to_string();
+    assert!(text.ends_with("[.] .  .  . <K> .  .  . \n"));
+    assert!(board.display(DisplayOptions { cursor, ansi_colors: true, ..options }).to_string().contains(CURSOR_SQUARE));
+  }
+
+  #[test]
+  fn test_display_other_sizes() {
+    let board = Board::from_fen_string("4k5/10/10/10/10/10/10/10/10/4K5 w - - 0 1").unwrap();
+    let text = board.to_string();
+    assert!(text.starts_with("10  .  .  .  .  k "));
+    assert!(text.ends_with("    a  b  c  d  e  f  g  h  i  j \n"));
+  }
+}
@@ -10,12 +10,14 @@ pub enum File {
   F = 6,
   G = 7,
   H = 8,
-  Invalid = std::isize::MAX,
+  I = 9,
+  J = 10,
+  Invalid = i8::MAX as isize,
 }
 
 impl fmt::Display for File {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    match self.clone() {
+    match *self {
       File::A => write!(f, "A"),
       File::B => write!(f, "B"),
       File::C => write!(f, "C"),
@@ -24,14 +26,16 @@ impl fmt::Display for File {
       File::F => write!(f, "F"),
       File::G => write!(f, "G"),
       File::H => write!(f, "H"),
+      File::I => write!(f, "I"),
+      File::J => write!(f, "J"),
       File::Invalid => write!(f, "!"),
     }
   }
 }
 
-impl Into<i8> for File {
-  fn into(self) -> i8 {
-    match self {
+impl From<File> for i8 {
+  fn from(x: File) -> i8 {
+    match x {
       File::A => 1,
       File::B => 2,
       File::C => 3,
@@ -40,6 +44,8 @@ impl Into<i8> for File {
       File::F => 6,
       File::G => 7,
       File::H => 8,
+      File::I => 9,
+      File::J => 10,
       File::Invalid => panic!("Trying to put invalid file into i8"),
     }
   }
@@ -56,14 +62,16 @@ impl From<i8> for File {
       6 => File::F,
       7 => File::G,
       8 => File::H,
+      9 => File::I,
+      10 => File::J,
       _ => File::Invalid,
     }
   }
 }
 
-impl Into<&str> for File {
-  fn into(self) -> &'static str {
-    match self {
+impl From<File> for &str {
+  fn from(x: File) -> &'static str {
+    match x {
       File::A => "A",
       File::B => "B",
       File::C => "C",
@@ -72,6 +80,8 @@ impl Into<&str> for File {
       File::F => "F",
       File::G => "G",
       File::H => "H",
+      File::I => "I",
+      File::J => "J",
       _ => "INVALID"
     }
   }
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::sync::Arc;
 
 pub mod square;
 use crate::board::square::{Square, SquareColor};
@@ -12,9 +13,20 @@ use crate::board::rank::Rank;
 pub mod coord;
 use crate::board::coord::Coordinate;
 
+pub mod chess960;
+pub mod display;
+pub mod moves;
+pub mod pocket;
+pub mod san;
+pub mod zobrist;
+
+use crate::board::pocket::Pocket;
 use crate::errors::Error;
+use crate::game::GameResult;
+use crate::variant::{Standard, Variant, VariantState};
 
-use crate::piece::{Color, Piece};
+use crate::piece::{Color, Piece, PieceKind, make_piece};
+use crate::piece::betza::{FairyPiece, PieceDefinition};
 use crate::piece::bishop::Bishop;
 use crate::piece::king::King;
 use crate::piece::knight::Knight;
@@ -22,8 +34,17 @@ use crate::piece::pawn::Pawn;
 use crate::piece::queen::Queen;
 use crate::piece::rook::Rook;
 
-const BOARD_WIDTH: i8 = 8;
-const BOARD_HEIGHT: i8 = 8;
+/// The width and height of a standard board.
+pub const DEFAULT_BOARD_SIZE: i8 = 8;
+/// The smallest width or height a board may have, as in Gardner's 5x5 minichess.
+pub const MIN_BOARD_SIZE: i8 = 5;
+/// The largest width or height a board may have, as in 10x10 Grand Chess.
+pub const MAX_BOARD_SIZE: i8 = 10;
+
+/// Chess boards flip colors every row, so a1 and every square diagonal from it are dark.
+fn get_square_color(x: i8, y: i8) -> SquareColor {
+  if (x + y) % 2 == 0 { SquareColor::Dark } else { SquareColor::Light }
+}
 
 fn make_piece_at_coord(coord: Coordinate) -> Option<Box<dyn Piece>> {
   match (coord.file, coord.rank) {
@@ -72,163 +93,275 @@ pub enum CastleAvailability {
   BlackQueenside,
 }
 
-fn get_castle_availability_str(avail: &HashSet<CastleAvailability>) -> String {
-  let mut _str = String::new();
-  if avail.contains(&CastleAvailability::WhiteKingside) { _str += "K"; }
-  if avail.contains(&CastleAvailability::WhiteQueenside) { _str += "Q"; }
-  if avail.contains(&CastleAvailability::BlackKingside) { _str += "k"; }
-  if avail.contains(&CastleAvailability::BlackQueenside) { _str += "q"; }
+impl CastleAvailability {
+  pub const ALL: [CastleAvailability; 4] = [CastleAvailability::WhiteKingside, CastleAvailability::WhiteQueenside,
+                                            CastleAvailability::BlackKingside, CastleAvailability::BlackQueenside];
 
-  if _str.is_empty() { _str = String::from("-"); }
+  pub fn new(color: Color, kingside: bool) -> CastleAvailability {
+    match (color, kingside) {
+      (Color::White, true) => CastleAvailability::WhiteKingside,
+      (Color::White, false) => CastleAvailability::WhiteQueenside,
+      (Color::Black, true) => CastleAvailability::BlackKingside,
+      (Color::Black, false) => CastleAvailability::BlackQueenside,
+    }
+  }
 
-  _str
+  pub fn index(self) -> usize {
+    self as usize
+  }
+
+  pub fn get_color(self) -> Color {
+    match self {
+      CastleAvailability::WhiteKingside | CastleAvailability::WhiteQueenside => Color::White,
+      CastleAvailability::BlackKingside | CastleAvailability::BlackQueenside => Color::Black,
+    }
+  }
+
+  pub fn is_kingside(self) -> bool {
+    matches!(self, CastleAvailability::WhiteKingside | CastleAvailability::BlackKingside)
+  }
+}
+
+/// The king's and rook's starting files for each castling right, indexed by
+/// [`CastleAvailability::index`](`crate::board::CastleAvailability::index`).
+type CastlingFiles = [(File, File); 4];
+
+/// The castling files of the usual starting position on a board of the given width: the king
+/// just right of the centre, and the rooks in the corners.
+fn get_default_castling_files(width: i8) -> CastlingFiles {
+  let (king, kingside, queenside) = (File::from(width / 2 + 1), File::from(width), File::A);
+  [(king, kingside), (king, queenside), (king, kingside), (king, queenside)]
 }
 
 fn get_default_castling_availability() -> HashSet<CastleAvailability> {
+  CastleAvailability::ALL.iter().copied().collect()
+}
+
+/// Reads the castling field of a FEN string. Besides `KQkq`, the X-FEN and Shredder-FEN file
+/// letters used for Chess960 are accepted; `K` and `Q` then mean the outermost rook on that side
+/// of the king. Returns the rights, along with the king's and rook's files for each of them.
+fn parse_castling_field(field: &str, squares: &[Square], width: i8, height: i8) -> Result<(HashSet<CastleAvailability>, CastlingFiles), Error> {
   let mut castling_availability = HashSet::new();
-  castling_availability.insert(CastleAvailability::WhiteKingside);
-  castling_availability.insert(CastleAvailability::WhiteQueenside);
-  castling_availability.insert(CastleAvailability::BlackKingside);
-  castling_availability.insert(CastleAvailability::BlackQueenside);
+  let default_castling_files = get_default_castling_files(width);
+  let mut castling_files = default_castling_files;
+  if field == "-" {
+    return Ok((castling_availability, castling_files));
+  }
 
-  castling_availability
+  for castle_char in field.chars() {
+    let color = if castle_char.is_ascii_uppercase() { Color::White } else { Color::Black };
+    let rank_start = if color == Color::White { 0 } else { ((height - 1) * width) as usize };
+    let is_own = |file: i8, kind: PieceKind| matches!(squares.get(rank_start + file as usize).and_then(|square| square.get_piece().as_ref()),
+      Some(piece) if *piece.get_color() == color && piece.get_kind() == Some(kind));
+    let king_file = (0..width).find(|file| is_own(*file, PieceKind::King));
+
+    let (castle, rook_file) = match (castle_char.to_ascii_uppercase(), king_file) {
+      ('K', Some(king_file)) => (CastleAvailability::new(color, true), (king_file + 1..width).rev().find(|file| is_own(*file, PieceKind::Rook))),
+      ('Q', Some(king_file)) => (CastleAvailability::new(color, false), (0..king_file).find(|file| is_own(*file, PieceKind::Rook))),
+      // Rights without a king or rook to match are kept, and simply never used
+      ('K', None) => (CastleAvailability::new(color, true), None),
+      ('Q', None) => (CastleAvailability::new(color, false), None),
+      (letter @ 'A'..='J', Some(king_file)) if ((letter as i8 - 'A' as i8) < width) => {
+        let rook_file = letter as i8 - 'A' as i8;
+        (CastleAvailability::new(color, rook_file > king_file), Some(rook_file))
+      }
+      _ => return Err(Error::InvalidFENString),
+    };
+
+    let (default_king, default_rook) = default_castling_files[castle.index()];
+    castling_files[castle.index()] = (
+      king_file.map(|file| File::from(file + 1)).unwrap_or(default_king),
+      rook_file.map(|file| File::from(file + 1)).unwrap_or(default_rook),
+    );
+    castling_availability.insert(castle);
+  }
+
+  Ok((castling_availability, castling_files))
 }
 
-/// Collection of [`Square`](`crate::board::Square`)s, 8x8.
+/// Collection of [`Square`](`crate::board::Square`)s, 8x8 unless set up otherwise. Boards
+/// can be anywhere from [`MIN_BOARD_SIZE`](`crate::board::MIN_BOARD_SIZE`) to
+/// [`MAX_BOARD_SIZE`](`crate::board::MAX_BOARD_SIZE`) squares wide and high.
+#[derive(Clone)]
 pub struct Board {
   squares: Vec<Square>,
+  width: i8,
+  height: i8,
   active_color: Color,
   castling_availability: HashSet<CastleAvailability>,
+  castling_files: CastlingFiles,
+  /// Whether castling follows Chess960 conventions: moves are written as the king taking its
+  /// own rook, and FEN names rooks by file when they aren't the outermost.
+  chess960: bool,
   en_passant_target: Option<Coordinate>,
   half_move_clock: i32,
   full_move: i32,
+  /// The rules the board is played by.
+  variant: Arc<dyn Variant>,
+  variant_state: VariantState,
+  /// White's and black's pockets, on boards of drop variants.
+  pockets: Option<[Pocket; 2]>,
+  /// Squares holding pieces promoted from pawns.
+  promoted: HashSet<Coordinate>,
 }
 
 impl Board {
-  /// Creates a new chess board with the given dimensions.
-  /// Board will always be rectangular (width * height).
+  /// Creates a new 8x8 chess board in the starting position.
   pub fn new() -> Board {
+    Board::with_pieces(DEFAULT_BOARD_SIZE, DEFAULT_BOARD_SIZE, make_piece_at_coord)
+  }
+
+  /// Creates an empty board of the given dimensions, with white to move and no castling rights.
+  pub fn new_empty(width: i8, height: i8) -> Result<Board, Error> {
+    if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&width) || !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&height) {
+      return Err(Error::InvalidBoardSize);
+    }
+
+    let mut board = Board::with_pieces(width, height, |_| None);
+    board.castling_availability.clear();
+    Ok(board)
+  }
+
+  /// Creates a board with white to move and every castling right, placing the pieces given
+  /// for each coordinate.
+  fn with_pieces<F: Fn(Coordinate) -> Option<Box<dyn Piece>>>(width: i8, height: i8, make_piece: F) -> Board {
     let mut squares: Vec<Square> = vec![];
-    let mut color = SquareColor::Dark;
 
-    for y in 0..BOARD_HEIGHT {
-      for x in 0..BOARD_WIDTH {
+    for y in 0..height {
+      for x in 0..width {
         let coord = Coordinate::make_coordinate(x, y);
-        let mut square = Square::new(color, coord);
-        square.set_piece(make_piece_at_coord(coord));
+        let mut square = Square::new(get_square_color(x, y), coord);
+        square.set_piece(make_piece(coord));
         squares.push(square);
-        color = if color == SquareColor::Dark { SquareColor::Light } else { SquareColor::Dark };
       }
-
-      // Chess boards flip colors every row, so repeat the last color we used by flipping again
-      color = if color == SquareColor::Dark { SquareColor::Light } else { SquareColor::Dark };
     }
 
     Board {
       squares,
+      width,
+      height,
       active_color: Color::White,
       castling_availability: get_default_castling_availability(),
+      castling_files: get_default_castling_files(width),
+      chess960: false,
       en_passant_target: None,
       half_move_clock: 0,
       full_move: 1,
+      variant: Arc::new(Standard),
+      variant_state: VariantState::default(),
+      pockets: None,
+      promoted: HashSet::new(),
     }
   }
 
-  /// Creates a board from a given FEN string.
-  pub fn from_fen_string(fen_string: &'static str) -> Result<Board, Error> {
+  /// Creates a board in the variant's starting position, played by its rules.
+  pub fn new_variant(variant: Arc<dyn Variant>) -> Result<Board, Error> {
+    let fen = variant.get_start_fen();
+    Board::from_variant_fen(variant, fen)
+  }
+
+  /// Creates a board from a FEN string, played by the variant's rules. The FEN string may use
+  /// the variant's fairy pieces.
+  pub fn from_variant_fen(variant: Arc<dyn Variant>, fen_string: &str) -> Result<Board, Error> {
+    let mut board = Board::from_fen_string_with_pieces(fen_string, &variant.get_fairy_pieces())?;
+    board.set_variant(variant);
+    Ok(board)
+  }
+
+  /// Creates a board from a given FEN string. Castling rights for a king or rook off its usual
+  /// square make it a [Chess960](`crate::board::Board::is_chess960`) board. A seventh field such
+  /// as `+1+2` gives the checks each side has given, for Three-check, and
+  /// [pockets](`crate::board::pocket`) may follow the piece placement.
+  pub fn from_fen_string(fen_string: &str) -> Result<Board, Error> {
+    Board::from_fen_string_with_pieces(fen_string, &[])
+  }
+
+  /// Creates a board from a FEN string that may also hold the given
+  /// [fairy pieces](`crate::piece::betza::PieceDefinition`), written by their letters.
+  pub fn from_fen_string_with_pieces(fen_string: &str, fairy_pieces: &[Arc<PieceDefinition>]) -> Result<Board, Error> {
     let fields: Vec<&str> = fen_string.split(" ").collect();
 
-    if fields.len() != 6 {
+    if fields.len() != 6 && fields.len() != 7 {
       return Err(Error::InvalidFENString);
     }
 
-    let pieces_str = fields[0];
+    let (pieces_str, pockets) = match fields[0].find('[') {
+      Some(start) if fields[0].ends_with(']') => {
+        (&fields[0][..start], Some(pocket::parse_pockets(&fields[0][start + 1..fields[0].len() - 1])?))
+      }
+      Some(_) => return Err(Error::InvalidFENString),
+      None => (fields[0], None),
+    };
+    let mut promoted = HashSet::new();
     // In FEN, black rows are listed first and white rows last, so we'll reverse it first
     let mut ranks: Vec<&str> = pieces_str.split("/").collect();
     ranks.reverse();
+    if !(MIN_BOARD_SIZE as usize..=MAX_BOARD_SIZE as usize).contains(&ranks.len()) {
+      return Err(Error::InvalidFENString);
+    }
+    let height = ranks.len() as i8;
 
+    // The first rank sets the width, and every other rank must match it
+    let mut width = None;
     let mut squares: Vec<Square> = vec![];
-    let mut square_idx = 0;
-    let mut color = SquareColor::Dark;
-    for rank in ranks {
-      let mut expected_pieces_remaining: i8 = 8;
-      for piece_char in rank.chars() {
-        if expected_pieces_remaining <= 0 {
-          println!("Found more pieces in a rank that was expecting!");
+    for (y, rank) in ranks.iter().enumerate() {
+      let y = y as i8;
+      let mut x: i8 = 0;
+      let mut chars = rank.chars().peekable();
+      while let Some(piece_char) = chars.next() {
+        if piece_char == '~' {
+          match squares.last() {
+            Some(square) if square.get_piece().is_some() => promoted.insert(*square.get_coord()),
+            _ => return Err(Error::InvalidFENString),
+          };
+          continue;
+        }
+
+        // Runs of empty squares can take two digits on wide boards
+        let mut empty_squares = match piece_char.to_digit(10) {
+          Some(0) => return Err(Error::InvalidFENString),
+          Some(digit) => digit,
+          None => 0,
+        };
+        while let Some(digit) = chars.peek().and_then(|next| next.to_digit(10)).filter(|_| empty_squares > 0) {
+          empty_squares = empty_squares * 10 + digit;
+          chars.next();
+        }
+        if empty_squares > MAX_BOARD_SIZE as u32 {
           return Err(Error::InvalidFENString);
         }
 
-        let mut additional_empty_squares: i8 = 0;
-
-        let coord = Coordinate::make_coordinate(square_idx % BOARD_WIDTH, square_idx / BOARD_WIDTH);
-        let mut square = Square::new(color, coord);
-        let piece: Result<Option<Box<dyn Piece>>, Error> = match piece_char {
-          'p' => Ok(Some(Box::new(Pawn::new(Color::Black, coord)))),
-          'r' => Ok(Some(Box::new(Rook::new(Color::Black, coord)))),
-          'n' => Ok(Some(Box::new(Knight::new(Color::Black, coord)))),
-          'b' => Ok(Some(Box::new(Bishop::new(Color::Black, coord)))),
-          'q' => Ok(Some(Box::new(Queen::new(Color::Black, coord)))),
-          'k' => Ok(Some(Box::new(King::new(Color::Black, coord)))),
-          'P' => Ok(Some(Box::new(Pawn::new(Color::White, coord)))),
-          'R' => Ok(Some(Box::new(Rook::new(Color::White, coord)))),
-          'N' => Ok(Some(Box::new(Knight::new(Color::White, coord)))),
-          'B' => Ok(Some(Box::new(Bishop::new(Color::White, coord)))),
-          'Q' => Ok(Some(Box::new(Queen::new(Color::White, coord)))),
-          'K' => Ok(Some(Box::new(King::new(Color::White, coord)))),
-          '1' => { Ok(None) }
-          '2' => {
-            additional_empty_squares = 1;
-            Ok(None)
-          }
-          '3' => {
-            additional_empty_squares = 2;
-            Ok(None)
-          }
-          '4' => {
-            additional_empty_squares = 3;
-            Ok(None)
-          }
-          '5' => {
-            additional_empty_squares = 4;
-            Ok(None)
-          }
-          '6' => {
-            additional_empty_squares = 5;
-            Ok(None)
+        let color = if piece_char.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let make = |coord: Coordinate| -> Result<Box<dyn Piece>, Error> {
+          if let Some(kind) = PieceKind::from_short_name(&piece_char.to_string()) {
+            return Ok(make_piece(kind, color, coord));
           }
-          '7' => {
-            additional_empty_squares = 6;
-            Ok(None)
-          }
-          '8' => {
-            additional_empty_squares = 7;
-            Ok(None)
-          }
-          _ => Err(Error::InvalidFENString),
+          fairy_pieces.iter()
+            .find(|definition| definition.get_letter() == piece_char.to_ascii_uppercase())
+            .map(|definition| Box::new(FairyPiece::new(Arc::clone(definition), color, coord)) as Box<dyn Piece>)
+            .ok_or(Error::InvalidFENString)
         };
-        square.set_piece(piece?);
-        squares.push(square);
-        color = if color == SquareColor::Dark { SquareColor::Light } else { SquareColor::Dark };
-        expected_pieces_remaining -= 1;
-        square_idx += 1;
-
-        for _ in 0..additional_empty_squares {
-          if expected_pieces_remaining <= 0 {
-            println!("Found more pieces in a rank that was expecting!");
+        for _ in 0..empty_squares.max(1) {
+          if x >= MAX_BOARD_SIZE {
             return Err(Error::InvalidFENString);
           }
-          let coord = Coordinate::make_coordinate(square_idx % BOARD_WIDTH, square_idx / BOARD_WIDTH);
-          let square = Square::new(color, coord);
+          let coord = Coordinate::make_coordinate(x, y);
+          let mut square = Square::new(get_square_color(x, y), coord);
+          if empty_squares == 0 {
+            square.set_piece(Some(make(coord)?));
+          }
           squares.push(square);
-          color = if color == SquareColor::Dark { SquareColor::Light } else { SquareColor::Dark };
-          expected_pieces_remaining -= 1;
-          square_idx += 1;
+          x += 1;
         }
       }
 
-      // Chess boards flip colors every row, so repeat the last color we used by flipping again
-      color = if color == SquareColor::Dark { SquareColor::Light } else { SquareColor::Dark };
+      match width {
+        None if (MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&x) => width = Some(x),
+        Some(width) if width == x => {}
+        _ => return Err(Error::InvalidFENString),
+      }
     }
+    let width = width.ok_or(Error::InvalidFENString)?;
 
     let active_color_str = fields[1];
     let active_color: Color = {
@@ -241,37 +374,18 @@ impl Board {
       }
     }?;
 
-    let castling_avail_str = fields[2];
-    let mut castling_availability = HashSet::new();
-    for castle_char in castling_avail_str.chars() {
-      let _ = match castle_char {
-        'K' => {
-          castling_availability.insert(CastleAvailability::WhiteKingside);
-          Ok(())
-        }
-        'Q' => {
-          castling_availability.insert(CastleAvailability::WhiteQueenside);
-          Ok(())
-        }
-        'k' => {
-          castling_availability.insert(CastleAvailability::BlackKingside);
-          Ok(())
-        }
-        'q' => {
-          castling_availability.insert(CastleAvailability::BlackQueenside);
-          Ok(())
-        }
-        '-' => { Ok(()) }
-        _ => Err(Error::InvalidFENString),
-      }?;
-    }
+    let (castling_availability, castling_files) = parse_castling_field(fields[2], &squares, width, height)?;
 
     let en_passant_str = fields[3];
     let en_passant_target: Option<Coordinate> = {
       if en_passant_str == "-" {
         Ok(None)
       } else {
-        Ok(Some(Coordinate::get_coordinate(en_passant_str)?))
+        let coord = Coordinate::get_coordinate(en_passant_str)?;
+        if Into::<i8>::into(coord.file) > width || Into::<i8>::into(coord.rank) > height {
+          return Err(Error::InvalidFENString);
+        }
+        Ok(Some(coord))
       }
     }?;
 
@@ -288,13 +402,33 @@ impl Board {
     }?;
 
 
+    let mut variant_state = VariantState::default();
+    if let Some(checks_str) = fields.get(6) {
+      let checks: Vec<&str> = checks_str.split('+').collect();
+      match checks[..] {
+        ["", white, black] => {
+          variant_state.set_checks(Color::White, white.parse().map_err(|_| Error::InvalidFENString)?);
+          variant_state.set_checks(Color::Black, black.parse().map_err(|_| Error::InvalidFENString)?);
+        }
+        _ => return Err(Error::InvalidFENString),
+      }
+    }
+
     let board = Board {
       squares,
+      width,
+      height,
       active_color,
       castling_availability,
+      castling_files,
+      chess960: castling_files != get_default_castling_files(width),
       en_passant_target,
       half_move_clock,
       full_move,
+      variant: Arc::new(Standard),
+      variant_state,
+      pockets,
+      promoted,
     };
 
     Ok(board)
@@ -303,9 +437,9 @@ impl Board {
   pub fn to_fen_string(&self) -> String {
     // Pieces
     let mut pieces_str = String::new();
-    for y in (0..BOARD_HEIGHT).rev() {
+    for y in (0..self.height).rev() {
       let mut consecutive_empty_squares: u8 = 0;
-      for x in 0..BOARD_WIDTH {
+      for x in 0..self.width {
         let square = self.get_square_by_coords(x, y).unwrap();
         match &square.get_piece() {
           Some(piece) => {
@@ -318,6 +452,9 @@ impl Board {
               Color::White => piece.get_short_name().to_uppercase(),
               Color::Black => piece.get_short_name().to_lowercase(),
             };
+            if self.pockets.is_some() && self.is_promoted(*square.get_coord()) {
+              pieces_str.push('~');
+            }
           }
           None => {
             consecutive_empty_squares += 1;
@@ -333,10 +470,13 @@ impl Board {
 
     // Remove extra / at end
     pieces_str.pop();
+    if let Some(pockets) = &self.pockets {
+      pieces_str += &format!("[{}]", pocket::format_pockets(pockets));
+    }
 
     let active_color_str = if self.get_active_color() == Color::White { "w" } else { "b" };
 
-    let castle_avail_str = get_castle_availability_str(&self.get_castling_availability());
+    let castle_avail_str = self.get_castling_field();
 
     let en_passant_str = match self.get_en_passant_target() {
       Some(coord) => Into::<String>::into(coord),
@@ -347,25 +487,148 @@ impl Board {
 
     let full_move_str = self.get_full_move().to_string();
 
-    format!("{} {} {} {} {} {}",
+    let mut fen = format!("{} {} {} {} {} {}",
       pieces_str,
       active_color_str,
       castle_avail_str,
       en_passant_str,
       half_move_str,
-      full_move_str)
+      full_move_str);
+    if self.variant_state != VariantState::default() {
+      let checks = |color: Color| self.variant_state.get_checks(color);
+      fen += &format!(" +{}+{}", checks(Color::White), checks(Color::Black));
+    }
+    fen
+  }
+
+  /// Returns every [`Square`](`crate::board::Square`) on the board, ordered from A1 to H8 rank by rank.
+  pub fn get_squares(&self) -> &[Square] {
+    &self.squares
+  }
+
+  /// The number of files on the board.
+  pub fn get_width(&self) -> i8 {
+    self.width
+  }
+
+  /// The number of ranks on the board.
+  pub fn get_height(&self) -> i8 {
+    self.height
+  }
+
+  /// Whether the board is the usual 8x8, which the evaluation tables, NNUE, opening books and
+  /// tablebases all assume.
+  pub fn is_standard_size(&self) -> bool {
+    self.width == DEFAULT_BOARD_SIZE && self.height == DEFAULT_BOARD_SIZE
+  }
+
+  /// The rank a color's pieces start on: the first for white and the last for black.
+  pub fn get_home_rank(&self, color: Color) -> Rank {
+    if color == Color::White { Rank::One } else { Rank::from(self.height) }
+  }
+
+  /// The rank a color's pawns promote on: the last for white and the first for black.
+  pub fn get_promotion_rank(&self, color: Color) -> Rank {
+    self.get_home_rank(color.opposite())
+  }
+
+  /// The index of the coordinate in [`get_squares`](`crate::board::Board::get_squares`), if it
+  /// is on the board.
+  pub fn get_square_index(&self, coord: Coordinate) -> Option<usize> {
+    if !coord.is_valid() {
+      return None;
+    }
+    let (x, y) = (Into::<i8>::into(coord.file) - 1, Into::<i8>::into(coord.rank) - 1);
+    if x < self.width && y < self.height { Some((y * self.width + x) as usize) } else { None }
   }
 
   pub fn get_active_color(&self) -> Color {
-    self.active_color.clone()
+    self.active_color
   }
 
   pub fn get_castling_availability(&self) -> HashSet<CastleAvailability> {
     self.castling_availability.clone()
   }
 
+  pub fn get_variant(&self) -> &dyn Variant {
+    self.variant.as_ref()
+  }
+
+  /// Switches the rules the board is played by, keeping the position. Drop variants get
+  /// empty pockets if the board has none.
+  pub fn set_variant(&mut self, variant: Arc<dyn Variant>) {
+    if variant.has_pockets() {
+      self.enable_pockets();
+    }
+    self.variant = variant;
+  }
+
+  pub fn get_variant_state(&self) -> &VariantState {
+    &self.variant_state
+  }
+
+  pub fn get_variant_state_mut(&mut self) -> &mut VariantState {
+    &mut self.variant_state
+  }
+
+  /// The result, if the variant's own rules have ended the game. Checkmate and stalemate
+  /// aren't included.
+  pub fn get_variant_result(&self) -> Option<GameResult> {
+    self.variant.get_result(self)
+  }
+
+  /// Whether the position reached after `mover` moved is allowed by the variant; in chess,
+  /// whether the mover's king is safe.
+  pub fn is_legal_position(&self, mover: Color) -> bool {
+    self.variant.is_legal_position(self, mover)
+  }
+
+  /// The king's and rook's starting files for a castling right.
+  pub fn get_castling_files(&self, castle: CastleAvailability) -> (File, File) {
+    self.castling_files[castle.index()]
+  }
+
+  /// Whether castling follows Chess960 conventions. Castling moves are then written as the king
+  /// taking its own rook, as UCI expects with `UCI_Chess960` on, rather than as the king moving
+  /// two squares.
+  pub fn is_chess960(&self) -> bool {
+    self.chess960
+  }
+
+  /// Switches Chess960 conventions on or off. They stay on for positions whose castling pieces
+  /// didn't start on the usual squares, since the king moving two squares can't describe those.
+  pub fn set_chess960(&mut self, chess960: bool) {
+    self.chess960 = chess960 || self.castling_files != get_default_castling_files(self.width);
+  }
+
+  /// Writes the castling field of a FEN string. Chess960 boards name a rook by its file when
+  /// another rook stands further out on the same side, as X-FEN does.
+  fn get_castling_field(&self) -> String {
+    let mut field = String::new();
+    for castle in CastleAvailability::ALL.iter().filter(|castle| self.castling_availability.contains(castle)) {
+      let color = castle.get_color();
+      let (king_file, rook_file) = self.get_castling_files(*castle);
+      let (king_file, rook_file): (i8, i8) = (king_file.into(), rook_file.into());
+      let step = if rook_file > king_file { 1 } else { -1 };
+      let rank = if color == Color::White { 1 } else { self.height };
+      let outermost = !(1..self.width).map(|i| rook_file + i * step).take_while(|file| (1..=self.width).contains(file)).any(|file| {
+        matches!(self.get_square_by_coords(file - 1, rank - 1).ok().and_then(|square| square.get_piece().as_ref()),
+          Some(piece) if *piece.get_color() == color && piece.get_kind() == Some(PieceKind::Rook))
+      });
+
+      let letter = if !self.chess960 || outermost {
+        if castle.is_kingside() { 'K' } else { 'Q' }
+      } else {
+        (b'A' + rook_file as u8 - 1) as char
+      };
+      field.push(if color == Color::White { letter } else { letter.to_ascii_lowercase() });
+    }
+
+    if field.is_empty() { String::from("-") } else { field }
+  }
+
   pub fn get_en_passant_target(&self) -> Option<Coordinate> {
-    self.en_passant_target.clone()
+    self.en_passant_target
   }
 
   pub fn get_half_move_clock(&self) -> i32 {
@@ -378,36 +641,49 @@ impl Board {
 
   /// Returns true if a move or capture a piece at the target coordinate given it's color
   pub fn can_capture(&self, target_coord: &Coordinate, mover_color: &Color) -> bool {
-    match self.get_square(*target_coord) {
-      Ok(square) if square.get_piece().is_some() && *square.get_piece().as_ref().unwrap().get_color() != *mover_color => true,
-      _ => false,
-    }
+    matches!(self.get_square(*target_coord),
+      Ok(square) if square.get_piece().is_some() && *square.get_piece().as_ref().unwrap().get_color() != *mover_color)
   }
 
   /// Returns true if a piece can move to a target coordinate given it's color
   pub fn can_move(&self, target_coord: &Coordinate) -> bool {
-    match self.get_square(*target_coord) {
-      Ok(square) if square.get_piece().is_none() => true,
-      _ => false,
-    }
+    matches!(self.get_square(*target_coord), Ok(square) if square.get_piece().is_none())
   }
 
-  pub fn is_in_check(&self, king_color: &Color) -> bool {
-    let king_pos: &Square = self.squares.iter()
+  /// Returns the coordinate of the given color's king, if it has one.
+  pub fn find_king(&self, king_color: &Color) -> Option<Coordinate> {
+    self.squares.iter()
       .filter(|sq| sq.get_piece().is_some())
       .filter(|sq| *sq.get_piece().as_ref().unwrap().get_short_name().to_lowercase() == *"k")
-      .filter(|sq| *sq.get_piece().as_ref().unwrap().get_color() == *king_color)
-      .collect::<Vec<&Square>>()[0];
+      .find(|sq| *sq.get_piece().as_ref().unwrap().get_color() == *king_color)
+      .map(|sq| *sq.get_coord())
+  }
+
+  pub fn is_in_check(&self, king_color: &Color) -> bool {
+    match self.find_king(king_color) {
+      Some(king_coord) => self.is_square_attacked(&king_coord, &king_color.opposite()),
+      None => false,
+    }
+  }
 
-    let attacker_color = if *king_color == Color::White { Color::Black } else { Color::White };
+  /// Returns true if any piece of the attacker's color could capture on the target coordinate,
+  /// were an enemy piece standing there.
+  pub fn is_square_attacked(&self, target_coord: &Coordinate, attacker_color: &Color) -> bool {
     let attacker_squares: Vec<&Square> = self.squares.iter()
       .filter(|sq| sq.get_piece().is_some())
-      .filter(|sq| *sq.get_piece().as_ref().unwrap().get_color() == attacker_color)
+      .filter(|sq| *sq.get_piece().as_ref().unwrap().get_color() == *attacker_color)
       .collect();
 
-    attacker_squares.iter()
-      .flat_map(|sq| sq.get_piece().as_ref().unwrap().get_moves(&self))
-      .any(|move_coord| move_coord == *king_pos.get_coord())
+    attacker_squares.iter().any(|sq| {
+      let piece = sq.get_piece().as_ref().unwrap();
+      if piece.get_short_name() == "P" {
+        // Pawns only list diagonal moves when there is something to capture, so check their geometry instead
+        let forward: i8 = if *attacker_color == Color::White { 1 } else { -1 };
+        *target_coord == *sq.get_coord() + (1, forward) || *target_coord == *sq.get_coord() + (-1, forward)
+      } else {
+        piece.get_attacks(self).contains(target_coord)
+      }
+    })
   }
 
   /// Returns a [`Square`](`crate::board::Square`) given the coordinates.
@@ -415,11 +691,11 @@ impl Board {
   /// For example, the coordinates (1, 8) would map to A8.
   /// This function handles the conversion from one-indexing to zero-indexing.
   fn get_square_by_coords(&self, x: i8, y: i8) -> Result<&Square, Error> {
-    if x < 0 || y < 0 {
+    if x < 0 || y < 0 || x >= self.width || y >= self.height {
       return Err(Error::InvalidRawCoordinatePair);
     }
 
-    match self.squares.get((BOARD_WIDTH * y + x) as usize) {
+    match self.squares.get((self.width * y + x) as usize) {
       Some(square) => Ok(square),
       None => Err(Error::InvalidRawCoordinatePair)
     }
@@ -429,6 +705,17 @@ impl Board {
   pub fn get_square(&self, coord: Coordinate) -> Result<&Square, Error> {
     self.get_square_by_coords(Into::<i8>::into(coord.file) - 1, Into::<i8>::into(coord.rank) - 1)
   }
+
+  fn get_square_mut(&mut self, coord: Coordinate) -> Result<&mut Square, Error> {
+    let idx = self.get_square_index(coord).ok_or(Error::InvalidRawCoordinatePair)?;
+    self.squares.get_mut(idx).ok_or(Error::InvalidRawCoordinatePair)
+  }
+}
+
+impl Default for Board {
+  fn default() -> Self {
+    Board::new()
+  }
 }
 
 #[cfg(test)]
@@ -519,6 +806,38 @@ mod tests {
     assert!(!board.is_in_check(&Color::Black));
   }
 
+  #[test]
+  fn test_other_board_sizes_fen_round_trip() {
+    let wide = "r4k3r/pppppppppp/10/10/10/10/PPPPPPPPPP/R4K3R w KQkq - 0 1";
+    let board = Board::from_fen_string(wide).unwrap();
+    assert_eq!((board.get_width(), board.get_height()), (10, 8));
+    assert!(!board.is_chess960());
+    assert_eq!(board.to_fen_string(), wide);
+
+    let tall = "4k5/pppppppppp/10/10/10/10/10/10/PPPPPPPPPP/4K5 b - j3 0 1";
+    let board = Board::from_fen_string(tall).unwrap();
+    assert_eq!((board.get_width(), board.get_height()), (10, 10));
+    let color = |name: &str| *board.get_square(Coordinate::get_coordinate(name).unwrap()).unwrap().get_color();
+    assert_eq!((color("j10"), color("j1")), (SquareColor::Dark, SquareColor::Light));
+    assert_eq!(board.to_fen_string(), tall);
+
+    let small = Board::new_empty(5, 6).unwrap();
+    assert_eq!(small.to_fen_string(), "5/5/5/5/5/5 w - - 0 1");
+    assert!(small.get_square(Coordinate::get_coordinate("f1").unwrap()).is_err());
+    assert_eq!(Board::new_empty(4, 8).err(), Some(Error::InvalidBoardSize));
+    assert_eq!(Board::new_empty(8, 11).err(), Some(Error::InvalidBoardSize));
+  }
+
+  #[test]
+  fn test_invalid_board_sizes_rejected() {
+    // Too narrow, too wide, too short and ragged
+    assert!(Board::from_fen_string("4k/4/4/4/4/K3 w - - 0 1").is_err());
+    assert!(Board::from_fen_string("k10/11/11/11/11/10K w - - 0 1").is_err());
+    assert!(Board::from_fen_string("k4/5/5/4K w - - 0 1").is_err());
+    assert!(Board::from_fen_string("k4/5/6/5/4K w - - 0 1").is_err());
+    assert!(Board::from_fen_string("k4/5/5/5/4K w - f1 0 1").is_err());
+  }
+
   #[test]
   fn test_white_is_in_check() {
     let board = Board::from_fen_string("rnbqk1nr/pppp1ppp/8/4P3/1b6/8/PPP1PPPP/RNBQKBNR w KQkq - 1 3").unwrap();
@@ -0,0 +1,727 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::board::{Board, CastleAvailability};
+use crate::board::coord::Coordinate;
+use crate::board::file::File;
+use crate::board::pocket::Pocket;
+use crate::board::rank::Rank;
+use crate::errors::Error;
+use crate::piece::{make_piece, Color, Piece, PieceKind};
+use crate::variant::VariantState;
+
+/// A move from one coordinate to another. Castling is written as the king moving two squares,
+/// or on [Chess960](`crate::board::Board::is_chess960`) boards as the king taking its own rook.
+/// A drop places a piece from the [pocket](`crate::board::pocket::Pocket`) and goes from its
+/// target square to itself.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Move {
+  pub from: Coordinate,
+  pub to: Coordinate,
+  pub promotion: Option<PieceKind>,
+  /// The kind of piece dropped, for drops.
+  pub drop: Option<PieceKind>,
+}
+
+impl Move {
+  pub fn new(from: Coordinate, to: Coordinate) -> Move {
+    Move { from, to, promotion: None, drop: None }
+  }
+
+  pub fn with_promotion(from: Coordinate, to: Coordinate, promotion: PieceKind) -> Move {
+    Move { from, to, promotion: Some(promotion), drop: None }
+  }
+
+  pub fn new_drop(kind: PieceKind, to: Coordinate) -> Move {
+    Move { from: to, to, promotion: None, drop: Some(kind) }
+  }
+
+  pub fn is_drop(&self) -> bool {
+    self.drop.is_some()
+  }
+
+  /// Parses a move in UCI long algebraic notation, such as `e2e4`, `e7e8q` or the drop `N@f3`.
+  /// On boards with ten ranks, coordinates such as `j10` take three characters.
+  pub fn from_uci(move_str: &str) -> Result<Move, Error> {
+    if !(4..=7).contains(&move_str.len()) || !move_str.is_ascii() {
+      return Err(Error::InvalidMoveString);
+    }
+
+    if &move_str[1..2] == "@" {
+      let to = Coordinate::get_coordinate(&move_str[2..]).map_err(|_| Error::InvalidMoveString)?;
+      return match PieceKind::from_short_name(&move_str[..1]) {
+        Some(kind) if kind != PieceKind::King && move_str[..1].chars().all(|c| c.is_ascii_uppercase()) => Ok(Move::new_drop(kind, to)),
+        _ => Err(Error::InvalidMoveString),
+      };
+    }
+
+    // Each coordinate is a file letter followed by the rank's digits
+    let coord_end = |start: usize| start + 1 + move_str.get(start + 1..).map_or(0, |rest| rest.chars().take_while(|c| c.is_ascii_digit()).count());
+    let (from_end, to_end) = (coord_end(0), coord_end(coord_end(0)));
+    let coord_at = |range: std::ops::Range<usize>| move_str.get(range).ok_or(Error::InvalidMoveString)
+      .and_then(|coord_str| Coordinate::get_coordinate(coord_str).map_err(|_| Error::InvalidMoveString));
+    let from = coord_at(0..from_end)?;
+    let to = coord_at(from_end..to_end)?;
+    let promotion = match move_str.get(to_end..) {
+      None | Some("") => None,
+      Some(kind_str) => match PieceKind::from_short_name(kind_str) {
+        // Kings are only promoted to in variants such as Antichess
+        Some(kind) if kind != PieceKind::Pawn => Some(kind),
+        _ => return Err(Error::InvalidMoveString),
+      },
+    };
+
+    Ok(Move { from, to, promotion, drop: None })
+  }
+}
+
+impl fmt::Display for Move {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(kind) = self.drop {
+      return write!(f, "{}@{}", kind.get_short_name(), self.to.to_string().to_lowercase());
+    }
+    write!(f, "{}{}", self.from.to_string().to_lowercase(), self.to.to_string().to_lowercase())?;
+    if let Some(kind) = self.promotion {
+      write!(f, "{}", kind.get_short_name().to_lowercase())?;
+    }
+    Ok(())
+  }
+}
+
+/// Everything needed to take back a move made with [`Board::make_move`](`crate::board::Board::make_move`).
+pub struct MoveUndo {
+  moved: Box<dyn Piece>,
+  destination: Coordinate,
+  captured: Option<(Coordinate, Box<dyn Piece>)>,
+  rook_move: Option<(Coordinate, Coordinate)>,
+  castling_availability: HashSet<CastleAvailability>,
+  en_passant_target: Option<Coordinate>,
+  half_move_clock: i32,
+  full_move: i32,
+  variant_state: VariantState,
+  pockets: Option<[Pocket; 2]>,
+  moved_promoted: bool,
+  captured_promoted: bool,
+  /// Further pieces taken off by the variant's rules, such as Atomic's explosions.
+  removed: Vec<(Coordinate, Box<dyn Piece>)>,
+}
+
+impl MoveUndo {
+  /// Returns where the moved piece ended up. This is the move's target square, except for
+  /// Chess960 castling, where the target is the rook.
+  pub fn get_destination(&self) -> Coordinate {
+    self.destination
+  }
+
+  /// Returns the piece captured by the move, if any, along with where it stood.
+  pub fn get_captured(&self) -> Option<(&Coordinate, &dyn Piece)> {
+    self.captured.as_ref().map(|(coord, piece)| (coord, piece.as_ref()))
+  }
+
+  /// Whether the captured piece had been promoted from a pawn.
+  pub fn is_captured_promoted(&self) -> bool {
+    self.captured_promoted
+  }
+
+  /// Returns the pieces the variant's rules took off the board besides the captured one.
+  pub fn get_removed(&self) -> impl Iterator<Item = (&Coordinate, &dyn Piece)> {
+    self.removed.iter().map(|(coord, piece)| (coord, piece.as_ref()))
+  }
+
+  /// Returns the castling rook's (from, to) coordinates, if the move was a castle.
+  pub fn get_rook_move(&self) -> Option<(Coordinate, Coordinate)> {
+    self.rook_move
+  }
+}
+
+/// Everything needed to take back a move made with [`Board::make_null_move`](`crate::board::Board::make_null_move`).
+pub struct NullMoveUndo {
+  en_passant_target: Option<Coordinate>,
+}
+
+/// Where the king and rook end up when castling, whatever their starting squares: next to
+/// the corner on the kingside, and on the c- and d-files on the queenside.
+fn castle_targets(kingside: bool, width: i8) -> (File, File) {
+  if kingside { (File::from(width - 1), File::from(width - 2)) } else { (File::C, File::D) }
+}
+
+impl Board {
+  pub(crate) fn piece_at(&self, coord: Coordinate) -> Option<&dyn Piece> {
+    match self.get_square(coord) {
+      Ok(square) => square.get_piece().as_deref(),
+      Err(_) => None,
+    }
+  }
+
+  fn take_piece(&mut self, coord: Coordinate) -> Option<Box<dyn Piece>> {
+    self.get_square_mut(coord).ok()?.take_piece()
+  }
+
+  fn put_piece(&mut self, coord: Coordinate, piece: Box<dyn Piece>) {
+    if let Ok(square) = self.get_square_mut(coord) {
+      square.set_piece(Some(piece));
+    }
+  }
+
+  fn get_castling_moves(&self) -> Vec<Move> {
+    let color = self.active_color;
+    if self.is_in_check(&color) {
+      return vec![];
+    }
+
+    let rank = self.get_home_rank(color);
+    let mut moves = vec![];
+    for castle in CastleAvailability::ALL.iter() {
+      if castle.get_color() != color || !self.castling_availability.contains(castle) {
+        continue;
+      }
+
+      let (king_file, rook_file) = self.get_castling_files(*castle);
+      let (king_target, rook_target) = castle_targets(castle.is_kingside(), self.width);
+      let king_coord = Coordinate { file: king_file, rank };
+      let rook_coord = Coordinate { file: rook_file, rank };
+      let is_own = |coord: Coordinate, kind: PieceKind| matches!(self.piece_at(coord),
+        Some(piece) if *piece.get_color() == color && piece.get_kind() == Some(kind));
+      if !is_own(king_coord, PieceKind::King) || !is_own(rook_coord, PieceKind::Rook) {
+        continue;
+      }
+
+      // Every square either piece crosses or lands on must be empty, apart from the two of them
+      let files: Vec<i8> = [king_file, rook_file, king_target, rook_target].iter().map(|file| Into::<i8>::into(*file)).collect();
+      let (lowest, highest) = (*files.iter().min().unwrap(), *files.iter().max().unwrap());
+      let between_clear = (lowest..=highest)
+        .map(|file| Coordinate { file: File::from(file), rank })
+        .all(|coord| coord == king_coord || coord == rook_coord || self.can_move(&coord));
+      // The king may not pass through an attacked square; its destination is checked like any other move
+      let king_distance = files[2] - files[0];
+      let passing_safe = (1..king_distance.abs())
+        .all(|i| !self.is_square_attacked(&(king_coord + (i * king_distance.signum(), 0)), &color.opposite()));
+
+      if between_clear && passing_safe {
+        let to = if self.chess960 { rook_coord } else { Coordinate { file: king_target, rank } };
+        moves.push(Move::new(king_coord, to));
+      }
+    }
+
+    moves
+  }
+
+  /// The king's destination and the rook's (from, to) coordinates, if the move is a castle.
+  fn get_castle_squares(&self, mv: &Move) -> Option<(Coordinate, Coordinate, Coordinate)> {
+    let king = self.piece_at(mv.from)?;
+    if king.get_kind() != Some(PieceKind::King) || mv.from.rank != mv.to.rank {
+      return None;
+    }
+
+    let color = *king.get_color();
+    let file_delta = Into::<i8>::into(mv.to.file) - Into::<i8>::into(mv.from.file);
+    let kingside = file_delta > 0;
+    let rank = mv.from.rank;
+    let (king_target, rook_target) = castle_targets(kingside, self.width);
+    let rook_from = match self.piece_at(mv.to) {
+      Some(rook) if *rook.get_color() == color && rook.get_kind() == Some(PieceKind::Rook) => mv.to,
+      _ if !self.chess960 && file_delta.abs() >= 2 && mv.to.file == king_target => {
+        Coordinate { file: self.get_castling_files(CastleAvailability::new(color, kingside)).1, rank }
+      }
+      _ => return None,
+    };
+
+    Some((Coordinate { file: king_target, rank }, rook_from, Coordinate { file: rook_target, rank }))
+  }
+
+  /// Returns true if the move is a castle, written either way.
+  pub fn is_castle(&self, mv: &Move) -> bool {
+    self.get_castle_squares(mv).is_some()
+  }
+
+  /// Returns every move the side to move could make, ignoring whether it leaves its own king in check.
+  pub fn get_pseudo_legal_moves(&self) -> Vec<Move> {
+    self.variant.get_pseudo_legal_moves(self)
+  }
+
+  /// Returns the pseudo-legal moves of chess, which variants start from.
+  pub fn get_standard_pseudo_legal_moves(&self) -> Vec<Move> {
+    let mut moves = vec![];
+    for square in self.squares.iter() {
+      let piece = match square.get_piece() {
+        Some(piece) if *piece.get_color() == self.active_color => piece,
+        _ => continue,
+      };
+
+      let is_pawn = piece.get_kind() == Some(PieceKind::Pawn);
+      for to in piece.get_moves(self) {
+        if is_pawn && to.rank == self.get_promotion_rank(self.active_color) {
+          for kind in self.variant.get_promotion_kinds().iter() {
+            moves.push(Move::with_promotion(*square.get_coord(), to, *kind));
+          }
+        } else {
+          moves.push(Move::new(*square.get_coord(), to));
+        }
+      }
+    }
+
+    moves.append(&mut self.get_castling_moves());
+    moves
+  }
+
+  /// Returns every legal move for the side to move. There are none once the variant's rules
+  /// have ended the game.
+  pub fn get_legal_moves(&self) -> Vec<Move> {
+    if self.get_variant_result().is_some() {
+      return vec![];
+    }
+    self.get_legal_moves_ignoring_result()
+  }
+
+  /// Returns the legal moves even if the variant's rules have ended the game, for variants
+  /// whose results depend on the moves available.
+  pub fn get_legal_moves_ignoring_result(&self) -> Vec<Move> {
+    let color = self.active_color;
+    let mut scratch = self.clone();
+    self.get_pseudo_legal_moves().into_iter()
+      .filter(|mv| {
+        let undo = scratch.make_move(*mv).expect("pseudo-legal moves should always be playable");
+        let is_legal = scratch.is_legal_position(color);
+        scratch.unmake_move(*mv, undo);
+        is_legal
+      })
+      .collect()
+  }
+
+  /// Returns true if the move is legal for the side to move.
+  pub fn is_legal_move(&self, mv: &Move) -> bool {
+    self.get_legal_moves().contains(mv)
+  }
+
+  /// Returns true if the move is one [`Board::get_pseudo_legal_moves`](`crate::board::Board::get_pseudo_legal_moves`)
+  /// would generate, without generating every move. Useful for checking moves remembered from other positions.
+  pub fn is_pseudo_legal_move(&self, mv: &Move) -> bool {
+    self.variant.is_pseudo_legal_move(self, mv)
+  }
+
+  /// Returns true if the move is one of the pseudo-legal moves of chess, or a drop from the pocket.
+  pub fn is_standard_pseudo_legal_move(&self, mv: &Move) -> bool {
+    if let Some(kind) = mv.drop {
+      return mv.from == mv.to
+        && self.get_pocket(self.active_color).is_some_and(|pocket| pocket.get_count(kind) > 0)
+        && self.is_droppable(kind, mv.to);
+    }
+
+    let piece = match self.piece_at(mv.from) {
+      Some(piece) if *piece.get_color() == self.active_color => piece,
+      _ => return false,
+    };
+
+    let kind = piece.get_kind();
+    if self.is_castle(mv) {
+      return self.get_castling_moves().contains(mv);
+    }
+
+    let promotes = kind == Some(PieceKind::Pawn) && mv.to.rank == self.get_promotion_rank(self.active_color);
+    let valid_promotion = match mv.promotion {
+      Some(promotion) => promotes && self.variant.get_promotion_kinds().contains(&promotion),
+      None => !promotes,
+    };
+
+    valid_promotion && piece.get_moves(self).contains(&mv.to)
+  }
+
+  /// Returns true if the move captures a piece, including en passant.
+  pub fn is_capture(&self, mv: &Move) -> bool {
+    if self.can_capture(&mv.to, &self.active_color) {
+      return true;
+    }
+
+    matches!(self.piece_at(mv.from), Some(piece) if piece.get_kind() == Some(PieceKind::Pawn))
+      && Some(mv.to) == self.en_passant_target
+      && mv.from.file != mv.to.file
+  }
+
+  /// Plays a move for the side to move, without checking whether it is legal.
+  /// An error is returned if there is no piece of the side to move on the origin square.
+  pub fn make_move(&mut self, mv: Move) -> Result<MoveUndo, Error> {
+    if let Some(kind) = mv.drop {
+      return self.make_drop(kind, mv.to);
+    }
+
+    let color = self.active_color;
+    match self.piece_at(mv.from) {
+      Some(piece) if *piece.get_color() == color && mv.to.is_valid() => {},
+      _ => return Err(Error::InvalidMove),
+    }
+
+    let castle = self.get_castle_squares(&mv);
+    let moved = self.take_piece(mv.from).unwrap();
+    let kind = moved.get_kind();
+    let mut undo = MoveUndo {
+      moved,
+      destination: castle.map_or(mv.to, |(king_to, _, _)| king_to),
+      captured: None,
+      rook_move: None,
+      castling_availability: self.castling_availability.clone(),
+      en_passant_target: self.en_passant_target,
+      half_move_clock: self.half_move_clock,
+      full_move: self.full_move,
+      variant_state: self.variant_state,
+      pockets: self.pockets,
+      moved_promoted: self.promoted.remove(&mv.from),
+      captured_promoted: false,
+      removed: vec![],
+    };
+
+    if let Some((_, rook_from, rook_to)) = castle {
+      if let Some(rook) = self.take_piece(rook_from) {
+        self.put_piece(rook_to, rook.clone_at(rook_to));
+        undo.rook_move = Some((rook_from, rook_to));
+      }
+    } else {
+      let is_en_passant = kind == Some(PieceKind::Pawn)
+        && Some(mv.to) == self.en_passant_target
+        && mv.from.file != mv.to.file
+        && self.can_move(&mv.to);
+      let captured_coord = if is_en_passant { Coordinate { file: mv.to.file, rank: mv.from.rank } } else { mv.to };
+      undo.captured = self.take_piece(captured_coord).map(|piece| (captured_coord, piece));
+      undo.captured_promoted = self.promoted.remove(&captured_coord);
+    }
+    if undo.moved_promoted || mv.promotion.is_some() {
+      self.promoted.insert(undo.destination);
+    }
+
+    let placed = match mv.promotion {
+      Some(promotion) => make_piece(promotion, color, mv.to),
+      None => undo.moved.clone_at(undo.destination),
+    };
+    self.put_piece(undo.destination, placed);
+
+    // Moving the king or a rook, or capturing a rook, forfeits the matching castling rights
+    self.forfeit_castling(&[mv.from, mv.to]);
+
+    // Only pawns passing over their third rank can be taken en passant, so a Horde pawn's
+    // double push from the first rank leaves no target
+    let rank_delta = Into::<i8>::into(mv.to.rank) - Into::<i8>::into(mv.from.rank);
+    let passed = mv.from + (0, rank_delta / 2);
+    let third_rank = if color == Color::White { Rank::Three } else { Rank::from(self.height - 2) };
+    self.en_passant_target = if kind == Some(PieceKind::Pawn) && rank_delta.abs() == 2 && passed.rank == third_rank {
+      Some(passed)
+    } else {
+      None
+    };
+
+    if kind == Some(PieceKind::Pawn) || undo.captured.is_some() {
+      self.half_move_clock = 0;
+    } else {
+      self.half_move_clock += 1;
+    }
+
+    if color == Color::Black {
+      self.full_move += 1;
+    }
+    self.active_color = color.opposite();
+
+    let variant = Arc::clone(&self.variant);
+    variant.after_move(self, &mut undo);
+    Ok(undo)
+  }
+
+  /// Drops the castling rights whose king or rook starts on one of the coordinates.
+  fn forfeit_castling(&mut self, coords: &[Coordinate]) {
+    for castle in CastleAvailability::ALL.iter() {
+      let (king_file, rook_file) = self.get_castling_files(*castle);
+      let rank = self.get_home_rank(castle.get_color());
+      let king_coord = Coordinate { file: king_file, rank };
+      let rook_coord = Coordinate { file: rook_file, rank };
+      if coords.iter().any(|coord| *coord == king_coord || *coord == rook_coord) {
+        self.castling_availability.remove(castle);
+      }
+    }
+  }
+
+  /// Takes a piece off the board as part of the move `undo` belongs to, for variants whose
+  /// moves remove more than the captured piece. Taking back the move puts it back.
+  pub fn remove_piece(&mut self, coord: Coordinate, undo: &mut MoveUndo) {
+    if let Some(piece) = self.take_piece(coord) {
+      self.forfeit_castling(&[coord]);
+      self.promoted.remove(&coord);
+      undo.removed.push((coord, piece));
+    }
+  }
+
+  fn make_drop(&mut self, kind: PieceKind, to: Coordinate) -> Result<MoveUndo, Error> {
+    let color = self.active_color;
+    if !self.is_droppable(kind, to) || !self.get_pocket_mut(color).is_some_and(|pocket| pocket.remove(kind)) {
+      return Err(Error::InvalidMove);
+    }
+
+    let placed = make_piece(kind, color, to);
+    let mut undo = MoveUndo {
+      moved: placed.clone_at(to),
+      destination: to,
+      captured: None,
+      rook_move: None,
+      castling_availability: self.castling_availability.clone(),
+      en_passant_target: self.en_passant_target,
+      half_move_clock: self.half_move_clock,
+      full_move: self.full_move,
+      variant_state: self.variant_state,
+      // The pocket has already given up the piece, so remember it with the piece still held
+      pockets: self.pockets.map(|mut pockets| {
+        pockets[color.index()].add(kind);
+        pockets
+      }),
+      moved_promoted: false,
+      captured_promoted: false,
+      removed: vec![],
+    };
+    self.put_piece(to, placed);
+
+    self.en_passant_target = None;
+    if kind == PieceKind::Pawn {
+      self.half_move_clock = 0;
+    } else {
+      self.half_move_clock += 1;
+    }
+    if color == Color::Black {
+      self.full_move += 1;
+    }
+    self.active_color = color.opposite();
+
+    let variant = Arc::clone(&self.variant);
+    variant.after_move(self, &mut undo);
+    Ok(undo)
+  }
+
+  /// Takes back a move made with [`Board::make_move`](`crate::board::Board::make_move`).
+  pub fn unmake_move(&mut self, mv: Move, mut undo: MoveUndo) {
+    for (coord, piece) in std::mem::take(&mut undo.removed).into_iter().rev() {
+      self.put_piece(coord, piece);
+    }
+    // The rook goes back before the king, since in Chess960 it may have landed where the king started
+    self.take_piece(undo.destination);
+    if let Some((rook_from, rook_to)) = undo.rook_move {
+      if let Some(rook) = self.take_piece(rook_to) {
+        self.put_piece(rook_from, rook.clone_at(rook_from));
+      }
+    }
+    if !mv.is_drop() {
+      self.put_piece(mv.from, undo.moved);
+    }
+
+    self.promoted.remove(&undo.destination);
+    if undo.moved_promoted {
+      self.promoted.insert(mv.from);
+    }
+    if let Some((coord, piece)) = undo.captured {
+      if undo.captured_promoted {
+        self.promoted.insert(coord);
+      }
+      self.put_piece(coord, piece);
+    }
+
+    self.castling_availability = undo.castling_availability;
+    self.en_passant_target = undo.en_passant_target;
+    self.half_move_clock = undo.half_move_clock;
+    self.full_move = undo.full_move;
+    self.variant_state = undo.variant_state;
+    self.pockets = undo.pockets;
+    self.active_color = self.active_color.opposite();
+  }
+
+  /// Passes the turn to the opponent without moving, as used by null-move pruning.
+  pub fn make_null_move(&mut self) -> NullMoveUndo {
+    let undo = NullMoveUndo { en_passant_target: self.en_passant_target };
+    self.en_passant_target = None;
+    self.active_color = self.active_color.opposite();
+    undo
+  }
+
+  /// Takes back a move made with [`Board::make_null_move`](`crate::board::Board::make_null_move`).
+  pub fn unmake_null_move(&mut self, undo: NullMoveUndo) {
+    self.en_passant_target = undo.en_passant_target;
+    self.active_color = self.active_color.opposite();
+  }
+
+  /// The number of pieces on the board, kings and pawns included.
+  pub fn get_piece_count(&self) -> usize {
+    self.squares.iter().filter(|sq| sq.get_piece().is_some()).count()
+  }
+
+  /// Returns true if the color has any pieces besides its king and pawns.
+  pub fn has_non_pawn_material(&self, color: &Color) -> bool {
+    self.squares.iter()
+      .filter_map(|sq| sq.get_piece().as_ref())
+      .filter(|piece| piece.get_color() == color)
+      .any(|piece| !matches!(piece.get_kind(), Some(PieceKind::Pawn) | Some(PieceKind::King)))
+  }
+
+  /// Returns true if the color could still checkmate with some series of legal moves.
+  /// A lone minor piece can only mate with help from the opponent's own pieces, so it
+  /// counts only when the opponent has something besides a king. Fairy pieces are assumed
+  /// to be able to mate.
+  pub fn has_mating_material(&self, color: &Color) -> bool {
+    let mut minors = 0;
+    let mut opponent_pieces = 0;
+    for piece in self.squares.iter().filter_map(|sq| sq.get_piece().as_ref()) {
+      let kind = piece.get_kind();
+      if piece.get_color() != color {
+        if kind != Some(PieceKind::King) {
+          opponent_pieces += 1;
+        }
+        continue;
+      }
+      match kind {
+        Some(PieceKind::Pawn) | Some(PieceKind::Rook) | Some(PieceKind::Queen) => return true,
+        Some(PieceKind::Knight) | Some(PieceKind::Bishop) => minors += 1,
+        None if piece.get_definition().is_some() => return true,
+        _ => {}
+      }
+    }
+
+    minors >= 2 || (minors == 1 && opponent_pieces > 0)
+  }
+
+  /// Counts the leaf nodes of the legal move tree to the given depth.
+  pub fn perft(&self, depth: u32) -> u64 {
+    if depth == 0 {
+      return 1;
+    }
+
+    let moves = self.get_legal_moves();
+    if depth == 1 {
+      return moves.len() as u64;
+    }
+
+    let mut scratch = self.clone();
+    moves.iter()
+      .map(|mv| {
+        let undo = scratch.make_move(*mv).unwrap();
+        let nodes = scratch.perft(depth - 1);
+        scratch.unmake_move(*mv, undo);
+        nodes
+      })
+      .sum()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::piece::betza::PieceDefinition;
+
+  const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+  #[test]
+  fn test_mating_material() {
+    let has = |fen: &str, color: Color| Board::from_fen_string(fen).unwrap().has_mating_material(&color);
+    assert!(has("8/8/4k3/8/8/8/4P3/4K3 w - - 0 1", Color::White));
+    assert!(!has("8/8/4k3/8/8/8/4P3/4K3 w - - 0 1", Color::Black));
+    assert!(!has("8/8/4k3/8/8/8/4N3/4K3 w - - 0 1", Color::White));
+    assert!(has("8/8/4k3/8/8/8/3BN3/4K3 w - - 0 1", Color::White));
+    assert!(has("7k/7p/8/8/8/8/4N3/4K3 w - - 0 1", Color::White));
+
+    let archbishop = Arc::new(PieceDefinition::new('A', "BN").unwrap());
+    let board = Board::from_fen_string_with_pieces("4k3/8/8/8/8/8/4A3/4K3 w - - 0 1", &[archbishop]).unwrap();
+    assert!(board.has_mating_material(&Color::White));
+    assert!(!board.has_mating_material(&Color::Black));
+  }
+
+  #[test]
+  fn test_move_uci_round_trip() {
+    let mv = Move::from_uci("e7e8q").unwrap();
+    assert_eq!(mv.promotion, Some(PieceKind::Queen));
+    assert_eq!(mv.to_string(), "e7e8q");
+    assert_eq!(Move::from_uci("e2e4").unwrap().to_string(), "e2e4");
+    assert_eq!(Move::from_uci("e7e8p").err().unwrap(), Error::InvalidMoveString);
+    let king_promotion = Move::from_uci("a7a8k").unwrap();
+    assert!(!Board::from_fen_string("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap().is_pseudo_legal_move(&king_promotion));
+
+    let drop = Move::from_uci("N@f3").unwrap();
+    assert_eq!(drop, Move::new_drop(PieceKind::Knight, Coordinate::get_coordinate("f3").unwrap()));
+    assert_eq!(drop.to_string(), "N@f3");
+    assert!(Move::from_uci("K@f3").is_err() && Move::from_uci("n@f3").is_err());
+  }
+
+  #[test]
+  fn test_moves_on_other_board_sizes() {
+    assert_eq!(Move::from_uci("j9j10q").unwrap().to_string(), "j9j10q");
+    assert_eq!(Move::from_uci("a10b9").unwrap().to, Coordinate::get_coordinate("b9").unwrap());
+    assert!(Move::from_uci("a100").is_err() && Move::from_uci("k1k2").is_err());
+
+    // The king castles to the i-file on a ten file board, and the rook lands beside it
+    let mut board = Board::from_fen_string("r4k3r/10/10/10/10/10/10/R4K3R w KQkq - 0 1").unwrap();
+    let castle = Move::from_uci("f1i1").unwrap();
+    assert!(board.is_legal_move(&castle) && board.is_legal_move(&Move::from_uci("f1c1").unwrap()));
+    board.make_move(castle).unwrap();
+    assert_eq!(board.to_fen_string(), "r4k3r/10/10/10/10/10/10/R6RK1 b kq - 1 1");
+
+    // Black pawns start a rank lower on a taller board, and leave an en passant target behind
+    let mut board = Board::from_fen_string("4k3/p7/8/1P6/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    board.make_move(Move::from_uci("a9a7").unwrap()).unwrap();
+    assert_eq!(board.get_en_passant_target(), Coordinate::get_coordinate("a8").ok());
+    assert_eq!(board.move_from_san("bxa8").unwrap(), Move::from_uci("b7a8").unwrap());
+    assert_eq!(board.perft(1), board.get_legal_moves().len() as u64);
+  }
+
+  #[test]
+  fn test_perft_starting_position() {
+    let board = Board::new();
+    assert_eq!(board.perft(1), 20);
+    assert_eq!(board.perft(2), 400);
+    assert_eq!(board.perft(3), 8902);
+  }
+
+  #[test]
+  fn test_perft_kiwipete() {
+    let board = Board::from_fen_string(KIWIPETE).unwrap();
+    assert_eq!(board.perft(1), 48);
+    assert_eq!(board.perft(2), 2039);
+  }
+
+  #[test]
+  fn test_perft_en_passant_and_promotion() {
+    // Position 3 and 4 from the Chess Programming Wiki's perft results
+    let board = Board::from_fen_string("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+    assert_eq!(board.perft(1), 14);
+    assert_eq!(board.perft(2), 191);
+    assert_eq!(board.perft(3), 2812);
+
+    let board = Board::from_fen_string("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
+    assert_eq!(board.perft(1), 6);
+    assert_eq!(board.perft(2), 264);
+  }
+
+  #[test]
+  fn test_make_and_unmake_restore_position() {
+    let mut board = Board::from_fen_string(KIWIPETE).unwrap();
+    for mv in board.get_legal_moves() {
+      let undo = board.make_move(mv).unwrap();
+      board.unmake_move(mv, undo);
+      assert_eq!(board.to_fen_string(), KIWIPETE);
+    }
+  }
+
+  #[test]
+  fn test_castling_moves_rook_and_clears_rights() {
+    let mut board = Board::from_fen_string(KIWIPETE).unwrap();
+    board.make_move(Move::from_uci("e1g1").unwrap()).unwrap();
+    assert_eq!(board.to_fen_string(), "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R4RK1 b kq - 1 1");
+  }
+
+  #[test]
+  fn test_pseudo_legal_moves_are_recognized() {
+    let board = Board::from_fen_string(KIWIPETE).unwrap();
+    for mv in board.get_pseudo_legal_moves() {
+      assert!(board.is_pseudo_legal_move(&mv), "{} should be pseudo-legal", mv);
+    }
+    assert!(board.is_pseudo_legal_move(&Move::from_uci("e1c1").unwrap()));
+    assert!(!board.is_pseudo_legal_move(&Move::from_uci("a8a7").unwrap()));
+    assert!(!board.is_pseudo_legal_move(&Move::from_uci("d5d7").unwrap()));
+  }
+
+  #[test]
+  fn test_en_passant_capture_removes_pawn() {
+    let mut board = Board::from_fen_string("rnbqkbnr/ppp2ppp/3p4/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 3").unwrap();
+    board.make_move(Move::from_uci("d5e6").unwrap()).unwrap();
+    assert_eq!(board.to_fen_string(), "rnbqkbnr/ppp2ppp/3pP3/8/8/8/PPP1PPPP/RNBQKBNR b KQkq - 0 3");
+  }
+}
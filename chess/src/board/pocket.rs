@@ -0,0 +1,164 @@
+//! Pockets of captured pieces, for drop variants such as
+//! [Crazyhouse](`crate::variant::Crazyhouse`). In FEN they follow the piece placement in
+//! brackets, e.g. `[QNnp]`, and a `~` after a piece marks it as promoted from a pawn.
+
+use crate::board::Board;
+use crate::board::coord::Coordinate;
+use crate::board::moves::Move;
+use crate::board::rank::Rank;
+use crate::errors::Error;
+use crate::piece::{Color, PieceKind};
+
+/// The kinds of piece that can be held in a pocket and dropped, in FEN order.
+pub const DROP_KINDS: [PieceKind; 5] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight, PieceKind::Pawn];
+
+/// The pieces one side holds, ready to be dropped.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Pocket {
+  counts: [u8; 5],
+}
+
+fn pocket_index(kind: PieceKind) -> Option<usize> {
+  DROP_KINDS.iter().position(|drop_kind| *drop_kind == kind)
+}
+
+impl Pocket {
+  pub fn get_count(&self, kind: PieceKind) -> u8 {
+    pocket_index(kind).map_or(0, |index| self.counts[index])
+  }
+
+  /// Adds a piece to the pocket. Kings can't be held, and are ignored.
+  pub fn add(&mut self, kind: PieceKind) {
+    if let Some(index) = pocket_index(kind) {
+      self.counts[index] = self.counts[index].saturating_add(1);
+    }
+  }
+
+  /// Takes a piece out of the pocket, returning false if there wasn't one.
+  pub fn remove(&mut self, kind: PieceKind) -> bool {
+    match pocket_index(kind) {
+      Some(index) if self.counts[index] > 0 => {
+        self.counts[index] -= 1;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.counts.iter().all(|count| *count == 0)
+  }
+
+  /// The kinds held, each once, in FEN order.
+  pub fn get_kinds(&self) -> Vec<PieceKind> {
+    DROP_KINDS.iter().copied().filter(|kind| self.get_count(*kind) > 0).collect()
+  }
+}
+
+/// Reads the inside of a FEN pocket field, such as `QNnp`, into white's and black's pockets.
+pub(crate) fn parse_pockets(field: &str) -> Result<[Pocket; 2], Error> {
+  let mut pockets = [Pocket::default(); 2];
+  for piece_char in field.chars() {
+    let color = if piece_char.is_ascii_uppercase() { Color::White } else { Color::Black };
+    match PieceKind::from_short_name(&piece_char.to_string()) {
+      Some(kind) if kind != PieceKind::King => pockets[color.index()].add(kind),
+      _ => return Err(Error::InvalidFENString),
+    }
+  }
+  Ok(pockets)
+}
+
+/// Writes pockets as the inside of a FEN pocket field, white's pieces first.
+pub(crate) fn format_pockets(pockets: &[Pocket; 2]) -> String {
+  let mut field = String::new();
+  for color in [Color::White, Color::Black].iter() {
+    for kind in DROP_KINDS.iter() {
+      let name = kind.get_short_name();
+      let name = if *color == Color::White { name.to_uppercase() } else { name.to_lowercase() };
+      field += &name.repeat(pockets[color.index()].get_count(*kind) as usize);
+    }
+  }
+  field
+}
+
+impl Board {
+  /// Whether the board has pockets, which only boards of drop variants do.
+  pub fn has_pockets(&self) -> bool {
+    self.pockets.is_some()
+  }
+
+  pub fn get_pocket(&self, color: Color) -> Option<&Pocket> {
+    self.pockets.as_ref().map(|pockets| &pockets[color.index()])
+  }
+
+  pub fn get_pocket_mut(&mut self, color: Color) -> Option<&mut Pocket> {
+    self.pockets.as_mut().map(|pockets| &mut pockets[color.index()])
+  }
+
+  /// Gives the board empty pockets, if it has none yet.
+  pub fn enable_pockets(&mut self) {
+    self.pockets.get_or_insert_with(Default::default);
+  }
+
+  /// Whether the piece on the coordinate was promoted from a pawn. Captured promoted pieces go
+  /// into a pocket as pawns.
+  pub fn is_promoted(&self, coord: Coordinate) -> bool {
+    self.promoted.contains(&coord)
+  }
+
+  /// Returns every drop the side to move could make from its pocket: any held piece onto any
+  /// empty square, except pawns onto the first or last rank.
+  pub fn get_drop_moves(&self) -> Vec<Move> {
+    let pocket = match self.get_pocket(self.active_color) {
+      Some(pocket) if !pocket.is_empty() => *pocket,
+      _ => return vec![],
+    };
+
+    let mut moves = vec![];
+    for square in self.squares.iter().filter(|square| square.get_piece().is_none()) {
+      let coord = *square.get_coord();
+      for kind in pocket.get_kinds() {
+        if self.is_droppable(kind, coord) {
+          moves.push(Move::new_drop(kind, coord));
+        }
+      }
+    }
+    moves
+  }
+
+  /// Whether a piece of the kind may be dropped on the coordinate, pocket aside.
+  pub(crate) fn is_droppable(&self, kind: PieceKind, coord: Coordinate) -> bool {
+    let back_rank = coord.rank == Rank::One || coord.rank == Rank::from(self.height);
+    self.piece_at(coord).is_none() && !(kind == PieceKind::Pawn && back_rank)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_pocket_fen_round_trip() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/8/3pP3/5N2/PPP2PPP/RNBQKB1R[Pp] w KQkq - 0 4";
+    let board = Board::from_fen_string(fen).unwrap();
+    assert!(board.has_pockets());
+    assert_eq!(board.get_pocket(Color::White).unwrap().get_count(PieceKind::Pawn), 1);
+    assert_eq!(board.to_fen_string(), fen);
+
+    let promoted = Board::from_fen_string("4k3/8/8/8/8/8/8/Q~3K3[] b - - 0 1").unwrap();
+    assert!(promoted.is_promoted(Coordinate::get_coordinate("a1").unwrap()));
+    assert_eq!(promoted.to_fen_string(), "4k3/8/8/8/8/8/8/Q~3K3[] b - - 0 1");
+
+    assert!(!Board::new().has_pockets());
+    assert!(Board::from_fen_string("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1").is_err());
+    assert!(Board::from_fen_string("4k3/8/8/8/8/8/8/~4K3[] w - - 0 1").is_err());
+  }
+
+  #[test]
+  fn test_pawns_are_not_dropped_on_back_ranks() {
+    let board = Board::from_fen_string("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1").unwrap();
+    let drops = board.get_drop_moves();
+    assert_eq!(drops.len(), 48);
+    assert!(drops.iter().all(|mv| mv.to.rank != Rank::One));
+  }
+}
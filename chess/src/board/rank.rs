@@ -10,12 +10,14 @@ pub enum Rank {
   Six = 6,
   Seven = 7,
   Eight = 8,
-  Invalid = std::isize::MAX,
+  Nine = 9,
+  Ten = 10,
+  Invalid = i8::MAX as isize,
 }
 
 impl fmt::Display for Rank {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    match self.clone() {
+    match *self {
       Rank::One => write!(f, "1"),
       Rank::Two => write!(f, "2"),
       Rank::Three => write!(f, "3"),
@@ -24,14 +26,16 @@ impl fmt::Display for Rank {
       Rank::Six => write!(f, "6"),
       Rank::Seven => write!(f, "7"),
       Rank::Eight => write!(f, "8"),
+      Rank::Nine => write!(f, "9"),
+      Rank::Ten => write!(f, "10"),
       Rank::Invalid => write!(f, "!"),
     }
   }
 }
 
-impl Into<i8> for Rank {
-  fn into(self) -> i8 {
-    match self {
+impl From<Rank> for i8 {
+  fn from(x: Rank) -> i8 {
+    match x {
       Rank::One => 1,
       Rank::Two => 2,
       Rank::Three => 3,
@@ -40,6 +44,8 @@ impl Into<i8> for Rank {
       Rank::Six => 6,
       Rank::Seven => 7,
       Rank::Eight => 8,
+      Rank::Nine => 9,
+      Rank::Ten => 10,
       Rank::Invalid => panic!("Trying to put invalid rank into i8"),
     }
   }
@@ -56,14 +62,16 @@ impl From<i8> for Rank {
       6 => Rank::Six,
       7 => Rank::Seven,
       8 => Rank::Eight,
+      9 => Rank::Nine,
+      10 => Rank::Ten,
       _ => Rank::Invalid,
     }
   }
 }
 
-impl Into<&str> for Rank {
-  fn into(self) -> &'static str {
-    match self {
+impl From<Rank> for &str {
+  fn from(x: Rank) -> &'static str {
+    match x {
       Rank::One => "1",
       Rank::Two => "2",
       Rank::Three => "3",
@@ -72,6 +80,8 @@ impl Into<&str> for Rank {
       Rank::Six => "6",
       Rank::Seven => "7",
       Rank::Eight => "8",
+      Rank::Nine => "9",
+      Rank::Ten => "10",
       _ => "INVALID"
     }
   }
@@ -0,0 +1,208 @@
+use crate::board::{Board, MAX_BOARD_SIZE};
+use crate::board::coord::Coordinate;
+use crate::board::moves::Move;
+use crate::errors::Error;
+use crate::piece::{Piece, PieceKind};
+
+/// The letter a piece goes by in SAN: its own letter for a fairy piece, `P` for a pawn.
+fn san_letter(piece: &dyn Piece) -> char {
+  match piece.get_definition() {
+    Some(definition) => definition.get_letter(),
+    None => piece.get_kind().unwrap_or(PieceKind::Pawn).get_short_name().chars().next().unwrap(),
+  }
+}
+
+impl Board {
+  /// Parses a move in Standard Algebraic Notation, such as `Nf3`, `exd5`, `e8=Q+`, `O-O` or
+  /// the drop `N@f3`, for the side to move. Check and annotation suffixes are ignored.
+  ///
+  /// Returns [`Error::InvalidMoveString`](`crate::errors::Error::InvalidMoveString`) if the text
+  /// isn't SAN, and [`Error::InvalidMove`](`crate::errors::Error::InvalidMove`) if it doesn't
+  /// name exactly one legal move.
+  pub fn move_from_san(&self, san: &str) -> Result<Move, Error> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    if !san.is_ascii() || san.len() < 2 {
+      return Err(Error::InvalidMoveString);
+    }
+
+    let legal_moves = self.get_legal_moves();
+    if let Some((kind_str, to_str)) = san.split_once('@') {
+      // Pawn drops may leave out the `P`
+      let kind = match kind_str {
+        "" => PieceKind::Pawn,
+        _ => PieceKind::from_short_name(kind_str).filter(|_| kind_str.chars().all(|c| c.is_ascii_uppercase())).ok_or(Error::InvalidMoveString)?,
+      };
+      let to = Coordinate::get_coordinate(to_str).map_err(|_| Error::InvalidMoveString)?;
+      let drop = Move::new_drop(kind, to);
+      return if legal_moves.contains(&drop) { Ok(drop) } else { Err(Error::InvalidMove) };
+    }
+
+    let kingside = match san {
+      "O-O" | "0-0" => Some(true),
+      "O-O-O" | "0-0-0" => Some(false),
+      _ => None,
+    };
+    if let Some(kingside) = kingside {
+      // Chess960 castles are written as the king taking its rook, so go by direction rather than target
+      return legal_moves.into_iter()
+        .find(|mv| self.is_castle(mv) && (mv.to.file > mv.from.file) == kingside)
+        .ok_or(Error::InvalidMove);
+    }
+
+    // Any uppercase letter may name a piece, since fairy pieces bring their own
+    let (letter, rest) = match san.chars().next() {
+      Some(c) if c.is_ascii_uppercase() => (c, &san[1..]),
+      _ => ('P', san),
+    };
+
+    // Promotions are usually written `e8=Q`, but `e8Q` turns up too
+    let (rest, promotion) = match rest.char_indices().last() {
+      Some((i, c)) if c.is_ascii_uppercase() && letter == 'P' => {
+        let promotion = PieceKind::from_short_name(&rest[i..]).ok_or(Error::InvalidMoveString)?;
+        (rest[..i].trim_end_matches('='), Some(promotion))
+      }
+      _ => (rest, None),
+    };
+
+    // The destination starts at the last file letter, since ranks can take two digits
+    let rest: String = rest.chars().filter(|c| *c != 'x' && *c != '-').collect();
+    let to_start = rest.rfind(|c: char| c.is_ascii_lowercase()).ok_or(Error::InvalidMoveString)?;
+    let to = Coordinate::get_coordinate(&rest[to_start..]).map_err(|_| Error::InvalidMoveString)?;
+    let (from_file_str, from_rank_str) = rest[..to_start].split_at(rest[..to_start].find(|c: char| c.is_ascii_digit()).unwrap_or(to_start));
+    let from_file = match from_file_str.as_bytes() {
+      [] => None,
+      [file @ b'a'..=b'j'] => Some((file - b'a') as i8 + 1),
+      _ => return Err(Error::InvalidMoveString),
+    };
+    let from_rank = match from_rank_str {
+      "" => None,
+      rank => Some(rank.parse::<i8>().ok().filter(|rank| (1..=MAX_BOARD_SIZE).contains(rank)).ok_or(Error::InvalidMoveString)?),
+    };
+
+    let mut candidates = legal_moves.into_iter().filter(|mv| {
+      mv.to == to
+        && mv.promotion == promotion
+        && self.piece_at(mv.from).map(san_letter) == Some(letter)
+        && from_file.is_none_or(|file| Into::<i8>::into(mv.from.file) == file)
+        && from_rank.is_none_or(|rank| Into::<i8>::into(mv.from.rank) == rank)
+    });
+    match (candidates.next(), candidates.next()) {
+      (Some(mv), None) => Ok(mv),
+      _ => Err(Error::InvalidMove),
+    }
+  }
+
+  /// Writes a legal move in Standard Algebraic Notation, with `+` or `#` when it gives check
+  /// or mate.
+  pub fn move_to_san(&self, mv: &Move) -> String {
+    let letter = match (mv.drop, self.piece_at(mv.from)) {
+      (Some(_), _) => 'P',
+      (None, Some(piece)) => san_letter(piece),
+      (None, None) => return mv.to_string(),
+    };
+    let to = mv.to.to_string().to_lowercase();
+
+    let mut san = if mv.is_drop() {
+      mv.to_string()
+    } else if self.is_castle(mv) {
+      String::from(if mv.to.file > mv.from.file { "O-O" } else { "O-O-O" })
+    } else if letter == 'P' {
+      let mut san = String::new();
+      if self.is_capture(mv) {
+        san += &mv.from.file.to_string().to_lowercase();
+        san += "x";
+      }
+      san += &to;
+      if let Some(promotion) = mv.promotion {
+        san += "=";
+        san += promotion.get_short_name();
+      }
+      san
+    } else {
+      let mut san = String::from(letter);
+      // Name the origin file, then rank, then both, until no other piece of the kind fits
+      let rivals: Vec<Move> = self.get_legal_moves().into_iter()
+        .filter(|other| other.to == mv.to && other.from != mv.from)
+        .filter(|other| self.piece_at(other.from).map(san_letter) == Some(letter))
+        .collect();
+      if !rivals.is_empty() {
+        let from = mv.from.to_string().to_lowercase();
+        if rivals.iter().all(|other| other.from.file != mv.from.file) {
+          san += &from[..1];
+        } else if rivals.iter().all(|other| other.from.rank != mv.from.rank) {
+          san += &from[1..];
+        } else {
+          san += &from;
+        }
+      }
+      if self.is_capture(mv) {
+        san += "x";
+      }
+      san += &to;
+      san
+    };
+
+    let mut after = self.clone();
+    if after.make_move(*mv).is_ok() && after.is_in_check(&after.active_color) {
+      san += if after.get_legal_moves().is_empty() { "#" } else { "+" };
+    }
+    san
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use super::{*};
+  use crate::piece::betza::PieceDefinition;
+
+  #[test]
+  fn test_parse_san() {
+    let board = Board::from_fen_string("r3k2r/1P6/8/8/3pP3/1N3N2/8/R3K2R b KQkq e3 0 1").unwrap();
+    assert_eq!(board.move_from_san("dxe3"), Move::from_uci("d4e3"));
+    assert_eq!(board.move_from_san("O-O"), Move::from_uci("e8g8"));
+    assert_eq!(board.move_from_san("O-O-O"), Err(Error::InvalidMove));
+    assert_eq!(board.move_from_san("Rxa1+"), Move::from_uci("a8a1"));
+    assert_eq!(board.move_from_san("Qd7"), Err(Error::InvalidMove));
+    assert_eq!(board.move_from_san("Zz9"), Err(Error::InvalidMoveString));
+
+    let board = Board::from_fen_string("r3k2r/1P6/8/8/3pP3/1N3N2/8/R3K2R w KQkq - 0 1").unwrap();
+    assert_eq!(board.move_from_san("bxa8=N"), Move::from_uci("b7a8n"));
+    assert_eq!(board.move_from_san("b8Q"), Move::from_uci("b7b8q"));
+    assert_eq!(board.move_from_san("Nd4"), Err(Error::InvalidMove));
+    assert_eq!(board.move_from_san("Nbd4"), Move::from_uci("b3d4"));
+    assert_eq!(board.move_from_san("0-0!?"), Move::from_uci("e1g1"));
+  }
+
+  #[test]
+  fn test_write_san() {
+    let board = Board::from_fen_string("r3k2r/1P6/8/8/3pP3/1N3N2/8/R3K2R w KQkq - 0 1").unwrap();
+    let san = |uci: &str| board.move_to_san(&Move::from_uci(uci).unwrap());
+    assert_eq!(san("b3d4"), "Nbxd4");
+    assert_eq!(san("e1g1"), "O-O");
+    assert_eq!(san("b7a8q"), "bxa8=Q+");
+    assert_eq!(san("a1a8"), "Rxa8+");
+    assert_eq!(san("e4e5"), "e5");
+
+    let board = Board::from_fen_string("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+    assert_eq!(board.move_to_san(&Move::from_uci("a1a8").unwrap()), "Ra8#");
+    for mv in board.get_legal_moves() {
+      assert_eq!(board.move_from_san(&board.move_to_san(&mv)), Ok(mv));
+    }
+  }
+
+  #[test]
+  fn test_fairy_san() {
+    let archbishop = Arc::new(PieceDefinition::new('A', "BN").unwrap());
+    let chancellor = Arc::new(PieceDefinition::new('C', "RN").unwrap());
+    let board = Board::from_fen_string_with_pieces("rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1", &[archbishop, chancellor]).unwrap();
+    assert_eq!(board.move_to_san(&Move::from_uci("c1d3").unwrap()), "Ad3");
+    assert_eq!(board.move_to_san(&Move::from_uci("h1g3").unwrap()), "Cg3");
+    assert_eq!(board.move_from_san("Ad3"), Move::from_uci("c1d3"));
+    assert_eq!(board.move_from_san("d3"), Move::from_uci("d2d3"));
+    for mv in board.get_legal_moves() {
+      assert_eq!(board.move_from_san(&board.move_to_san(&mv)), Ok(mv));
+    }
+  }
+}
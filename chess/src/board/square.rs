@@ -11,7 +11,7 @@ pub enum SquareColor {
 
 impl fmt::Display for SquareColor {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    match self.clone() {
+    match *self {
       SquareColor::Light => write!(f, "L"),
       SquareColor::Dark => write!(f, "D"),
     }
@@ -25,6 +25,16 @@ pub struct Square {
   piece: Option<Box<dyn Piece>>,
 }
 
+impl Clone for Square {
+  fn clone(&self) -> Square {
+    Square {
+      color: self.color,
+      coord: self.coord,
+      piece: self.piece.as_ref().map(|piece| piece.clone_at(*piece.get_position())),
+    }
+  }
+}
+
 impl Square {
   /// Creates a [`Square`](`crate::board::Square`)
   /// with a given [`SquareColor`](`crate::board::SquareColor`).
@@ -44,4 +54,7 @@ impl Square {
   pub fn get_piece(&self) -> &Option<Box<dyn Piece>> { &self.piece }
 
   pub fn set_piece(&mut self, piece: Option<Box<dyn Piece>>) { self.piece = piece; }
+
+  /// Removes and returns the piece on this square, leaving it empty.
+  pub fn take_piece(&mut self) -> Option<Box<dyn Piece>> { self.piece.take() }
 }
\ No newline at end of file
@@ -0,0 +1,138 @@
+use crate::board::{Board, CastleAvailability, MAX_BOARD_SIZE};
+use crate::board::coord::Coordinate;
+use crate::board::pocket::DROP_KINDS;
+use crate::piece::{Color, PieceKind};
+
+/// Keys are laid out for the largest board, so a square keeps its key whatever the board's size.
+const SQUARE_KEYS: usize = MAX_BOARD_SIZE as usize * MAX_BOARD_SIZE as usize;
+const PIECE_KEYS: usize = 12 * SQUARE_KEYS;
+const CASTLING_OFFSET: usize = PIECE_KEYS;
+const EN_PASSANT_OFFSET: usize = CASTLING_OFFSET + 4;
+const SIDE_TO_MOVE: usize = EN_PASSANT_OFFSET + MAX_BOARD_SIZE as usize;
+/// Checks given by each side, for Three-check; counts past the last key share it.
+const CHECKS_OFFSET: usize = SIDE_TO_MOVE + 1;
+const CHECK_KEYS: usize = 3;
+/// Pocket counts for every color and droppable kind, again sharing the last key past the end.
+const POCKET_OFFSET: usize = CHECKS_OFFSET + 2 * CHECK_KEYS;
+const POCKET_KEYS: usize = 16;
+const PROMOTED_OFFSET: usize = POCKET_OFFSET + 2 * DROP_KINDS.len() * POCKET_KEYS;
+/// Fairy pieces, by letter and color.
+const FAIRY_OFFSET: usize = PROMOTED_OFFSET + SQUARE_KEYS;
+const KEY_COUNT: usize = FAIRY_OFFSET + 26 * 2 * SQUARE_KEYS;
+
+/// Random keys for every (piece, square), castling right, en passant file, the side to move,
+/// check count, pocket count, promoted square and fairy piece, generated at compile time so hashes are stable between runs.
+static KEYS: [u64; KEY_COUNT] = generate_keys();
+
+const fn generate_keys() -> [u64; KEY_COUNT] {
+  let mut keys = [0u64; KEY_COUNT];
+  let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+  let mut i = 0;
+  while i < KEY_COUNT {
+    // xorshift64*
+    state ^= state >> 12;
+    state ^= state << 25;
+    state ^= state >> 27;
+    keys[i] = state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    i += 1;
+  }
+  keys
+}
+
+fn square_key_index(coord: Coordinate) -> usize {
+  ((Into::<i8>::into(coord.rank) - 1) * MAX_BOARD_SIZE + Into::<i8>::into(coord.file) - 1) as usize
+}
+
+fn piece_key(kind: PieceKind, color: Color, coord: Coordinate) -> u64 {
+  let color_offset = if color == Color::White { 0 } else { 6 };
+  KEYS[(color_offset + kind.index()) * SQUARE_KEYS + square_key_index(coord)]
+}
+
+impl Board {
+  /// Returns the Zobrist hash of the position. Positions that differ only in their move
+  /// counters hash the same.
+  pub fn get_hash(&self) -> u64 {
+    let mut hash = 0;
+    for square in self.get_squares().iter() {
+      if let Some(piece) = square.get_piece() {
+        if let Some(kind) = piece.get_kind() {
+          hash ^= piece_key(kind, *piece.get_color(), *square.get_coord());
+        } else if let Some(definition) = piece.get_definition() {
+          let letter = (definition.get_letter() as u8 - b'A') as usize;
+          hash ^= KEYS[FAIRY_OFFSET + (letter * 2 + piece.get_color().index()) * SQUARE_KEYS + square_key_index(*square.get_coord())];
+        }
+      }
+    }
+
+    let castles = [
+      CastleAvailability::WhiteKingside,
+      CastleAvailability::WhiteQueenside,
+      CastleAvailability::BlackKingside,
+      CastleAvailability::BlackQueenside,
+    ];
+    for (i, castle) in castles.iter().enumerate() {
+      if self.castling_availability.contains(castle) {
+        hash ^= KEYS[CASTLING_OFFSET + i];
+      }
+    }
+
+    if let Some(target) = self.en_passant_target {
+      hash ^= KEYS[EN_PASSANT_OFFSET + (Into::<i8>::into(target.file) - 1) as usize];
+    }
+
+    if self.active_color == Color::Black {
+      hash ^= KEYS[SIDE_TO_MOVE];
+    }
+
+    for (i, color) in [Color::White, Color::Black].iter().enumerate() {
+      let checks = self.variant_state.get_checks(*color) as usize;
+      if checks > 0 {
+        hash ^= KEYS[CHECKS_OFFSET + i * CHECK_KEYS + checks.min(CHECK_KEYS) - 1];
+      }
+
+      for (j, kind) in DROP_KINDS.iter().enumerate() {
+        let count = self.get_pocket(*color).map_or(0, |pocket| pocket.get_count(*kind)) as usize;
+        if count > 0 {
+          hash ^= KEYS[POCKET_OFFSET + (i * DROP_KINDS.len() + j) * POCKET_KEYS + count.min(POCKET_KEYS) - 1];
+        }
+      }
+    }
+
+    // Only drop variants care which pieces were promoted
+    for coord in self.promoted.iter().filter(|_| self.has_pockets()) {
+      hash ^= KEYS[PROMOTED_OFFSET + square_key_index(*coord)];
+    }
+
+    hash
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::board::moves::Move;
+
+  #[test]
+  fn test_transpositions_hash_the_same() {
+    let mut first = Board::new();
+    let mut second = Board::new();
+    for mv in ["g1f3", "g8f6", "b1c3", "b8c6"].iter() {
+      first.make_move(Move::from_uci(mv).unwrap()).unwrap();
+    }
+    for mv in ["b1c3", "b8c6", "g1f3", "g8f6"].iter() {
+      second.make_move(Move::from_uci(mv).unwrap()).unwrap();
+    }
+    assert_eq!(first.get_hash(), second.get_hash());
+    assert_ne!(first.get_hash(), Board::new().get_hash());
+  }
+
+  #[test]
+  fn test_side_castling_and_en_passant_change_the_hash() {
+    let hash = |fen: &str| Board::from_fen_string(fen).unwrap().get_hash();
+    let base = hash("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq - 0 1");
+    assert_ne!(base, hash("r3k2r/8/8/3pP3/8/8/8/R3K2R b KQkq - 0 1"));
+    assert_ne!(base, hash("r3k2r/8/8/3pP3/8/8/8/R3K2R w Kkq - 0 1"));
+    assert_ne!(base, hash("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1"));
+    assert_eq!(base, hash("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq - 12 30"));
+  }
+}
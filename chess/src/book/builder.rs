@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::book::{encode_move, polyglot_key};
+use crate::book::native::{MoveStats, NativeBook, NativeEntry};
+use crate::game::GameResult;
+use crate::pgn::PgnGame;
+use crate::piece::Color;
+use crate::variant::{Standard, Variant};
+
+#[derive(Clone, Debug)]
+pub struct BookBuilderOptions {
+  /// How many plies of each game to record.
+  pub max_ply: usize,
+  /// Moves played in fewer games than this are left out of the book.
+  pub min_games: u32,
+}
+
+impl Default for BookBuilderOptions {
+  fn default() -> Self {
+    BookBuilderOptions { max_ply: 24, min_games: 3 }
+  }
+}
+
+/// Gathers move statistics from games to build an opening book.
+pub struct BookBuilder {
+  options: BookBuilderOptions,
+  /// Statistics by position key, then by Polyglot move.
+  positions: HashMap<u64, HashMap<u16, MoveStats>>,
+  games: usize,
+}
+
+fn parse_rating(game: &PgnGame, tag: &str) -> Option<u32> {
+  game.get_tag(tag).and_then(|rating| rating.parse().ok()).filter(|rating| *rating > 0)
+}
+
+impl BookBuilder {
+  pub fn new(options: BookBuilderOptions) -> BookBuilder {
+    BookBuilder { options, positions: HashMap::new(), games: 0 }
+  }
+
+  pub fn get_options(&self) -> &BookBuilderOptions {
+    &self.options
+  }
+
+  /// The number of games added so far.
+  pub fn get_games(&self) -> usize {
+    self.games
+  }
+
+  /// The number of positions with at least one recorded move, before filtering.
+  pub fn get_positions(&self) -> usize {
+    self.positions.len()
+  }
+
+  /// Records the opening moves of a game. Games without a result teach nothing about which
+  /// moves are good, and Polyglot books only describe chess on an 8x8 board, so such games are
+  /// skipped and `false` is returned.
+  pub fn add_game(&mut self, game: &PgnGame) -> bool {
+    let result = match game.get_result() {
+      Some(result) => result,
+      None => return false,
+    };
+    let start = game.get_start();
+    if start.get_variant().get_name() != Standard.get_name() || !start.is_standard_size() {
+      return false;
+    }
+    let white_rating = parse_rating(game, "WhiteElo");
+    let black_rating = parse_rating(game, "BlackElo");
+
+    let mut board = start.clone();
+    for mv in game.get_moves().iter().take(self.options.max_ply) {
+      let key = match polyglot_key(&board) {
+        Some(key) => key,
+        None => break,
+      };
+      let color = board.get_active_color();
+      let stats = self.positions.entry(key).or_default()
+        .entry(encode_move(&board, mv)).or_default();
+      stats.games += 1;
+      match result {
+        GameResult::Draw => stats.draws += 1,
+        won if won == GameResult::win_for(color) => stats.wins += 1,
+        _ => stats.losses += 1,
+      }
+      if let Some(rating) = if color == Color::White { white_rating } else { black_rating } {
+        stats.rating_total += rating as u64;
+        stats.rated_games += 1;
+      }
+
+      if board.make_move(*mv).is_err() {
+        break;
+      }
+    }
+
+    self.games += 1;
+    true
+  }
+
+  /// Builds a book of every move played at least [`min_games`](`BookBuilderOptions::min_games`) times.
+  pub fn build(&self) -> NativeBook {
+    let entries = self.positions.iter()
+      .flat_map(|(key, moves)| moves.iter().map(move |(raw_move, stats)| NativeEntry { key: *key, raw_move: *raw_move, stats: *stats }))
+      .filter(|entry| entry.stats.games >= self.options.min_games)
+      .collect();
+    NativeBook::from_entries(entries)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::board::Board;
+  use crate::board::moves::Move;
+  use crate::pgn::parse_pgn;
+
+  const GAMES: &str = r#"
+[WhiteElo "2500"] [BlackElo "2300"] 1. e4 e5 2. Nf3 Nc6 1-0
+[WhiteElo "2700"] [BlackElo "2100"] 1. e4 c5 2. Nf3 d6 1/2-1/2
+[WhiteElo "2300"] 1. e4 e5 2. Bc4 Nf6 0-1
+1. d4 d5 *
+"#;
+
+  #[test]
+  fn test_statistics_and_filtering() {
+    let mut builder = BookBuilder::new(BookBuilderOptions { max_ply: 2, min_games: 1 });
+    let added = parse_pgn(GAMES).filter(|game| builder.add_game(game.as_ref().unwrap())).count();
+    assert_eq!(added, 3);
+    assert_eq!(builder.get_games(), 3);
+    // The start position and the position after 1. e4
+    assert_eq!(builder.get_positions(), 2);
+
+    let book = builder.build();
+    let start = Board::new();
+    let e4 = &book.get_entries_for(polyglot_key(&start).unwrap())[0];
+    assert_eq!(e4.raw_move, encode_move(&start, &Move::from_uci("e2e4").unwrap()));
+    assert_eq!(e4.stats, MoveStats { games: 3, wins: 1, draws: 1, losses: 1, rating_total: 7500, rated_games: 3 });
+    assert_eq!(e4.stats.get_average_rating(), Some(2500));
+
+    let mut after_e4 = start.clone();
+    after_e4.make_move(Move::from_uci("e2e4").unwrap()).unwrap();
+    let replies = book.get_entries_for(polyglot_key(&after_e4).unwrap());
+    assert_eq!(replies.len(), 2);
+    assert_eq!(replies[0].stats, MoveStats { games: 2, wins: 1, draws: 0, losses: 1, rating_total: 2300, rated_games: 1 });
+
+    let filtered = BookBuilder { options: BookBuilderOptions { max_ply: 2, min_games: 2 }, ..builder }.build();
+    assert_eq!(filtered.get_entries().len(), 2);
+    let polyglot = filtered.to_polyglot();
+    assert_eq!(polyglot.best_move(&start), Move::from_uci("e2e4").ok());
+    assert_eq!(polyglot.best_move(&after_e4), Move::from_uci("e7e5").ok());
+  }
+
+  #[test]
+  fn test_variant_games_are_skipped() {
+    let games = r#"
+[Variant "Crazyhouse"] 1. e4 d5 2. exd5 Qxd5 3. P@e4 1-0
+[Variant "Gardner"] 1. a3 bxa3 0-1
+[FEN "4k5/10/10/10/10/10/10/10/10/4K5 w - - 0 1"] 1. Ke2 Ke9 1/2-1/2
+[Variant "Standard"] 1. e4 e5 1-0
+"#;
+    let mut builder = BookBuilder::new(BookBuilderOptions { max_ply: 4, min_games: 1 });
+    let added: Vec<bool> = parse_pgn(games).map(|game| builder.add_game(game.as_ref().unwrap())).collect();
+    assert_eq!(added, vec![false, false, false, true]);
+    assert_eq!(builder.get_games(), 1);
+    assert_eq!(builder.get_positions(), 2);
+  }
+}
@@ -0,0 +1,312 @@
+//! Opening books in the Polyglot `.bin` format.
+//!
+//! A book is a list of 16 byte entries sorted by position key. Each entry holds, big endian:
+//!
+//! | Bytes | Field                                              |
+//! |-------|----------------------------------------------------|
+//! | 8     | [Polyglot key](`crate::book::keys::polyglot_key`)  |
+//! | 2     | move                                               |
+//! | 2     | weight                                             |
+//! | 4     | learn (unused here, kept when rewriting)           |
+//!
+//! Moves pack the destination file and row into bits 0-5, the origin into bits 6-11 and the
+//! promotion piece (1 knight to 4 queen) into bits 12-14. Castling is written as the king
+//! capturing its own rook, so `e1h1` rather than `e1g1`.
+
+pub mod builder;
+pub mod keys;
+pub mod native;
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::board::Board;
+use crate::board::coord::Coordinate;
+use crate::board::moves::Move;
+use crate::errors::Error;
+use crate::piece::PieceKind;
+
+pub use keys::polyglot_key;
+
+const ENTRY_BYTES: usize = 16;
+const PROMOTIONS: [PieceKind; 4] = [PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BookEntry {
+  pub key: u64,
+  /// The move in Polyglot's packed form; see [`encode_move`](`crate::book::encode_move`).
+  pub raw_move: u16,
+  pub weight: u16,
+  pub learn: u32,
+}
+
+impl BookEntry {
+  pub fn from_bytes(bytes: &[u8; ENTRY_BYTES]) -> BookEntry {
+    BookEntry {
+      key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+      raw_move: u16::from_be_bytes([bytes[8], bytes[9]]),
+      weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+      learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+    }
+  }
+
+  pub fn to_bytes(&self) -> [u8; ENTRY_BYTES] {
+    let mut bytes = [0; ENTRY_BYTES];
+    bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+    bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+    bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+    bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+    bytes
+  }
+}
+
+/// A legal move found in the book, with how often it should be played relative to the others.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BookMove {
+  pub mv: Move,
+  pub weight: u16,
+}
+
+fn square_bits(coord: Coordinate) -> u16 {
+  ((Into::<i8>::into(coord.rank) - 1) * 8 + Into::<i8>::into(coord.file) - 1) as u16
+}
+
+fn bits_square(bits: u16) -> Coordinate {
+  Coordinate::make_coordinate((bits & 0b111) as i8, ((bits >> 3) & 0b111) as i8)
+}
+
+fn is_king_on(board: &Board, coord: Coordinate) -> bool {
+  board.get_square(coord).ok()
+    .and_then(|square| square.get_piece().as_ref())
+    .is_some_and(|piece| piece.get_kind() == Some(PieceKind::King))
+}
+
+/// Packs a move made from `board` the way Polyglot stores it.
+pub fn encode_move(board: &Board, mv: &Move) -> u16 {
+  let from_file: i8 = mv.from.file.into();
+  let to_file: i8 = mv.to.file.into();
+  let mut to = mv.to;
+  // Chess960 castles are already written as the king taking its rook
+  if is_king_on(board, mv.from) && (from_file - to_file).abs() == 2 && !board.is_chess960() {
+    let rook_file = if to_file > from_file { 7 } else { 0 };
+    to = Coordinate::make_coordinate(rook_file, Into::<i8>::into(mv.from.rank) - 1);
+  }
+
+  let promotion = mv.promotion.and_then(|kind| PROMOTIONS.iter().position(|p| *p == kind)).map(|i| i as u16 + 1).unwrap_or(0);
+  promotion << 12 | square_bits(mv.from) << 6 | square_bits(to)
+}
+
+/// Unpacks a Polyglot move for `board`, turning king-takes-rook castling back into a two
+/// square king move unless the board writes castling the Chess960 way.
+pub fn decode_move(board: &Board, raw_move: u16) -> Move {
+  let from = bits_square(raw_move >> 6);
+  let mut to = bits_square(raw_move);
+  let promotion = match (raw_move >> 12) & 0b111 {
+    i @ 1..=4 => Some(PROMOTIONS[i as usize - 1]),
+    _ => None,
+  };
+
+  let from_file: i8 = from.file.into();
+  let rank: i8 = from.rank.into();
+  let back_rank = rank == 1 || rank == 8;
+  if back_rank && from_file == 5 && to.rank == from.rank && is_king_on(board, from) && !board.is_chess960() {
+    match Into::<i8>::into(to.file) {
+      8 => to = Coordinate::make_coordinate(6, rank - 1),
+      1 => to = Coordinate::make_coordinate(2, rank - 1),
+      _ => {}
+    }
+  }
+
+  Move { from, to, promotion, drop: None }
+}
+
+/// A Polyglot opening book held in memory.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PolyglotBook {
+  entries: Vec<BookEntry>,
+}
+
+impl PolyglotBook {
+  /// Creates a book from entries in any order.
+  pub fn from_entries(mut entries: Vec<BookEntry>) -> PolyglotBook {
+    entries.sort_by_key(|entry| entry.key);
+    PolyglotBook { entries }
+  }
+
+  /// Parses a `.bin` book. The entries must already be sorted by key, as Polyglot writes them.
+  pub fn from_bytes(bytes: &[u8]) -> Result<PolyglotBook, Error> {
+    if !bytes.len().is_multiple_of(ENTRY_BYTES) {
+      return Err(Error::InvalidBook);
+    }
+
+    let entries: Vec<BookEntry> = bytes.chunks_exact(ENTRY_BYTES)
+      .map(|chunk| BookEntry::from_bytes(chunk.try_into().unwrap()))
+      .collect();
+    if entries.windows(2).any(|pair| pair[0].key > pair[1].key) {
+      return Err(Error::InvalidBook);
+    }
+
+    Ok(PolyglotBook { entries })
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    self.entries.iter().flat_map(|entry| entry.to_bytes()).collect()
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<PolyglotBook, Error> {
+    let bytes = fs::read(path).map_err(|_| Error::IoError)?;
+    PolyglotBook::from_bytes(&bytes)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+    fs::write(path, self.to_bytes()).map_err(|_| Error::IoError)
+  }
+
+  pub fn get_entries(&self) -> &[BookEntry] {
+    &self.entries
+  }
+
+  /// Returns every entry stored for a position key, found by binary search.
+  pub fn get_entries_for(&self, key: u64) -> &[BookEntry] {
+    let start = self.entries.partition_point(|entry| entry.key < key);
+    let end = start + self.entries[start..].partition_point(|entry| entry.key == key);
+    &self.entries[start..end]
+  }
+
+  /// Returns the legal book moves for a position, in the order the book stores them.
+  /// Entries for moves that aren't legal, say from a hash collision, are skipped.
+  pub fn get_moves(&self, board: &Board) -> Vec<BookMove> {
+    // Polyglot keys only describe 8x8 boards
+    let key = match polyglot_key(board) {
+      Some(key) => key,
+      None => return vec![],
+    };
+    self.get_entries_for(key).iter()
+      .map(|entry| BookMove { mv: decode_move(board, entry.raw_move), weight: entry.weight })
+      .filter(|book_move| board.is_legal_move(&book_move.mv))
+      .collect()
+  }
+
+  /// Returns the book move with the highest weight, taking the first stored on ties.
+  pub fn best_move(&self, board: &Board) -> Option<Move> {
+    self.get_moves(board).iter()
+      .fold(None, |best: Option<BookMove>, book_move| match best {
+        Some(best) if best.weight >= book_move.weight => Some(best),
+        _ => Some(*book_move),
+      })
+      .map(|book_move| book_move.mv)
+  }
+
+  /// Picks a book move at random, in proportion to the weights. `random` can be any number,
+  /// such as the output of a random number generator. Moves with a weight of zero are never
+  /// picked.
+  pub fn weighted_move(&self, board: &Board, random: u64) -> Option<Move> {
+    let moves = self.get_moves(board);
+    let total: u64 = moves.iter().map(|book_move| book_move.weight as u64).sum();
+    if total == 0 {
+      return None;
+    }
+
+    let mut roll = random % total;
+    for book_move in moves.iter() {
+      if roll < book_move.weight as u64 {
+        return Some(book_move.mv);
+      }
+      roll -= book_move.weight as u64;
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  /// Builds a book holding the given `(moves played, book move, weight)` triples.
+  fn make_book(lines: &[(&str, &str, u16)]) -> PolyglotBook {
+    let entries = lines.iter().map(|(moves, book_move, weight)| {
+      let mut board = Board::new();
+      for mv in moves.split_whitespace() {
+        board.make_move(Move::from_uci(mv).unwrap()).unwrap();
+      }
+      BookEntry {
+        key: polyglot_key(&board).unwrap(),
+        raw_move: encode_move(&board, &Move::from_uci(book_move).unwrap()),
+        weight: *weight,
+        learn: 0,
+      }
+    }).collect();
+    PolyglotBook::from_entries(entries)
+  }
+
+  #[test]
+  fn test_round_trip_through_bytes() {
+    let book = make_book(&[("", "e2e4", 10), ("", "d2d4", 5), ("e2e4", "c7c5", 3)]);
+    let bytes = book.to_bytes();
+    assert_eq!(bytes.len(), 3 * ENTRY_BYTES);
+    assert_eq!(PolyglotBook::from_bytes(&bytes), Ok(book));
+
+    assert_eq!(PolyglotBook::from_bytes(&bytes[1..]), Err(Error::InvalidBook));
+    let entry = |key: u64| BookEntry { key, raw_move: 0, weight: 1, learn: 0 }.to_bytes();
+    let unsorted: Vec<u8> = entry(2).iter().chain(entry(1).iter()).copied().collect();
+    assert_eq!(PolyglotBook::from_bytes(&unsorted), Err(Error::InvalidBook));
+  }
+
+  #[test]
+  fn test_lookup_and_selection() {
+    let book = make_book(&[("", "e2e4", 10), ("e2e4", "c7c5", 3), ("", "d2d4", 30), ("", "g1f3", 0), ("e2e4", "e7e5", 3)]);
+    let start = Board::new();
+    assert_eq!(book.get_entries_for(polyglot_key(&start).unwrap()).len(), 3);
+    assert_eq!(book.best_move(&start), Move::from_uci("d2d4").ok());
+
+    let mut board = start.clone();
+    board.make_move(Move::from_uci("e2e4").unwrap()).unwrap();
+    let replies: Vec<String> = book.get_moves(&board).iter().map(|book_move| book_move.mv.to_string()).collect();
+    assert_eq!(replies.len(), 2);
+    assert!(replies.contains(&"c7c5".to_string()) && replies.contains(&"e7e5".to_string()));
+    assert_eq!(book.best_move(&board).map(|mv| mv.to_string()), Some(replies[0].clone()));
+
+    board.make_move(Move::from_uci("e7e5").unwrap()).unwrap();
+    assert_eq!(book.best_move(&board), None);
+    assert_eq!(book.weighted_move(&board, 7), None);
+  }
+
+  #[test]
+  fn test_weighted_selection_follows_the_weights() {
+    let book = make_book(&[("", "e2e4", 1), ("", "d2d4", 3), ("", "g1f3", 0)]);
+    let start = Board::new();
+    let picks: Vec<String> = (0..8).map(|random| book.weighted_move(&start, random).unwrap().to_string()).collect();
+    assert_eq!(picks.iter().filter(|mv| *mv == "e2e4").count(), 2);
+    assert_eq!(picks.iter().filter(|mv| *mv == "d2d4").count(), 6);
+  }
+
+  #[test]
+  fn test_castling_and_promotion_encoding() {
+    let board = Board::from_fen_string("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let castle = Move::from_uci("e1g1").unwrap();
+    let raw = encode_move(&board, &castle);
+    assert_eq!(raw, square_bits(Coordinate::get_coordinate("e1").unwrap()) << 6 | square_bits(Coordinate::get_coordinate("h1").unwrap()));
+    assert_eq!(decode_move(&board, raw), castle);
+
+    let long_castle = Move::from_uci("e1c1").unwrap();
+    assert_eq!(decode_move(&board, encode_move(&board, &long_castle)), long_castle);
+
+    // A rook moving along the back rank is left alone
+    let rook_move = Move::from_uci("a1c1").unwrap();
+    assert_eq!(decode_move(&board, encode_move(&board, &rook_move)), rook_move);
+
+    let promotion = Move::from_uci("b7a8n").unwrap();
+    let raw = encode_move(&board, &promotion);
+    assert_eq!(raw >> 12, 1);
+    assert_eq!(decode_move(&board, raw), promotion);
+
+    // With the rook on g1, the Chess960 castle e1g1 takes the rook where it stands
+    let mut board = Board::from_fen_string("4k3/8/8/8/8/8/8/4K1R1 w K - 0 1").unwrap();
+    board.set_chess960(true);
+    let castle = Move::from_uci("e1g1").unwrap();
+    let raw = encode_move(&board, &castle);
+    assert_eq!(raw, square_bits(Coordinate::get_coordinate("e1").unwrap()) << 6 | square_bits(Coordinate::get_coordinate("g1").unwrap()));
+    assert_eq!(decode_move(&board, raw), castle);
+  }
+}
@@ -0,0 +1,200 @@
+//! The crate's own book format, which keeps the full statistics behind each book move.
+//!
+//! All values are little endian:
+//!
+//! | Field   | Type  | Notes                                         |
+//! |---------|-------|-----------------------------------------------|
+//! | magic   | 4     | `RCBK`                                        |
+//! | version | u32   | currently 1                                   |
+//! | count   | u32   | number of entries                             |
+//! | entries | 38 \* count | sorted by key, see below                |
+//!
+//! Each entry is the Polyglot key (u64) and move (u16) followed by the
+//! [`MoveStats`](`crate::book::native::MoveStats`) fields in order: games, wins, draws and
+//! losses (u32), the rating total (u64) and the number of rated games (u32).
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::book::{BookEntry, PolyglotBook};
+use crate::errors::Error;
+
+const MAGIC: &[u8; 4] = b"RCBK";
+const VERSION: u32 = 1;
+const HEADER_BYTES: usize = 12;
+const ENTRY_BYTES: usize = 38;
+
+/// How a move has fared in the games a book was built from. Wins and losses are from the
+/// point of view of the side making the move.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MoveStats {
+  pub games: u32,
+  pub wins: u32,
+  pub draws: u32,
+  pub losses: u32,
+  /// The sum of the mover's rating over the games where it was known.
+  pub rating_total: u64,
+  pub rated_games: u32,
+}
+
+impl MoveStats {
+  /// The mover's average rating, if any of the games were rated.
+  pub fn get_average_rating(&self) -> Option<u32> {
+    if self.rated_games == 0 {
+      return None;
+    }
+    Some((self.rating_total / self.rated_games as u64) as u32)
+  }
+
+  /// The mover's score as a fraction between 0 and 1, counting draws as half a point.
+  pub fn get_score(&self) -> f64 {
+    if self.games == 0 {
+      return 0.0;
+    }
+    (self.wins as f64 + self.draws as f64 / 2.0) / self.games as f64
+  }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NativeEntry {
+  pub key: u64,
+  pub raw_move: u16,
+  pub stats: MoveStats,
+}
+
+/// A book in the native format.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NativeBook {
+  entries: Vec<NativeEntry>,
+}
+
+impl NativeBook {
+  /// Creates a book from entries in any order. Moves from the same position are kept with the
+  /// most played first.
+  pub fn from_entries(mut entries: Vec<NativeEntry>) -> NativeBook {
+    entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.stats.games.cmp(&a.stats.games)).then(a.raw_move.cmp(&b.raw_move)));
+    NativeBook { entries }
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Result<NativeBook, Error> {
+    if bytes.len() < HEADER_BYTES || &bytes[0..4] != MAGIC {
+      return Err(Error::InvalidBook);
+    }
+    let read_u32 = |slice: &[u8]| u32::from_le_bytes(slice.try_into().unwrap());
+    let count = read_u32(&bytes[8..12]) as usize;
+    if read_u32(&bytes[4..8]) != VERSION || bytes.len() != HEADER_BYTES + count * ENTRY_BYTES {
+      return Err(Error::InvalidBook);
+    }
+
+    let entries: Vec<NativeEntry> = bytes[HEADER_BYTES..].chunks_exact(ENTRY_BYTES).map(|chunk| NativeEntry {
+      key: u64::from_le_bytes(chunk[0..8].try_into().unwrap()),
+      raw_move: u16::from_le_bytes([chunk[8], chunk[9]]),
+      stats: MoveStats {
+        games: read_u32(&chunk[10..14]),
+        wins: read_u32(&chunk[14..18]),
+        draws: read_u32(&chunk[18..22]),
+        losses: read_u32(&chunk[22..26]),
+        rating_total: u64::from_le_bytes(chunk[26..34].try_into().unwrap()),
+        rated_games: read_u32(&chunk[34..38]),
+      },
+    }).collect();
+    if entries.windows(2).any(|pair| pair[0].key > pair[1].key) {
+      return Err(Error::InvalidBook);
+    }
+
+    Ok(NativeBook { entries })
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_BYTES + self.entries.len() * ENTRY_BYTES);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+    for entry in self.entries.iter() {
+      let stats = &entry.stats;
+      bytes.extend_from_slice(&entry.key.to_le_bytes());
+      bytes.extend_from_slice(&entry.raw_move.to_le_bytes());
+      for value in [stats.games, stats.wins, stats.draws, stats.losses].iter() {
+        bytes.extend_from_slice(&value.to_le_bytes());
+      }
+      bytes.extend_from_slice(&stats.rating_total.to_le_bytes());
+      bytes.extend_from_slice(&stats.rated_games.to_le_bytes());
+    }
+    bytes
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<NativeBook, Error> {
+    let bytes = fs::read(path).map_err(|_| Error::IoError)?;
+    NativeBook::from_bytes(&bytes)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+    fs::write(path, self.to_bytes()).map_err(|_| Error::IoError)
+  }
+
+  pub fn get_entries(&self) -> &[NativeEntry] {
+    &self.entries
+  }
+
+  /// Returns every entry stored for a position key, found by binary search.
+  pub fn get_entries_for(&self, key: u64) -> &[NativeEntry] {
+    let start = self.entries.partition_point(|entry| entry.key < key);
+    let end = start + self.entries[start..].partition_point(|entry| entry.key == key);
+    &self.entries[start..end]
+  }
+
+  /// Converts the book to Polyglot, weighting each move by two points per win and one per
+  /// draw as Polyglot's own book maker does. Moves that never scored are left out, and
+  /// weights are scaled down per position when they would overflow.
+  pub fn to_polyglot(&self) -> PolyglotBook {
+    let mut entries = vec![];
+    let mut start = 0;
+    while start < self.entries.len() {
+      let key = self.entries[start].key;
+      let end = start + self.entries[start..].partition_point(|entry| entry.key == key);
+      let position = &self.entries[start..end];
+      let points = |stats: &MoveStats| 2 * stats.wins as u64 + stats.draws as u64;
+      let most = position.iter().map(|entry| points(&entry.stats)).max().unwrap_or(0);
+
+      for entry in position.iter() {
+        let weight = if most > u16::MAX as u64 { points(&entry.stats) * u16::MAX as u64 / most } else { points(&entry.stats) };
+        if weight > 0 {
+          entries.push(BookEntry { key, raw_move: entry.raw_move, weight: weight as u16, learn: 0 });
+        }
+      }
+      start = end;
+    }
+    PolyglotBook::from_entries(entries)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  fn entry(key: u64, raw_move: u16, games: u32, wins: u32, draws: u32) -> NativeEntry {
+    let stats = MoveStats { games, wins, draws, losses: games - wins - draws, rating_total: 2400 * games as u64, rated_games: games };
+    NativeEntry { key, raw_move, stats }
+  }
+
+  #[test]
+  fn test_round_trip_through_bytes() {
+    let book = NativeBook::from_entries(vec![entry(9, 1, 3, 1, 1), entry(4, 2, 10, 5, 5), entry(9, 3, 8, 0, 0)]);
+    assert_eq!(book.get_entries_for(9).iter().map(|entry| entry.raw_move).collect::<Vec<_>>(), [3, 1]);
+    assert_eq!(book.get_entries_for(9)[1].stats.get_average_rating(), Some(2400));
+
+    let bytes = book.to_bytes();
+    assert_eq!(NativeBook::from_bytes(&bytes), Ok(book));
+    assert_eq!(NativeBook::from_bytes(&bytes[..bytes.len() - 1]), Err(Error::InvalidBook));
+    assert_eq!(NativeBook::from_bytes(b"nope"), Err(Error::InvalidBook));
+  }
+
+  #[test]
+  fn test_polyglot_weights() {
+    let book = NativeBook::from_entries(vec![entry(1, 1, 4, 2, 1), entry(1, 2, 5, 0, 0), entry(2, 3, 60000, 60000, 0), entry(2, 4, 100, 0, 100)]);
+    let polyglot = book.to_polyglot();
+    let weights: Vec<(u16, u16)> = polyglot.get_entries().iter().map(|entry| (entry.raw_move, entry.weight)).collect();
+    assert_eq!(weights, [(1, 5), (3, u16::MAX), (4, 54)]);
+  }
+}
@@ -0,0 +1,406 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod source;
+pub use crate::clock::source::{MockTimeSource, SystemTimeSource, TimeSource};
+
+use crate::board::Board;
+use crate::errors::Error;
+use crate::game::GameResult;
+use crate::piece::Color;
+
+/// Time given back to a player for each move they complete.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bonus {
+  None,
+  /// Fischer: the increment is added after every move, even if the move took less time.
+  Increment(Duration),
+  /// Bronstein: the time used is given back after the move, up to the delay.
+  Bronstein(Duration),
+  /// Simple (US) delay: the clock waits this long each move before it starts counting down.
+  SimpleDelay(Duration),
+}
+
+/// One period of a time control, e.g. "40 moves in 90 minutes, plus 30 seconds a move".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stage {
+  /// Moves to complete in this stage, or `None` for the rest of the game.
+  pub moves: Option<u32>,
+  pub time: Duration,
+  pub bonus: Bonus,
+}
+
+/// How much time each player gets.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeControl {
+  /// Stages played in order. Time left over carries into the next stage. A last stage with a
+  /// move count repeats, as in "40 moves in 2 hours, repeating".
+  Stages(Vec<Stage>),
+  /// Time spent by one player is added to the other's clock; both start with this much.
+  Hourglass(Duration),
+}
+
+impl TimeControl {
+  pub fn sudden_death(time: Duration) -> TimeControl {
+    TimeControl::single(time, Bonus::None)
+  }
+
+  pub fn fischer(time: Duration, increment: Duration) -> TimeControl {
+    TimeControl::single(time, Bonus::Increment(increment))
+  }
+
+  pub fn bronstein(time: Duration, delay: Duration) -> TimeControl {
+    TimeControl::single(time, Bonus::Bronstein(delay))
+  }
+
+  pub fn simple_delay(time: Duration, delay: Duration) -> TimeControl {
+    TimeControl::single(time, Bonus::SimpleDelay(delay))
+  }
+
+  pub fn hourglass(time: Duration) -> TimeControl {
+    TimeControl::Hourglass(time)
+  }
+
+  fn single(time: Duration, bonus: Bonus) -> TimeControl {
+    TimeControl::Stages(vec![Stage { moves: None, time, bonus }])
+  }
+
+  /// Parses a PGN `TimeControl` tag, with times in seconds: `300`, `180+2`, `40/5400+30:1800+30`
+  /// for "40/90+30, then 30+30", or `*60` for an hourglass.
+  pub fn from_pgn_tag(tag: &str) -> Result<TimeControl, Error> {
+    let seconds = |value: &str| value.trim().parse::<u64>().map(Duration::from_secs).map_err(|_| Error::InvalidTimeControl);
+    if let Some(time) = tag.trim().strip_prefix('*') {
+      return Ok(TimeControl::Hourglass(seconds(time)?));
+    }
+
+    let mut stages = vec![];
+    for period in tag.split(':') {
+      let (moves, rest) = match period.split_once('/') {
+        Some((moves, rest)) => (Some(moves.trim().parse::<u32>().map_err(|_| Error::InvalidTimeControl)?), rest),
+        None => (None, period),
+      };
+      let (time, bonus) = match rest.split_once('+') {
+        Some((time, increment)) => (seconds(time)?, Bonus::Increment(seconds(increment)?)),
+        None => (seconds(rest)?, Bonus::None),
+      };
+      if moves == Some(0) {
+        return Err(Error::InvalidTimeControl);
+      }
+      stages.push(Stage { moves, time, bonus });
+    }
+    Ok(TimeControl::Stages(stages))
+  }
+
+  fn initial_time(&self) -> Duration {
+    match self {
+      TimeControl::Stages(stages) => stages.first().map_or(Duration::ZERO, |stage| stage.time),
+      TimeControl::Hourglass(time) => *time,
+    }
+  }
+}
+
+fn side(color: Color) -> usize {
+  if color == Color::White { 0 } else { 1 }
+}
+
+/// A two-sided game clock. Only the side whose turn it is has their time running; pressing the
+/// clock ends that turn, applies the time control's bonus and starts the opponent's time.
+pub struct ChessClock {
+  control: TimeControl,
+  source: Arc<dyn TimeSource>,
+  /// Time left at the start of the current turn; the running side's live time is lower.
+  remaining: [Duration; 2],
+  moves_made: [u32; 2],
+  /// Index into the stages, and the move count at which each player entered it.
+  stage: [(usize, u32); 2],
+  running: Option<Color>,
+  turn_started: Duration,
+}
+
+impl ChessClock {
+  pub fn new(control: TimeControl, source: Arc<dyn TimeSource>) -> ChessClock {
+    let initial = control.initial_time();
+    ChessClock {
+      control,
+      source,
+      remaining: [initial, initial],
+      moves_made: [0, 0],
+      stage: [(0, 0), (0, 0)],
+      running: None,
+      turn_started: Duration::ZERO,
+    }
+  }
+
+  pub fn get_time_control(&self) -> &TimeControl {
+    &self.control
+  }
+
+  /// Starts (or restarts after a pause) the given side's time.
+  pub fn start(&mut self, color: Color) {
+    self.stop();
+    self.running = Some(color);
+    self.turn_started = self.source.now();
+  }
+
+  /// Stops both sides' time, keeping what has been used so far this turn.
+  pub fn stop(&mut self) {
+    if let Some(color) = self.running.take() {
+      self.charge(color, self.elapsed());
+    }
+  }
+
+  pub fn get_running(&self) -> Option<Color> {
+    self.running
+  }
+
+  pub fn get_moves_made(&self, color: Color) -> u32 {
+    self.moves_made[side(color)]
+  }
+
+  /// Time the color gets back for each move in their current stage: the increment, or the
+  /// delay, which any move taking at least that long saves in full.
+  pub fn get_increment(&self, color: Color) -> Duration {
+    match self.current_stage(color).map(|stage| stage.bonus) {
+      Some(Bonus::Increment(time)) | Some(Bonus::Bronstein(time)) | Some(Bonus::SimpleDelay(time)) => time,
+      _ => Duration::ZERO,
+    }
+  }
+
+  /// Moves the color has left to make before their next stage's time is added, or `None` if
+  /// their current stage lasts the rest of the game.
+  pub fn get_moves_to_go(&self, color: Color) -> Option<u32> {
+    let (_, entered_at) = self.stage[side(color)];
+    let moves = self.current_stage(color)?.moves?;
+    Some(moves - (self.moves_made[side(color)] - entered_at))
+  }
+
+  fn elapsed(&self) -> Duration {
+    self.source.now().saturating_sub(self.turn_started)
+  }
+
+  fn current_stage(&self, color: Color) -> Option<&Stage> {
+    match &self.control {
+      TimeControl::Stages(stages) => stages.get(self.stage[side(color)].0),
+      TimeControl::Hourglass(_) => None,
+    }
+  }
+
+  /// Time actually taken off the clock for a turn lasting `elapsed`.
+  fn charged_time(&self, color: Color, elapsed: Duration) -> Duration {
+    match self.current_stage(color).map(|stage| stage.bonus) {
+      Some(Bonus::SimpleDelay(delay)) => elapsed.saturating_sub(delay),
+      _ => elapsed,
+    }
+  }
+
+  fn charge(&mut self, color: Color, elapsed: Duration) {
+    let charged = self.charged_time(color, elapsed);
+    self.remaining[side(color)] = self.remaining[side(color)].saturating_sub(charged);
+    if let TimeControl::Hourglass(_) = self.control {
+      self.remaining[1 - side(color)] += charged;
+    }
+  }
+
+  /// Time left on the color's clock right now, counting the turn in progress.
+  pub fn get_remaining(&self, color: Color) -> Duration {
+    let stored = self.remaining[side(color)];
+    match self.running {
+      Some(running) if running == color => stored.saturating_sub(self.charged_time(color, self.elapsed())),
+      Some(running) if matches!(self.control, TimeControl::Hourglass(_)) => {
+        stored + self.charged_time(running, self.elapsed())
+      }
+      _ => stored,
+    }
+  }
+
+  /// Returns the side whose flag has fallen, if any. The flag stays down if the clock is stopped.
+  pub fn get_flagged(&self) -> Option<Color> {
+    [Color::White, Color::Black].iter().copied().find(|color| self.get_remaining(*color) == Duration::ZERO)
+  }
+
+  /// Ends the running side's turn and starts the opponent's time. Has no effect once a flag
+  /// has fallen, or while the clock is stopped.
+  pub fn press(&mut self) {
+    let color = match self.running {
+      Some(color) if self.get_flagged().is_none() => color,
+      _ => return,
+    };
+
+    let elapsed = self.elapsed();
+    self.running = None;
+    self.charge(color, elapsed);
+    let index = side(color);
+    match self.current_stage(color).map(|stage| stage.bonus) {
+      Some(Bonus::Increment(increment)) => self.remaining[index] += increment,
+      Some(Bonus::Bronstein(delay)) => self.remaining[index] += elapsed.min(delay),
+      _ => {}
+    }
+
+    self.moves_made[index] += 1;
+    self.advance_stage(color);
+    self.start(color.opposite());
+  }
+
+  /// Moves the color on to the next stage once they've made the current stage's moves,
+  /// adding that stage's time.
+  fn advance_stage(&mut self, color: Color) {
+    let index = side(color);
+    let stages = match &self.control {
+      TimeControl::Stages(stages) => stages,
+      TimeControl::Hourglass(_) => return,
+    };
+    let (stage_index, entered_at) = self.stage[index];
+    let stage_moves = match stages[stage_index].moves {
+      Some(moves) => moves,
+      None => return,
+    };
+    if self.moves_made[index] - entered_at < stage_moves {
+      return;
+    }
+
+    let next = (stage_index + 1).min(stages.len() - 1);
+    self.remaining[index] += stages[next].time;
+    self.stage[index] = (next, self.moves_made[index]);
+  }
+
+  /// The result once a flag has fallen: a loss for the flagged side, unless their opponent
+  /// couldn't possibly checkmate them, in which case it's a draw.
+  pub fn get_result(&self, board: &Board) -> Option<GameResult> {
+    let flagged = self.get_flagged()?;
+    if !board.has_mating_material(&flagged.opposite()) {
+      return Some(GameResult::Draw);
+    }
+    Some(GameResult::win_for(flagged.opposite()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  fn secs(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+  }
+
+  fn clock(control: TimeControl) -> (ChessClock, Arc<MockTimeSource>) {
+    let source = Arc::new(MockTimeSource::default());
+    let mut clock = ChessClock::new(control, source.clone());
+    clock.start(Color::White);
+    (clock, source)
+  }
+
+  /// Lets `seconds` pass and then presses the clock.
+  fn play(clock: &mut ChessClock, source: &MockTimeSource, seconds: u64) {
+    source.advance(secs(seconds));
+    clock.press();
+  }
+
+  #[test]
+  fn test_sudden_death() {
+    let (mut clock, source) = clock(TimeControl::sudden_death(secs(60)));
+    play(&mut clock, &source, 10);
+    assert_eq!(clock.get_remaining(Color::White), secs(50));
+    assert_eq!(clock.get_running(), Some(Color::Black));
+
+    source.advance(secs(20));
+    assert_eq!(clock.get_remaining(Color::Black), secs(40));
+    assert_eq!(clock.get_remaining(Color::White), secs(50));
+  }
+
+  #[test]
+  fn test_fischer_increment() {
+    let (mut clock, source) = clock(TimeControl::fischer(secs(60), secs(5)));
+    play(&mut clock, &source, 2);
+    assert_eq!(clock.get_remaining(Color::White), secs(63));
+    assert_eq!(clock.get_increment(Color::White), secs(5));
+    assert_eq!(clock.get_moves_to_go(Color::White), None);
+  }
+
+  #[test]
+  fn test_bronstein_delay_gives_back_at_most_the_delay() {
+    let (mut clock, source) = clock(TimeControl::bronstein(secs(60), secs(5)));
+    play(&mut clock, &source, 2);
+    assert_eq!(clock.get_remaining(Color::White), secs(60));
+    play(&mut clock, &source, 8);
+    assert_eq!(clock.get_remaining(Color::Black), secs(57));
+  }
+
+  #[test]
+  fn test_simple_delay_waits_before_counting_down() {
+    let (mut clock, source) = clock(TimeControl::simple_delay(secs(60), secs(5)));
+    source.advance(secs(3));
+    assert_eq!(clock.get_remaining(Color::White), secs(60));
+    play(&mut clock, &source, 4);
+    assert_eq!(clock.get_remaining(Color::White), secs(58));
+  }
+
+  #[test]
+  fn test_hourglass_moves_time_between_players() {
+    let (mut clock, source) = clock(TimeControl::hourglass(secs(60)));
+    source.advance(secs(10));
+    assert_eq!(clock.get_remaining(Color::White), secs(50));
+    assert_eq!(clock.get_remaining(Color::Black), secs(70));
+    clock.press();
+    play(&mut clock, &source, 4);
+    assert_eq!(clock.get_remaining(Color::White), secs(54));
+    assert_eq!(clock.get_remaining(Color::Black), secs(66));
+  }
+
+  #[test]
+  fn test_multi_stage_control() {
+    // 40/90+30, then 30+30
+    let control = TimeControl::from_pgn_tag("40/5400+30:1800+30").unwrap();
+    let (mut clock, source) = clock(control);
+    assert_eq!(clock.get_moves_to_go(Color::White), Some(40));
+    for _ in 0..39 {
+      play(&mut clock, &source, 60);
+      play(&mut clock, &source, 1);
+    }
+    assert_eq!(clock.get_remaining(Color::White), secs(5400 - 39 * 30));
+    assert_eq!(clock.get_moves_to_go(Color::White), Some(1));
+    assert_eq!(clock.get_increment(Color::White), secs(30));
+
+    play(&mut clock, &source, 60);
+    assert_eq!(clock.get_moves_made(Color::White), 40);
+    assert_eq!(clock.get_remaining(Color::White), secs(5400 - 40 * 30 + 1800));
+    assert_eq!(clock.get_remaining(Color::Black), secs(5400 + 39 * 29));
+    assert_eq!(clock.get_moves_to_go(Color::White), None);
+    assert_eq!(clock.get_moves_to_go(Color::Black), Some(1));
+
+    // The second stage lasts the rest of the game
+    play(&mut clock, &source, 1);
+    for _ in 0..50 {
+      play(&mut clock, &source, 30);
+      play(&mut clock, &source, 30);
+    }
+    assert_eq!(clock.get_remaining(Color::White), secs(5400 - 40 * 30 + 1800));
+  }
+
+  #[test]
+  fn test_parse_pgn_tags() {
+    assert_eq!(TimeControl::from_pgn_tag("300"), Ok(TimeControl::sudden_death(secs(300))));
+    assert_eq!(TimeControl::from_pgn_tag("180+2"), Ok(TimeControl::fischer(secs(180), secs(2))));
+    assert_eq!(TimeControl::from_pgn_tag("*60"), Ok(TimeControl::hourglass(secs(60))));
+    assert_eq!(TimeControl::from_pgn_tag("40/"), Err(Error::InvalidTimeControl));
+    assert_eq!(TimeControl::from_pgn_tag("0/60"), Err(Error::InvalidTimeControl));
+  }
+
+  #[test]
+  fn test_flag_fall() {
+    let (mut clock, source) = clock(TimeControl::sudden_death(secs(60)));
+    play(&mut clock, &source, 10);
+    source.advance(secs(60));
+    assert_eq!(clock.get_flagged(), Some(Color::Black));
+
+    // A fallen flag can't be undone by pressing or stopping the clock
+    clock.press();
+    assert_eq!(clock.get_flagged(), Some(Color::Black));
+    clock.stop();
+    assert_eq!(clock.get_flagged(), Some(Color::Black));
+
+    let white_can_mate = Board::from_fen_string("8/8/4k3/8/8/8/4R3/4K3 b - - 0 1").unwrap();
+    assert_eq!(clock.get_result(&white_can_mate), Some(GameResult::WhiteWins));
+    let white_cannot_mate = Board::from_fen_string("8/8/4k3/8/8/8/4N3/4K3 b - - 0 1").unwrap();
+    assert_eq!(clock.get_result(&white_cannot_mate), Some(GameResult::Draw));
+  }
+}
@@ -0,0 +1,43 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Where time comes from, so anything that budgets time can be tested without waiting.
+pub trait TimeSource: Send + Sync {
+  /// Time elapsed since some fixed point; only differences between calls are meaningful.
+  fn now(&self) -> Duration;
+}
+
+/// Wall clock time.
+pub struct SystemTimeSource {
+  start: Instant,
+}
+
+impl Default for SystemTimeSource {
+  fn default() -> Self {
+    SystemTimeSource { start: Instant::now() }
+  }
+}
+
+impl TimeSource for SystemTimeSource {
+  fn now(&self) -> Duration {
+    self.start.elapsed()
+  }
+}
+
+/// A clock that only moves when told to.
+#[derive(Default)]
+pub struct MockTimeSource {
+  micros: AtomicU64,
+}
+
+impl MockTimeSource {
+  pub fn advance(&self, duration: Duration) {
+    self.micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+  }
+}
+
+impl TimeSource for MockTimeSource {
+  fn now(&self) -> Duration {
+    Duration::from_micros(self.micros.load(Ordering::Relaxed))
+  }
+}
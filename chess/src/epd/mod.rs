@@ -0,0 +1,252 @@
+//! Reading positions in Extended Position Description, the format test suites such as WAC, STS
+//! and Bratko-Kopec are distributed in.
+//!
+//! A record is the first four fields of a FEN string followed by operations, each an opcode and
+//! its operands ending in a semicolon:
+//!
+//! ```text
+//! 2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";
+//! ```
+//!
+//! Every operation is kept, and the common ones are also parsed: `bm`, `am`, `id`, `c0` to
+//! `c9`, `acd`, `ce`, and `hmvc` and `fmvn` for the move counters.
+
+pub mod suite;
+
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::errors::Error;
+
+/// One position from an EPD file.
+#[derive(Clone)]
+pub struct EpdRecord {
+  board: Board,
+  operations: Vec<(String, Vec<String>)>,
+  best_moves: Vec<Move>,
+  avoid_moves: Vec<Move>,
+}
+
+impl EpdRecord {
+  /// Parses one line of EPD. Moves in `bm` and `am` may be SAN or UCI.
+  ///
+  /// Returns [`Error::InvalidEpd`](`crate::errors::Error::InvalidEpd`) if the line can't be
+  /// read, or a move doesn't name a legal move.
+  pub fn parse(line: &str) -> Result<EpdRecord, Error> {
+    let line = line.trim();
+    let mut fields = vec![];
+    let mut rest = line;
+    for _ in 0..4 {
+      let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+      fields.push(&rest[..end]);
+      rest = rest[end..].trim_start();
+    }
+    if fields.iter().any(|field| field.is_empty()) {
+      return Err(Error::InvalidEpd);
+    }
+
+    let operations = parse_operations(rest)?;
+    let counter = |opcode: &str, default: &str| {
+      operations.iter().find(|(op, _)| op == opcode).and_then(|(_, operands)| operands.first().cloned()).unwrap_or_else(|| default.to_string())
+    };
+    let fen = format!("{} {} {}", fields.join(" "), counter("hmvc", "0"), counter("fmvn", "1"));
+    let board = Board::from_fen_string(&fen).map_err(|_| Error::InvalidEpd)?;
+
+    let moves = |opcode: &str| -> Result<Vec<Move>, Error> {
+      let operands = operations.iter().filter(|(op, _)| op == opcode).flat_map(|(_, operands)| operands.iter());
+      operands.map(|text| parse_move(&board, text)).collect()
+    };
+    let best_moves = moves("bm")?;
+    let avoid_moves = moves("am")?;
+    Ok(EpdRecord { board, operations, best_moves, avoid_moves })
+  }
+
+  pub fn get_board(&self) -> &Board {
+    &self.board
+  }
+
+  /// Every operation in the order it was written, with its operands. Quotes around string
+  /// operands are removed.
+  pub fn get_operations(&self) -> &[(String, Vec<String>)] {
+    &self.operations
+  }
+
+  /// Returns the operands of an operation such as `bm` or `c0`.
+  pub fn get_operation(&self, opcode: &str) -> Option<&[String]> {
+    self.operations.iter().find(|(op, _)| op == opcode).map(|(_, operands)| operands.as_slice())
+  }
+
+  /// The moves the suite expects to be played, from `bm`.
+  pub fn get_best_moves(&self) -> &[Move] {
+    &self.best_moves
+  }
+
+  /// The moves the suite expects to be avoided, from `am`.
+  pub fn get_avoid_moves(&self) -> &[Move] {
+    &self.avoid_moves
+  }
+
+  pub fn get_id(&self) -> Option<&str> {
+    self.get_string("id")
+  }
+
+  /// One of the comments `c0` to `c9`.
+  pub fn get_comment(&self, index: usize) -> Option<&str> {
+    if index > 9 {
+      return None;
+    }
+    self.get_string(&format!("c{}", index))
+  }
+
+  /// Analysis count depth: how deep the position was searched.
+  pub fn get_acd(&self) -> Option<u32> {
+    self.get_string("acd")?.parse().ok()
+  }
+
+  /// Centipawn evaluation, from the side to move's point of view.
+  pub fn get_ce(&self) -> Option<i32> {
+    self.get_string("ce")?.parse().ok()
+  }
+
+  /// Whether a move passes: it must be one of the best moves if any are given, and none of the
+  /// moves to avoid.
+  pub fn is_solved_by(&self, mv: &Move) -> bool {
+    (self.best_moves.is_empty() || self.best_moves.contains(mv)) && !self.avoid_moves.contains(mv)
+  }
+
+  /// Points for a move, as Strategic Test Suite records list in `c0`, e.g.
+  /// `"Nf3=10, e4=5, d4=3"`. Returns `None` if there is no such list.
+  pub fn get_move_points(&self, mv: &Move) -> Option<u32> {
+    let list = self.get_comment(0)?;
+    let mut points = 0;
+    for entry in list.split(',') {
+      let (text, value) = entry.split_once('=')?;
+      let value: u32 = value.trim().parse().ok()?;
+      if parse_move(&self.board, text.trim()).ok()? == *mv {
+        points = value;
+      }
+    }
+    Some(points)
+  }
+
+  /// The most points any move earns in the `c0` list, or `None` if there is no such list.
+  pub fn get_max_points(&self) -> Option<u32> {
+    let list = self.get_comment(0)?;
+    let values = list.split(',').map(|entry| entry.split_once('=')?.1.trim().parse().ok());
+    values.collect::<Option<Vec<u32>>>()?.into_iter().max()
+  }
+
+  fn get_string(&self, opcode: &str) -> Option<&str> {
+    self.get_operation(opcode)?.first().map(|operand| operand.as_str())
+  }
+}
+
+fn parse_move(board: &Board, text: &str) -> Result<Move, Error> {
+  board.move_from_san(text)
+    .or_else(|_| Move::from_uci(text).ok().filter(|mv| board.get_legal_moves().contains(mv)).ok_or(Error::InvalidEpd))
+    .map_err(|_| Error::InvalidEpd)
+}
+
+/// Splits operations on semicolons outside quotes, and each operation into its opcode and
+/// operands.
+pub(crate) fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, Error> {
+  let mut operations = vec![];
+  let mut words: Vec<String> = vec![];
+  let mut word = String::new();
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '"' => {
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some(c) => word.push(c),
+            None => return Err(Error::InvalidEpd),
+          }
+        }
+        words.push(std::mem::take(&mut word));
+      }
+      ';' => {
+        if !word.is_empty() {
+          words.push(std::mem::take(&mut word));
+        }
+        if words.is_empty() {
+          return Err(Error::InvalidEpd);
+        }
+        let opcode = words.remove(0);
+        operations.push((opcode, std::mem::take(&mut words)));
+      }
+      c if c.is_whitespace() => {
+        if !word.is_empty() {
+          words.push(std::mem::take(&mut word));
+        }
+      }
+      c => word.push(c),
+    }
+  }
+  // The last operation's semicolon is often left off
+  if !word.is_empty() {
+    words.push(word);
+  }
+  if !words.is_empty() {
+    let opcode = words.remove(0);
+    operations.push((opcode, words));
+  }
+  Ok(operations)
+}
+
+/// Parses every record of an EPD file. Blank lines and lines starting with `#` are skipped; a
+/// record that can't be read is returned as an error, and parsing carries on with the next.
+pub fn parse_epd(text: &str) -> Vec<Result<EpdRecord, Error>> {
+  text.lines()
+    .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+    .map(EpdRecord::parse)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_parse_record() {
+    let record = EpdRecord::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\"; c0 \"mate; in three\"; acd 12; ce +325;").unwrap();
+    assert_eq!(record.get_board().to_fen_string(), "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1");
+    assert_eq!(record.get_best_moves(), [Move::from_uci("g3g6").unwrap()]);
+    assert!(record.get_avoid_moves().is_empty());
+    assert_eq!(record.get_id(), Some("WAC.001"));
+    assert_eq!(record.get_comment(0), Some("mate; in three"));
+    assert_eq!(record.get_comment(1), None);
+    assert_eq!(record.get_acd(), Some(12));
+    assert_eq!(record.get_ce(), Some(325));
+    assert!(record.is_solved_by(&Move::from_uci("g3g6").unwrap()));
+    assert!(!record.is_solved_by(&Move::from_uci("g3h4").unwrap()));
+  }
+
+  #[test]
+  fn test_avoid_moves_and_counters() {
+    let record = EpdRecord::parse("4k3/8/8/8/8/8/4P3/4K3 w - - am e4 Kd1; hmvc 7; fmvn 30").unwrap();
+    assert_eq!(record.get_board().get_half_move_clock(), 7);
+    assert_eq!(record.get_avoid_moves().len(), 2);
+    assert!(!record.is_solved_by(&Move::from_uci("e2e4").unwrap()));
+    assert!(record.is_solved_by(&Move::from_uci("e2e3").unwrap()));
+    assert_eq!(record.get_operation("fmvn"), Some(&["30".to_string()][..]));
+  }
+
+  #[test]
+  fn test_move_points() {
+    let record = EpdRecord::parse("4k3/8/8/8/8/8/4P3/4K3 w - - bm e4; c0 \"e4=10, e3=4\";").unwrap();
+    assert_eq!(record.get_move_points(&Move::from_uci("e2e4").unwrap()), Some(10));
+    assert_eq!(record.get_move_points(&Move::from_uci("e2e3").unwrap()), Some(4));
+    assert_eq!(record.get_move_points(&Move::from_uci("e1d1").unwrap()), Some(0));
+    assert_eq!(record.get_max_points(), Some(10));
+    assert_eq!(EpdRecord::parse("4k3/8/8/8/8/8/4P3/4K3 w - - c0 \"e3=4, e4=7\";").unwrap().get_max_points(), Some(7));
+  }
+
+  #[test]
+  fn test_parse_epd_skips_comments() {
+    let records = parse_epd("# a suite\n\n4k3/8/8/8/8/8/4P3/4K3 w - - bm e4;\n4k3/8/8/8/8/8/8/4K3 w - - bm e4;\n");
+    assert_eq!(records.len(), 2);
+    assert!(records[0].is_ok());
+    assert_eq!(records[1].as_ref().err(), Some(&Error::InvalidEpd));
+  }
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::board::moves::Move;
+use crate::epd::EpdRecord;
+use crate::search::{SearchLimits, Searcher};
+use crate::search::time::{SystemTimeSource, TimeManager};
+
+/// How long each position of a suite is searched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SuiteLimit {
+  Depth(u32),
+  Time(Duration),
+}
+
+/// What the search made of one position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionResult {
+  pub id: Option<String>,
+  pub best_move: Option<Move>,
+  pub passed: bool,
+  /// Points from the record's `c0` list, for suites that grade moves.
+  pub points: Option<u32>,
+  /// The most points any move in the `c0` list earns.
+  pub max_points: Option<u32>,
+  pub score: i32,
+  pub depth: u32,
+  pub nodes: u64,
+  pub elapsed: Duration,
+}
+
+/// Results of a whole suite.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SuiteSummary {
+  results: Vec<PositionResult>,
+}
+
+impl SuiteSummary {
+  pub fn get_results(&self) -> &[PositionResult] {
+    &self.results
+  }
+
+  pub fn get_total(&self) -> usize {
+    self.results.len()
+  }
+
+  pub fn get_passed(&self) -> usize {
+    self.results.iter().filter(|result| result.passed).count()
+  }
+
+  /// Points scored, out of the best possible, over the records that grade moves.
+  pub fn get_points(&self) -> (u32, u32) {
+    let graded = self.results.iter().filter(|result| result.points.is_some());
+    let scored = graded.clone().filter_map(|result| result.points).sum();
+    (scored, graded.filter_map(|result| result.max_points).sum())
+  }
+
+  pub fn get_nodes(&self) -> u64 {
+    self.results.iter().map(|result| result.nodes).sum()
+  }
+
+  pub fn get_elapsed(&self) -> Duration {
+    self.results.iter().map(|result| result.elapsed).sum()
+  }
+}
+
+/// Searches every record within the limit, calling `listener` after each one so progress can
+/// be shown. The searcher is cleared before each position, so results don't depend on order.
+pub fn run_suite<F: FnMut(&PositionResult)>(searcher: &mut Searcher, records: &[EpdRecord], limit: SuiteLimit, mut listener: F) -> SuiteSummary {
+  let mut summary = SuiteSummary::default();
+  for record in records.iter() {
+    searcher.clear();
+    let limits = match limit {
+      SuiteLimit::Depth(depth) => SearchLimits::depth(depth),
+      SuiteLimit::Time(duration) => SearchLimits::time(TimeManager::fixed(duration, Arc::new(SystemTimeSource::default()))),
+    };
+
+    let start = Instant::now();
+    let search = searcher.search_with_listener(record.get_board(), limits, |_| {});
+    let result = PositionResult {
+      id: record.get_id().map(|id| id.to_string()),
+      best_move: search.best_move,
+      passed: search.best_move.is_some_and(|mv| record.is_solved_by(&mv)),
+      points: search.best_move.and_then(|mv| record.get_move_points(&mv)),
+      max_points: record.get_max_points(),
+      score: search.score,
+      depth: search.depth,
+      nodes: search.nodes,
+      elapsed: start.elapsed(),
+    };
+    listener(&result);
+    summary.results.push(result);
+  }
+  summary
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::epd::parse_epd;
+
+  #[test]
+  fn test_run_suite() {
+    let records: Vec<EpdRecord> = parse_epd(
+      "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - bm Ra8; id \"mate\";\n\
+       6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - am Ra8; id \"avoid mate\"; c0 \"Ra8=10, Kf1=2\";\n\
+       6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - id \"quiet\"; c0 \"Kf1=7, h3=3\";\n"
+    ).into_iter().map(Result::unwrap).collect();
+
+    let mut seen = 0;
+    let summary = run_suite(&mut Searcher::default(), &records, SuiteLimit::Depth(2), |_| seen += 1);
+    assert_eq!(seen, 3);
+    assert_eq!(summary.get_total(), 3);
+    assert_eq!(summary.get_passed(), 2);
+    assert!(summary.get_results()[0].passed);
+    assert_eq!(summary.get_results()[1].id.as_deref(), Some("avoid mate"));
+    // Mating earns nothing in the last record, which tops out at 7
+    assert_eq!(summary.get_points(), (10, 17));
+  }
+}
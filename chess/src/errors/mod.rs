@@ -1,7 +1,21 @@
-
 #[derive(Debug, PartialEq)]
 pub enum Error {
     InvalidPositionString,
     InvalidRawCoordinatePair,
-    InvalidFENString
+    InvalidFENString,
+    InvalidEvalParams,
+    IoError,
+    InvalidLabeledPosition,
+    InvalidMove,
+    InvalidMoveString,
+    InvalidNetwork,
+    InvalidTimeControl,
+    GameOver,
+    InvalidBook,
+    InvalidPgn,
+    InvalidTablebase,
+    InvalidEpd,
+    InvalidChess960Index,
+    InvalidBoardSize,
+    InvalidBetza,
 }
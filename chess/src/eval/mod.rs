@@ -0,0 +1,411 @@
+use std::fmt;
+
+pub mod params;
+pub mod tuner;
+pub use crate::eval::params::{EvalParams, Score, MAX_PHASE};
+
+use crate::board::{Board, DEFAULT_BOARD_SIZE};
+use crate::board::coord::Coordinate;
+use crate::piece::{Color, PieceKind};
+
+/// A piece as seen by the evaluation: its kind, color and zero-indexed (x, y) location.
+struct EvalPiece {
+  kind: PieceKind,
+  color: Color,
+  x: i8,
+  y: i8,
+  /// The location on the 8x8 board the tables were tuned for, which the piece-square and
+  /// passed pawn weights are looked up by.
+  table: (i8, i8),
+  mobility: Vec<(i8, i8)>,
+}
+
+fn color_index(color: Color) -> usize {
+  match color {
+    Color::White => 0,
+    Color::Black => 1,
+  }
+}
+
+/// Returns the rank index (0-7) as seen from `color`'s side of the board.
+fn relative_rank(color: Color, y: i8) -> i8 {
+  match color {
+    Color::White => y,
+    Color::Black => 7 - y,
+  }
+}
+
+fn psqt_index(color: Color, x: i8, y: i8) -> usize {
+  (relative_rank(color, y) * 8 + x) as usize
+}
+
+/// The contribution of each evaluation term, from White's point of view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvalBreakdown {
+  pub material: Score,
+  pub psqt: Score,
+  pub mobility: Score,
+  pub pawn_structure: Score,
+  pub king_safety: Score,
+  pub bishop_pair: Score,
+  pub rook_files: Score,
+  /// Game phase, from 0 (bare kings and pawns) to [`MAX_PHASE`](`crate::eval::MAX_PHASE`).
+  pub phase: i32,
+  /// The color to move, which [`EvalBreakdown::total`](`crate::eval::EvalBreakdown::total`) is relative to.
+  pub side_to_move: Color,
+}
+
+impl EvalBreakdown {
+  fn terms(&self) -> [(&'static str, Score); 7] {
+    [
+      ("Material", self.material),
+      ("Piece-square", self.psqt),
+      ("Mobility", self.mobility),
+      ("Pawn structure", self.pawn_structure),
+      ("King safety", self.king_safety),
+      ("Bishop pair", self.bishop_pair),
+      ("Rook files", self.rook_files),
+    ]
+  }
+
+  /// Sum of every term, still split into midgame and endgame values, from White's point of view.
+  pub fn sum(&self) -> Score {
+    self.terms().iter().fold(Score::default(), |acc, (_, score)| acc + *score)
+  }
+
+  /// Tapered score in centipawns from White's point of view.
+  pub fn white_total(&self) -> i32 {
+    self.sum().taper(self.phase)
+  }
+
+  /// Tapered score in centipawns from the side to move's point of view.
+  pub fn total(&self) -> i32 {
+    match self.side_to_move {
+      Color::White => self.white_total(),
+      Color::Black => -self.white_total(),
+    }
+  }
+}
+
+impl fmt::Display for EvalBreakdown {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{:<16}{:>8}{:>8}{:>8}", "Term", "MG", "EG", "Total")?;
+    for (name, score) in self.terms().iter() {
+      writeln!(f, "{:<16}{:>8}{:>8}{:>8}", name, score.mg, score.eg, score.taper(self.phase))?;
+    }
+    let sum = self.sum();
+    writeln!(f, "{:<16}{:>8}{:>8}{:>8}", "Sum (White)", sum.mg, sum.eg, self.white_total())?;
+    writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+    write!(f, "Side to move ({}): {}", self.side_to_move, self.total())
+  }
+}
+
+/// Zero-indexed (x, y) of a coordinate.
+fn eval_coords(coord: &Coordinate) -> (i8, i8) {
+  (Into::<i8>::into(coord.file) - 1, Into::<i8>::into(coord.rank) - 1)
+}
+
+/// Zero-indexed (x, y) on the 8x8 board the tables were tuned for. Boards of other sizes are
+/// stretched or squeezed onto it.
+fn table_coords(board: &Board, (x, y): (i8, i8)) -> (i8, i8) {
+  let scale = |value: i8, size: i8| if size == DEFAULT_BOARD_SIZE { value } else { value * (DEFAULT_BOARD_SIZE - 1) / (size - 1) };
+  (scale(x, board.get_width()), scale(y, board.get_height()))
+}
+
+fn collect_pieces(board: &Board) -> Vec<EvalPiece> {
+  board.get_squares().iter()
+    .filter_map(|sq| {
+      let piece = sq.get_piece().as_ref()?;
+      let kind = piece.get_kind()?;
+      let (x, y) = eval_coords(sq.get_coord());
+      let mobility = match kind {
+        PieceKind::Pawn => vec![],
+        _ => piece.get_moves(board).iter().map(eval_coords).collect(),
+      };
+      Some(EvalPiece { kind, color: *piece.get_color(), x, y, table: table_coords(board, (x, y)), mobility })
+    })
+    .collect()
+}
+
+/// A coefficient of +1 for White or -1 for Black, repeated `n` times, in both the midgame and endgame slots.
+fn count(color: Color, n: i32) -> Score {
+  match color {
+    Color::White => Score::new(n, n),
+    Color::Black => Score::new(-n, -n),
+  }
+}
+
+/// Multiplies coefficients and weights slot by slot, keeping midgame and endgame apart.
+fn dot(coefficients: &[Score], weights: &[Score]) -> Score {
+  coefficients.iter().zip(weights.iter())
+    .fold(Score::default(), |acc, (c, w)| acc + Score::new(c.mg * w.mg, c.eg * w.eg))
+}
+
+/// How often each weight applies to a position, White's occurrences minus Black's.
+///
+/// The evaluation is linear in its weights: the midgame and endgame sums are the dot products of
+/// `coefficients` with [`EvalParams`](`crate::eval::EvalParams`), and only then tapered by `phase`.
+/// Coefficients are stored in an `EvalParams`, with the same count in both the `mg` and `eg` slots,
+/// so [`EvalParams::to_vec`](`crate::eval::params::EvalParams::to_vec`) lines them up with the weights.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalTrace {
+  pub coefficients: EvalParams,
+  /// The [rough value](`crate::piece::betza::PieceDefinition::get_value`) of any fairy pieces,
+  /// which have no weights of their own, White's minus Black's.
+  pub fairy_material: Score,
+  pub phase: i32,
+  pub side_to_move: Color,
+}
+
+impl EvalTrace {
+  /// Scores the traced position with the given weights, reporting every term separately.
+  pub fn breakdown(&self, params: &EvalParams) -> EvalBreakdown {
+    let c = &self.coefficients;
+    EvalBreakdown {
+      material: dot(&c.piece_values, &params.piece_values) + self.fairy_material,
+      psqt: c.psqt.iter().zip(params.psqt.iter())
+        .fold(Score::default(), |acc, (c, w)| acc + dot(c, w)),
+      mobility: dot(&c.mobility, &params.mobility),
+      pawn_structure: dot(&[c.doubled_pawn, c.isolated_pawn, c.backward_pawn], &[params.doubled_pawn, params.isolated_pawn, params.backward_pawn])
+        + dot(&c.passed_pawn, &params.passed_pawn),
+      king_safety: dot(&[c.king_shield, c.king_attack], &[params.king_shield, params.king_attack]),
+      bishop_pair: dot(&[c.bishop_pair], &[params.bishop_pair]),
+      rook_files: dot(&[c.rook_open_file, c.rook_semi_open_file], &[params.rook_open_file, params.rook_semi_open_file]),
+      phase: self.phase,
+      side_to_move: self.side_to_move,
+    }
+  }
+}
+
+fn trace_pawn_structure(pieces: &[EvalPiece], width: i8, coefficients: &mut EvalParams) {
+  // Ranks of every pawn, indexed by color and then file
+  let mut pawn_ranks: [Vec<Vec<i8>>; 2] = [vec![vec![]; width as usize], vec![vec![]; width as usize]];
+  for piece in pieces.iter().filter(|p| p.kind == PieceKind::Pawn) {
+    pawn_ranks[color_index(piece.color)][piece.x as usize].push(piece.y);
+  }
+
+  let files_at = |color: Color, x: i8| -> &[i8] {
+    if !(0..width).contains(&x) { return &[]; }
+    &pawn_ranks[color_index(color)][x as usize]
+  };
+
+  for color in &[Color::White, Color::Black] {
+    let color = *color;
+    let enemy = color.opposite();
+    let forward: i8 = if color == Color::White { 1 } else { -1 };
+
+    for file_ranks in pawn_ranks[color_index(color)].iter() {
+      if file_ranks.len() > 1 {
+        coefficients.doubled_pawn += count(color, file_ranks.len() as i32 - 1);
+      }
+    }
+
+    for pawn in pieces.iter().filter(|p| p.kind == PieceKind::Pawn && p.color == color) {
+      let neighbours: Vec<i8> = files_at(color, pawn.x - 1).iter()
+        .chain(files_at(color, pawn.x + 1).iter())
+        .copied()
+        .collect();
+
+      if neighbours.is_empty() {
+        coefficients.isolated_pawn += count(color, 1);
+      }
+
+      let is_ahead = |y: i8| (y - pawn.y) * forward > 0;
+      let is_passed = (pawn.x - 1..=pawn.x + 1)
+        .all(|x| !files_at(enemy, x).iter().any(|y| is_ahead(*y)));
+      if is_passed {
+        coefficients.passed_pawn[relative_rank(color, pawn.table.1) as usize] += count(color, 1);
+        continue;
+      }
+
+      // Backward: every neighbouring pawn is ahead of us, and our stop square is covered by an enemy pawn
+      let is_supportable = neighbours.iter().any(|y| !is_ahead(*y));
+      let stop_attacked = [pawn.x - 1, pawn.x + 1].iter()
+        .any(|x| files_at(enemy, *x).contains(&(pawn.y + 2 * forward)));
+      if !neighbours.is_empty() && !is_supportable && stop_attacked {
+        coefficients.backward_pawn += count(color, 1);
+      }
+    }
+  }
+}
+
+fn trace_king_safety(pieces: &[EvalPiece], coefficients: &mut EvalParams) {
+  for king in pieces.iter().filter(|p| p.kind == PieceKind::King) {
+    let forward: i8 = if king.color == Color::White { 1 } else { -1 };
+
+    let shield_pawns = pieces.iter()
+      .filter(|p| p.kind == PieceKind::Pawn && p.color == king.color)
+      .filter(|p| (p.x - king.x).abs() <= 1)
+      .filter(|p| p.y == king.y + forward || p.y == king.y + 2 * forward)
+      .count();
+    coefficients.king_shield += count(king.color, shield_pawns as i32);
+
+    let is_near_king = |(x, y): &(i8, i8)| (x - king.x).abs() <= 1 && (y - king.y).abs() <= 1;
+    let attacks: usize = pieces.iter()
+      .filter(|p| p.color != king.color && p.kind != PieceKind::King)
+      .map(|p| p.mobility.iter().filter(|sq| is_near_king(sq)).count())
+      .sum();
+    coefficients.king_attack += count(king.color, attacks as i32);
+  }
+}
+
+fn trace_rook_files(pieces: &[EvalPiece], coefficients: &mut EvalParams) {
+  for rook in pieces.iter().filter(|p| p.kind == PieceKind::Rook) {
+    let pawns_on_file = |color: Color| pieces.iter()
+      .any(|p| p.kind == PieceKind::Pawn && p.color == color && p.x == rook.x);
+
+    if !pawns_on_file(rook.color) {
+      if pawns_on_file(rook.color.opposite()) {
+        coefficients.rook_semi_open_file += count(rook.color, 1);
+      } else {
+        coefficients.rook_open_file += count(rook.color, 1);
+      }
+    }
+  }
+}
+
+/// Counts how often each evaluation weight applies to a board, without scoring it.
+pub fn trace(board: &Board) -> EvalTrace {
+  let pieces = collect_pieces(board);
+  let mut coefficients = EvalParams::zeroed();
+  let mut phase = 0;
+
+  let mut bishops = [0; 2];
+  for piece in pieces.iter() {
+    let kind_idx = piece.kind.index();
+    coefficients.piece_values[kind_idx] += count(piece.color, 1);
+    coefficients.psqt[kind_idx][psqt_index(piece.color, piece.table.0, piece.table.1)] += count(piece.color, 1);
+    coefficients.mobility[kind_idx] += count(piece.color, piece.mobility.len() as i32);
+
+    phase += match piece.kind {
+      PieceKind::Knight | PieceKind::Bishop => 1,
+      PieceKind::Rook => 2,
+      PieceKind::Queen => 4,
+      _ => 0,
+    };
+
+    if piece.kind == PieceKind::Bishop {
+      bishops[color_index(piece.color)] += 1;
+    }
+  }
+
+  for color in &[Color::White, Color::Black] {
+    if bishops[color_index(*color)] >= 2 {
+      coefficients.bishop_pair += count(*color, 1);
+    }
+  }
+
+  trace_pawn_structure(&pieces, board.get_width(), &mut coefficients);
+  trace_king_safety(&pieces, &mut coefficients);
+  trace_rook_files(&pieces, &mut coefficients);
+
+  EvalTrace {
+    coefficients,
+    fairy_material: trace_fairy_material(board),
+    phase: phase.min(MAX_PHASE),
+    side_to_move: board.get_active_color(),
+  }
+}
+
+fn trace_fairy_material(board: &Board) -> Score {
+  board.get_squares().iter()
+    .filter_map(|sq| sq.get_piece().as_ref())
+    .filter_map(|piece| piece.get_definition().map(|definition| (*piece.get_color(), definition.get_value())))
+    .fold(Score::default(), |acc, (color, value)| acc + count(color, value))
+}
+
+/// Evaluates a board with the given weights, reporting every term separately.
+pub fn evaluate_breakdown(board: &Board, params: &EvalParams) -> EvalBreakdown {
+  trace(board).breakdown(params)
+}
+
+/// Evaluates a board with the given weights, in centipawns from the side to move's point of view.
+pub fn evaluate(board: &Board, params: &EvalParams) -> i32 {
+  evaluate_breakdown(board, params).total()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_starting_position_is_balanced() {
+    let board = Board::new();
+    let breakdown = evaluate_breakdown(&board, &EvalParams::default());
+    assert_eq!(breakdown.sum(), Score::default());
+    assert_eq!(breakdown.phase, MAX_PHASE);
+    assert_eq!(evaluate(&board, &EvalParams::default()), 0);
+  }
+
+  #[test]
+  fn test_score_is_relative_to_side_to_move() {
+    let white = Board::from_fen_string("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+    let black = Board::from_fen_string("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+    let params = EvalParams::default();
+    assert!(evaluate(&white, &params) > 0);
+    assert_eq!(evaluate(&white, &params), -evaluate(&black, &params));
+  }
+
+  #[test]
+  fn test_bishop_pair_and_pawn_structure_terms() {
+    let board = Board::from_fen_string("4k3/8/8/8/8/2P5/2P5/2BBK3 w - - 0 1").unwrap();
+    let params = EvalParams::default();
+    let breakdown = evaluate_breakdown(&board, &params);
+    assert_eq!(breakdown.bishop_pair, params.bishop_pair);
+    // Both c-pawns are passed and isolated, and one of them is doubled
+    let expected = params.doubled_pawn + params.isolated_pawn * 2 + params.passed_pawn[1] + params.passed_pawn[2];
+    assert_eq!(breakdown.pawn_structure, expected);
+  }
+
+  #[test]
+  fn test_pawn_files_on_wide_boards() {
+    // On 10x10, the a- and b-pawns aren't doubled, and the c-pawn next to them isn't isolated
+    let board = Board::from_fen_string("5k4/10/10/10/10/10/10/PPP7/10/5K4 w - - 0 1").unwrap();
+    let params = EvalParams::default();
+    let breakdown = evaluate_breakdown(&board, &params);
+    assert_eq!(breakdown.pawn_structure, params.passed_pawn[1] * 3);
+
+    // The rook on b1 is alone on its file even though the a-file has a pawn
+    let board = Board::from_fen_string("5k4/10/10/10/10/10/10/10/P9/1R3K4 w - - 0 1").unwrap();
+    assert_eq!(evaluate_breakdown(&board, &params).rook_files, params.rook_open_file);
+  }
+
+  #[test]
+  fn test_rook_on_open_file() {
+    let board = Board::from_fen_string("4k3/p7/8/8/8/8/8/R3K2R w - - 0 1").unwrap();
+    let params = EvalParams::default();
+    let breakdown = evaluate_breakdown(&board, &params);
+    assert_eq!(breakdown.rook_files, params.rook_semi_open_file + params.rook_open_file);
+  }
+
+  #[test]
+  fn test_params_text_round_trip() {
+    let mut params = EvalParams { bishop_pair: Score::new(11, 22), ..Default::default() };
+    params.psqt[PieceKind::Knight.index()][28] = Score::new(-3, 7);
+
+    let parsed = EvalParams::from_text(&params.to_text()).unwrap();
+    assert_eq!(parsed, params);
+    assert_eq!(parsed.to_vec(), params.to_vec());
+  }
+
+  #[test]
+  fn test_trace_is_linear_in_weights() {
+    let board = Board::from_fen_string("r1bqk2r/pp3ppp/2n2n2/3p4/1b1P4/2NB1N2/PP3PPP/R1BQK2R w KQkq - 0 8").unwrap();
+    let params = EvalParams::default();
+    let trace = trace(&board);
+
+    let (mut mg, mut eg) = (0, 0);
+    let weights = params.to_vec();
+    for (idx, coefficient) in trace.coefficients.to_vec().iter().enumerate() {
+      if idx % 2 == 0 { mg += coefficient * weights[idx]; } else { eg += coefficient * weights[idx]; }
+    }
+
+    assert_eq!(Score::new(mg, eg), evaluate_breakdown(&board, &params).sum());
+  }
+
+  #[test]
+  fn test_params_text_rejects_unknown_names() {
+    assert_eq!(EvalParams::from_text("not.a.param = 3").err().unwrap(), crate::errors::Error::InvalidEvalParams);
+    assert_eq!(EvalParams::from_text("bishop_pair.mg = 45").unwrap().bishop_pair.mg, 45);
+  }
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+use std::fs;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::path::Path;
+
+use crate::errors::Error;
+use crate::piece::PieceKind;
+
+/// A pair of midgame and endgame values, blended together by game phase.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Score {
+  pub mg: i32,
+  pub eg: i32,
+}
+
+impl Score {
+  pub const fn new(mg: i32, eg: i32) -> Score {
+    Score { mg, eg }
+  }
+
+  /// Blends the midgame and endgame values, where `phase` is in `0..=MAX_PHASE`
+  /// and `MAX_PHASE` is a full midgame.
+  pub fn taper(&self, phase: i32) -> i32 {
+    let phase = phase.clamp(0, MAX_PHASE);
+    (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+  }
+}
+
+impl Add for Score {
+  type Output = Score;
+
+  fn add(self, rhs: Score) -> Score {
+    Score::new(self.mg + rhs.mg, self.eg + rhs.eg)
+  }
+}
+
+impl AddAssign for Score {
+  fn add_assign(&mut self, rhs: Score) {
+    self.mg += rhs.mg;
+    self.eg += rhs.eg;
+  }
+}
+
+impl Sub for Score {
+  type Output = Score;
+
+  fn sub(self, rhs: Score) -> Score {
+    Score::new(self.mg - rhs.mg, self.eg - rhs.eg)
+  }
+}
+
+impl SubAssign for Score {
+  fn sub_assign(&mut self, rhs: Score) {
+    self.mg -= rhs.mg;
+    self.eg -= rhs.eg;
+  }
+}
+
+impl Mul<i32> for Score {
+  type Output = Score;
+
+  fn mul(self, rhs: i32) -> Score {
+    Score::new(self.mg * rhs, self.eg * rhs)
+  }
+}
+
+impl Neg for Score {
+  type Output = Score;
+
+  fn neg(self) -> Score {
+    Score::new(-self.mg, -self.eg)
+  }
+}
+
+/// Game phase of the starting position; each knight and bishop counts 1, rook 2, queen 4.
+pub const MAX_PHASE: i32 = 24;
+
+pub(crate) const PIECE_KINDS: [PieceKind; 6] = [
+  PieceKind::Pawn,
+  PieceKind::Knight,
+  PieceKind::Bishop,
+  PieceKind::Rook,
+  PieceKind::Queen,
+  PieceKind::King,
+];
+
+fn piece_kind_name(kind: PieceKind) -> &'static str {
+  match kind {
+    PieceKind::Pawn => "pawn",
+    PieceKind::Knight => "knight",
+    PieceKind::Bishop => "bishop",
+    PieceKind::Rook => "rook",
+    PieceKind::Queen => "queen",
+    PieceKind::King => "king",
+  }
+}
+
+fn square_name(idx: usize) -> String {
+  let file = (b'a' + (idx % 8) as u8) as char;
+  let rank = (b'1' + (idx / 8) as u8) as char;
+  format!("{}{}", file, rank)
+}
+
+/// Every weight used by the handcrafted evaluation.
+///
+/// Penalties are stored as negative values, so every term is simply added to the score.
+/// Piece-square tables are indexed from White's point of view, with A1 at index 0 and H8 at 63;
+/// Black's pieces use the vertically mirrored square.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+  pub piece_values: [Score; 6],
+  pub psqt: [[Score; 64]; 6],
+  /// Bonus per pseudo-legal move, by piece kind.
+  pub mobility: [Score; 6],
+  pub doubled_pawn: Score,
+  pub isolated_pawn: Score,
+  pub backward_pawn: Score,
+  /// Bonus for a passed pawn, by rank relative to its owner (index 0 is the first rank).
+  pub passed_pawn: [Score; 8],
+  /// Bonus per friendly pawn directly in front of the king.
+  pub king_shield: Score,
+  /// Penalty per enemy attack on a square next to the king.
+  pub king_attack: Score,
+  pub bishop_pair: Score,
+  pub rook_open_file: Score,
+  pub rook_semi_open_file: Score,
+}
+
+impl EvalParams {
+  /// Returns weights that are all zero.
+  pub fn zeroed() -> EvalParams {
+    EvalParams {
+      piece_values: [Score::default(); 6],
+      psqt: [[Score::default(); 64]; 6],
+      mobility: [Score::default(); 6],
+      doubled_pawn: Score::default(),
+      isolated_pawn: Score::default(),
+      backward_pawn: Score::default(),
+      passed_pawn: [Score::default(); 8],
+      king_shield: Score::default(),
+      king_attack: Score::default(),
+      bishop_pair: Score::default(),
+      rook_open_file: Score::default(),
+      rook_semi_open_file: Score::default(),
+    }
+  }
+
+  /// Visits every weight along with its stable name, such as `psqt.knight.mg.e4`.
+  /// Names are used by the parameter file format and the tuner.
+  /// Each midgame weight is visited immediately before its endgame counterpart.
+  pub fn for_each_param<F: FnMut(&str, &mut i32)>(&mut self, mut f: F) {
+    fn visit_score<F: FnMut(&str, &mut i32)>(f: &mut F, name: &str, score: &mut Score) {
+      f(&format!("{}.mg", name), &mut score.mg);
+      f(&format!("{}.eg", name), &mut score.eg);
+    }
+
+    for kind in PIECE_KINDS.iter() {
+      let name = format!("piece_value.{}", piece_kind_name(*kind));
+      visit_score(&mut f, &name, &mut self.piece_values[kind.index()]);
+    }
+
+    for kind in PIECE_KINDS.iter() {
+      for idx in 0..64 {
+        let score = &mut self.psqt[kind.index()][idx];
+        f(&format!("psqt.{}.mg.{}", piece_kind_name(*kind), square_name(idx)), &mut score.mg);
+        f(&format!("psqt.{}.eg.{}", piece_kind_name(*kind), square_name(idx)), &mut score.eg);
+      }
+    }
+
+    for kind in &[PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen] {
+      let name = format!("mobility.{}", piece_kind_name(*kind));
+      visit_score(&mut f, &name, &mut self.mobility[kind.index()]);
+    }
+
+    visit_score(&mut f, "pawn.doubled", &mut self.doubled_pawn);
+    visit_score(&mut f, "pawn.isolated", &mut self.isolated_pawn);
+    visit_score(&mut f, "pawn.backward", &mut self.backward_pawn);
+    for rank in 1..7 {
+      visit_score(&mut f, &format!("pawn.passed.rank{}", rank + 1), &mut self.passed_pawn[rank]);
+    }
+
+    visit_score(&mut f, "king.shield", &mut self.king_shield);
+    visit_score(&mut f, "king.attack", &mut self.king_attack);
+    visit_score(&mut f, "bishop_pair", &mut self.bishop_pair);
+    visit_score(&mut f, "rook.open_file", &mut self.rook_open_file);
+    visit_score(&mut f, "rook.semi_open_file", &mut self.rook_semi_open_file);
+  }
+
+  /// Returns every weight in a fixed order, matching [`EvalParams::for_each_param`](`crate::eval::params::EvalParams::for_each_param`).
+  pub fn to_vec(&self) -> Vec<i32> {
+    let mut values = vec![];
+    self.clone().for_each_param(|_, value| values.push(*value));
+    values
+  }
+
+  /// Overwrites every weight from a slice produced by [`EvalParams::to_vec`](`crate::eval::params::EvalParams::to_vec`).
+  pub fn set_from_slice(&mut self, values: &[i32]) -> Result<(), Error> {
+    if values.len() != self.to_vec().len() {
+      return Err(Error::InvalidEvalParams);
+    }
+
+    let mut iter = values.iter();
+    self.for_each_param(|_, value| *value = *iter.next().unwrap());
+    Ok(())
+  }
+
+  /// Serializes the weights as `name = value` lines.
+  pub fn to_text(&self) -> String {
+    let mut text = String::new();
+    self.clone().for_each_param(|name, value| {
+      text += &format!("{} = {}\n", name, value);
+    });
+    text
+  }
+
+  /// Parses weights from `name = value` lines, starting from the default weights.
+  /// Blank lines and lines starting with `#` are ignored. Unknown names are an error.
+  pub fn from_text(text: &str) -> Result<EvalParams, Error> {
+    let mut assignments: HashMap<String, i32> = HashMap::new();
+    for line in text.lines() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let mut parts = line.splitn(2, '=');
+      let name = parts.next().ok_or(Error::InvalidEvalParams)?.trim();
+      let value = parts.next().ok_or(Error::InvalidEvalParams)?.trim();
+      let value: i32 = value.parse().map_err(|_| Error::InvalidEvalParams)?;
+      assignments.insert(String::from(name), value);
+    }
+
+    let mut params = EvalParams::default();
+    let mut matched = 0;
+    params.for_each_param(|name, value| {
+      if let Some(assigned_value) = assignments.get(name) {
+        *value = *assigned_value;
+        matched += 1;
+      }
+    });
+
+    if matched != assignments.len() {
+      return Err(Error::InvalidEvalParams);
+    }
+
+    Ok(params)
+  }
+
+  /// Loads weights from a file written by [`EvalParams::save`](`crate::eval::params::EvalParams::save`).
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<EvalParams, Error> {
+    let text = fs::read_to_string(path).map_err(|_| Error::IoError)?;
+    EvalParams::from_text(&text)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+    fs::write(path, self.to_text()).map_err(|_| Error::IoError)
+  }
+}
+
+impl Default for EvalParams {
+  fn default() -> Self {
+    let mut psqt = [[Score::default(); 64]; 6];
+    for kind in PIECE_KINDS.iter() {
+      let (mg_table, eg_table) = DEFAULT_PSQT[kind.index()];
+      for (idx, score) in psqt[kind.index()].iter_mut().enumerate() {
+        // The default tables are written as a diagram, with the eighth rank first
+        let diagram_idx = (7 - idx / 8) * 8 + idx % 8;
+        *score = Score::new(mg_table[diagram_idx], eg_table[diagram_idx]);
+      }
+    }
+
+    EvalParams {
+      piece_values: [
+        Score::new(82, 94),
+        Score::new(337, 281),
+        Score::new(365, 297),
+        Score::new(477, 512),
+        Score::new(1025, 936),
+        Score::new(0, 0),
+      ],
+      psqt,
+      mobility: [
+        Score::new(0, 0),
+        Score::new(4, 4),
+        Score::new(5, 5),
+        Score::new(2, 4),
+        Score::new(1, 2),
+        Score::new(0, 0),
+      ],
+      doubled_pawn: Score::new(-10, -20),
+      isolated_pawn: Score::new(-10, -10),
+      backward_pawn: Score::new(-8, -6),
+      passed_pawn: [
+        Score::new(0, 0),
+        Score::new(0, 10),
+        Score::new(5, 15),
+        Score::new(10, 25),
+        Score::new(20, 45),
+        Score::new(35, 75),
+        Score::new(60, 120),
+        Score::new(0, 0),
+      ],
+      king_shield: Score::new(12, 0),
+      king_attack: Score::new(-8, -2),
+      bishop_pair: Score::new(30, 50),
+      rook_open_file: Score::new(25, 10),
+      rook_semi_open_file: Score::new(12, 6),
+    }
+  }
+}
+
+type Table = [i32; 64];
+
+#[rustfmt::skip]
+const MG_PAWN: Table = [
+    0,   0,   0,   0,   0,   0,   0,   0,
+   98, 134,  61,  95,  68, 126,  34, -11,
+   -6,   7,  26,  31,  65,  56,  25, -20,
+  -14,  13,   6,  21,  23,  12,  17, -23,
+  -27,  -2,  -5,  12,  17,   6,  10, -25,
+  -26,  -4,  -4, -10,   3,   3,  33, -12,
+  -35,  -1, -20, -23, -15,  24,  38, -22,
+    0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const EG_PAWN: Table = [
+    0,   0,   0,   0,   0,   0,   0,   0,
+  178, 173, 158, 134, 147, 132, 165, 187,
+   94, 100,  85,  67,  56,  53,  82,  84,
+   32,  24,  13,   5,  -2,   4,  17,  17,
+   13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+    4,   7,  -6,   1,   0,  -5,  -1,  -8,
+   13,   8,   8,  10,  13,   0,   2,  -7,
+    0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const MG_KNIGHT: Table = [
+  -167, -89, -34, -49,  61, -97, -15, -107,
+   -73, -41,  72,  36,  23,  62,   7,  -17,
+   -47,  60,  37,  65,  84, 129,  73,   44,
+    -9,  17,  19,  53,  37,  69,  18,   22,
+   -13,   4,  16,  13,  28,  19,  21,   -8,
+   -23,  -9,  12,  10,  19,  17,  25,  -16,
+   -29, -53, -12,  -3,  -1,  18, -14,  -19,
+  -105, -21, -58, -33, -17, -28, -19,  -23,
+];
+
+#[rustfmt::skip]
+const EG_KNIGHT: Table = [
+  -58, -38, -13, -28, -31, -27, -63, -99,
+  -25,  -8, -25,  -2,  -9, -25, -24, -52,
+  -24, -20,  10,   9,  -1,  -9, -19, -41,
+  -17,   3,  22,  22,  22,  11,   8, -18,
+  -18,  -6,  16,  25,  16,  17,   4, -18,
+  -23,  -3,  -1,  15,  10,  -3, -20, -22,
+  -42, -20, -10,  -5,  -2, -20, -23, -44,
+  -29, -51, -23, -15, -22, -18, -50, -64,
+];
+
+#[rustfmt::skip]
+const MG_BISHOP: Table = [
+  -29,   4, -82, -37, -25, -42,   7,  -8,
+  -26,  16, -18, -13,  30,  59,  18, -47,
+  -16,  37,  43,  40,  35,  50,  37,  -2,
+   -4,   5,  19,  50,  37,  37,   7,  -2,
+   -6,  13,  13,  26,  34,  12,  10,   4,
+    0,  15,  15,  15,  14,  27,  18,  10,
+    4,  15,  16,   0,   7,  21,  33,   1,
+  -33,  -3, -14, -21, -13, -12, -39, -21,
+];
+
+#[rustfmt::skip]
+const EG_BISHOP: Table = [
+  -14, -21, -11,  -8,  -7,  -9, -17, -24,
+   -8,  -4,   7, -12,  -3, -13,  -4, -14,
+    2,  -8,   0,  -1,  -2,   6,   0,   4,
+   -3,   9,  12,   9,  14,  10,   3,   2,
+   -6,   3,  13,  19,   7,  10,  -3,  -9,
+  -12,  -3,   8,  10,  13,   3,  -7, -15,
+  -14, -18,  -7,  -1,   4,  -9, -15, -27,
+  -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+];
+
+#[rustfmt::skip]
+const MG_ROOK: Table = [
+   32,  42,  32,  51,  63,   9,  31,  43,
+   27,  32,  58,  62,  80,  67,  26,  44,
+   -5,  19,  26,  36,  17,  45,  61,  16,
+  -24, -11,   7,  26,  24,  35,  -8, -20,
+  -36, -26, -12,  -1,   9,  -7,   6, -23,
+  -45, -25, -16, -17,   3,   0,  -5, -33,
+  -44, -16, -20,  -9,  -1,  11,  -6, -71,
+  -19, -13,   1,  17,  16,   7, -37, -26,
+];
+
+#[rustfmt::skip]
+const EG_ROOK: Table = [
+   13,  10,  18,  15,  12,  12,   8,   5,
+   11,  13,  13,  11,  -3,   3,   8,   3,
+    7,   7,   7,   5,   4,  -3,  -5,  -3,
+    4,   3,  13,   1,   2,   1,  -1,   2,
+    3,   5,   8,   4,  -5,  -6,  -8, -11,
+   -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+   -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+   -9,   2,   3,  -1,  -5, -13,   4, -20,
+];
+
+#[rustfmt::skip]
+const MG_QUEEN: Table = [
+  -28,   0,  29,  12,  59,  44,  43,  45,
+  -24, -39,  -5,   1, -16,  57,  28,  54,
+  -13, -17,   7,   8,  29,  56,  47,  57,
+  -27, -27, -16, -16,  -1,  17,  -2,   1,
+   -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+  -14,   2, -11,  -2,  -5,   2,  14,   5,
+  -35,  -8,  11,   2,   8,  15,  -3,   1,
+   -1, -18,  -9,  10, -15, -25, -31, -50,
+];
+
+#[rustfmt::skip]
+const EG_QUEEN: Table = [
+   -9,  22,  22,  27,  27,  19,  10,  20,
+  -17,  20,  32,  41,  58,  25,  30,   0,
+  -20,   6,   9,  49,  47,  35,  19,   9,
+    3,  22,  24,  45,  57,  40,  57,  36,
+  -18,  28,  19,  47,  31,  34,  39,  23,
+  -16, -27,  15,   6,   9,  17,  10,   5,
+  -22, -23, -30, -16, -16, -23, -36, -32,
+  -33, -28, -22, -43,  -5, -32, -20, -41,
+];
+
+#[rustfmt::skip]
+const MG_KING: Table = [
+  -65,  23,  16, -15, -56, -34,   2,  13,
+   29,  -1, -20,  -7,  -8,  -4, -38, -29,
+   -9,  24,   2, -16, -20,   6,  22, -22,
+  -17, -20, -12, -27, -30, -25, -14, -36,
+  -49,  -1, -27, -39, -46, -44, -33, -51,
+  -14, -14, -22, -46, -44, -30, -15, -27,
+    1,   7,  -8, -64, -43, -16,   9,   8,
+  -15,  36,  12, -54,   8, -28,  24,  14,
+];
+
+#[rustfmt::skip]
+const EG_KING: Table = [
+  -74, -35, -18, -18, -11,  15,   4, -17,
+  -12,  17,  14,  17,  17,  38,  23,  11,
+   10,  17,  23,  15,  20,  45,  44,  13,
+   -8,  22,  24,  27,  26,  33,  26,   3,
+  -18,  -4,  21,  24,  27,  23,   9, -11,
+  -19,  -3,  11,  21,  23,  16,   7,  -9,
+  -27, -11,   4,  13,  14,   4,  -5, -17,
+  -53, -34, -21, -11, -28, -14, -24, -43,
+];
+
+const DEFAULT_PSQT: [(Table, Table); 6] = [
+  (MG_PAWN, EG_PAWN),
+  (MG_KNIGHT, EG_KNIGHT),
+  (MG_BISHOP, EG_BISHOP),
+  (MG_ROOK, EG_ROOK),
+  (MG_QUEEN, EG_QUEEN),
+  (MG_KING, EG_KING),
+];
@@ -0,0 +1,359 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use crate::board::Board;
+use crate::epd::parse_operations;
+use crate::errors::Error;
+use crate::eval::{self, EvalParams, MAX_PHASE};
+
+/// A position labeled with the result of the game it was taken from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabeledPosition {
+  pub fen: String,
+  /// Game result from White's point of view: 1.0 for a win, 0.5 for a draw and 0.0 for a loss.
+  pub result: f64,
+}
+
+fn parse_result(result_str: &str) -> Result<f64, Error> {
+  match result_str.trim().trim_matches(|c| c == '"' || c == ';' || c == '[' || c == ']') {
+    "1-0" | "1.0" => Ok(1.0),
+    "0-1" | "0.0" => Ok(0.0),
+    "1/2-1/2" | "0.5" | "1/2" => Ok(0.5),
+    _ => Err(Error::InvalidLabeledPosition),
+  }
+}
+
+/// Builds a full FEN string from the leading fields of a line, which may be a 4-field EPD
+/// position, and returns it with the number of fields it took.
+fn parse_fen_fields(fields: &[&str]) -> Result<(String, usize), Error> {
+  let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+  match fields.len() {
+    len if len >= 6 && is_number(fields[4]) && is_number(fields[5]) => Ok((fields[..6].join(" "), 6)),
+    len if len >= 4 => Ok((format!("{} 0 1", fields[..4].join(" ")), 4)),
+    _ => Err(Error::InvalidLabeledPosition),
+  }
+}
+
+/// Parses a single labeled position. Supported formats are:
+///
+/// - EPD with the result as an opcode, e.g. `<epd> c9 "1-0";` or `<epd> result "1/2-1/2";`
+/// - FEN or EPD followed by a bracketed score, e.g. `<fen> [0.5]`
+/// - FEN or EPD followed by a bare outcome, e.g. `<fen> 0-1`
+pub fn parse_labeled_position(line: &str) -> Result<LabeledPosition, Error> {
+  let fields: Vec<&str> = line.split_whitespace().collect();
+  let (fen, used) = parse_fen_fields(&fields)?;
+  let label = fields[used..].join(" ");
+
+  let result = if label.starts_with('[') || (!label.is_empty() && !label.contains(char::is_whitespace) && !label.contains(';')) {
+    parse_result(&label)?
+  } else {
+    let operations = parse_operations(&label).map_err(|_| Error::InvalidLabeledPosition)?;
+    let operand = operations.iter()
+      .find(|(opcode, _)| opcode == "c9" || opcode == "result")
+      .and_then(|(_, operands)| operands.first())
+      .ok_or(Error::InvalidLabeledPosition)?;
+    parse_result(operand)?
+  };
+
+  Board::from_fen_string(&fen).map_err(|_| Error::InvalidLabeledPosition)?;
+  Ok(LabeledPosition { fen, result })
+}
+
+/// Reads one labeled position per line. Blank lines and lines starting with `#` are skipped.
+pub fn load_labeled_positions<P: AsRef<Path>>(path: P) -> Result<Vec<LabeledPosition>, Error> {
+  let text = fs::read_to_string(path).map_err(|_| Error::IoError)?;
+  text.lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(parse_labeled_position)
+    .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct TunerOptions {
+  pub iterations: usize,
+  /// Step size of the optimizer, in centipawns.
+  pub learning_rate: f64,
+  pub threads: usize,
+  /// Scaling constant of the sigmoid. When `None`, it is fitted to the initial weights.
+  pub k: Option<f64>,
+}
+
+impl Default for TunerOptions {
+  fn default() -> Self {
+    TunerOptions {
+      iterations: 1000,
+      learning_rate: 1.0,
+      threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+      k: None,
+    }
+  }
+}
+
+/// A position reduced to its evaluation coefficients, stored sparsely as (weight index, coefficient).
+struct TuningEntry {
+  coefficients: Vec<(usize, f64)>,
+  phase: f64,
+  result: f64,
+}
+
+impl TuningEntry {
+  /// Tapered evaluation from White's point of view.
+  fn evaluate(&self, weights: &[f64]) -> f64 {
+    let (mut mg, mut eg) = (0.0, 0.0);
+    for (idx, coefficient) in self.coefficients.iter() {
+      if idx % 2 == 0 { mg += coefficient * weights[*idx]; } else { eg += coefficient * weights[*idx]; }
+    }
+    (mg * self.phase + eg * (MAX_PHASE as f64 - self.phase)) / MAX_PHASE as f64
+  }
+}
+
+fn sigmoid(k: f64, score: f64) -> f64 {
+  1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+/// Fits evaluation weights to game results by minimizing the mean squared error between each
+/// result and a sigmoid of the evaluation ("Texel tuning"), using the Adam gradient descent optimizer.
+pub struct Tuner {
+  entries: Vec<TuningEntry>,
+  params: EvalParams,
+  weights: Vec<f64>,
+  k: f64,
+  options: TunerOptions,
+  // Adam moment estimates
+  momentum: Vec<f64>,
+  velocity: Vec<f64>,
+  step_count: i32,
+}
+
+impl Tuner {
+  pub fn new(positions: &[LabeledPosition], initial: &EvalParams, options: TunerOptions) -> Result<Tuner, Error> {
+    if positions.is_empty() {
+      return Err(Error::InvalidLabeledPosition);
+    }
+
+    let threads = options.threads.max(1);
+    let chunk_size = positions.len().div_ceil(threads);
+    let entries: Result<Vec<Vec<TuningEntry>>, Error> = thread::scope(|scope| {
+      let handles: Vec<_> = positions.chunks(chunk_size)
+        .map(|chunk| scope.spawn(move || {
+          chunk.iter().map(|position| {
+            let board = Board::from_fen_string(&position.fen)?;
+            let trace = eval::trace(&board);
+            let coefficients = trace.coefficients.to_vec().iter()
+              .enumerate()
+              .filter(|(_, c)| **c != 0)
+              .map(|(idx, c)| (idx, *c as f64))
+              .collect();
+            Ok(TuningEntry { coefficients, phase: trace.phase as f64, result: position.result })
+          }).collect::<Result<Vec<TuningEntry>, Error>>()
+        }))
+        .collect();
+      handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let weights: Vec<f64> = initial.to_vec().iter().map(|w| *w as f64).collect();
+    let weight_count = weights.len();
+    let mut tuner = Tuner {
+      entries: entries?.into_iter().flatten().collect(),
+      params: initial.clone(),
+      weights,
+      k: options.k.unwrap_or(1.0),
+      options,
+      momentum: vec![0.0; weight_count],
+      velocity: vec![0.0; weight_count],
+      step_count: 0,
+    };
+
+    if tuner.options.k.is_none() {
+      tuner.k = tuner.fit_k();
+    }
+
+    Ok(tuner)
+  }
+
+  pub fn k(&self) -> f64 {
+    self.k
+  }
+
+  /// Splits the entries across the configured threads, then combines each thread's result in order.
+  fn parallel_map<T: Send, F: Fn(&[TuningEntry]) -> T + Sync>(&self, f: F) -> Vec<T> {
+    let chunk_size = self.entries.len().div_ceil(self.options.threads.max(1));
+    thread::scope(|scope| {
+      let handles: Vec<_> = self.entries.chunks(chunk_size)
+        .map(|chunk| {
+          let f = &f;
+          scope.spawn(move || f(chunk))
+        })
+        .collect();
+      handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
+  }
+
+  fn error_with_k(&self, k: f64) -> f64 {
+    let total: f64 = self.parallel_map(|chunk| {
+      chunk.iter()
+        .map(|entry| (entry.result - sigmoid(k, entry.evaluate(&self.weights))).powi(2))
+        .sum::<f64>()
+    }).iter().sum();
+    total / self.entries.len() as f64
+  }
+
+  /// Mean squared error of the current weights.
+  pub fn error(&self) -> f64 {
+    self.error_with_k(self.k)
+  }
+
+  /// Finds the sigmoid scale that best fits the current weights, by golden-section search.
+  fn fit_k(&self) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.0, 10.0);
+    for _ in 0..60 {
+      let a = high - ratio * (high - low);
+      let b = low + ratio * (high - low);
+      if self.error_with_k(a) < self.error_with_k(b) { high = b; } else { low = a; }
+    }
+    (low + high) / 2.0
+  }
+
+  fn gradient(&self) -> Vec<f64> {
+    let k = self.k;
+    let partials = self.parallel_map(|chunk| {
+      let mut gradient = vec![0.0; self.weights.len()];
+      for entry in chunk {
+        let s = sigmoid(k, entry.evaluate(&self.weights));
+        let d = (s - entry.result) * s * (1.0 - s) * k * 10f64.ln() / 400.0;
+        let mg_scale = entry.phase / MAX_PHASE as f64;
+        for (idx, coefficient) in entry.coefficients.iter() {
+          let phase_scale = if idx % 2 == 0 { mg_scale } else { 1.0 - mg_scale };
+          gradient[*idx] += d * coefficient * phase_scale;
+        }
+      }
+      gradient
+    });
+
+    let scale = 2.0 / self.entries.len() as f64;
+    let mut gradient = vec![0.0; self.weights.len()];
+    for partial in partials {
+      for (total, value) in gradient.iter_mut().zip(partial.iter()) {
+        *total += value * scale;
+      }
+    }
+    gradient
+  }
+
+  /// Performs a single optimizer step.
+  pub fn step(&mut self) {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPSILON: f64 = 1e-8;
+
+    let gradient = self.gradient();
+    self.step_count += 1;
+    let bias1 = 1.0 - BETA1.powi(self.step_count);
+    let bias2 = 1.0 - BETA2.powi(self.step_count);
+    for (idx, g) in gradient.iter().enumerate() {
+      self.momentum[idx] = BETA1 * self.momentum[idx] + (1.0 - BETA1) * g;
+      self.velocity[idx] = BETA2 * self.velocity[idx] + (1.0 - BETA2) * g * g;
+      let m = self.momentum[idx] / bias1;
+      let v = self.velocity[idx] / bias2;
+      self.weights[idx] -= self.options.learning_rate * m / (v.sqrt() + EPSILON);
+    }
+  }
+
+  /// Runs every configured iteration, calling `progress` with the iteration number and error after each one.
+  pub fn run<F: FnMut(usize, f64)>(&mut self, mut progress: F) -> EvalParams {
+    for iteration in 1..=self.options.iterations {
+      self.step();
+      progress(iteration, self.error());
+    }
+    self.params()
+  }
+
+  /// The current weights, rounded to whole centipawns.
+  pub fn params(&self) -> EvalParams {
+    let rounded: Vec<i32> = self.weights.iter().map(|w| w.round() as i32).collect();
+    let mut params = self.params.clone();
+    params.set_from_slice(&rounded).expect("weight count should not change while tuning");
+    params
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::eval::Score;
+  use crate::piece::PieceKind;
+
+  #[test]
+  fn test_parse_epd_with_result_opcode() {
+    let position = parse_labeled_position("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 c9 \"1/2-1/2\";").unwrap();
+    assert_eq!(position.fen, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    assert_eq!(position.result, 0.5);
+  }
+
+  #[test]
+  fn test_parse_fen_with_outcome() {
+    let bracketed = parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 w - - 3 40 [1.0]").unwrap();
+    assert_eq!(bracketed.fen, "4k3/8/8/8/8/8/8/3QK3 w - - 3 40");
+    assert_eq!(bracketed.result, 1.0);
+
+    let bare = parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 w - - 3 40 0-1").unwrap();
+    assert_eq!(bare.result, 0.0);
+  }
+
+  #[test]
+  fn test_parse_invalid_result() {
+    assert_eq!(parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 w - - 3 40 [2.0]").err().unwrap(), Error::InvalidLabeledPosition);
+    // The move counters of an unlabeled FEN are not a result
+    assert!(parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").is_err());
+    assert!(parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 w - - 3 40 1").is_err());
+    assert!(parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 w - - id \"no result\";").is_err());
+  }
+
+  #[test]
+  fn test_parse_result_opcode_among_others() {
+    let position = parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 w - - id \"game 1-0\"; c9 \"0-1\"; c0 \"1/2-1/2\";").unwrap();
+    assert_eq!(position.result, 0.0);
+    let position = parse_labeled_position("4k3/8/8/8/8/8/8/3QK3 b - - 3 40 result \"1/2-1/2\";").unwrap();
+    assert_eq!(position.fen, "4k3/8/8/8/8/8/8/3QK3 b - - 3 40");
+    assert_eq!(position.result, 0.5);
+  }
+
+  #[test]
+  fn test_tuning_reduces_error() {
+    // White is a knight up in every won game, so the knight should gain value
+    let positions: Vec<LabeledPosition> = [
+      ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", 1.0),
+      ("4k3/8/8/8/8/8/8/4KN2 b - - 0 1", 1.0),
+      ("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1", 1.0),
+      ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 0.5),
+      ("4k3/8/8/8/8/8/8/3K4 b - - 0 1", 0.5),
+      ("1n2k3/8/8/8/8/8/8/4K3 w - - 0 1", 0.0),
+    ].iter().map(|(fen, result)| LabeledPosition { fen: String::from(*fen), result: *result }).collect();
+
+    let mut initial = EvalParams::default();
+    initial.piece_values[PieceKind::Knight.index()] = Score::new(10, 10);
+    let options = TunerOptions { iterations: 50, learning_rate: 5.0, threads: 2, k: Some(1.0) };
+    let mut tuner = Tuner::new(&positions, &initial, options).unwrap();
+
+    let initial_error = tuner.error();
+    let tuned = tuner.run(|_, _| {});
+    assert!(tuner.error() < initial_error);
+    assert!(tuned.piece_values[PieceKind::Knight.index()].eg > 10);
+  }
+
+  #[test]
+  fn test_thread_count_does_not_change_error() {
+    let positions = vec![
+      LabeledPosition { fen: String::from("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"), result: 0.5 },
+      LabeledPosition { fen: String::from("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"), result: 1.0 },
+      LabeledPosition { fen: String::from("4k3/8/8/8/8/8/8/3qK3 w - - 0 1"), result: 0.0 },
+    ];
+    let params = EvalParams::default();
+    let single = Tuner::new(&positions, &params, TunerOptions { threads: 1, ..Default::default() }).unwrap();
+    let multi = Tuner::new(&positions, &params, TunerOptions { threads: 3, ..Default::default() }).unwrap();
+    assert!((single.error() - multi.error()).abs() < 1e-12);
+  }
+}
@@ -1,32 +1,228 @@
+use std::sync::Arc;
 
 use crate::board::Board;
-use crate::board::coord::Coordinate;
+use crate::board::moves::{Move, MoveUndo};
+use crate::clock::{ChessClock, TimeControl, TimeSource};
+use crate::errors::Error;
+use crate::pgn::PgnGame;
 use crate::piece::Color;
+use crate::variant::Variant;
 
-struct Game {
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameResult {
+  WhiteWins,
+  BlackWins,
+  Draw,
+}
+
+impl GameResult {
+  pub fn win_for(color: Color) -> GameResult {
+    if color == Color::White { GameResult::WhiteWins } else { GameResult::BlackWins }
+  }
+}
+
+pub struct Game {
+  start: Board,
   board: Board,
-  current_player: Color
+  /// The moves played since the start, with what it takes to take each one back.
+  history: Vec<(Move, MoveUndo)>,
+  /// The same moves in SAN.
+  san: Vec<String>,
+  clock: Option<ChessClock>,
 }
 
 impl Game {
   pub fn new() -> Game {
+    Game::from_board(Board::new())
+  }
+
+  /// Creates a game starting from the given position.
+  pub fn from_board(board: Board) -> Game {
     Game {
-      board: Board::new(),
-      current_player: Color::White,
+      start: board.clone(),
+      board,
+      history: vec![],
+      san: vec![],
+      clock: None,
+    }
+  }
+
+  /// Creates a game of a variant, from its starting position.
+  pub fn with_variant(variant: Arc<dyn Variant>) -> Result<Game, Error> {
+    Ok(Game::from_board(Board::new_variant(variant)?))
+  }
+
+  /// Creates a game played on the clock. White's time starts running straight away.
+  pub fn with_clock(control: TimeControl, source: Arc<dyn TimeSource>) -> Game {
+    let mut game = Game::new();
+    game.set_clock(control, source);
+    game
+  }
+
+  /// Creates a game from a PGN game, playing through its moves.
+  pub fn from_pgn(pgn: &PgnGame) -> Result<Game, Error> {
+    let mut game = Game::from_board(pgn.get_start().clone());
+    for mv in pgn.get_moves().iter() {
+      game.make_move(*mv)?;
     }
+    Ok(game)
+  }
+
+  /// Writes the game as a PGN game with the given tags, ending with the result so far.
+  pub fn to_pgn(&self, tags: Vec<(String, String)>) -> PgnGame {
+    PgnGame::from_legal_moves(tags, self.start.clone(), self.get_moves(), self.san.clone(), self.get_result())
+  }
+
+  /// Puts the game on the clock, starting the time of the side to move.
+  pub fn set_clock(&mut self, control: TimeControl, source: Arc<dyn TimeSource>) {
+    let mut clock = ChessClock::new(control, source);
+    clock.start(self.board.get_active_color());
+    self.clock = Some(clock);
   }
 
   pub fn current_player(&self) -> Color {
     self.board.get_active_color()
   }
+
+  pub fn get_board(&self) -> &Board {
+    &self.board
+  }
+
+  pub fn get_clock(&self) -> Option<&ChessClock> {
+    self.clock.as_ref()
+  }
+
+  /// The position the game started from.
+  pub fn get_start(&self) -> &Board {
+    &self.start
+  }
+
+  /// The moves played so far, in order.
+  pub fn get_moves(&self) -> Vec<Move> {
+    self.history.iter().map(|(mv, _)| *mv).collect()
+  }
+
+  /// The moves played so far in SAN, numbered with
+  /// [`pgn::full_moves`](`crate::pgn::full_moves`) from the [start](`crate::game::Game::get_start`).
+  pub fn get_san_moves(&self) -> &[String] {
+    &self.san
+  }
+
+  pub fn get_last_move(&self) -> Option<Move> {
+    self.history.last().map(|(mv, _)| *mv)
+  }
+
+  /// Plays a move for the side to move and presses their clock.
+  pub fn make_move(&mut self, mv: Move) -> Result<(), Error> {
+    if self.get_result().is_some() {
+      return Err(Error::GameOver);
+    }
+    if !self.board.is_legal_move(&mv) {
+      return Err(Error::InvalidMove);
+    }
+
+    let san = self.board.move_to_san(&mv);
+    let undo = self.board.make_move(mv)?;
+    self.history.push((mv, undo));
+    self.san.push(san);
+    if let Some(clock) = self.clock.as_mut() {
+      clock.press();
+    }
+    Ok(())
+  }
+
+  /// Takes back the last move, returning it, and hands the clock back to the side who made it.
+  /// The time they had used stays used.
+  pub fn take_back(&mut self) -> Option<Move> {
+    let (mv, undo) = self.history.pop()?;
+    self.san.pop();
+    self.board.unmake_move(mv, undo);
+    if let Some(clock) = self.clock.as_mut() {
+      clock.start(self.board.get_active_color());
+    }
+    Some(mv)
+  }
+
+  /// Returns the result if the game is over by checkmate, stalemate, a fallen flag or the
+  /// variant's own rules.
+  pub fn get_result(&self) -> Option<GameResult> {
+    if let Some(result) = self.clock.as_ref().and_then(|clock| clock.get_result(&self.board)) {
+      return Some(result);
+    }
+    if let Some(result) = self.board.get_variant_result() {
+      return Some(result);
+    }
+
+    if self.board.get_legal_moves().is_empty() {
+      return Some(self.board.get_variant().get_result_without_moves(&self.board));
+    }
+    None
+  }
+}
+
+impl Default for Game {
+  fn default() -> Self {
+    Game::new()
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::{*};
+  use std::time::Duration;
+  use crate::clock::MockTimeSource;
+
   #[test]
   fn test_create_new_game() {
     let game = Game::new();
     assert_eq!(game.current_player(), Color::White);
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_variant_game_ends_by_its_rules() {
+    let mut game = Game::with_variant(Arc::new(crate::variant::KingOfTheHill)).unwrap();
+    for mv in ["e2e3", "e7e6", "e1e2", "e8e7", "e2d3", "e7d6"].iter() {
+      game.make_move(Move::from_uci(mv).unwrap()).unwrap();
+    }
+    assert_eq!(game.get_result(), None);
+    game.make_move(Move::from_uci("d3d4").unwrap()).unwrap();
+    assert_eq!(game.get_result(), Some(GameResult::WhiteWins));
+    assert!(game.make_move(Move::from_uci("d6c6").unwrap()).is_err());
+  }
+
+  #[test]
+  fn test_moves_press_the_clock_until_a_flag_falls() {
+    let source = Arc::new(MockTimeSource::default());
+    let mut game = Game::with_clock(TimeControl::fischer(Duration::from_secs(60), Duration::from_secs(1)), source.clone());
+    source.advance(Duration::from_secs(5));
+    game.make_move(Move::from_uci("e2e4").unwrap()).unwrap();
+
+    let clock = game.get_clock().unwrap();
+    assert_eq!(clock.get_remaining(Color::White), Duration::from_secs(56));
+    assert_eq!(clock.get_running(), Some(Color::Black));
+    assert_eq!(game.make_move(Move::from_uci("e2e4").unwrap()), Err(Error::InvalidMove));
+
+    source.advance(Duration::from_secs(60));
+    assert_eq!(game.get_result(), Some(GameResult::WhiteWins));
+    assert_eq!(game.make_move(Move::from_uci("e7e5").unwrap()), Err(Error::GameOver));
+  }
+
+  #[test]
+  fn test_take_back_and_pgn() {
+    let mut game = Game::from_board(Board::from_fen_string("6k1/5ppp/8/8/8/8/5PPP/R5K1 b - - 0 1").unwrap());
+    assert_eq!(game.take_back(), None);
+    for mv in ["g8f8", "g1f1", "f8g8"].iter() {
+      game.make_move(Move::from_uci(mv).unwrap()).unwrap();
+    }
+    assert_eq!(game.take_back(), Some(Move::from_uci("f8g8").unwrap()));
+    assert_eq!(game.get_moves().len(), 2);
+    assert_eq!(game.get_san_moves(), ["Kf8", "Kf1"]);
+    assert_eq!(game.get_board().to_fen_string(), "5k2/5ppp/8/8/8/8/5PPP/R4K2 b - - 2 2");
+
+    let text = game.to_pgn(vec![(String::from("Event"), String::from("Test"))]).to_pgn_string();
+    let loaded = Game::from_pgn(&crate::pgn::parse_pgn(&text).next().unwrap().unwrap()).unwrap();
+    assert_eq!(loaded.get_start().to_fen_string(), game.get_start().to_fen_string());
+    assert_eq!(loaded.get_moves(), game.get_moves());
+    assert_eq!(loaded.get_board().to_fen_string(), game.get_board().to_fen_string());
+  }
+}
@@ -1,4 +1,16 @@
 pub mod board;
+pub mod book;
+pub mod clock;
+pub mod epd;
 pub mod errors;
 pub mod piece;
-pub mod game;
\ No newline at end of file
+pub mod game;
+pub mod pgn;
+pub mod eval;
+pub mod search;
+pub mod svg;
+pub mod tablebase;
+pub mod uci;
+pub mod variant;
+#[cfg(feature = "nnue")]
+pub mod nnue;
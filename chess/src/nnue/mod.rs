@@ -0,0 +1,357 @@
+//! Efficiently updatable neural network evaluation.
+//!
+//! The network has 768 binary inputs per perspective (2 colors x 6 piece kinds x 64 squares),
+//! one hidden layer of `H` neurons shared by both perspectives, and a single output.
+//! The side to move's hidden layer is concatenated with the opponent's, passed through a
+//! clipped ReLU (`0..=QA`) and multiplied by the output weights.
+//!
+//! Inputs are seen from each perspective's side of the board: the perspective's own pieces come
+//! first, and Black's perspective mirrors squares vertically, so A8 is Black's "A1".
+//!
+//! # File format
+//!
+//! All integers are little-endian.
+//!
+//! | Field            | Type  | Count     |
+//! |------------------|-------|-----------|
+//! | Magic `RCNN`     | bytes | 4         |
+//! | Version (`1`)    | u32   | 1         |
+//! | Hidden size `H`  | u32   | 1         |
+//! | Feature weights  | i16   | 768 x `H`, one row of `H` per input |
+//! | Feature biases   | i16   | `H`       |
+//! | Output weights   | i16   | 2 x `H`, side to move's half first |
+//! | Output bias      | i32   | 1         |
+//!
+//! The raw output is scaled by `SCALE / (QA * QB)` to give centipawns.
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+pub mod simd;
+
+use crate::board::Board;
+use crate::board::coord::Coordinate;
+use crate::board::moves::{Move, MoveUndo};
+use crate::errors::Error;
+use crate::piece::{Color, PieceKind};
+
+pub const INPUTS: usize = 768;
+/// Quantization of the hidden layer; also the clipped ReLU ceiling.
+pub const QA: i32 = 255;
+/// Quantization of the output weights.
+pub const QB: i32 = 64;
+/// Converts network output to centipawns.
+pub const SCALE: i32 = 400;
+
+const MAGIC: &[u8; 4] = b"RCNN";
+const VERSION: u32 = 1;
+
+fn perspective_index(color: Color) -> usize {
+  match color {
+    Color::White => 0,
+    Color::Black => 1,
+  }
+}
+
+/// Returns the input index of a piece as seen from the given perspective. Networks only have
+/// inputs for the squares of an 8x8 board.
+pub fn feature_index(perspective: Color, piece_color: Color, kind: PieceKind, coord: Coordinate) -> usize {
+  let mut square = ((Into::<i8>::into(coord.rank) - 1) * 8 + Into::<i8>::into(coord.file) - 1) as usize;
+  if perspective == Color::Black {
+    square ^= 56;
+  }
+  let side = if piece_color == perspective { 0 } else { 1 };
+  side * 384 + kind.index() * 64 + square
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Network {
+  hidden_size: usize,
+  feature_weights: Vec<i16>,
+  feature_biases: Vec<i16>,
+  output_weights: Vec<i16>,
+  output_bias: i32,
+}
+
+/// A small xorshift generator, so random networks are reproducible without extra dependencies.
+struct XorShift(u64);
+
+impl XorShift {
+  fn next_in(&mut self, bound: i16) -> i16 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    ((self.0 % (2 * bound as u64 + 1)) as i64 - bound as i64) as i16
+  }
+}
+
+impl Network {
+  /// Creates a network with small random weights. Useful for testing and as a training starting point.
+  pub fn random(hidden_size: usize, seed: u64) -> Network {
+    let mut rng = XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+    Network {
+      hidden_size,
+      feature_weights: (0..INPUTS * hidden_size).map(|_| rng.next_in(64)).collect(),
+      feature_biases: (0..hidden_size).map(|_| rng.next_in(64)).collect(),
+      output_weights: (0..2 * hidden_size).map(|_| rng.next_in(64)).collect(),
+      output_bias: rng.next_in(1000) as i32,
+    }
+  }
+
+  pub fn get_hidden_size(&self) -> usize {
+    self.hidden_size
+  }
+
+  fn feature_row(&self, feature: usize) -> &[i16] {
+    &self.feature_weights[feature * self.hidden_size..(feature + 1) * self.hidden_size]
+  }
+
+  /// Parses a network in the format described in the [module documentation](`crate::nnue`).
+  pub fn from_bytes(bytes: &[u8]) -> Result<Network, Error> {
+    let mut offset = 0;
+    let mut take = |len: usize| -> Result<&[u8], Error> {
+      let slice = bytes.get(offset..offset + len).ok_or(Error::InvalidNetwork)?;
+      offset += len;
+      Ok(slice)
+    };
+    let read_u32 = |slice: &[u8]| u32::from_le_bytes(slice.try_into().unwrap());
+    let read_i16s = |slice: &[u8]| -> Vec<i16> {
+      slice.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect()
+    };
+
+    if take(4)? != MAGIC || read_u32(take(4)?) != VERSION {
+      return Err(Error::InvalidNetwork);
+    }
+
+    let hidden_size = read_u32(take(4)?) as usize;
+    if hidden_size == 0 {
+      return Err(Error::InvalidNetwork);
+    }
+
+    let feature_weights = read_i16s(take(INPUTS * hidden_size * 2)?);
+    let feature_biases = read_i16s(take(hidden_size * 2)?);
+    let output_weights = read_i16s(take(2 * hidden_size * 2)?);
+    let output_bias = i32::from_le_bytes(take(4)?.try_into().unwrap());
+    if offset != bytes.len() {
+      return Err(Error::InvalidNetwork);
+    }
+
+    Ok(Network { hidden_size, feature_weights, feature_biases, output_weights, output_bias })
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(self.hidden_size as u32).to_le_bytes());
+    for value in self.feature_weights.iter().chain(self.feature_biases.iter()).chain(self.output_weights.iter()) {
+      bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+    bytes
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Network, Error> {
+    let bytes = fs::read(path).map_err(|_| Error::IoError)?;
+    Network::from_bytes(&bytes)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+    fs::write(path, self.to_bytes()).map_err(|_| Error::IoError)
+  }
+
+  /// Computes both perspectives' hidden layers from scratch. Networks only have inputs for an
+  /// 8x8 board, so other sizes are rejected.
+  pub fn refresh(&self, board: &Board) -> Result<Accumulator, Error> {
+    if !board.is_standard_size() {
+      return Err(Error::InvalidBoardSize);
+    }
+
+    let mut accumulator = Accumulator {
+      values: [self.feature_biases.clone(), self.feature_biases.clone()],
+    };
+
+    for square in board.get_squares() {
+      let piece = match square.get_piece() {
+        Some(piece) => piece,
+        None => continue,
+      };
+      if let Some(kind) = piece.get_kind() {
+        accumulator.add(self, *piece.get_color(), kind, *square.get_coord());
+      }
+    }
+
+    Ok(accumulator)
+  }
+
+  /// Scores an accumulator in centipawns from the side to move's point of view.
+  pub fn evaluate(&self, accumulator: &Accumulator, side_to_move: Color) -> i32 {
+    let us = &accumulator.values[perspective_index(side_to_move)];
+    let them = &accumulator.values[perspective_index(side_to_move.opposite())];
+    let (our_weights, their_weights) = self.output_weights.split_at(self.hidden_size);
+
+    let output = simd::clipped_relu_dot(us, our_weights, QA as i16)
+      + simd::clipped_relu_dot(them, their_weights, QA as i16)
+      + self.output_bias;
+    output * SCALE / (QA * QB)
+  }
+}
+
+/// The hidden layer of both perspectives for one position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accumulator {
+  values: [Vec<i16>; 2],
+}
+
+impl Accumulator {
+  pub fn add(&mut self, network: &Network, piece_color: Color, kind: PieceKind, coord: Coordinate) {
+    for perspective in &[Color::White, Color::Black] {
+      let row = network.feature_row(feature_index(*perspective, piece_color, kind, coord));
+      simd::add_assign(&mut self.values[perspective_index(*perspective)], row);
+    }
+  }
+
+  pub fn remove(&mut self, network: &Network, piece_color: Color, kind: PieceKind, coord: Coordinate) {
+    for perspective in &[Color::White, Color::Black] {
+      let row = network.feature_row(feature_index(*perspective, piece_color, kind, coord));
+      simd::sub_assign(&mut self.values[perspective_index(*perspective)], row);
+    }
+  }
+
+  /// Returns the hidden layer as seen from the given perspective.
+  pub fn get_values(&self, perspective: Color) -> &[i16] {
+    &self.values[perspective_index(perspective)]
+  }
+}
+
+/// Keeps an accumulator in step with a board, updating it incrementally as moves are made
+/// and restoring the previous one when they are taken back.
+pub struct NnueEvaluator<'a> {
+  network: &'a Network,
+  stack: Vec<Accumulator>,
+}
+
+impl<'a> NnueEvaluator<'a> {
+  /// Fails with [`InvalidBoardSize`](`crate::errors::Error::InvalidBoardSize`) unless the board is 8x8.
+  pub fn new(network: &'a Network, board: &Board) -> Result<NnueEvaluator<'a>, Error> {
+    Ok(NnueEvaluator { network, stack: vec![network.refresh(board)?] })
+  }
+
+  /// Discards any history and recomputes the accumulator from scratch.
+  pub fn refresh(&mut self, board: &Board) -> Result<(), Error> {
+    self.stack = vec![self.network.refresh(board)?];
+    Ok(())
+  }
+
+  pub fn get_accumulator(&self) -> &Accumulator {
+    self.stack.last().unwrap()
+  }
+
+  /// Plays a move on the board and updates the accumulator with only the features it changes.
+  pub fn make_move(&mut self, board: &mut Board, mv: Move) -> Result<MoveUndo, Error> {
+    let (color, kind) = match (mv.drop, board.get_square(mv.from)?.get_piece()) {
+      (Some(kind), _) => (board.get_active_color(), kind),
+      (None, Some(piece)) => (*piece.get_color(), piece.get_kind().ok_or(Error::InvalidMove)?),
+      (None, None) => return Err(Error::InvalidMove),
+    };
+
+    let undo = board.make_move(mv)?;
+    let mut accumulator = self.get_accumulator().clone();
+    if !mv.is_drop() {
+      accumulator.remove(self.network, color, kind, mv.from);
+    }
+    accumulator.add(self.network, color, mv.promotion.unwrap_or(kind), undo.get_destination());
+
+    if let Some((coord, captured)) = undo.get_captured() {
+      if let Some(captured_kind) = captured.get_kind() {
+        accumulator.remove(self.network, *captured.get_color(), captured_kind, *coord);
+      }
+    }
+
+    for (coord, removed) in undo.get_removed() {
+      if let Some(removed_kind) = removed.get_kind() {
+        accumulator.remove(self.network, *removed.get_color(), removed_kind, *coord);
+      }
+    }
+
+    if let Some((rook_from, rook_to)) = undo.get_rook_move() {
+      accumulator.remove(self.network, color, PieceKind::Rook, rook_from);
+      accumulator.add(self.network, color, PieceKind::Rook, rook_to);
+    }
+
+    self.stack.push(accumulator);
+    Ok(undo)
+  }
+
+  /// Takes back a move on the board and restores the accumulator from before it.
+  pub fn unmake_move(&mut self, board: &mut Board, mv: Move, undo: MoveUndo) {
+    board.unmake_move(mv, undo);
+    if self.stack.len() > 1 {
+      self.stack.pop();
+    }
+  }
+
+  /// Scores the current position in centipawns from the side to move's point of view.
+  pub fn evaluate(&self, board: &Board) -> i32 {
+    self.network.evaluate(self.get_accumulator(), board.get_active_color())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_network_bytes_round_trip() {
+    let network = Network::random(8, 1);
+    let bytes = network.to_bytes();
+    assert_eq!(bytes.len(), 4 + 4 + 4 + (INPUTS * 8 + 8 + 16) * 2 + 4);
+    assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+    assert_eq!(Network::from_bytes(&bytes[..bytes.len() - 1]).err().unwrap(), Error::InvalidNetwork);
+  }
+
+  #[test]
+  fn test_incremental_updates_match_refresh() {
+    let network = Network::random(24, 7);
+    // Castling, en passant and promotions are all available along this line
+    let mut board = Board::from_fen_string("r3k2r/1P1pqpb1/bn2pnp1/2pPN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq c6 0 1").unwrap();
+    let mut evaluator = NnueEvaluator::new(&network, &board).unwrap();
+    let line = ["d5c6", "e8g8", "e1g1", "b4c3", "b7a8q", "c3d2", "a8b8"];
+
+    let mut played = vec![];
+    for move_str in line.iter() {
+      let mv = Move::from_uci(move_str).unwrap();
+      assert!(board.is_legal_move(&mv), "{} should be legal", move_str);
+      let undo = evaluator.make_move(&mut board, mv).unwrap();
+      assert_eq!(*evaluator.get_accumulator(), network.refresh(&board).unwrap());
+      assert_eq!(evaluator.evaluate(&board), network.evaluate(&network.refresh(&board).unwrap(), board.get_active_color()));
+      played.push((mv, undo));
+    }
+
+    while let Some((mv, undo)) = played.pop() {
+      evaluator.unmake_move(&mut board, mv, undo);
+      assert_eq!(*evaluator.get_accumulator(), network.refresh(&board).unwrap());
+    }
+  }
+
+  #[test]
+  fn test_mirrored_position_scores_the_same() {
+    let network = Network::random(16, 3);
+    let white = Board::from_fen_string("4k3/8/8/8/8/8/3P4/3QK3 w - - 0 1").unwrap();
+    let black = Board::from_fen_string("3qk3/3p4/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(
+      network.evaluate(&network.refresh(&white).unwrap(), Color::White),
+      network.evaluate(&network.refresh(&black).unwrap(), Color::Black));
+  }
+
+  #[test]
+  fn test_other_board_sizes_are_rejected() {
+    let network = Network::random(16, 1);
+    let large = Board::from_fen_string("rnbbqkbnrr/pppppppppp/10/10/10/10/10/10/PPPPPPPPPP/RNBBQKBNRR w - - 0 1").unwrap();
+    assert_eq!(network.refresh(&large).err(), Some(Error::InvalidBoardSize));
+    assert!(matches!(NnueEvaluator::new(&network, &large), Err(Error::InvalidBoardSize)));
+    let small = Board::from_fen_string("rnbqk/ppppp/5/PPPPP/RNBQK w - - 0 1").unwrap();
+    assert_eq!(network.refresh(&small).err(), Some(Error::InvalidBoardSize));
+  }
+}
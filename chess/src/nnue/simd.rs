@@ -0,0 +1,137 @@
+//! Vector kernels for the accumulator and output layer, with an AVX2 path on x86-64
+//! and a portable scalar fallback. Both paths produce identical results.
+
+/// Portable implementations, also used to check the vectorized ones.
+pub mod scalar {
+  pub fn add_assign(dst: &mut [i16], src: &[i16]) {
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+      *d = d.wrapping_add(*s);
+    }
+  }
+
+  pub fn sub_assign(dst: &mut [i16], src: &[i16]) {
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+      *d = d.wrapping_sub(*s);
+    }
+  }
+
+  /// Clamps each value to `0..=max` and returns its dot product with the weights.
+  pub fn clipped_relu_dot(values: &[i16], weights: &[i16], max: i16) -> i32 {
+    values.iter().zip(weights.iter())
+      .map(|(v, w)| (*v).clamp(0, max) as i32 * *w as i32)
+      .sum()
+  }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+  use std::arch::x86_64::*;
+
+  const LANES: usize = 16;
+
+  #[target_feature(enable = "avx2")]
+  pub unsafe fn add_assign(dst: &mut [i16], src: &[i16]) {
+    let chunks = dst.len().min(src.len()) / LANES;
+    for i in 0..chunks {
+      let d = dst.as_mut_ptr().add(i * LANES) as *mut __m256i;
+      let s = src.as_ptr().add(i * LANES) as *const __m256i;
+      _mm256_storeu_si256(d, _mm256_add_epi16(_mm256_loadu_si256(d), _mm256_loadu_si256(s)));
+    }
+    super::scalar::add_assign(&mut dst[chunks * LANES..], &src[chunks * LANES..]);
+  }
+
+  #[target_feature(enable = "avx2")]
+  pub unsafe fn sub_assign(dst: &mut [i16], src: &[i16]) {
+    let chunks = dst.len().min(src.len()) / LANES;
+    for i in 0..chunks {
+      let d = dst.as_mut_ptr().add(i * LANES) as *mut __m256i;
+      let s = src.as_ptr().add(i * LANES) as *const __m256i;
+      _mm256_storeu_si256(d, _mm256_sub_epi16(_mm256_loadu_si256(d), _mm256_loadu_si256(s)));
+    }
+    super::scalar::sub_assign(&mut dst[chunks * LANES..], &src[chunks * LANES..]);
+  }
+
+  #[target_feature(enable = "avx2")]
+  pub unsafe fn clipped_relu_dot(values: &[i16], weights: &[i16], max: i16) -> i32 {
+    let chunks = values.len().min(weights.len()) / LANES;
+    let zero = _mm256_setzero_si256();
+    let ceiling = _mm256_set1_epi16(max);
+    let mut sum = _mm256_setzero_si256();
+    for i in 0..chunks {
+      let v = _mm256_loadu_si256(values.as_ptr().add(i * LANES) as *const __m256i);
+      let w = _mm256_loadu_si256(weights.as_ptr().add(i * LANES) as *const __m256i);
+      let clamped = _mm256_min_epi16(_mm256_max_epi16(v, zero), ceiling);
+      sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clamped, w));
+    }
+
+    let mut lanes = [0i32; 8];
+    _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum);
+    lanes.iter().sum::<i32>()
+      + super::scalar::clipped_relu_dot(&values[chunks * LANES..], &weights[chunks * LANES..], max)
+  }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+  is_x86_feature_detected!("avx2")
+}
+
+pub fn add_assign(dst: &mut [i16], src: &[i16]) {
+  #[cfg(target_arch = "x86_64")]
+  {
+    if has_avx2() {
+      // Safety: AVX2 support was detected at runtime
+      return unsafe { avx2::add_assign(dst, src) };
+    }
+  }
+  scalar::add_assign(dst, src)
+}
+
+pub fn sub_assign(dst: &mut [i16], src: &[i16]) {
+  #[cfg(target_arch = "x86_64")]
+  {
+    if has_avx2() {
+      // Safety: AVX2 support was detected at runtime
+      return unsafe { avx2::sub_assign(dst, src) };
+    }
+  }
+  scalar::sub_assign(dst, src)
+}
+
+pub fn clipped_relu_dot(values: &[i16], weights: &[i16], max: i16) -> i32 {
+  #[cfg(target_arch = "x86_64")]
+  {
+    if has_avx2() {
+      // Safety: AVX2 support was detected at runtime
+      return unsafe { avx2::clipped_relu_dot(values, weights, max) };
+    }
+  }
+  scalar::clipped_relu_dot(values, weights, max)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  fn sample(len: usize, seed: i32) -> Vec<i16> {
+    (0..len as i32).map(|i| ((i * 7919 + seed * 104729) % 601 - 300) as i16).collect()
+  }
+
+  #[test]
+  fn test_vector_kernels_match_scalar() {
+    // An odd length exercises both the vector body and the scalar tail
+    let (a, b) = (sample(37, 1), sample(37, 2));
+
+    let (mut fast, mut slow) = (a.clone(), a.clone());
+    add_assign(&mut fast, &b);
+    scalar::add_assign(&mut slow, &b);
+    assert_eq!(fast, slow);
+
+    sub_assign(&mut fast, &a);
+    scalar::sub_assign(&mut slow, &a);
+    assert_eq!(fast, slow);
+    assert_eq!(fast, b);
+
+    assert_eq!(clipped_relu_dot(&a, &b, 255), scalar::clipped_relu_dot(&a, &b, 255));
+  }
+}
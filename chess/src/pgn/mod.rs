@@ -0,0 +1,442 @@
+//! Reading and writing games in Portable Game Notation.
+//!
+//! Comments, variations, NAGs and move numbers are skipped; only the tag pairs, the main line
+//! and the result are kept.
+
+use std::sync::Arc;
+
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::errors::Error;
+use crate::game::GameResult;
+use crate::piece::Color;
+use crate::variant::{self, Standard, Variant, STANDARD_START_FEN};
+
+/// Movetext lines are wrapped before this many characters.
+const MAX_LINE_LENGTH: usize = 80;
+
+fn result_symbol(result: Option<GameResult>) -> &'static str {
+  match result {
+    Some(GameResult::WhiteWins) => "1-0",
+    Some(GameResult::BlackWins) => "0-1",
+    Some(GameResult::Draw) => "1/2-1/2",
+    None => "*",
+  }
+}
+
+/// A numbered pair of moves, as in `12. Nf3 Nc6`. White's move is missing when the game starts
+/// with black to move, and black's when the game ends after white's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FullMove<'a> {
+  pub number: i32,
+  pub white: Option<&'a str>,
+  pub black: Option<&'a str>,
+}
+
+/// Numbers moves written in SAN, played from `start`.
+pub fn full_moves<'a>(start: &Board, san: &'a [String]) -> Vec<FullMove<'a>> {
+  let mut full_moves = vec![];
+  let mut number = start.get_full_move();
+  let mut moves = san.iter().map(String::as_str);
+  if start.get_active_color() == Color::Black {
+    if let Some(black) = moves.next() {
+      full_moves.push(FullMove { number, white: None, black: Some(black) });
+      number += 1;
+    }
+  }
+  while let Some(white) = moves.next() {
+    full_moves.push(FullMove { number, white: Some(white), black: moves.next() });
+    number += 1;
+  }
+  full_moves
+}
+
+/// One game from a PGN file.
+#[derive(Clone)]
+pub struct PgnGame {
+  tags: Vec<(String, String)>,
+  start: Board,
+  moves: Vec<Move>,
+  /// The moves in SAN, as they're written.
+  san: Vec<String>,
+  result: Option<GameResult>,
+}
+
+impl PgnGame {
+  /// A game of `moves` from `start`, ending with `result`, or `None` if it isn't over.
+  ///
+  /// Returns [`Error::InvalidMove`](`crate::errors::Error::InvalidMove`) if a move isn't legal
+  /// where it's played.
+  pub fn new(tags: Vec<(String, String)>, start: Board, moves: Vec<Move>, result: Option<GameResult>) -> Result<PgnGame, Error> {
+    let mut board = start.clone();
+    let mut san = vec![];
+    for mv in moves.iter() {
+      if !board.is_legal_move(mv) {
+        return Err(Error::InvalidMove);
+      }
+      san.push(board.move_to_san(mv));
+      board.make_move(*mv)?;
+    }
+    Ok(PgnGame { tags, start, moves, san, result })
+  }
+
+  /// A game whose moves are already known to be legal, with their SAN.
+  pub(crate) fn from_legal_moves(tags: Vec<(String, String)>, start: Board, moves: Vec<Move>, san: Vec<String>, result: Option<GameResult>) -> PgnGame {
+    PgnGame { tags, start, moves, san, result }
+  }
+
+  pub fn get_tags(&self) -> &[(String, String)] {
+    &self.tags
+  }
+
+  /// Returns the value of a tag pair such as `White` or `WhiteElo`.
+  pub fn get_tag(&self, name: &str) -> Option<&str> {
+    self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+  }
+
+  /// The position the game starts from: the `FEN` tag if there is one, otherwise the usual one,
+  /// in the variant the `Variant` tag names.
+  pub fn get_start(&self) -> &Board {
+    &self.start
+  }
+
+  pub fn get_moves(&self) -> &[Move] {
+    &self.moves
+  }
+
+  /// The moves in SAN.
+  pub fn get_san_moves(&self) -> &[String] {
+    &self.san
+  }
+
+  /// The result from the game termination marker, or `None` for `*`.
+  pub fn get_result(&self) -> Option<GameResult> {
+    self.result
+  }
+
+  /// Writes the game as PGN. The `Result`, `Variant`, `SetUp` and `FEN` tags are written from
+  /// the game itself, after the other tags; the variant only for games that aren't chess, and
+  /// the position only when the game doesn't start from the usual one.
+  pub fn to_pgn_string(&self) -> String {
+    let mut text = String::new();
+    for (name, value) in self.tags.iter().filter(|(name, _)| !["Result", "Variant", "SetUp", "FEN"].contains(&name.as_str())) {
+      text += &format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""));
+    }
+    let result = result_symbol(self.result);
+    text += &format!("[Result \"{}\"]\n", result);
+    let variant = self.start.get_variant().get_name();
+    if variant != Standard.get_name() {
+      text += &format!("[Variant \"{}\"]\n", variant);
+    } else if self.start.is_chess960() {
+      text += "[Variant \"Chess960\"]\n";
+    }
+    let start_fen = self.start.to_fen_string();
+    if start_fen != STANDARD_START_FEN {
+      text += &format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", start_fen);
+    }
+    text += "\n";
+
+    let mut words = vec![];
+    for full_move in full_moves(&self.start, &self.san).iter() {
+      match full_move.white {
+        Some(white) => {
+          words.push(format!("{}.", full_move.number));
+          words.push(white.to_string());
+        }
+        None => words.push(format!("{}...", full_move.number)),
+      }
+      if let Some(black) = full_move.black {
+        words.push(black.to_string());
+      }
+    }
+    words.push(result.to_string());
+
+    let mut line = String::new();
+    for word in words.iter() {
+      if !line.is_empty() && line.len() + 1 + word.len() >= MAX_LINE_LENGTH {
+        text += &line;
+        text += "\n";
+        line.clear();
+      }
+      if !line.is_empty() {
+        line.push(' ');
+      }
+      line += word;
+    }
+    text += &line;
+    text += "\n";
+    text
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+  Tag(String, String),
+  Move(&'a str),
+  Result(Option<GameResult>),
+}
+
+/// Reads games one at a time from PGN text. A game that can't be followed is returned as an
+/// error, and reading carries on with the next one.
+pub struct PgnReader<'a> {
+  text: &'a str,
+  pos: usize,
+  /// A tag read while finishing a game that had no termination marker.
+  pending: Option<Token<'a>>,
+}
+
+fn parse_tag(body: &str) -> Result<Token<'_>, Error> {
+  let body = body.trim();
+  let split = body.find(char::is_whitespace).ok_or(Error::InvalidPgn)?;
+  let value = body[split..].trim();
+  if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+    return Err(Error::InvalidPgn);
+  }
+  let value = value[1..value.len() - 1].replace("\\\"", "\"").replace("\\\\", "\\");
+  Ok(Token::Tag(body[..split].to_string(), value))
+}
+
+impl<'a> PgnReader<'a> {
+  pub fn new(text: &'a str) -> PgnReader<'a> {
+    PgnReader { text, pos: 0, pending: None }
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.text[self.pos..]
+  }
+
+  /// Moves past everything up to and including `end`, or to the end of the text.
+  fn skip_past(&mut self, end: char) {
+    self.pos = match self.rest().find(end) {
+      Some(i) => self.pos + i + end.len_utf8(),
+      None => self.text.len(),
+    };
+  }
+
+  fn next_token(&mut self) -> Option<Result<Token<'a>, Error>> {
+    if let Some(token) = self.pending.take() {
+      return Some(Ok(token));
+    }
+
+    loop {
+      let rest = self.rest();
+      let trimmed = rest.trim_start();
+      self.pos += rest.len() - trimmed.len();
+      let c = trimmed.chars().next()?;
+      match c {
+        '[' => {
+          let start = self.pos + 1;
+          self.skip_past(']');
+          let end = if self.text[..self.pos].ends_with(']') { self.pos - 1 } else { self.pos };
+          return Some(parse_tag(&self.text[start..end]));
+        }
+        '{' => self.skip_past('}'),
+        ';' | '%' => self.skip_past('\n'),
+        '$' => self.pos += trimmed.find(|c: char| c != '$' && !c.is_ascii_digit()).unwrap_or(trimmed.len()),
+        '(' => {
+          let mut depth = 0;
+          let mut in_comment = false;
+          let end = trimmed.char_indices().find(|(_, c)| {
+            match c {
+              '{' => in_comment = true,
+              '}' => in_comment = false,
+              '(' if !in_comment => depth += 1,
+              ')' if !in_comment => depth -= 1,
+              _ => {}
+            }
+            depth == 0
+          });
+          self.pos += end.map(|(i, _)| i + 1).unwrap_or(trimmed.len());
+        }
+        ')' => self.pos += 1,
+        _ => {
+          let len = trimmed.find(|c: char| c.is_whitespace() || "[]{}();$".contains(c)).unwrap_or(trimmed.len());
+          self.pos += len;
+          let symbol = &trimmed[..len];
+          let result = [Some(GameResult::WhiteWins), Some(GameResult::BlackWins), Some(GameResult::Draw), None]
+            .iter().copied().find(|result| result_symbol(*result) == symbol);
+          if let Some(result) = result {
+            return Some(Ok(Token::Result(result)));
+          }
+
+          // Move numbers may be written on their own or stuck to the move, as in `12...Nf6`
+          let symbol = match symbol.find('.') {
+            Some(i) if symbol[..i].chars().all(|c| c.is_ascii_digit()) => symbol[i..].trim_start_matches('.'),
+            _ => symbol,
+          };
+          if symbol.chars().any(|c| c.is_ascii_alphanumeric()) {
+            return Some(Ok(Token::Move(symbol)));
+          }
+        }
+      }
+    }
+  }
+}
+
+/// The position a game starts from, given its tags.
+fn start_position(tags: &[(String, String)]) -> Result<Board, Error> {
+  let tag = |name: &str| tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str());
+  let chess960 = tag("Variant").is_some_and(|name| name.eq_ignore_ascii_case("chess960"));
+  let variant = match tag("Variant") {
+    Some(name) if !chess960 => variant::from_pgn_tag(name).ok_or(Error::InvalidPgn)?,
+    _ => Arc::new(Standard),
+  };
+  let mut board = match tag("FEN") {
+    Some(fen) => Board::from_variant_fen(variant, fen)?,
+    None => Board::new_variant(variant)?,
+  };
+  board.set_chess960(chess960);
+  Ok(board)
+}
+
+impl<'a> Iterator for PgnReader<'a> {
+  type Item = Result<PgnGame, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let mut tags = vec![];
+    // The position reached so far, once the movetext has started
+    let mut board: Option<Result<Board, Error>> = None;
+    let mut moves = vec![];
+    let mut san_moves = vec![];
+    let mut error = None;
+    let mut seen_anything = false;
+
+    while let Some(token) = self.next_token() {
+      seen_anything = true;
+      match token {
+        Err(err) => error = error.or(Some(err)),
+        Ok(Token::Tag(name, value)) => {
+          if board.is_some() {
+            // A new game started before this one was terminated
+            self.pending = Some(Token::Tag(name, value));
+            break;
+          }
+          tags.push((name, value));
+        }
+        Ok(Token::Move(san)) => {
+          let position = board.get_or_insert_with(|| start_position(&tags));
+          if let (Ok(position), None) = (position, &error) {
+            match position.move_from_san(san) {
+              Ok(mv) => {
+                san_moves.push(position.move_to_san(&mv));
+                position.make_move(mv).expect("legal moves can be made");
+                moves.push(mv);
+              }
+              Err(err) => error = Some(err),
+            }
+          }
+        }
+        Ok(Token::Result(result)) => {
+          let start = start_position(&tags);
+          return Some(match (error, start) {
+            (Some(err), _) | (None, Err(err)) => Err(err),
+            (None, Ok(start)) => Ok(PgnGame { tags, start, moves, san: san_moves, result }),
+          });
+        }
+      }
+    }
+
+    if !seen_anything {
+      return None;
+    }
+    Some(Err(error.unwrap_or(Error::InvalidPgn)))
+  }
+}
+
+/// Reads every game in some PGN text.
+pub fn parse_pgn(text: &str) -> PgnReader<'_> {
+  PgnReader::new(text)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  const GAMES: &str = r#"
+[Event "Casual"]
+[White "Anderssen, A."]
+[WhiteElo "2600"]
+[Annotator "Someone \"quoted\""]
+
+1. e4 e5 2. f4 {King's Gambit} exf4 (2... d5 3. exd5 {counter} (3. Nf3) e4) 3. Bc4 $1 Qh4+
+4. Kf1 b5?! 5.Bxb5 Nf6 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1"]
+
+1. Ra8# 1-0
+
+[Event "Broken"]
+
+1. e4 e5 2. Ke3 d6 *
+
+; a line comment
+[Event "Unfinished"]
+1. d4 d5 *
+"#;
+
+  #[test]
+  fn test_read_games() {
+    let games: Vec<Result<PgnGame, Error>> = parse_pgn(GAMES).collect();
+    assert_eq!(games.len(), 4);
+
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.get_tag("White"), Some("Anderssen, A."));
+    assert_eq!(first.get_tag("WhiteElo"), Some("2600"));
+    assert_eq!(first.get_tag("Annotator"), Some("Someone \"quoted\""));
+    assert_eq!(first.get_result(), Some(GameResult::WhiteWins));
+    let moves: Vec<String> = first.get_moves().iter().map(|mv| mv.to_string()).collect();
+    assert_eq!(moves, ["e2e4", "e7e5", "f2f4", "e5f4", "f1c4", "d8h4", "e1f1", "b7b5", "c4b5", "g8f6"]);
+
+    let second = games[1].as_ref().unwrap();
+    assert_eq!(second.get_start().to_fen_string(), "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+    assert_eq!(second.get_moves(), &[Move::from_uci("a1a8").unwrap()]);
+
+    assert_eq!(games[2].as_ref().err(), Some(&Error::InvalidMove));
+    assert_eq!(games[3].as_ref().unwrap().get_result(), None);
+  }
+
+  #[test]
+  fn test_game_without_a_result_ends_at_the_next_tags() {
+    let games: Vec<Result<PgnGame, Error>> = parse_pgn("[Event \"a\"]\n1. e4\n\n[Event \"b\"]\n1. d4 1-0").collect();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].as_ref().err(), Some(&Error::InvalidPgn));
+    assert_eq!(games[1].as_ref().unwrap().get_tag("Event"), Some("b"));
+  }
+
+  #[test]
+  fn test_write_games() {
+    let games: Vec<Result<PgnGame, Error>> = parse_pgn(GAMES).collect();
+    let text = games[0].as_ref().unwrap().to_pgn_string();
+    assert!(text.starts_with("[Event \"Casual\"]\n[White \"Anderssen, A.\"]\n[WhiteElo \"2600\"]\n[Annotator \"Someone \\\"quoted\\\"\"]\n[Result \"1-0\"]\n\n"));
+    assert!(text.ends_with("\n1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ 4. Kf1 b5 5. Bxb5 Nf6 1-0\n"));
+
+    let second = games[1].as_ref().unwrap();
+    let start = Board::from_fen_string("6k1/5ppp/8/8/8/8/5PPP/R5K1 b - - 0 7").unwrap();
+    assert_eq!(PgnGame::new(vec![], start.clone(), vec![Move::from_uci("a1a8").unwrap()], None).err(), Some(Error::InvalidMove));
+    let black_first = PgnGame::new(vec![], start, vec![Move::from_uci("g8f8").unwrap()], None).unwrap();
+    assert!(black_first.to_pgn_string().ends_with("[SetUp \"1\"]\n[FEN \"6k1/5ppp/8/8/8/8/5PPP/R5K1 b - - 0 7\"]\n\n7... Kf8 *\n"));
+
+    for game in [second, &black_first].iter() {
+      let read = parse_pgn(&game.to_pgn_string()).next().unwrap().unwrap();
+      assert_eq!(read.get_start().to_fen_string(), game.get_start().to_fen_string());
+      assert_eq!(read.get_moves(), game.get_moves());
+      assert_eq!(read.get_result(), game.get_result());
+    }
+  }
+
+  #[test]
+  fn test_variant_games() {
+    let start = Board::new_variant(variant::from_name("kingofthehill").unwrap()).unwrap();
+    let game = PgnGame::new(vec![], start, vec![Move::from_uci("e2e4").unwrap()], None).unwrap();
+    let text = game.to_pgn_string();
+    assert!(text.contains("[Variant \"kingofthehill\"]\n"));
+    let read = parse_pgn(&text).next().unwrap().unwrap();
+    assert_eq!(read.get_start().get_variant().get_name(), "kingofthehill");
+
+    let read = parse_pgn("[Variant \"Three-check\"]\n\n1. e4 *").next().unwrap().unwrap();
+    assert_eq!(read.get_start().get_variant().get_name(), "3check");
+    assert_eq!(parse_pgn("[Variant \"Shogi\"]\n\n1. e4 *").next().unwrap().err(), Some(Error::InvalidPgn));
+  }
+}
@@ -0,0 +1,335 @@
+//! Fairy pieces whose movement is described in Betza notation, so new pieces need no code of
+//! their own.
+//!
+//! A description is a list of atoms, each a basic move repeated in all its directions:
+//!
+//! | Atom | Leap   | Atom | Leap   |
+//! |------|--------|------|--------|
+//! | `W`  | (1, 0) | `H`  | (3, 0) |
+//! | `F`  | (1, 1) | `C`  | (3, 1) |
+//! | `D`  | (2, 0) | `Z`  | (3, 2) |
+//! | `N`  | (2, 1) | `G`  | (3, 3) |
+//! | `A`  | (2, 2) |      |        |
+//!
+//! `K`, `R`, `B` and `Q` stand for the chess pieces. A doubled atom rides, so `NN` is a
+//! nightrider, and a number limits how far, as in `W2`. Lowercase prefixes restrict an atom:
+//! `m` to moves and `c` to captures, and `f`, `b`, `l`, `r`, `v` (forwards and backwards) and
+//! `s` (sideways) to those directions, as the mover sees them. A forward or backward prefix
+//! directly followed by a left or right one picks out a single diagonal, as in `flF`. On oblique
+//! atoms such as `N`, `v` and `s` pick the narrow and wide moves, and a doubled prefix the narrow
+//! ones on its side, so `ffN` is the two knight moves straight ahead.
+//!
+//! So `BN` is an archbishop, `RN` a chancellor, `WfF` a Shogi gold general and `FfW` a silver.
+
+use std::sync::Arc;
+
+use crate::board::{Board, MAX_BOARD_SIZE};
+use crate::board::coord::Coordinate;
+use crate::errors::Error;
+use crate::piece::{Color, Piece, PieceKind};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Modality {
+  MoveOrCapture,
+  MoveOnly,
+  CaptureOnly,
+}
+
+/// One part of a piece's movement: steps in the given directions, repeated up to `range` times
+/// along a line until something is in the way.
+#[derive(Clone, Debug, PartialEq)]
+struct Atom {
+  /// Directions as white sees them; black's are mirrored.
+  directions: Vec<(i8, i8)>,
+  range: i8,
+  modality: Modality,
+}
+
+impl Atom {
+  fn can_move(&self) -> bool {
+    self.modality != Modality::CaptureOnly
+  }
+
+  fn can_capture(&self) -> bool {
+    self.modality != Modality::MoveOnly
+  }
+}
+
+/// Every orientation of a leap, without repeats.
+fn leap_directions((x, y): (i8, i8)) -> Vec<(i8, i8)> {
+  let mut directions = vec![];
+  for (dx, dy) in [(x, y), (y, x)].iter() {
+    for (sx, sy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)].iter() {
+      let direction = (dx * sx, dy * sy);
+      if !directions.contains(&direction) {
+        directions.push(direction);
+      }
+    }
+  }
+  directions
+}
+
+/// The leaps an atom letter stands for, and whether it rides without a range being given.
+fn atom_leaps(letter: char) -> Option<(Vec<(i8, i8)>, bool)> {
+  let leaps = match letter {
+    'W' => (vec![(1, 0)], false),
+    'F' => (vec![(1, 1)], false),
+    'D' => (vec![(2, 0)], false),
+    'N' => (vec![(2, 1)], false),
+    'A' => (vec![(2, 2)], false),
+    'H' => (vec![(3, 0)], false),
+    'C' => (vec![(3, 1)], false),
+    'Z' => (vec![(3, 2)], false),
+    'G' => (vec![(3, 3)], false),
+    'K' => (vec![(1, 0), (1, 1)], false),
+    'R' => (vec![(1, 0)], true),
+    'B' => (vec![(1, 1)], true),
+    'Q' => (vec![(1, 0), (1, 1)], true),
+    _ => return None,
+  };
+  Some(leaps)
+}
+
+/// Whether a direction, as the mover sees it, fits one of the direction prefixes.
+fn fits_direction(prefix: &str, (dx, dy): (i8, i8)) -> bool {
+  let vertical = dy.abs() > dx.abs();
+  let sideways = dx.abs() > dy.abs();
+  let doubled = prefix.len() == 2 && prefix[..1] == prefix[1..];
+  prefix.chars().all(|c| match c {
+    'f' => dy > 0 && (!doubled || vertical),
+    'b' => dy < 0 && (!doubled || vertical),
+    'l' => dx < 0 && (!doubled || sideways),
+    'r' => dx > 0 && (!doubled || sideways),
+    'v' => vertical,
+    's' => sideways,
+    _ => true,
+  })
+}
+
+/// Reads the atoms of a Betza description.
+fn parse_atoms(betza: &str) -> Result<Vec<Atom>, Error> {
+  let mut atoms = vec![];
+  let mut chars = betza.chars().peekable();
+  while chars.peek().is_some() {
+    let mut modality = Modality::MoveOrCapture;
+    // Direction prefixes, each a single letter, a doubled one or a forward/backward and left/right pair
+    let mut prefixes: Vec<String> = vec![];
+    while let Some(c) = chars.next_if(char::is_ascii_lowercase) {
+      match c {
+        'm' => modality = Modality::MoveOnly,
+        'c' => modality = Modality::CaptureOnly,
+        'f' | 'b' | 'l' | 'r' if chars.peek() == Some(&c) => {
+          prefixes.push([c, chars.next().unwrap()].iter().collect());
+        }
+        'f' | 'b' if chars.peek().is_some_and(|next| *next == 'l' || *next == 'r') => {
+          prefixes.push([c, chars.next().unwrap()].iter().collect());
+        }
+        'f' | 'b' | 'l' | 'r' | 'v' | 's' => prefixes.push(c.to_string()),
+        _ => return Err(Error::InvalidBetza),
+      }
+    }
+
+    let letter = chars.next().ok_or(Error::InvalidBetza)?;
+    let (leaps, rides) = atom_leaps(letter).ok_or(Error::InvalidBetza)?;
+    let mut range = if rides { MAX_BOARD_SIZE } else { 1 };
+    if chars.next_if_eq(&letter).is_some() {
+      range = MAX_BOARD_SIZE;
+    } else if chars.peek().is_some_and(char::is_ascii_digit) {
+      let mut digits = String::new();
+      while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+      }
+      range = digits.parse().ok().filter(|range| (1..=MAX_BOARD_SIZE).contains(range)).ok_or(Error::InvalidBetza)?;
+    }
+
+    let directions: Vec<(i8, i8)> = leaps.into_iter().flat_map(leap_directions)
+      .filter(|direction| prefixes.is_empty() || prefixes.iter().any(|prefix| fits_direction(prefix, *direction)))
+      .collect();
+    if directions.is_empty() {
+      return Err(Error::InvalidBetza);
+    }
+    atoms.push(Atom { directions, range, modality });
+  }
+
+  if atoms.is_empty() {
+    return Err(Error::InvalidBetza);
+  }
+  Ok(atoms)
+}
+
+/// A kind of fairy piece: the letter it goes by in FEN and how it moves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PieceDefinition {
+  letter: char,
+  betza: String,
+  atoms: Vec<Atom>,
+}
+
+impl PieceDefinition {
+  /// Defines a piece written as `letter` in FEN, uppercase for white and lowercase for black,
+  /// moving as the Betza description says. The letters of the chess pieces are taken.
+  pub fn new(letter: char, betza: &str) -> Result<PieceDefinition, Error> {
+    let letter = letter.to_ascii_uppercase();
+    if !letter.is_ascii_uppercase() || PieceKind::from_short_name(&letter.to_string()).is_some() {
+      return Err(Error::InvalidBetza);
+    }
+    Ok(PieceDefinition { letter, betza: String::from(betza), atoms: parse_atoms(betza)? })
+  }
+
+  /// The piece's letter, in uppercase.
+  pub fn get_letter(&self) -> char {
+    self.letter
+  }
+
+  pub fn get_betza(&self) -> &str {
+    &self.betza
+  }
+
+  /// A rough value in centipawns, from how many directions the piece has and how far it goes.
+  /// A knight comes out at 320 and a rook at 400.
+  pub fn get_value(&self) -> i32 {
+    self.atoms.iter().map(|atom| {
+      let per_direction = (40 + 20 * (atom.range as i32 - 1)).min(100);
+      let per_direction = if atom.modality == Modality::MoveOrCapture { per_direction } else { per_direction / 2 };
+      atom.directions.len() as i32 * per_direction
+    }).sum()
+  }
+}
+
+/// A piece moving by a [`PieceDefinition`](`crate::piece::betza::PieceDefinition`).
+pub struct FairyPiece {
+  definition: Arc<PieceDefinition>,
+  short_name: String,
+  color: Color,
+  position: Coordinate,
+}
+
+impl FairyPiece {
+  pub fn new(definition: Arc<PieceDefinition>, color: Color, position: Coordinate) -> Self {
+    let short_name = definition.get_letter().to_string();
+    FairyPiece { definition, short_name, color, position }
+  }
+
+  /// The squares the piece reaches, following the atoms that allow moving or capturing.
+  fn get_targets(&self, board: &Board, attacks_only: bool) -> Vec<Coordinate> {
+    let forward: i8 = if self.color == Color::White { 1 } else { -1 };
+    let mut targets: Vec<Coordinate> = vec![];
+    for atom in self.definition.atoms.iter() {
+      for (dx, dy) in atom.directions.iter() {
+        for i in 1..=atom.range {
+          let coord = self.position + (i * dx, i * dy * forward);
+          if !coord.is_valid() {
+            break;
+          }
+          let empty = board.can_move(&coord);
+          let reaches = if attacks_only {
+            atom.can_capture() && (empty || board.can_capture(&coord, &self.color))
+          } else if empty {
+            atom.can_move()
+          } else {
+            atom.can_capture() && board.can_capture(&coord, &self.color)
+          };
+          if reaches && !targets.contains(&coord) {
+            targets.push(coord);
+          }
+          if !empty {
+            break;
+          }
+        }
+      }
+    }
+    targets
+  }
+}
+
+impl Piece for FairyPiece {
+  fn get_color(&self) -> &Color { &self.color }
+
+  fn get_position(&self) -> &Coordinate { &self.position }
+
+  fn get_short_name(&self) -> &str { &self.short_name }
+
+  fn get_definition(&self) -> Option<&PieceDefinition> { Some(&self.definition) }
+
+  fn clone_at(&self, position: Coordinate) -> Box<dyn Piece> {
+    Box::new(FairyPiece::new(Arc::clone(&self.definition), self.color, position))
+  }
+
+  fn get_moves(&self, board: &Board) -> Vec<Coordinate> {
+    self.get_targets(board, false)
+  }
+
+  fn get_attacks(&self, board: &Board) -> Vec<Coordinate> {
+    self.get_targets(board, true)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  fn moves(fen: &str, pieces: &[Arc<PieceDefinition>], square: &str) -> Vec<String> {
+    let board = Board::from_fen_string_with_pieces(fen, pieces).unwrap();
+    let coord = Coordinate::get_coordinate(square).unwrap();
+    let mut moves: Vec<String> = board.get_square(coord).unwrap().get_piece().as_ref().unwrap()
+      .get_moves(&board).iter().map(|coord| coord.to_string().to_lowercase()).collect();
+    moves.sort();
+    moves
+  }
+
+  #[test]
+  fn test_parse_betza() {
+    assert_eq!(PieceDefinition::new('A', "BN").unwrap().atoms.len(), 2);
+    assert_eq!(PieceDefinition::new('x', "NN").unwrap().atoms[0].range, MAX_BOARD_SIZE);
+    assert_eq!(PieceDefinition::new('W', "W2").unwrap().atoms[0].range, 2);
+    assert_eq!(PieceDefinition::new('G', "WfF").unwrap().atoms[1].directions, vec![(1, 1), (-1, 1)]);
+    assert_eq!(PieceDefinition::new('L', "flF").unwrap().atoms[0].directions, vec![(-1, 1)]);
+    assert_eq!(PieceDefinition::new('V', "vW").unwrap().atoms[0].directions, vec![(0, 1), (0, -1)]);
+    assert_eq!(PieceDefinition::new('V', "vN").unwrap().atoms[0].directions, vec![(1, 2), (1, -2), (-1, 2), (-1, -2)]);
+    assert_eq!(PieceDefinition::new('S', "sN").unwrap().atoms[0].directions, vec![(2, 1), (2, -1), (-2, 1), (-2, -1)]);
+    assert_eq!(PieceDefinition::new('F', "ffN").unwrap().atoms[0].directions, vec![(1, 2), (-1, 2)]);
+    assert_eq!(PieceDefinition::new('F', "fN").unwrap().atoms[0].directions.len(), 4);
+    assert_eq!(PieceDefinition::new('A', "N").unwrap().get_value(), 320);
+    assert_eq!(PieceDefinition::new('A', "R").unwrap().get_value(), 400);
+
+    assert_eq!(PieceDefinition::new('Q', "BN").err(), Some(Error::InvalidBetza));
+    assert_eq!(PieceDefinition::new('1', "BN").err(), Some(Error::InvalidBetza));
+    for betza in ["", "X", "fs", "W0", "W11", "xW", "sF", "ffF"].iter() {
+      assert_eq!(PieceDefinition::new('A', betza).err(), Some(Error::InvalidBetza), "{}", betza);
+    }
+  }
+
+  #[test]
+  fn test_fairy_moves() {
+    let gold = Arc::new(PieceDefinition::new('G', "WfF").unwrap());
+    assert_eq!(moves("4k/5/2G2/5/K4 w - - 0 1", &[Arc::clone(&gold)], "c3"), vec!["b3", "b4", "c2", "c4", "d3", "d4"]);
+    // Black's forward is down the board
+    assert_eq!(moves("4k/5/2g2/5/K4 b - - 0 1", &[gold], "c3"), vec!["b2", "b3", "c2", "c4", "d2", "d3"]);
+
+    // Moves straight but only captures diagonally, like a pawn that can go any way
+    let sergeant = Arc::new(PieceDefinition::new('S', "mWcF").unwrap());
+    let board = Board::from_fen_string_with_pieces("4k/5/1pS2/5/K4 w - - 0 1", &[Arc::clone(&sergeant)]).unwrap();
+    assert_eq!(moves("4k/3p1/1pS2/5/K4 w - - 0 1", &[Arc::clone(&sergeant)], "c3"), vec!["c2", "c4", "d3", "d4"]);
+    assert!(board.is_square_attacked(&Coordinate::get_coordinate("b2").unwrap(), &Color::White));
+    assert!(!board.is_square_attacked(&Coordinate::get_coordinate("c2").unwrap(), &Color::White));
+
+    // A nightrider stops at the first piece in its way
+    let nightrider = Arc::new(PieceDefinition::new('X', "NN").unwrap());
+    assert_eq!(moves("k5/6/6/6/6/X5 w - - 0 1", &[Arc::clone(&nightrider)], "a1"), vec!["b3", "c2", "c5", "e3"]);
+    assert_eq!(moves("k5/6/6/6/2p3/X5 w - - 0 1", &[nightrider], "a1"), vec!["b3", "c2", "c5"]);
+  }
+
+  #[test]
+  fn test_capablanca_perft() {
+    let archbishop = Arc::new(PieceDefinition::new('A', "BN").unwrap());
+    let chancellor = Arc::new(PieceDefinition::new('C', "RN").unwrap());
+    let fen = "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1";
+    let board = Board::from_fen_string_with_pieces(fen, &[archbishop, chancellor]).unwrap();
+    assert_eq!(board.to_fen_string(), fen);
+    assert_eq!(board.perft(1), 28);
+    assert_eq!(board.perft(2), 784);
+    assert_eq!(board.perft(3), 25228);
+
+    assert_eq!(Board::from_fen_string(fen).err(), Some(Error::InvalidFENString));
+  }
+}
@@ -5,17 +5,21 @@ pub struct King {
   position: Coordinate,
 }
 
-impl Piece for King {
-  fn new(color: Color, position: Coordinate) -> Self {
+impl King {
+  pub fn new(color: Color, position: Coordinate) -> Self {
     King { color, position }
   }
+}
 
+impl Piece for King {
   fn get_color(&self) -> &Color { &self.color }
 
   fn get_position(&self) -> &Coordinate { &self.position }
 
   fn get_short_name(&self) -> &'static str { "K" }
 
+  fn clone_at(&self, position: Coordinate) -> Box<dyn Piece> { Box::new(King::new(self.color, position)) }
+
   fn get_moves(&self, board: &Board) -> Vec<Coordinate> {
     let mut potential_moves: Vec<Coordinate> = vec!();
 
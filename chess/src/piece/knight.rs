@@ -5,17 +5,21 @@ pub struct Knight {
   position: Coordinate,
 }
 
-impl Piece for Knight {
-  fn new(color: Color, position: Coordinate) -> Self {
+impl Knight {
+  pub fn new(color: Color, position: Coordinate) -> Self {
     Knight { color, position }
   }
+}
 
+impl Piece for Knight {
   fn get_color(&self) -> &Color { &self.color }
 
   fn get_position(&self) -> &Coordinate { &self.position }
 
   fn get_short_name(&self) -> &'static str { "N" }
 
+  fn clone_at(&self, position: Coordinate) -> Box<dyn Piece> { Box::new(Knight::new(self.color, position)) }
+
   fn get_moves(&self, board: &Board) -> Vec<Coordinate> {
     let mut potential_moves: Vec<Coordinate> = vec!();
 
@@ -1,3 +1,4 @@
+pub mod betza;
 pub mod bishop;
 pub mod king;
 pub mod knight;
@@ -8,31 +9,119 @@ pub mod rook;
 use std::fmt::Display;
 use std::fmt;
 use crate::board::{Board, coord::Coordinate};
+use crate::piece::betza::PieceDefinition;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Color {
   White,
   Black,
 }
 
+impl Color {
+  /// Returns the opposing [`Color`](`crate::piece::Color`).
+  pub fn opposite(&self) -> Color {
+    match *self {
+      Color::White => Color::Black,
+      Color::Black => Color::White,
+    }
+  }
+
+  /// Zero-based index: 0 for white, 1 for black.
+  pub fn index(&self) -> usize {
+    *self as usize
+  }
+}
+
+/// The kind of a standard chess piece, independent of its color or position.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PieceKind {
+  Pawn,
+  Knight,
+  Bishop,
+  Rook,
+  Queen,
+  King,
+}
+
+impl PieceKind {
+  /// Attempts to map a piece's short name (case-insensitive) to a [`PieceKind`](`crate::piece::PieceKind`).
+  pub fn from_short_name(short_name: &str) -> Option<PieceKind> {
+    match short_name {
+      "P" | "p" => Some(PieceKind::Pawn),
+      "N" | "n" => Some(PieceKind::Knight),
+      "B" | "b" => Some(PieceKind::Bishop),
+      "R" | "r" => Some(PieceKind::Rook),
+      "Q" | "q" => Some(PieceKind::Queen),
+      "K" | "k" => Some(PieceKind::King),
+      _ => None,
+    }
+  }
+
+  pub fn get_short_name(&self) -> &'static str {
+    match *self {
+      PieceKind::Pawn => "P",
+      PieceKind::Knight => "N",
+      PieceKind::Bishop => "B",
+      PieceKind::Rook => "R",
+      PieceKind::Queen => "Q",
+      PieceKind::King => "K",
+    }
+  }
+
+  /// Zero-based index, in the order pawn, knight, bishop, rook, queen, king.
+  pub fn index(&self) -> usize {
+    *self as usize
+  }
+}
+
 impl Display for Color {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    match self.clone() {
+    match *self {
       Color::White => write!(f, "W"),
       Color::Black => write!(f, "B"),
     }
   }
 }
 
-pub trait Piece {
-  fn new(color: Color, position: Coordinate) -> Self where Self: Sized;
+/// Creates a standard chess piece of the given kind.
+pub fn make_piece(kind: PieceKind, color: Color, position: Coordinate) -> Box<dyn Piece> {
+  match kind {
+    PieceKind::Pawn => Box::new(pawn::Pawn::new(color, position)),
+    PieceKind::Knight => Box::new(knight::Knight::new(color, position)),
+    PieceKind::Bishop => Box::new(bishop::Bishop::new(color, position)),
+    PieceKind::Rook => Box::new(rook::Rook::new(color, position)),
+    PieceKind::Queen => Box::new(queen::Queen::new(color, position)),
+    PieceKind::King => Box::new(king::King::new(color, position)),
+  }
+}
 
+/// Pieces are plain data, so boards can be shared between search threads.
+pub trait Piece: Send + Sync {
   fn get_color(&self) -> &Color;
 
   fn get_position(&self) -> &Coordinate;
 
-  fn get_short_name(&self) -> &'static str;
+  fn get_short_name(&self) -> &str;
+
+  /// Returns the [`PieceKind`](`crate::piece::PieceKind`) of this piece, if it is a standard chess piece.
+  fn get_kind(&self) -> Option<PieceKind> {
+    PieceKind::from_short_name(self.get_short_name())
+  }
+
+  /// Returns how the piece moves, if it is a [fairy piece](`crate::piece::betza::FairyPiece`).
+  fn get_definition(&self) -> Option<&PieceDefinition> {
+    None
+  }
+
+  /// Returns a copy of this piece placed at another coordinate.
+  fn clone_at(&self, position: Coordinate) -> Box<dyn Piece>;
 
   /// Returns the legal moves a piece can make given the board state and it's own coordinates.
   fn get_moves(&self, board: &Board) -> Vec<Coordinate>;
+
+  /// Returns the squares the piece could capture on, were an enemy piece standing there. Only
+  /// pieces that move and capture differently need to override it.
+  fn get_attacks(&self, board: &Board) -> Vec<Coordinate> {
+    self.get_moves(board)
+  }
 }
\ No newline at end of file
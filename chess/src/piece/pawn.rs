@@ -6,17 +6,21 @@ pub struct Pawn {
   position: Coordinate,
 }
 
-impl Piece for Pawn {
-  fn new(color: Color, position: Coordinate) -> Self {
+impl Pawn {
+  pub fn new(color: Color, position: Coordinate) -> Self {
     Pawn { color, position }
   }
+}
 
+impl Piece for Pawn {
   fn get_color(&self) -> &Color { &self.color }
 
   fn get_position(&self) -> &Coordinate { &self.position }
 
   fn get_short_name(&self) -> &'static str { "P" }
 
+  fn clone_at(&self, position: Coordinate) -> Box<dyn Piece> { Box::new(Pawn::new(self.color, position)) }
+
   fn get_moves(&self, board: &Board) -> Vec<Coordinate> {
     let forward: i8 = if self.color == Color::White { 1 } else { -1 };
     let forward_move: (i8, i8) = (0, forward);
@@ -24,13 +28,13 @@ impl Piece for Pawn {
     let mut potential_moves: Vec<Coordinate> = vec!();
 
     let one_square_move = self.position + forward_move;
-    match board.get_square(one_square_move) {
-      Ok(square) if square.get_piece().as_ref().is_none() => { potential_moves.push(one_square_move); },
-      _ => {}
+    let one_square_clear = one_square_move.is_valid() && board.can_move(&one_square_move);
+    if one_square_clear {
+      potential_moves.push(one_square_move);
     }
 
-    // Pawns that haven't moved yet can move two squares
-    if *self.get_color() == Color::White && self.position.rank == Rank::Two || *self.get_color() == Color::Black && self.position.rank == Rank::Seven {
+    // Pawns that haven't moved yet can move two squares, as long as nothing blocks the first one
+    if one_square_clear && board.get_variant().can_double_push(board, self.color, self.position.rank) {
       let two_square_move = self.position + (0, forward * 2);
       match board.get_square(two_square_move) {
         Ok(square) if square.get_piece().as_ref().is_none() => { potential_moves.push(two_square_move); },
@@ -38,12 +42,14 @@ impl Piece for Pawn {
       }
     }
 
+    // Only the side that can be captured en passant leaves a target behind it, on its third rank
+    let en_passant_rank = if self.color == Color::White { Rank::from(board.get_height() - 2) } else { Rank::Three };
     let capture_squares: Vec<Coordinate> = vec![self.position + (1, forward), self.position + (-1, forward)];
     for capture in capture_squares {
       if !capture.is_valid() { continue; }
       match board.get_square(capture) {
         Ok(_) if board.can_capture(&capture, &self.color) => { potential_moves.push(capture); },
-        Ok(_) if board.get_en_passant_target().as_ref().is_some() && board.get_en_passant_target().as_ref().unwrap() == &capture => { potential_moves.push(capture); }
+        Ok(_) if board.get_en_passant_target().as_ref() == Some(&capture) && capture.rank == en_passant_rank => { potential_moves.push(capture); }
         _ => {},
       }
     }
@@ -1,25 +1,30 @@
 use crate::piece::{*};
+use crate::board::MAX_BOARD_SIZE;
 
 pub struct Rook {
   color: Color,
   position: Coordinate,
 }
 
-impl Piece for Rook {
-  fn new(color: Color, position: Coordinate) -> Self {
+impl Rook {
+  pub fn new(color: Color, position: Coordinate) -> Self {
     Rook { color, position }
   }
+}
 
+impl Piece for Rook {
   fn get_color(&self) -> &Color { &self.color }
 
   fn get_position(&self) -> &Coordinate { &self.position }
 
   fn get_short_name(&self) -> &'static str { "R" }
 
+  fn clone_at(&self, position: Coordinate) -> Box<dyn Piece> { Box::new(Rook::new(self.color, position)) }
+
   fn get_moves(&self, board: &Board) -> Vec<Coordinate> {
     let mut potential_moves: Vec<Coordinate> = vec!();
     for offset in &[(0, 1), (0, -1), (1, 0), (-1, 0)] {
-      for i in 1..8 {
+      for i in 1..MAX_BOARD_SIZE {
         match self.position + (i * offset.0, i * offset.1) {
           coord if !coord.is_valid() => { break; },
           coord if board.can_move(&coord) => { potential_moves.push(coord); },
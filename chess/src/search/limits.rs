@@ -0,0 +1,65 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::search::MAX_PLY;
+use crate::search::time::TimeManager;
+
+/// When a search should end.
+pub struct SearchLimits {
+  pub depth: u32,
+  pub time: Option<TimeManager>,
+  /// Keep searching until told to stop, even after reaching `depth`.
+  pub infinite: bool,
+}
+
+impl SearchLimits {
+  pub fn depth(depth: u32) -> SearchLimits {
+    SearchLimits { depth, time: None, infinite: false }
+  }
+
+  pub fn time(time: TimeManager) -> SearchLimits {
+    SearchLimits { depth: MAX_PLY as u32 / 2, time: Some(time), infinite: false }
+  }
+
+  pub fn infinite() -> SearchLimits {
+    SearchLimits { depth: MAX_PLY as u32 / 2, time: None, infinite: true }
+  }
+}
+
+/// Lets another thread stop a running search or tell a pondering search that the expected move
+/// was played.
+///
+/// Signals are not cleared when a search starts, since a `stop` may arrive before the search
+/// thread gets going. Call [`reset`](`SearchSignals::reset`) before starting each search instead.
+#[derive(Clone, Default)]
+pub struct SearchSignals {
+  stop: Arc<AtomicBool>,
+  pondering: Arc<AtomicBool>,
+}
+
+impl SearchSignals {
+  /// Prepares for a new search; `ponder` starts it on the opponent's time.
+  pub fn reset(&self, ponder: bool) {
+    self.stop.store(false, Ordering::SeqCst);
+    self.pondering.store(ponder, Ordering::SeqCst);
+  }
+
+  /// Asks the search to return its best move as soon as possible.
+  pub fn stop(&self) {
+    self.pondering.store(false, Ordering::SeqCst);
+    self.stop.store(true, Ordering::SeqCst);
+  }
+
+  /// The opponent played the move being pondered on; from now on the search keeps time normally.
+  pub fn ponderhit(&self) {
+    self.pondering.store(false, Ordering::SeqCst);
+  }
+
+  pub fn is_stop_requested(&self) -> bool {
+    self.stop.load(Ordering::Relaxed)
+  }
+
+  pub fn is_pondering(&self) -> bool {
+    self.pondering.load(Ordering::Relaxed)
+  }
+}
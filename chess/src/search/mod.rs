@@ -0,0 +1,896 @@
+pub mod limits;
+pub mod movepick;
+pub mod options;
+pub mod smp;
+pub mod time;
+pub mod tt;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::eval::{self, EvalParams};
+use crate::game::GameResult;
+pub use crate::search::limits::{SearchLimits, SearchSignals};
+use crate::search::movepick::{MoveOrdering, MovePicker};
+pub use crate::search::options::SearchOptions;
+use crate::search::smp::BestMoveAggregator;
+use crate::search::time::{SystemTimeSource, TimeManager, TimeSource};
+use crate::search::tt::{Bound, TranspositionTable, TtEntry};
+use crate::tablebase::{self, Tablebase, Wdl};
+
+/// Score of delivering checkmate right now; mates further away score slightly less.
+pub const MATE_SCORE: i32 = 30000;
+/// Scores beyond this are mates.
+pub const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+pub const INFINITY: i32 = 32000;
+pub const MAX_PLY: usize = 128;
+/// Score of reaching a tablebase win right now; below every mate score.
+pub const TB_WIN_SCORE: i32 = MATE_BOUND - MAX_PLY as i32;
+
+/// Half-width of the first aspiration window, in centipawns.
+const ASPIRATION_WINDOW: i32 = 25;
+const REVERSE_FUTILITY_MARGIN: i32 = 80;
+const FUTILITY_MARGINS: [i32; 4] = [0, 200, 350, 500];
+/// How many nodes are searched between looks at the clock and the signals.
+const CHECKUP_INTERVAL: u64 = 1024;
+/// How often a finished infinite or ponder search looks for a `stop` or `ponderhit`.
+const WAIT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Outcome of a search: the best move found, its score and the line the search expects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+  pub best_move: Option<Move>,
+  /// Centipawns from the side to move's point of view, or a mate score.
+  pub score: i32,
+  /// Last fully completed depth.
+  pub depth: u32,
+  pub nodes: u64,
+  pub pv: Vec<Move>,
+  /// The best lines found, best first; as many as the `multi_pv` option asks for,
+  /// or fewer if there aren't that many legal moves. The first line is `pv`.
+  pub lines: Vec<PvLine>,
+}
+
+impl SearchResult {
+  fn empty() -> SearchResult {
+    SearchResult { best_move: None, score: 0, depth: 0, nodes: 0, pv: vec![], lines: vec![] }
+  }
+}
+
+/// Progress of a running search, reported after every completed iteration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo {
+  pub depth: u32,
+  /// Deepest ply reached, including quiescence and extensions.
+  pub seldepth: u32,
+  /// Nodes searched so far by all threads. Helper threads report in batches, so this is approximate.
+  pub nodes: u64,
+  pub nps: u64,
+  /// How full the transposition table is, in permille.
+  pub hashfull: u32,
+  pub elapsed: Duration,
+  pub score: i32,
+  pub pv: Vec<Move>,
+  /// Every principal variation in MultiPV mode, best first.
+  pub lines: Vec<PvLine>,
+}
+
+/// One of the principal variations reported in MultiPV mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PvLine {
+  pub score: i32,
+  pub depth: u32,
+  pub pv: Vec<Move>,
+}
+
+/// Iterative deepening alpha-beta search over a handcrafted evaluation.
+///
+/// With more than one thread, helper threads search the same position alongside the main
+/// thread (Lazy SMP), sharing what they learn through the transposition table.
+pub struct Searcher {
+  params: EvalParams,
+  options: SearchOptions,
+  ordering: MoveOrdering,
+  nodes: u64,
+  /// Triangular principal variation table; `pv[ply]` is the best line found from that ply.
+  pv: Vec<Vec<Move>>,
+  /// Best move of the last completed iteration, tried first at the root.
+  root_best: Option<Move>,
+  /// Root moves already reported as better lines in this MultiPV iteration.
+  excluded_root_moves: Vec<Move>,
+  tablebase: Option<Arc<dyn Tablebase>>,
+  /// The root moves that keep the tablebase result, when the root is in the tablebase.
+  tablebase_root_moves: Option<Vec<Move>>,
+  tt: Arc<TranspositionTable>,
+  /// Raised once the main thread is done, so helper threads give up their current iteration.
+  stop: Arc<AtomicBool>,
+  /// Only the main thread keeps time.
+  time: Option<TimeManager>,
+  signals: SearchSignals,
+  /// Whether the main thread last saw the search pondering, to notice a `ponderhit`.
+  pondering: bool,
+  time_source: Arc<dyn TimeSource>,
+  search_start: Duration,
+  seldepth: usize,
+  /// Nodes of every thread, added in batches.
+  total_nodes: Arc<AtomicU64>,
+}
+
+impl Default for Searcher {
+  fn default() -> Self {
+    Searcher::new(EvalParams::default())
+  }
+}
+
+impl Searcher {
+  pub fn new(params: EvalParams) -> Searcher {
+    Searcher::with_options(params, SearchOptions::default())
+  }
+
+  pub fn with_options(params: EvalParams, options: SearchOptions) -> Searcher {
+    Searcher {
+      params,
+      ordering: MoveOrdering::default(),
+      nodes: 0,
+      pv: vec![vec![]; MAX_PLY + 1],
+      root_best: None,
+      excluded_root_moves: vec![],
+      tablebase: None,
+      tablebase_root_moves: None,
+      tt: Arc::new(TranspositionTable::new(options.hash_size_mb)),
+      stop: Arc::new(AtomicBool::new(false)),
+      time: None,
+      signals: SearchSignals::default(),
+      pondering: false,
+      time_source: Arc::new(SystemTimeSource::default()),
+      search_start: Duration::ZERO,
+      seldepth: 0,
+      total_nodes: Arc::new(AtomicU64::new(0)),
+      options,
+    }
+  }
+
+  /// Creates a searcher for a helper thread, sharing this one's table and stop flag.
+  fn make_helper(&self) -> Searcher {
+    Searcher {
+      params: self.params.clone(),
+      options: self.options.clone(),
+      ordering: MoveOrdering::default(),
+      nodes: 0,
+      pv: vec![vec![]; MAX_PLY + 1],
+      root_best: None,
+      excluded_root_moves: vec![],
+      tablebase: self.tablebase.clone(),
+      tablebase_root_moves: self.tablebase_root_moves.clone(),
+      tt: Arc::clone(&self.tt),
+      stop: Arc::clone(&self.stop),
+      time: None,
+      signals: self.signals.clone(),
+      pondering: false,
+      time_source: Arc::clone(&self.time_source),
+      search_start: self.search_start,
+      seldepth: 0,
+      total_nodes: Arc::clone(&self.total_nodes),
+    }
+  }
+
+  pub fn get_options(&self) -> &SearchOptions {
+    &self.options
+  }
+
+  pub fn set_options(&mut self, options: SearchOptions) {
+    if options.hash_size_mb != self.options.hash_size_mb {
+      self.tt = Arc::new(TranspositionTable::new(options.hash_size_mb));
+    }
+    self.options = options;
+  }
+
+  /// Returns the signals that stop this searcher or end its pondering from another thread.
+  pub fn get_signals(&self) -> SearchSignals {
+    self.signals.clone()
+  }
+
+  /// Sets where elapsed time for `nps` comes from.
+  pub fn set_time_source(&mut self, time_source: Arc<dyn TimeSource>) {
+    self.time_source = time_source;
+  }
+
+  /// Sets the endgame tablebase consulted at the root and during the search, if any.
+  pub fn set_tablebase(&mut self, tablebase: Option<Arc<dyn Tablebase>>) {
+    self.tablebase = tablebase;
+  }
+
+  pub fn get_ordering(&self) -> &MoveOrdering {
+    &self.ordering
+  }
+
+  /// Forgets everything learned in earlier searches, e.g. when starting a new game.
+  pub fn clear(&mut self) {
+    self.ordering.clear();
+    self.tt.clear();
+    self.root_best = None;
+  }
+
+  /// Searches the position to the given depth, one iteration at a time, on as many threads
+  /// as the options allow. `nodes` in the result counts every thread's nodes.
+  pub fn search(&mut self, board: &Board, max_depth: u32) -> SearchResult {
+    self.search_with_listener(board, SearchLimits::depth(max_depth), |_| {})
+  }
+
+  /// Searches until the time manager says to stop, or to `max_depth` if that comes first.
+  pub fn search_with_time(&mut self, board: &Board, max_depth: u32, time: TimeManager) -> SearchResult {
+    self.search_with_listener(board, SearchLimits { depth: max_depth, time: Some(time), infinite: false }, |_| {})
+  }
+
+  /// Searches within the given limits, calling `listener` after every completed iteration so
+  /// progress can be shown while the search is still running. To receive updates on another
+  /// thread, send them down a channel from the listener.
+  ///
+  /// Infinite and pondering searches only return once [`stop`](`SearchSignals::stop`) is called,
+  /// or for a pondering search, once [`ponderhit`](`SearchSignals::ponderhit`) is called and
+  /// time runs out.
+  pub fn search_with_listener<F: FnMut(&SearchInfo)>(&mut self, board: &Board, limits: SearchLimits, mut listener: F) -> SearchResult {
+    let SearchLimits { depth: max_depth, time, infinite } = limits;
+    self.time = time;
+    self.pondering = self.signals.is_pondering();
+    self.search_start = self.time_source.now();
+    self.total_nodes.store(0, Ordering::Relaxed);
+    self.stop.store(false, Ordering::Relaxed);
+    self.tablebase_root_moves = self.tablebase.as_ref().and_then(|tablebase| tablebase::filter_root_moves(tablebase.as_ref(), board));
+    let aggregator = BestMoveAggregator::new();
+    let helpers: Vec<Searcher> = (1..self.options.threads.max(1)).map(|_| self.make_helper()).collect();
+
+    let helper_nodes: u64 = thread::scope(|scope| {
+      let handles: Vec<_> = helpers.into_iter().enumerate().map(|(i, mut helper)| {
+        let aggregator = &aggregator;
+        scope.spawn(move || {
+          helper.iterate(board, max_depth, i + 1, aggregator, &mut |_| {});
+          helper.nodes
+        })
+      }).collect();
+
+      self.iterate(board, max_depth, 0, &aggregator, &mut listener);
+      // The best move can't be played until the GUI says so
+      while (infinite || self.pondering) && !self.signals.is_stop_requested() {
+        thread::sleep(WAIT_INTERVAL);
+        self.check_ponderhit();
+      }
+      self.stop.store(true, Ordering::Relaxed);
+      handles.into_iter().map(|handle| handle.join().expect("search thread panicked")).sum()
+    });
+
+    let mut result = aggregator.get_best().unwrap_or_else(SearchResult::empty);
+    result.nodes = self.nodes + helper_nodes;
+    self.time = None;
+    result
+  }
+
+  /// Switches from pondering to keeping time once the GUI sends `ponderhit`.
+  fn check_ponderhit(&mut self) {
+    if self.pondering && !self.signals.is_pondering() {
+      self.pondering = false;
+      if let Some(time) = self.time.as_mut() {
+        time.restart();
+      }
+    }
+  }
+
+  fn make_info(&self, result: &SearchResult) -> SearchInfo {
+    let elapsed = self.time_source.now().saturating_sub(self.search_start);
+    let nodes = self.total_nodes.load(Ordering::Relaxed) + self.nodes % CHECKUP_INTERVAL;
+    let nps = if elapsed.as_micros() == 0 { 0 } else { (nodes as u128 * 1_000_000 / elapsed.as_micros()) as u64 };
+    SearchInfo {
+      depth: result.depth,
+      seldepth: self.seldepth as u32,
+      nodes,
+      nps,
+      hashfull: self.tt.hashfull(),
+      elapsed,
+      score: result.score,
+      pv: result.pv.clone(),
+      lines: result.lines.clone(),
+    }
+  }
+
+  /// Runs iterative deepening for one thread, reporting each completed iteration.
+  /// Odd numbered helpers search a ply ahead of the main thread so the threads spread out.
+  fn iterate(&mut self, board: &Board, max_depth: u32, thread_id: usize, aggregator: &BestMoveAggregator, listener: &mut dyn FnMut(&SearchInfo)) {
+    let mut board = board.clone();
+    self.nodes = 0;
+    self.seldepth = 0;
+    self.root_best = None;
+    self.ordering.killers.clear();
+
+    let root_moves = match self.tablebase_root_moves.as_ref() {
+      Some(moves) => moves.len(),
+      None => board.get_legal_moves().len(),
+    };
+    let line_count = self.options.multi_pv.clamp(1, root_moves.max(1));
+    let mut previous_lines: Vec<PvLine> = vec![];
+    for iteration in 1..=max_depth.max(1) {
+      let depth = (iteration + (thread_id % 2) as u32).min(max_depth.max(1));
+
+      // Each line is the best move once the moves of the lines above it are taken away
+      let mut lines = Vec::with_capacity(line_count);
+      self.excluded_root_moves.clear();
+      for index in 0..line_count {
+        let previous = previous_lines.get(index);
+        self.root_best = previous.and_then(|line| line.pv.first().copied());
+        let score = self.aspiration_search(&mut board, depth as i32, previous.map_or(0, |line| line.score));
+        let pv = self.pv[0].clone();
+        self.excluded_root_moves.extend(pv.first());
+        lines.push(PvLine { score, depth, pv });
+      }
+      self.excluded_root_moves.clear();
+
+      if self.stop.load(Ordering::Relaxed) && (thread_id > 0 || iteration > 1) {
+        break;
+      }
+
+      lines.sort_by_key(|line| -line.score);
+      let best = lines[0].clone();
+      self.root_best = best.pv.first().copied();
+      previous_lines = lines.clone();
+      let result = SearchResult { best_move: self.root_best, score: best.score, depth, nodes: self.nodes, pv: best.pv, lines };
+      listener(&self.make_info(&result));
+      self.check_ponderhit();
+      let out_of_time = self.time.as_mut().is_some_and(|time| !time.should_continue(&result, root_moves));
+      // While pondering the clock isn't ours, so the search must go on
+      let out_of_time = out_of_time && !self.pondering;
+      aggregator.submit(result);
+      if out_of_time {
+        break;
+      }
+    }
+  }
+
+  /// Searches the root with a window around the previous iteration's score, widening it on failure.
+  fn aspiration_search(&mut self, board: &mut Board, depth: i32, previous_score: i32) -> i32 {
+    if !self.options.aspiration_windows || depth < 4 || previous_score.abs() >= MATE_BOUND {
+      return self.negamax(board, depth, 0, -INFINITY, INFINITY, None, true);
+    }
+
+    let mut delta = ASPIRATION_WINDOW;
+    let mut alpha = (previous_score - delta).max(-INFINITY);
+    let mut beta = (previous_score + delta).min(INFINITY);
+    loop {
+      let score = self.negamax(board, depth, 0, alpha, beta, None, true);
+      if self.stop.load(Ordering::Relaxed) {
+        return score;
+      }
+      if score <= alpha {
+        alpha = (score - delta).max(-INFINITY);
+      } else if score >= beta {
+        beta = (score + delta).min(INFINITY);
+      } else {
+        return score;
+      }
+      delta *= 2;
+    }
+  }
+
+  fn update_pv(&mut self, ply: usize, mv: Move) {
+    let (head, tail) = self.pv.split_at_mut(ply + 1);
+    head[ply].clear();
+    head[ply].push(mv);
+    head[ply].extend_from_slice(&tail[0]);
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn negamax(&mut self, board: &mut Board, mut depth: i32, ply: usize, mut alpha: i32, beta: i32, previous: Option<Move>, allow_null: bool) -> i32 {
+    self.pv[ply].clear();
+    if self.stop.load(Ordering::Relaxed) || (ply > 0 && board.get_half_move_clock() >= 100) {
+      return 0;
+    }
+    if let Some(score) = variant_score(board, ply).filter(|_| ply > 0) {
+      return score;
+    }
+
+    let color = board.get_active_color();
+    let in_check = board.is_in_check(&color);
+    if in_check && self.options.check_extensions && ply < MAX_PLY / 2 {
+      depth += 1;
+    }
+
+    if depth <= 0 || ply >= MAX_PLY {
+      return self.quiescence(board, ply, alpha, beta);
+    }
+    self.nodes += 1;
+    self.checkup(ply);
+
+    let is_pv = beta - alpha > 1;
+    let original_alpha = alpha;
+    let hash = board.get_hash();
+    let tt_entry = self.tt.probe(hash, ply);
+    if let Some(entry) = tt_entry {
+      let usable = match entry.bound {
+        Bound::Exact => true,
+        Bound::Lower => entry.score >= beta,
+        Bound::Upper => entry.score <= alpha,
+      };
+      if usable && ply > 0 && !is_pv && entry.depth >= depth {
+        return entry.score;
+      }
+    }
+
+    // Positions just reached by a capture or pawn move are looked up in the tablebase, which
+    // settles them unless a win or loss still leaves the window open. Then the result still
+    // bounds the score of a PV node, whose exact score is wanted.
+    let mut min_score = -INFINITY;
+    let mut max_score = INFINITY;
+    if ply > 0 && board.get_half_move_clock() == 0 {
+      if let Some(wdl) = self.probe_tablebase(board) {
+        let (score, bound) = match wdl {
+          Wdl::Win => (TB_WIN_SCORE - ply as i32, Bound::Lower),
+          Wdl::Loss => (-TB_WIN_SCORE + ply as i32, Bound::Upper),
+          // The fifty-move rule makes the rest draws
+          _ => (0, Bound::Exact),
+        };
+        let cutoff = match bound {
+          Bound::Exact => true,
+          Bound::Lower => score >= beta,
+          Bound::Upper => score <= alpha,
+        };
+        if cutoff {
+          let depth = (depth + 6).min(MAX_PLY as i32);
+          self.tt.store(hash, ply, TtEntry { best_move: None, score, depth, bound });
+          return score;
+        }
+        if is_pv && bound == Bound::Lower {
+          min_score = score;
+          alpha = alpha.max(score);
+        } else if is_pv {
+          max_score = score;
+        }
+      }
+    }
+
+    let static_eval = if in_check { -INFINITY } else { eval::evaluate(board, &self.params) };
+    let can_prune = ply > 0 && !is_pv && !in_check && beta.abs() < MATE_BOUND;
+
+    if can_prune && self.options.reverse_futility_pruning && depth <= 6
+      && static_eval - REVERSE_FUTILITY_MARGIN * depth >= beta {
+      return static_eval;
+    }
+
+    // Zugzwang is common when only pawns are left, where passing would be better than any move
+    if can_prune && self.options.null_move_pruning && allow_null && depth >= 3
+      && static_eval >= beta && board.has_non_pawn_material(&color) {
+      let reduction = 2 + depth / 4;
+      let undo = board.make_null_move();
+      let score = -self.negamax(board, depth - 1 - reduction, ply + 1, -beta, -beta + 1, None, false);
+      board.unmake_null_move(undo);
+      if score >= beta {
+        return if score >= MATE_BOUND { beta } else { score };
+      }
+    }
+
+    let futile = can_prune && self.options.futility_pruning && (depth as usize) < FUTILITY_MARGINS.len()
+      && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha;
+
+    let hash_move = if ply == 0 && self.root_best.is_some() {
+      self.root_best
+    } else {
+      tt_entry.and_then(|entry| entry.best_move)
+    };
+    let counter_move = self.ordering.counters.get(previous);
+    let mut picker = MovePicker::new(hash_move, self.ordering.killers.get(ply), counter_move);
+
+    let mut best_score = min_score;
+    let mut best_move = None;
+    let mut moves_played = 0;
+    let mut failed_quiets = vec![];
+    while let Some(mv) = picker.next(board, &self.ordering) {
+      if ply == 0 && (self.excluded_root_moves.contains(&mv)
+        || self.tablebase_root_moves.as_ref().is_some_and(|moves| !moves.contains(&mv))) {
+        continue;
+      }
+      let is_quiet = mv.promotion.is_none() && !board.is_capture(&mv);
+      let undo = board.make_move(mv).expect("picked moves should be playable");
+      let gives_check = board.is_in_check(&color.opposite());
+
+      if futile && moves_played > 0 && is_quiet && !gives_check {
+        board.unmake_move(mv, undo);
+        continue;
+      }
+      moves_played += 1;
+
+      let reduction = if self.options.late_move_reductions && depth >= 3 && moves_played > 3
+        && is_quiet && !in_check && !gives_check {
+        let r = ((depth as f64).ln() * (moves_played as f64).ln() / 2.0) as i32;
+        r.clamp(1, depth - 2)
+      } else {
+        0
+      };
+
+      let score = if moves_played == 1 {
+        -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, Some(mv), true)
+      } else if self.options.principal_variation_search {
+        let mut score = -self.negamax(board, depth - 1 - reduction, ply + 1, -alpha - 1, -alpha, Some(mv), true);
+        if score > alpha && reduction > 0 {
+          score = -self.negamax(board, depth - 1, ply + 1, -alpha - 1, -alpha, Some(mv), true);
+        }
+        if score > alpha && score < beta {
+          score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, Some(mv), true);
+        }
+        score
+      } else {
+        let mut score = alpha + 1;
+        if reduction > 0 {
+          score = -self.negamax(board, depth - 1 - reduction, ply + 1, -alpha - 1, -alpha, Some(mv), true);
+        }
+        if score > alpha {
+          score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, Some(mv), true);
+        }
+        score
+      };
+      board.unmake_move(mv, undo);
+
+      if score > best_score {
+        best_score = score;
+        best_move = Some(mv);
+        if score > alpha {
+          alpha = score;
+          self.update_pv(ply, mv);
+        }
+      }
+
+      if alpha >= beta {
+        if is_quiet {
+          self.ordering.record_cutoff(color, ply, depth, previous, mv, &failed_quiets);
+        }
+        break;
+      }
+
+      if is_quiet {
+        failed_quiets.push(mv);
+      }
+    }
+
+    // Futility pruning always keeps the first move, so no moves played means no legal moves
+    if moves_played == 0 {
+      return result_score(board, board.get_variant().get_result_without_moves(board), ply);
+    }
+
+    best_score = best_score.min(max_score);
+
+    // A root searched without some of its moves doesn't have its true score
+    if !self.stop.load(Ordering::Relaxed) && (ply > 0 || (self.excluded_root_moves.is_empty() && self.tablebase_root_moves.is_none())) {
+      let bound = if best_score >= beta {
+        Bound::Lower
+      } else if best_score > original_alpha {
+        Bound::Exact
+      } else {
+        Bound::Upper
+      };
+      self.tt.store(hash, ply, TtEntry { best_move, score: best_score, depth, bound });
+    }
+
+    best_score
+  }
+
+  fn probe_tablebase(&self, board: &Board) -> Option<Wdl> {
+    let tablebase = self.tablebase.as_ref()?;
+    if !tablebase::is_probeable(tablebase.as_ref(), board) {
+      return None;
+    }
+    tablebase.probe_wdl(board)
+  }
+
+  /// Called at every node. Tracks the selective depth and now and then raises the stop flag
+  /// if a stop was requested or the hard time limit has passed.
+  fn checkup(&mut self, ply: usize) {
+    self.seldepth = self.seldepth.max(ply);
+    if !self.nodes.is_multiple_of(CHECKUP_INTERVAL) {
+      return;
+    }
+
+    self.total_nodes.fetch_add(CHECKUP_INTERVAL, Ordering::Relaxed);
+    self.check_ponderhit();
+    let out_of_time = !self.pondering && self.time.as_ref().is_some_and(|time| time.is_hard_limit_reached());
+    if out_of_time || self.signals.is_stop_requested() {
+      self.stop.store(true, Ordering::Relaxed);
+    }
+  }
+
+  /// Searches captures until the position is quiet, so the evaluation isn't taken mid-exchange.
+  fn quiescence(&mut self, board: &mut Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+    self.nodes += 1;
+    self.checkup(ply);
+    if let Some(score) = variant_score(board, ply) {
+      return score;
+    }
+    let stand_pat = eval::evaluate(board, &self.params);
+    if stand_pat >= beta || ply >= MAX_PLY {
+      return stand_pat;
+    }
+    alpha = alpha.max(stand_pat);
+
+    let mut picker = MovePicker::new_captures(None);
+    while let Some(mv) = picker.next(board, &self.ordering) {
+      let undo = board.make_move(mv).expect("picked moves should be playable");
+      let score = -self.quiescence(board, ply + 1, -beta, -alpha);
+      board.unmake_move(mv, undo);
+
+      if score >= beta {
+        return score;
+      }
+      alpha = alpha.max(score);
+    }
+
+    alpha
+  }
+}
+
+/// The score of a finished game from the side to move's point of view. Wins score like mates,
+/// so the search prefers the quickest.
+fn result_score(board: &Board, result: GameResult, ply: usize) -> i32 {
+  match result {
+    GameResult::Draw => 0,
+    result if result == GameResult::win_for(board.get_active_color()) => MATE_SCORE - ply as i32,
+    _ => -MATE_SCORE + ply as i32,
+  }
+}
+
+/// The score of a game the variant's rules have ended.
+fn variant_score(board: &Board, ply: usize) -> Option<i32> {
+  board.get_variant_result().map(|result| result_score(board, result, ply))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::time::Duration;
+  use crate::search::time::{ClockState, MockTimeSource};
+  use crate::piece::Color;
+
+  #[test]
+  fn test_finds_mate_in_one() {
+    let board = Board::from_fen_string("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+    let result = Searcher::default().search(&board, 2);
+    assert_eq!(result.best_move, Some(Move::from_uci("a1a8").unwrap()));
+    assert_eq!(result.score, MATE_SCORE - 1);
+  }
+
+  #[test]
+  fn test_captures_hanging_queen() {
+    let board = Board::from_fen_string("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1").unwrap();
+    let result = Searcher::default().search(&board, 2);
+    assert_eq!(result.best_move, Some(Move::from_uci("e3d5").unwrap()));
+    assert_eq!(result.pv[0], Move::from_uci("e3d5").unwrap());
+  }
+
+  #[test]
+  fn test_every_option_finds_the_same_tactic() {
+    // Scholar's mate
+    let board = Board::from_fen_string("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+    let toggles: Vec<fn(&mut SearchOptions)> = vec![
+      |o| o.null_move_pruning = false,
+      |o| o.late_move_reductions = false,
+      |o| o.futility_pruning = false,
+      |o| o.reverse_futility_pruning = false,
+      |o| o.aspiration_windows = false,
+      |o| o.check_extensions = false,
+      |o| o.principal_variation_search = false,
+      |o| *o = SearchOptions::none(),
+    ];
+
+    for toggle in toggles {
+      let mut options = SearchOptions::default();
+      toggle(&mut options);
+      let result = Searcher::with_options(EvalParams::default(), options.clone()).search(&board, 2);
+      assert_eq!(result.best_move, Some(Move::from_uci("h5f7").unwrap()), "{:?}", options);
+      assert_eq!(result.score, MATE_SCORE - 1);
+    }
+  }
+
+  #[test]
+  fn test_pruning_searches_fewer_nodes() {
+    let board = Board::from_fen_string("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+    let full = Searcher::with_options(EvalParams::default(), SearchOptions::none()).search(&board, 3);
+    let pruned = Searcher::default().search(&board, 3);
+    assert!(pruned.nodes < full.nodes, "{} should be fewer than {}", pruned.nodes, full.nodes);
+  }
+
+  #[test]
+  fn test_null_move_is_skipped_in_pawn_endings() {
+    // King and pawn endings are full of zugzwang, so passing must not be considered here
+    let board = Board::from_fen_string("8/8/8/4k3/8/4K3/4P3/8 w - - 0 1").unwrap();
+    assert!(!board.has_non_pawn_material(&crate::piece::Color::White));
+    let without_null_move = SearchOptions { null_move_pruning: false, ..SearchOptions::default() };
+    let with = Searcher::default().search(&board, 5);
+    let without = Searcher::with_options(EvalParams::default(), without_null_move).search(&board, 5);
+    assert_eq!(with, without);
+  }
+
+  #[test]
+  fn test_stalemate_is_a_draw_near_the_leaves() {
+    // Kb6 stalemates; Rc8+ would have mated after Kb6 if black had a move
+    let board = Board::from_fen_string("k7/P7/2K5/8/8/8/8/2R5 w - - 0 1").unwrap();
+    for depth in 2..=4 {
+      let result = Searcher::default().search(&board, depth);
+      assert_ne!(result.best_move, Some(Move::from_uci("c6b6").unwrap()));
+      assert!(result.score > 500);
+    }
+
+    // At a non-PV node far below alpha, where futility pruning is on
+    let mut stalemate = Board::from_fen_string("k7/P7/1K6/8/8/8/8/2R5 b - - 1 1").unwrap();
+    let mut searcher = Searcher::default();
+    assert_eq!(searcher.negamax(&mut stalemate, 2, 1, -500, -499, None, true), 0);
+  }
+
+  #[test]
+  fn test_single_thread_is_deterministic() {
+    let board = Board::from_fen_string("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+    let first = Searcher::default().search(&board, 4);
+    let second = Searcher::default().search(&board, 4);
+    assert_eq!(first, second);
+  }
+
+  #[test]
+  fn test_helper_threads_agree_on_tactics() {
+    let options = SearchOptions { threads: 4, ..SearchOptions::default() };
+    let mut searcher = Searcher::with_options(EvalParams::default(), options);
+
+    let board = Board::from_fen_string("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+    let result = searcher.search(&board, 3);
+    assert_eq!(result.best_move, Some(Move::from_uci("a1a8").unwrap()));
+    assert_eq!(result.score, MATE_SCORE - 1);
+    assert_eq!(result.depth, 3);
+
+    let board = Board::from_fen_string("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1").unwrap();
+    searcher.clear();
+    assert_eq!(searcher.search(&board, 3).best_move, Some(Move::from_uci("e3d5").unwrap()));
+  }
+
+  #[test]
+  fn test_time_manager_limits_search() {
+    let board = Board::from_fen_string("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+    let source = Arc::new(MockTimeSource::default());
+    let clock = ClockState { time_left: Duration::from_secs(60), increment: Duration::ZERO, moves_to_go: None };
+
+    // The clock never moves, so the search only ends at the depth limit
+    let result = Searcher::default().search_with_time(&board, 3, TimeManager::new(&clock, source.clone()));
+    assert_eq!(result.depth, 3);
+
+    // Out of time before starting: the first iteration is still completed so there is a move to play
+    let time = TimeManager::new(&clock, source.clone());
+    source.advance(Duration::from_secs(60));
+    let result = Searcher::default().search_with_time(&board, 10, time);
+    assert_eq!(result.depth, 1);
+    assert!(result.best_move.is_some());
+  }
+
+  #[test]
+  fn test_single_reply_is_played_without_searching_deeper() {
+    let board = Board::from_fen_string("k7/8/8/8/8/8/1q6/K7 w - - 0 1").unwrap();
+    let clock = ClockState { time_left: Duration::from_secs(60), increment: Duration::ZERO, moves_to_go: None };
+    let time = TimeManager::new(&clock, Arc::new(MockTimeSource::default()));
+    let result = Searcher::default().search_with_time(&board, 10, time);
+    assert_eq!(result.depth, 1);
+    assert_eq!(result.best_move, Some(Move::from_uci("a1b2").unwrap()));
+  }
+
+  #[test]
+  fn test_multi_pv_ranks_lines() {
+    // Taking the rook with check and taking the queen are far ahead of everything else
+    let board = Board::from_fen_string("4k3/8/8/3q4/r7/4N3/8/3BK3 w - - 0 1").unwrap();
+    let options = SearchOptions { multi_pv: 3, ..SearchOptions::default() };
+    let mut searcher = Searcher::with_options(EvalParams::default(), options);
+
+    let mut iterations = vec![];
+    let result = searcher.search_with_listener(&board, SearchLimits::depth(3), |info| iterations.push(info.depth));
+    assert_eq!(iterations, vec![1, 2, 3]);
+
+    assert_eq!(result.lines.len(), 3);
+    let mut winning: Vec<String> = result.lines[0..2].iter().map(|line| line.pv[0].to_string()).collect();
+    winning.sort();
+    assert_eq!(winning, vec!["d1a4", "e3d5"]);
+    assert!(result.lines[1].score > result.lines[2].score + 300);
+    assert_eq!(result.lines[0].pv, result.pv);
+    assert!(result.lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    assert!(result.lines.iter().all(|line| line.depth == 3));
+
+    let mut first_moves: Vec<Move> = result.lines.iter().map(|line| line.pv[0]).collect();
+    first_moves.dedup();
+    assert_eq!(first_moves.len(), 3);
+  }
+
+  #[test]
+  fn test_multi_pv_is_capped_by_legal_moves() {
+    let board = Board::from_fen_string("k7/8/8/8/8/8/1q6/K7 w - - 0 1").unwrap();
+    let options = SearchOptions { multi_pv: 4, ..SearchOptions::default() };
+    let result = Searcher::with_options(EvalParams::default(), options).search(&board, 2);
+    assert_eq!(result.lines.len(), 1);
+    assert_eq!(result.best_move, Some(Move::from_uci("a1b2").unwrap()));
+  }
+
+  #[test]
+  fn test_infinite_search_streams_info_until_stopped() {
+    let board = Board::from_fen_string("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+    let mut searcher = Searcher::default();
+    let signals = searcher.get_signals();
+    signals.reset(false);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let stopper = thread::spawn(move || {
+      let first: SearchInfo = receiver.recv().unwrap();
+      signals.stop();
+      first
+    });
+    let result = searcher.search_with_listener(&board, SearchLimits::infinite(), |info| {
+      let _ = sender.send(info.clone());
+    });
+
+    let first = stopper.join().unwrap();
+    assert_eq!(first.depth, 1);
+    assert!(first.seldepth >= 1);
+    assert!(first.nodes > 0);
+    assert_eq!(first.pv, first.lines[0].pv);
+    assert!(result.best_move.is_some());
+  }
+
+  #[test]
+  fn test_stalemate_scores_zero() {
+    let board = Board::from_fen_string("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    let result = Searcher::default().search(&board, 1);
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, 0);
+  }
+
+  /// Decides every position of up to four pieces with a rule of its own.
+  struct RuleTablebase(fn(&Board) -> Wdl);
+
+  impl Tablebase for RuleTablebase {
+    fn get_max_pieces(&self) -> usize {
+      4
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+      Some((self.0)(board))
+    }
+
+    fn probe_dtz(&self, board: &Board) -> Option<i32> {
+      Some(self.probe_wdl(board)?.value() * 3)
+    }
+  }
+
+  #[test]
+  fn test_tablebase_scores_positions_reached_by_captures() {
+    // Taking the rook leaves four pieces, which the tablebase calls a win for the queen
+    let queen_wins = RuleTablebase(|board| {
+      let white_queen = tablebase::material_signature(board).starts_with("KQ");
+      if white_queen == (board.get_active_color() == Color::White) { Wdl::Win } else { Wdl::Loss }
+    });
+    let board = Board::from_fen_string("3r2k1/8/8/8/3Q4/8/8/K1N5 w - - 0 1").unwrap();
+    let mut searcher = Searcher::default();
+    searcher.set_tablebase(Some(Arc::new(queen_wins)));
+    let result = searcher.search(&board, 2);
+    assert_eq!(result.best_move, Move::from_uci("d4d8").ok());
+    assert_eq!(result.score, TB_WIN_SCORE - 1);
+  }
+
+  #[test]
+  fn test_tablebase_restricts_root_moves() {
+    // A tablebase that calls queen against bare king a draw, so taking the rook throws the win away
+    let board = Board::from_fen_string("3r2k1/8/8/8/3Q4/8/8/K7 w - - 0 1").unwrap();
+    let rook_matters = RuleTablebase(|board| {
+      match tablebase::material_signature(board).as_str() {
+        "KQvKR" if board.get_active_color() == Color::White => Wdl::Win,
+        "KQvKR" => Wdl::Loss,
+        _ => Wdl::Draw,
+      }
+    });
+    let mut searcher = Searcher::default();
+    assert_eq!(searcher.search(&board, 2).best_move, Move::from_uci("d4d8").ok());
+
+    searcher.set_tablebase(Some(Arc::new(rook_matters)));
+    let result = searcher.search(&board, 2);
+    assert!(result.best_move.is_some());
+    assert_ne!(result.best_move, Move::from_uci("d4d8").ok());
+  }
+}
@@ -0,0 +1,480 @@
+use crate::board::{Board, MAX_BOARD_SIZE};
+use crate::board::coord::Coordinate;
+use crate::board::moves::Move;
+use crate::piece::{Color, PieceKind};
+
+/// Piece values used for move ordering and static exchange evaluation.
+pub fn piece_value(kind: PieceKind) -> i32 {
+  match kind {
+    PieceKind::Pawn => 100,
+    PieceKind::Knight => 320,
+    PieceKind::Bishop => 330,
+    PieceKind::Rook => 500,
+    PieceKind::Queen => 900,
+    PieceKind::King => 20000,
+  }
+}
+
+fn kind_at(board: &Board, coord: Coordinate) -> Option<PieceKind> {
+  board.get_square(coord).ok()?.get_piece().as_ref()?.get_kind()
+}
+
+/// Value of the piece a move captures, counting en passant as a pawn.
+fn victim_value(board: &Board, mv: &Move) -> i32 {
+  match kind_at(board, mv.to) {
+    Some(kind) => piece_value(kind),
+    None if board.is_capture(mv) => piece_value(PieceKind::Pawn),
+    None => 0,
+  }
+}
+
+/// Squares are numbered as on the largest board, so the tables fit boards of any size.
+const SQUARES: usize = MAX_BOARD_SIZE as usize * MAX_BOARD_SIZE as usize;
+
+fn square_index(coord: Coordinate) -> usize {
+  ((Into::<i8>::into(coord.rank) - 1) * MAX_BOARD_SIZE + Into::<i8>::into(coord.file) - 1) as usize
+}
+
+/// Finds the side to move's least valuable piece that could capture on the target square.
+fn least_valuable_attacker(board: &Board, target: Coordinate) -> Option<Move> {
+  let color = board.get_active_color();
+  let forward: i8 = if color == Color::White { 1 } else { -1 };
+  board.get_squares().iter()
+    .filter_map(|sq| {
+      let piece = sq.get_piece().as_ref()?;
+      if *piece.get_color() != color {
+        return None;
+      }
+
+      let kind = piece.get_kind()?;
+      let attacks = match kind {
+        PieceKind::Pawn => target == *sq.get_coord() + (1, forward) || target == *sq.get_coord() + (-1, forward),
+        _ => piece.get_moves(board).contains(&target),
+      };
+      if attacks { Some((piece_value(kind), Move::new(*sq.get_coord(), target))) } else { None }
+    })
+    .min_by_key(|(value, _)| *value)
+    .map(|(_, mv)| mv)
+}
+
+/// Material the side to move can win by starting a sequence of captures on the target square,
+/// where either side may stop capturing whenever it would lose material.
+fn exchange_on(board: &mut Board, target: Coordinate) -> i32 {
+  let attacker = match least_valuable_attacker(board, target) {
+    Some(attacker) => attacker,
+    None => return 0,
+  };
+
+  let gain = kind_at(board, target).map(piece_value).unwrap_or(0);
+  let undo = match board.make_move(attacker) {
+    Ok(undo) => undo,
+    Err(_) => return 0,
+  };
+  let value = gain - exchange_on(board, target);
+  board.unmake_move(attacker, undo);
+
+  value.max(0)
+}
+
+/// Static exchange evaluation: the material balance after a capture and the best sequence of
+/// recaptures on the same square. Negative values mean the capture loses material.
+pub fn see(board: &Board, mv: &Move) -> i32 {
+  let mut scratch = board.clone();
+  let mut gain = victim_value(board, mv);
+  if let Some(promotion) = mv.promotion {
+    gain += piece_value(promotion) - piece_value(PieceKind::Pawn);
+  }
+
+  let undo = match scratch.make_move(*mv) {
+    Ok(undo) => undo,
+    Err(_) => return 0,
+  };
+  let value = gain - exchange_on(&mut scratch, mv.to);
+  scratch.unmake_move(*mv, undo);
+  value
+}
+
+/// Quiet moves that caused a beta cutoff, two per ply.
+#[derive(Clone, Debug, Default)]
+pub struct KillerMoves {
+  table: Vec<[Option<Move>; 2]>,
+}
+
+impl KillerMoves {
+  pub fn get(&self, ply: usize) -> [Option<Move>; 2] {
+    self.table.get(ply).copied().unwrap_or([None, None])
+  }
+
+  pub fn store(&mut self, ply: usize, mv: Move) {
+    if self.table.len() <= ply {
+      self.table.resize(ply + 1, [None, None]);
+    }
+
+    let killers = &mut self.table[ply];
+    if killers[0] != Some(mv) {
+      killers[1] = killers[0];
+      killers[0] = Some(mv);
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.table.clear();
+  }
+}
+
+/// Largest magnitude a history score can reach.
+pub const MAX_HISTORY: i32 = 16384;
+
+/// How often quiet moves, by color and (from, to) squares, caused beta cutoffs.
+#[derive(Clone, Debug)]
+pub struct HistoryTable {
+  table: Vec<i32>,
+}
+
+impl Default for HistoryTable {
+  fn default() -> Self {
+    HistoryTable { table: vec![0; 2 * SQUARES * SQUARES] }
+  }
+}
+
+impl HistoryTable {
+  fn index(color: Color, mv: &Move) -> usize {
+    let color_idx = if color == Color::White { 0 } else { 1 };
+    color_idx * SQUARES * SQUARES + square_index(mv.from) * SQUARES + square_index(mv.to)
+  }
+
+  pub fn get(&self, color: Color, mv: &Move) -> i32 {
+    self.table[HistoryTable::index(color, mv)]
+  }
+
+  /// Adds a bonus (or a penalty, when negative). Scores decay towards zero as they grow,
+  /// so they stay within [`MAX_HISTORY`](`crate::search::movepick::MAX_HISTORY`).
+  pub fn update(&mut self, color: Color, mv: &Move, bonus: i32) {
+    let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+    let entry = &mut self.table[HistoryTable::index(color, mv)];
+    *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+  }
+
+  pub fn clear(&mut self) {
+    self.table.iter_mut().for_each(|entry| *entry = 0);
+  }
+}
+
+/// The quiet move that last refuted each previous move, by the previous move's (from, to) squares.
+#[derive(Clone, Debug)]
+pub struct CounterMoves {
+  table: Vec<Option<Move>>,
+}
+
+impl Default for CounterMoves {
+  fn default() -> Self {
+    CounterMoves { table: vec![None; SQUARES * SQUARES] }
+  }
+}
+
+impl CounterMoves {
+  fn index(previous: &Move) -> usize {
+    square_index(previous.from) * SQUARES + square_index(previous.to)
+  }
+
+  pub fn get(&self, previous: Option<Move>) -> Option<Move> {
+    previous.and_then(|previous| self.table[CounterMoves::index(&previous)])
+  }
+
+  pub fn store(&mut self, previous: Option<Move>, mv: Move) {
+    if let Some(previous) = previous {
+      self.table[CounterMoves::index(&previous)] = Some(mv);
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.table.iter_mut().for_each(|entry| *entry = None);
+  }
+}
+
+/// Everything the search learns about move ordering as it goes.
+#[derive(Clone, Debug, Default)]
+pub struct MoveOrdering {
+  pub killers: KillerMoves,
+  pub history: HistoryTable,
+  pub counters: CounterMoves,
+}
+
+impl MoveOrdering {
+  /// Records a quiet move that caused a beta cutoff, penalizing the quiet moves searched before it.
+  pub fn record_cutoff(&mut self, color: Color, ply: usize, depth: i32, previous: Option<Move>, mv: Move, failed_quiets: &[Move]) {
+    let bonus = depth * depth;
+    self.killers.store(ply, mv);
+    self.counters.store(previous, mv);
+    self.history.update(color, &mv, bonus);
+    for failed in failed_quiets {
+      self.history.update(color, failed, -bonus);
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.killers.clear();
+    self.history.clear();
+    self.counters.clear();
+  }
+}
+
+/// The stages a [`MovePicker`](`crate::search::movepick::MovePicker`) goes through, in order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd)]
+pub enum Stage {
+  HashMove,
+  GenerateCaptures,
+  GoodCaptures,
+  Killers,
+  CounterMove,
+  GenerateQuiets,
+  Quiets,
+  BadCaptures,
+  Done,
+}
+
+/// Yields the legal moves of a position one at a time, best guesses first: the hash move,
+/// captures that win material (by MVV-LVA, filtered by SEE), killer moves, the countermove,
+/// quiet moves by history score, and finally captures that lose material.
+///
+/// Work is done lazily: moves are only generated, scored and checked for legality when a stage
+/// needs them, so a cutoff on an early move skips the rest.
+pub struct MovePicker {
+  stage: Stage,
+  captures_only: bool,
+  hash_move: Option<Move>,
+  killers: [Option<Move>; 2],
+  counter_move: Option<Move>,
+  killer_idx: usize,
+  captures: Vec<(Move, i32)>,
+  bad_captures: Vec<Move>,
+  quiets: Vec<Move>,
+  scored_quiets: Vec<(Move, i32)>,
+  yielded: Vec<Move>,
+  scratch: Option<Board>,
+}
+
+/// Removes and returns the highest scored entry, keeping the rest unsorted.
+fn pop_best(moves: &mut Vec<(Move, i32)>) -> Option<Move> {
+  let best_idx = moves.iter().enumerate().max_by_key(|(_, (_, score))| *score)?.0;
+  Some(moves.swap_remove(best_idx).0)
+}
+
+impl MovePicker {
+  /// Creates a picker over every legal move, for the main search.
+  pub fn new(hash_move: Option<Move>, killers: [Option<Move>; 2], counter_move: Option<Move>) -> MovePicker {
+    MovePicker {
+      stage: Stage::HashMove,
+      captures_only: false,
+      hash_move,
+      killers,
+      counter_move,
+      killer_idx: 0,
+      captures: vec![],
+      bad_captures: vec![],
+      quiets: vec![],
+      scored_quiets: vec![],
+      yielded: vec![],
+      scratch: None,
+    }
+  }
+
+  /// Creates a picker over captures and promotions that do not lose material, for quiescence search.
+  pub fn new_captures(hash_move: Option<Move>) -> MovePicker {
+    let mut picker = MovePicker::new(hash_move, [None, None], None);
+    picker.captures_only = true;
+    picker
+  }
+
+  pub fn get_stage(&self) -> Stage {
+    self.stage
+  }
+
+  fn is_tactical(board: &Board, mv: &Move) -> bool {
+    mv.promotion.is_some() || board.is_capture(mv)
+  }
+
+  fn is_legal(&mut self, board: &Board, mv: &Move) -> bool {
+    let scratch = self.scratch.get_or_insert_with(|| board.clone());
+    let color = board.get_active_color();
+    match scratch.make_move(*mv) {
+      Ok(undo) => {
+        let is_legal = scratch.is_legal_position(color);
+        scratch.unmake_move(*mv, undo);
+        is_legal
+      }
+      Err(_) => false,
+    }
+  }
+
+  /// Yields the move if it is legal and has not been yielded already.
+  fn accept(&mut self, board: &Board, mv: Move) -> Option<Move> {
+    if self.yielded.contains(&mv) || !self.is_legal(board, &mv) {
+      return None;
+    }
+    self.yielded.push(mv);
+    Some(mv)
+  }
+
+  /// Returns the next legal move, or `None` once every move has been yielded.
+  /// Must always be called with the same position.
+  pub fn next(&mut self, board: &Board, ordering: &MoveOrdering) -> Option<Move> {
+    loop {
+      match self.stage {
+        Stage::HashMove => {
+          self.stage = Stage::GenerateCaptures;
+          if let Some(mv) = self.hash_move {
+            let usable = board.is_pseudo_legal_move(&mv) && (!self.captures_only || MovePicker::is_tactical(board, &mv));
+            if usable {
+              if let Some(mv) = self.accept(board, mv) {
+                return Some(mv);
+              }
+            }
+          }
+        }
+        Stage::GenerateCaptures => {
+          for mv in board.get_pseudo_legal_moves() {
+            if MovePicker::is_tactical(board, &mv) {
+              // Most valuable victim first, then least valuable attacker
+              let attacker = board.get_square(mv.from).ok()
+                .and_then(|sq| sq.get_piece().as_ref().and_then(|p| p.get_kind()))
+                .map(piece_value)
+                .unwrap_or(0);
+              let promotion = mv.promotion.map(piece_value).unwrap_or(0);
+              self.captures.push((mv, (victim_value(board, &mv) + promotion) * 16 - attacker / 10));
+            } else {
+              self.quiets.push(mv);
+            }
+          }
+          self.stage = Stage::GoodCaptures;
+        }
+        Stage::GoodCaptures => {
+          match pop_best(&mut self.captures) {
+            Some(mv) => {
+              if see(board, &mv) < 0 {
+                self.bad_captures.push(mv);
+              } else if let Some(mv) = self.accept(board, mv) {
+                return Some(mv);
+              }
+            }
+            None => self.stage = if self.captures_only { Stage::Done } else { Stage::Killers },
+          }
+        }
+        Stage::Killers => {
+          if self.killer_idx >= self.killers.len() {
+            self.stage = Stage::CounterMove;
+            continue;
+          }
+          let killer = self.killers[self.killer_idx];
+          self.killer_idx += 1;
+          if let Some(mv) = killer.filter(|mv| self.quiets.contains(mv)) {
+            if let Some(mv) = self.accept(board, mv) {
+              return Some(mv);
+            }
+          }
+        }
+        Stage::CounterMove => {
+          self.stage = Stage::GenerateQuiets;
+          if let Some(mv) = self.counter_move.filter(|mv| self.quiets.contains(mv)) {
+            if let Some(mv) = self.accept(board, mv) {
+              return Some(mv);
+            }
+          }
+        }
+        Stage::GenerateQuiets => {
+          let color = board.get_active_color();
+          self.scored_quiets = self.quiets.iter()
+            .map(|mv| (*mv, ordering.history.get(color, mv)))
+            .collect();
+          self.stage = Stage::Quiets;
+        }
+        Stage::Quiets => {
+          match pop_best(&mut self.scored_quiets) {
+            Some(mv) => {
+              if let Some(mv) = self.accept(board, mv) {
+                return Some(mv);
+              }
+            }
+            None => self.stage = Stage::BadCaptures,
+          }
+        }
+        Stage::BadCaptures => {
+          if self.bad_captures.is_empty() {
+            self.stage = Stage::Done;
+            continue;
+          }
+          let mv = self.bad_captures.remove(0);
+          if let Some(mv) = self.accept(board, mv) {
+            return Some(mv);
+          }
+        }
+        Stage::Done => return None,
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  fn mv(move_str: &str) -> Move {
+    Move::from_uci(move_str).unwrap()
+  }
+
+  #[test]
+  fn test_see_of_defended_and_undefended_captures() {
+    // The e5 pawn is defended by the d6 pawn, the b5 knight is not defended at all
+    let board = Board::from_fen_string("4k3/8/3p4/1n2p3/8/2N2N2/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(see(&board, &mv("f3e5")), 100 - 320);
+    assert_eq!(see(&board, &mv("c3b5")), 320);
+  }
+
+  #[test]
+  fn test_see_counts_x_rays() {
+    // Rook takes a pawn defended by a rook, but a second rook stands behind the first
+    let board = Board::from_fen_string("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+    assert_eq!(see(&board, &mv("d2d5")), 100);
+  }
+
+  #[test]
+  fn test_stages_come_in_order() {
+    let board = Board::from_fen_string("4k3/8/3p4/1n2p3/8/2N2N2/8/4K3 w - - 0 1").unwrap();
+    let mut ordering = MoveOrdering::default();
+    ordering.history.update(Color::White, &mv("e1f2"), 500);
+
+    let hash_move = mv("f3d4");
+    let mut picker = MovePicker::new(Some(hash_move), [Some(mv("e1d2")), Some(mv("h1h2"))], Some(mv("c3a4")));
+    let moves: Vec<Move> = std::iter::from_fn(|| picker.next(&board, &ordering)).collect();
+
+    // Hash move, winning capture, killer (the second killer is not a move here), countermove,
+    // then quiets by history, and the losing capture last
+    assert_eq!(&moves[0..5], &[hash_move, mv("c3b5"), mv("e1d2"), mv("c3a4"), mv("e1f2")]);
+    assert_eq!(*moves.last().unwrap(), mv("f3e5"));
+
+    let mut legal = board.get_legal_moves();
+    let mut picked = moves.clone();
+    legal.sort_by_key(|m| m.to_string());
+    picked.sort_by_key(|m| m.to_string());
+    assert_eq!(picked, legal);
+  }
+
+  #[test]
+  fn test_generation_is_lazy() {
+    let board = Board::new();
+    let ordering = MoveOrdering::default();
+    let mut picker = MovePicker::new(Some(mv("e2e4")), [None, None], None);
+    assert_eq!(picker.next(&board, &ordering), Some(mv("e2e4")));
+    assert_eq!(picker.get_stage(), Stage::GenerateCaptures);
+    assert!(picker.captures.is_empty() && picker.quiets.is_empty());
+  }
+
+  #[test]
+  fn test_capture_picker_skips_quiets_and_losing_captures() {
+    let board = Board::from_fen_string("4k3/8/3p4/1n2p3/8/2N2N2/8/4K3 w - - 0 1").unwrap();
+    let ordering = MoveOrdering::default();
+    let mut picker = MovePicker::new_captures(None);
+    let moves: Vec<Move> = std::iter::from_fn(|| picker.next(&board, &ordering)).collect();
+    assert_eq!(moves, vec![mv("c3b5")]);
+  }
+}
@@ -0,0 +1,62 @@
+pub const DEFAULT_HASH_SIZE_MB: usize = 16;
+
+/// Switches for each search technique, so their effect can be measured in self-play.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOptions {
+  /// Give the opponent a free move; if we are still above beta, the node is pruned.
+  /// Skipped when the side to move has only pawns, where zugzwang is common.
+  pub null_move_pruning: bool,
+  /// Search quiet moves late in the ordering to a reduced depth first.
+  pub late_move_reductions: bool,
+  /// Skip quiet moves near the leaves that cannot bring the score back up to alpha.
+  pub futility_pruning: bool,
+  /// Return early near the leaves when the static evaluation is far above beta.
+  pub reverse_futility_pruning: bool,
+  /// Search each iteration with a narrow window around the previous score.
+  pub aspiration_windows: bool,
+  /// Search one ply deeper when the side to move is in check.
+  pub check_extensions: bool,
+  /// Search moves after the first with a null window, re-searching only if they beat alpha.
+  pub principal_variation_search: bool,
+  /// Number of threads searching at once. One thread always finds the same result.
+  pub threads: usize,
+  /// Size of the transposition table shared by all threads, in megabytes.
+  pub hash_size_mb: usize,
+  /// Number of best lines to find, each with its own principal variation.
+  pub multi_pv: usize,
+}
+
+impl SearchOptions {
+  /// Plain single-threaded alpha-beta, with every technique turned off.
+  pub fn none() -> SearchOptions {
+    SearchOptions {
+      null_move_pruning: false,
+      late_move_reductions: false,
+      futility_pruning: false,
+      reverse_futility_pruning: false,
+      aspiration_windows: false,
+      check_extensions: false,
+      principal_variation_search: false,
+      threads: 1,
+      hash_size_mb: DEFAULT_HASH_SIZE_MB,
+      multi_pv: 1,
+    }
+  }
+}
+
+impl Default for SearchOptions {
+  fn default() -> Self {
+    SearchOptions {
+      null_move_pruning: true,
+      late_move_reductions: true,
+      futility_pruning: true,
+      reverse_futility_pruning: true,
+      aspiration_windows: true,
+      check_extensions: true,
+      principal_variation_search: true,
+      threads: 1,
+      hash_size_mb: DEFAULT_HASH_SIZE_MB,
+      multi_pv: 1,
+    }
+  }
+}
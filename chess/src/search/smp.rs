@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+
+use crate::search::SearchResult;
+
+/// Collects the completed iterations of every search thread and keeps the most trustworthy one:
+/// the deepest, with the earliest report winning ties.
+pub struct BestMoveAggregator {
+  best: Mutex<Option<SearchResult>>,
+}
+
+impl Default for BestMoveAggregator {
+  fn default() -> Self {
+    BestMoveAggregator::new()
+  }
+}
+
+impl BestMoveAggregator {
+  pub fn new() -> BestMoveAggregator {
+    BestMoveAggregator { best: Mutex::new(None) }
+  }
+
+  /// Offers a thread's completed iteration. Iterations without a move are ignored unless
+  /// nothing else has been reported, since they only happen when there are no legal moves.
+  pub fn submit(&self, result: SearchResult) {
+    let mut best = self.best.lock().expect("aggregator lock poisoned");
+    let replace = match best.as_ref() {
+      None => true,
+      Some(current) => result.best_move.is_some() && (current.best_move.is_none() || result.depth > current.depth),
+    };
+    if replace {
+      *best = Some(result);
+    }
+  }
+
+  pub fn get_best(&self) -> Option<SearchResult> {
+    self.best.lock().expect("aggregator lock poisoned").clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::board::moves::Move;
+
+  fn result(mv: &str, depth: u32) -> SearchResult {
+    SearchResult { best_move: Some(Move::from_uci(mv).unwrap()), score: 0, depth, nodes: 0, pv: vec![], lines: vec![] }
+  }
+
+  #[test]
+  fn test_keeps_deepest_and_earliest_result() {
+    let aggregator = BestMoveAggregator::new();
+    assert_eq!(aggregator.get_best(), None);
+
+    aggregator.submit(result("e2e4", 3));
+    aggregator.submit(result("d2d4", 3));
+    assert_eq!(aggregator.get_best(), Some(result("e2e4", 3)));
+
+    aggregator.submit(result("c2c4", 4));
+    aggregator.submit(result("g1f3", 2));
+    assert_eq!(aggregator.get_best(), Some(result("c2c4", 4)));
+  }
+}
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::board::moves::Move;
+pub use crate::clock::{MockTimeSource, SystemTimeSource, TimeSource};
+use crate::search::SearchResult;
+
+/// The side to move's clock, as sent with `go wtime btime winc binc movestogo`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockState {
+  pub time_left: Duration,
+  pub increment: Duration,
+  /// Moves until the next time control, or `None` if the rest of the game must fit in `time_left`.
+  pub moves_to_go: Option<u32>,
+}
+
+/// Kept in reserve for communication lag between the engine and the GUI.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+/// How many more moves a sudden death game is assumed to last.
+const EXPECTED_MOVES_TO_GO: u32 = 30;
+/// The hard limit is at most this many times the soft limit.
+const HARD_LIMIT_FACTOR: u32 = 4;
+/// A score drop of at least this many centipawns between iterations earns more time.
+const SCORE_DROP_MARGIN: i32 = 30;
+
+/// Decides how long to think about a move.
+///
+/// The soft limit is checked between iterations: a new iteration is only started while under it.
+/// It stretches when the best move keeps changing or the score falls, since those are the
+/// positions where thinking longer pays off. The hard limit is checked during the search and
+/// is never exceeded.
+pub struct TimeManager {
+  source: Arc<dyn TimeSource>,
+  start: Duration,
+  soft_limit: Duration,
+  hard_limit: Duration,
+  previous_best: Option<Move>,
+  previous_score: Option<i32>,
+  /// Grows each time the best move changes between iterations and decays when it holds.
+  instability: f64,
+  score_dropped: bool,
+}
+
+impl TimeManager {
+  /// Budgets time for one move from the side to move's clock.
+  pub fn new(clock: &ClockState, source: Arc<dyn TimeSource>) -> TimeManager {
+    let available = clock.time_left.saturating_sub(MOVE_OVERHEAD);
+    let moves_to_go = clock.moves_to_go.unwrap_or(EXPECTED_MOVES_TO_GO).max(1);
+
+    let soft_limit = (available / moves_to_go + clock.increment * 3 / 4).min(available);
+    // Never bet more than half of what's left, unless this is the last move before the time control
+    let ceiling = if moves_to_go == 1 { available } else { available / 2 };
+    let hard_limit = (soft_limit * HARD_LIMIT_FACTOR).min(ceiling).max(soft_limit);
+    TimeManager::with_limits(soft_limit, hard_limit, source)
+  }
+
+  /// Thinks for exactly `duration`, as with `go movetime`.
+  pub fn fixed(duration: Duration, source: Arc<dyn TimeSource>) -> TimeManager {
+    TimeManager::with_limits(duration, duration, source)
+  }
+
+  fn with_limits(soft_limit: Duration, hard_limit: Duration, source: Arc<dyn TimeSource>) -> TimeManager {
+    TimeManager {
+      start: source.now(),
+      source,
+      soft_limit,
+      hard_limit,
+      previous_best: None,
+      previous_score: None,
+      instability: 0.0,
+      score_dropped: false,
+    }
+  }
+
+  pub fn get_soft_limit(&self) -> Duration {
+    self.soft_limit
+  }
+
+  pub fn get_hard_limit(&self) -> Duration {
+    self.hard_limit
+  }
+
+  /// Starts the budget over from now, e.g. when a ponder search becomes a normal one.
+  pub fn restart(&mut self) {
+    self.start = self.source.now();
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    self.source.now().saturating_sub(self.start)
+  }
+
+  /// The soft limit after stretching it for an unstable best move or a falling score.
+  pub fn get_adjusted_soft_limit(&self) -> Duration {
+    let mut scale = 1.0 + self.instability;
+    if self.score_dropped {
+      scale *= 1.5;
+    }
+    self.soft_limit.mul_f64(scale).min(self.hard_limit)
+  }
+
+  pub fn is_hard_limit_reached(&self) -> bool {
+    self.elapsed() >= self.hard_limit
+  }
+
+  /// Records a completed iteration and decides whether another one should be started.
+  /// `legal_moves` is the number of legal moves at the root.
+  pub fn should_continue(&mut self, result: &SearchResult, legal_moves: usize) -> bool {
+    if legal_moves <= 1 {
+      return false;
+    }
+
+    if self.previous_best.is_some() && self.previous_best != result.best_move {
+      self.instability += 1.0;
+    } else {
+      self.instability *= 0.5;
+    }
+    self.score_dropped = self.previous_score.is_some_and(|previous| result.score <= previous - SCORE_DROP_MARGIN);
+    self.previous_best = result.best_move;
+    self.previous_score = Some(result.score);
+
+    self.elapsed() < self.get_adjusted_soft_limit()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  fn result(mv: &str, score: i32) -> SearchResult {
+    SearchResult { best_move: Some(Move::from_uci(mv).unwrap()), score, depth: 1, nodes: 0, pv: vec![], lines: vec![] }
+  }
+
+  fn clock(time_left: u64, increment: u64, moves_to_go: Option<u32>) -> ClockState {
+    ClockState { time_left: Duration::from_millis(time_left), increment: Duration::from_millis(increment), moves_to_go }
+  }
+
+  #[test]
+  fn test_limits_from_clock() {
+    let source = Arc::new(MockTimeSource::default());
+    let time = TimeManager::new(&clock(60_030, 1_000, None), source.clone());
+    assert_eq!(time.get_soft_limit(), Duration::from_millis(2_000 + 750));
+    assert_eq!(time.get_hard_limit(), Duration::from_millis(11_000));
+
+    let time = TimeManager::new(&clock(10_030, 0, Some(2)), source.clone());
+    assert_eq!(time.get_soft_limit(), Duration::from_millis(5_000));
+    assert_eq!(time.get_hard_limit(), Duration::from_millis(5_000));
+
+    let time = TimeManager::new(&clock(10_030, 0, Some(1)), source.clone());
+    assert_eq!(time.get_hard_limit(), Duration::from_millis(10_000));
+
+    let time = TimeManager::new(&clock(10, 0, None), source);
+    assert_eq!(time.get_hard_limit(), Duration::from_millis(0));
+  }
+
+  #[test]
+  fn test_stops_once_soft_limit_is_used() {
+    let source = Arc::new(MockTimeSource::default());
+    let mut time = TimeManager::new(&clock(30_030, 0, None), source.clone());
+    assert!(time.should_continue(&result("e2e4", 20), 20));
+
+    source.advance(Duration::from_millis(999));
+    assert!(time.should_continue(&result("e2e4", 20), 20));
+    source.advance(Duration::from_millis(1));
+    assert!(!time.should_continue(&result("e2e4", 20), 20));
+    assert!(!time.is_hard_limit_reached());
+
+    source.advance(Duration::from_millis(3_000));
+    assert!(time.is_hard_limit_reached());
+  }
+
+  #[test]
+  fn test_extends_when_best_move_changes_or_score_drops() {
+    let source = Arc::new(MockTimeSource::default());
+    let mut time = TimeManager::new(&clock(30_030, 0, None), source.clone());
+    time.should_continue(&result("e2e4", 20), 20);
+    source.advance(Duration::from_millis(1_200));
+
+    assert!(time.should_continue(&result("d2d4", 20), 20));
+    assert_eq!(time.get_adjusted_soft_limit(), Duration::from_millis(2_000));
+
+    let mut time = TimeManager::new(&clock(30_030, 0, None), source.clone());
+    time.should_continue(&result("e2e4", 20), 20);
+    source.advance(Duration::from_millis(1_200));
+    assert!(time.should_continue(&result("e2e4", -40), 20));
+    assert_eq!(time.get_adjusted_soft_limit(), Duration::from_millis(1_500));
+  }
+
+  #[test]
+  fn test_single_reply_stops_immediately() {
+    let source = Arc::new(MockTimeSource::default());
+    let mut time = TimeManager::new(&clock(30_030, 0, None), source);
+    assert!(!time.should_continue(&result("e1f1", 0), 1));
+  }
+}
@@ -0,0 +1,205 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::board::MAX_BOARD_SIZE;
+use crate::board::coord::Coordinate;
+use crate::board::moves::Move;
+use crate::board::pocket::DROP_KINDS;
+use crate::piece::PieceKind;
+use crate::search::MATE_BOUND;
+
+/// How a stored score relates to the true score of the position.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Bound {
+  Exact,
+  /// The search failed high; the true score is at least this.
+  Lower,
+  /// The search failed low; the true score is at most this.
+  Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TtEntry {
+  pub best_move: Option<Move>,
+  pub score: i32,
+  pub depth: i32,
+  pub bound: Bound,
+}
+
+/// A fixed size hash table of search results, shared between search threads without locks.
+///
+/// Each slot is a pair of atomics holding the packed entry and the entry xor'd with its key.
+/// A slot torn by two threads writing at once fails the key check on the next probe and is
+/// treated as empty.
+pub struct TranspositionTable {
+  slots: Vec<(AtomicU64, AtomicU64)>,
+}
+
+const SLOT_BYTES: usize = 16;
+const PROMOTIONS: [PieceKind; 4] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+
+/// Squares are numbered as on the largest board, which takes seven bits.
+const SQUARE_BITS: u64 = 7;
+const SQUARE_MASK: u64 = (1 << SQUARE_BITS) - 1;
+const FROM_SHIFT: u64 = SQUARE_BITS;
+const PROMOTION_SHIFT: u64 = 2 * SQUARE_BITS;
+const MOVE_PRESENT: u64 = 1 << (PROMOTION_SHIFT + 3);
+const MOVE_BITS: u64 = PROMOTION_SHIFT + 4;
+const SCORE_SHIFT: u64 = MOVE_BITS;
+const DEPTH_SHIFT: u64 = SCORE_SHIFT + 16;
+const BOUND_SHIFT: u64 = DEPTH_SHIFT + 8;
+
+fn square_index(coord: Coordinate) -> u64 {
+  ((Into::<i8>::into(coord.rank) - 1) * MAX_BOARD_SIZE + Into::<i8>::into(coord.file) - 1) as u64
+}
+
+/// Marks a drop in a packed move's promotion bits; the origin bits then hold the dropped kind.
+const DROP_MARKER: u64 = 0b111;
+
+fn pack_move(mv: Option<Move>) -> u64 {
+  match mv {
+    None => 0,
+    Some(Move { to, drop: Some(kind), .. }) => {
+      let kind_index = DROP_KINDS.iter().position(|drop_kind| *drop_kind == kind).unwrap_or(0) as u64;
+      MOVE_PRESENT | DROP_MARKER << PROMOTION_SHIFT | kind_index << FROM_SHIFT | square_index(to)
+    }
+    Some(mv) => {
+      let promotion = mv.promotion.and_then(|kind| PROMOTIONS.iter().position(|p| *p == kind)).map(|i| i as u64 + 1).unwrap_or(0);
+      MOVE_PRESENT | promotion << PROMOTION_SHIFT | square_index(mv.from) << FROM_SHIFT | square_index(mv.to)
+    }
+  }
+}
+
+fn unpack_move(bits: u64) -> Option<Move> {
+  if bits & MOVE_PRESENT == 0 {
+    return None;
+  }
+  let size = MAX_BOARD_SIZE as u64;
+  let coord = |index: u64| Coordinate::make_coordinate((index % size) as i8, (index / size) as i8);
+  let (from, to) = ((bits >> FROM_SHIFT) & SQUARE_MASK, bits & SQUARE_MASK);
+  let promotion = match (bits >> PROMOTION_SHIFT) & 0b111 {
+    0 => None,
+    DROP_MARKER => return Some(Move::new_drop(DROP_KINDS[from as usize], coord(to))),
+    i => Some(PROMOTIONS[i as usize - 1]),
+  };
+  Some(Move { from: coord(from), to: coord(to), promotion, drop: None })
+}
+
+fn pack(entry: &TtEntry) -> u64 {
+  let bound = match entry.bound {
+    Bound::Exact => 1,
+    Bound::Lower => 2,
+    Bound::Upper => 3,
+  };
+  pack_move(entry.best_move)
+    | (entry.score as i16 as u16 as u64) << SCORE_SHIFT
+    | (entry.depth.clamp(0, u8::MAX as i32) as u64) << DEPTH_SHIFT
+    | bound << BOUND_SHIFT
+}
+
+fn unpack(data: u64) -> Option<TtEntry> {
+  let bound = match (data >> BOUND_SHIFT) & 0b11 {
+    1 => Bound::Exact,
+    2 => Bound::Lower,
+    3 => Bound::Upper,
+    _ => return None,
+  };
+  Some(TtEntry {
+    best_move: unpack_move(data & ((1 << MOVE_BITS) - 1)),
+    score: (data >> SCORE_SHIFT) as u16 as i16 as i32,
+    depth: ((data >> DEPTH_SHIFT) & 0xFF) as i32,
+    bound,
+  })
+}
+
+impl TranspositionTable {
+  /// Creates a table using roughly `size_mb` megabytes.
+  pub fn new(size_mb: usize) -> TranspositionTable {
+    let count = (size_mb.max(1) * 1024 * 1024 / SLOT_BYTES).max(1);
+    TranspositionTable { slots: (0..count).map(|_| (AtomicU64::new(0), AtomicU64::new(0))).collect() }
+  }
+
+  pub fn get_size(&self) -> usize {
+    self.slots.len()
+  }
+
+  fn slot(&self, hash: u64) -> &(AtomicU64, AtomicU64) {
+    &self.slots[(hash % self.slots.len() as u64) as usize]
+  }
+
+  /// Looks up a position, adjusting mate scores to be relative to `ply`.
+  pub fn probe(&self, hash: u64, ply: usize) -> Option<TtEntry> {
+    let (checked, data) = self.slot(hash);
+    let data = data.load(Ordering::Relaxed);
+    if checked.load(Ordering::Relaxed) ^ data != hash {
+      return None;
+    }
+
+    let mut entry = unpack(data)?;
+    if entry.score >= MATE_BOUND {
+      entry.score -= ply as i32;
+    } else if entry.score <= -MATE_BOUND {
+      entry.score += ply as i32;
+    }
+    Some(entry)
+  }
+
+  /// Stores a search result, replacing whatever was in the slot. Mate scores found `ply`
+  /// moves from the root are stored relative to the position itself.
+  pub fn store(&self, hash: u64, ply: usize, mut entry: TtEntry) {
+    if entry.score >= MATE_BOUND {
+      entry.score += ply as i32;
+    } else if entry.score <= -MATE_BOUND {
+      entry.score -= ply as i32;
+    }
+
+    let (checked, data) = self.slot(hash);
+    let packed = pack(&entry);
+    checked.store(hash ^ packed, Ordering::Relaxed);
+    data.store(packed, Ordering::Relaxed);
+  }
+
+  /// Estimates how full the table is, in permille, from a sample of its slots.
+  pub fn hashfull(&self) -> u32 {
+    let sample = self.slots.len().min(1000);
+    let used = self.slots[..sample].iter().filter(|(_, data)| data.load(Ordering::Relaxed) != 0).count();
+    (used * 1000 / sample) as u32
+  }
+
+  pub fn clear(&self) {
+    for (checked, data) in self.slots.iter() {
+      checked.store(0, Ordering::Relaxed);
+      data.store(0, Ordering::Relaxed);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::search::MATE_SCORE;
+
+  #[test]
+  fn test_store_and_probe() {
+    let tt = TranspositionTable::new(1);
+    let entry = TtEntry { best_move: Some(Move::from_uci("e7e8n").unwrap()), score: -312, depth: 7, bound: Bound::Lower };
+    tt.store(0xDEAD_BEEF, 3, entry);
+    assert_eq!(tt.probe(0xDEAD_BEEF, 3), Some(entry));
+    assert_eq!(tt.probe(0xDEAD_BEEF + tt.get_size() as u64, 3), None);
+    let drop = TtEntry { best_move: Some(Move::from_uci("N@f3").unwrap()), ..entry };
+    tt.store(7, 0, drop);
+    assert_eq!(tt.probe(7, 0), Some(drop));
+
+    tt.clear();
+    assert_eq!(tt.probe(0xDEAD_BEEF, 3), None);
+    assert_eq!(tt.hashfull(), 0);
+  }
+
+  #[test]
+  fn test_mate_scores_are_relative_to_the_probing_ply() {
+    let tt = TranspositionTable::new(1);
+    let entry = TtEntry { best_move: None, score: MATE_SCORE - 5, depth: 2, bound: Bound::Exact };
+    tt.store(42, 4, entry);
+    assert_eq!(tt.probe(42, 4).unwrap().score, MATE_SCORE - 5);
+    assert_eq!(tt.probe(42, 2).unwrap().score, MATE_SCORE - 3);
+  }
+}
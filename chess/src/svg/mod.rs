@@ -0,0 +1,342 @@
+//! Drawing boards as SVG diagrams, for web pages and print.
+//!
+//! Pieces come from a built-in set drawn from simple shapes, so diagrams need no fonts or
+//! images, and the same board and options always give the same SVG text.
+
+use std::fmt::Write;
+
+use crate::board::Board;
+use crate::board::coord::Coordinate;
+use crate::board::square::SquareColor;
+use crate::piece::{Color, PieceKind};
+
+/// The pieces are drawn in a 45x45 box, then scaled to the squares.
+const PIECE_BOX: f64 = 45.0;
+
+const BASE: &str = r#"<path d="M 11 39 L 34 39 L 34 35 L 11 35 Z"/>"#;
+
+/// The shapes of each kind of piece, drawn with the piece's fill and outline.
+fn piece_shape(kind: PieceKind) -> String {
+  let body = match kind {
+    PieceKind::Pawn => r#"<circle cx="22.5" cy="15" r="6"/><path d="M 16 35 L 29 35 L 26 22 L 19 22 Z"/>"#,
+    PieceKind::Knight => r#"<path d="M 14 35 L 31 35 C 32 25 30 16 23 11 L 21 7 L 18 11 L 13 16 L 10 23 L 13 26 L 18 23 L 20 25 L 14 33 Z"/>"#,
+    PieceKind::Bishop => r#"<path d="M 15 35 L 30 35 C 31 27 29 20 22.5 12 C 16 20 14 27 15 35 Z"/><circle cx="22.5" cy="9" r="2.5"/>"#,
+    PieceKind::Rook => r#"<path d="M 14 35 L 31 35 L 29 17 L 32 17 L 32 9 L 28 9 L 28 12 L 24.5 12 L 24.5 9 L 20.5 9 L 20.5 12 L 17 12 L 17 9 L 13 9 L 13 17 L 16 17 Z"/>"#,
+    PieceKind::Queen => r#"<path d="M 13 35 L 32 35 L 36 14 L 29 26 L 27 11 L 22.5 25 L 18 11 L 16 26 L 9 14 Z"/><circle cx="9" cy="13" r="2"/><circle cx="18" cy="10" r="2"/><circle cx="27" cy="10" r="2"/><circle cx="36" cy="13" r="2"/>"#,
+    PieceKind::King => r#"<path d="M 13 35 L 32 35 L 31 32 C 36 27 34 20 28 19 L 22.5 24 L 17 19 C 11 20 9 27 14 32 Z"/><path d="M 22.5 6 L 22.5 18 M 18 10 L 27 10" fill="none"/>"#,
+  };
+  format!("{}{}", body, BASE)
+}
+
+fn piece_id(kind: PieceKind, color: Color) -> String {
+  let color = if color == Color::White { "white" } else { "black" };
+  let kind = match kind {
+    PieceKind::Pawn => "pawn",
+    PieceKind::Knight => "knight",
+    PieceKind::Bishop => "bishop",
+    PieceKind::Rook => "rook",
+    PieceKind::Queen => "queen",
+    PieceKind::King => "king",
+  };
+  format!("{}-{}", color, kind)
+}
+
+/// The colors a diagram is drawn in, as SVG color values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgTheme {
+  pub light_square: String,
+  pub dark_square: String,
+  /// Drawn over highlighted squares, half see-through.
+  pub highlight: String,
+  /// Drawn under a king in check.
+  pub check: String,
+  /// Arrows without a color of their own.
+  pub arrow: String,
+  pub coordinates: String,
+  pub white_piece: String,
+  pub black_piece: String,
+  /// Outlines of every piece.
+  pub piece_outline: String,
+}
+
+impl SvgTheme {
+  /// Browns, as on a wooden board.
+  pub fn brown() -> SvgTheme {
+    SvgTheme::new(["#f0d9b5", "#b58863", "#cdd26a", "#e05050", "#15781b", "#404040"])
+  }
+
+  /// Blues, as on many chess sites.
+  pub fn blue() -> SvgTheme {
+    SvgTheme::new(["#dee3e6", "#8ca2ad", "#9bc7e0", "#e05050", "#003088", "#404040"])
+  }
+
+  fn new([light, dark, highlight, check, arrow, coordinates]: [&str; 6]) -> SvgTheme {
+    SvgTheme {
+      light_square: String::from(light),
+      dark_square: String::from(dark),
+      highlight: String::from(highlight),
+      check: String::from(check),
+      arrow: String::from(arrow),
+      coordinates: String::from(coordinates),
+      white_piece: String::from("#ffffff"),
+      black_piece: String::from("#202020"),
+      piece_outline: String::from("#000000"),
+    }
+  }
+}
+
+impl Default for SvgTheme {
+  fn default() -> Self {
+    SvgTheme::brown()
+  }
+}
+
+/// An arrow from the centre of one square to another, as for showing a plan or a threat.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arrow {
+  pub from: Coordinate,
+  pub to: Coordinate,
+  /// The arrow's color, or the theme's when `None`.
+  pub color: Option<String>,
+}
+
+impl Arrow {
+  pub fn new(from: Coordinate, to: Coordinate) -> Arrow {
+    Arrow { from, to, color: None }
+  }
+}
+
+/// How [`render_svg`](`crate::svg::render_svg`) draws a board.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgOptions {
+  /// The width and height of each square, in pixels.
+  pub square_size: u32,
+  /// Label the files and ranks in a margin around the board.
+  pub coordinates: bool,
+  /// Draw the board from black's side.
+  pub flipped: bool,
+  /// Squares to highlight, such as those of the last move.
+  pub highlights: Vec<Coordinate>,
+  /// Highlight the king of the side to move when it is in check.
+  pub highlight_check: bool,
+  pub arrows: Vec<Arrow>,
+  pub theme: SvgTheme,
+}
+
+impl Default for SvgOptions {
+  fn default() -> Self {
+    SvgOptions {
+      square_size: 45,
+      coordinates: true,
+      flipped: false,
+      highlights: vec![],
+      highlight_check: true,
+      arrows: vec![],
+      theme: SvgTheme::default(),
+    }
+  }
+}
+
+/// Where things are drawn: the margin around the board and each square's size.
+struct Layout {
+  margin: f64,
+  square: f64,
+  width: i8,
+  height: i8,
+  flipped: bool,
+}
+
+impl Layout {
+  /// The top left corner of the square at zero-indexed (x, y).
+  fn corner(&self, x: i8, y: i8) -> (f64, f64) {
+    let (column, row) = if self.flipped { (self.width - 1 - x, y) } else { (x, self.height - 1 - y) };
+    (self.margin + column as f64 * self.square, self.margin + row as f64 * self.square)
+  }
+
+  fn coord_corner(&self, coord: Coordinate) -> (f64, f64) {
+    self.corner(Into::<i8>::into(coord.file) - 1, Into::<i8>::into(coord.rank) - 1)
+  }
+
+  fn centre(&self, coord: Coordinate) -> (f64, f64) {
+    let (left, top) = self.coord_corner(coord);
+    (left + self.square / 2.0, top + self.square / 2.0)
+  }
+}
+
+/// Writes an arrow as a shaft from the source's centre and a triangular head with its tip on the target's centre.
+fn write_arrow(svg: &mut String, layout: &Layout, arrow: &Arrow, color: &str) {
+  let ((x1, y1), (x2, y2)) = (layout.centre(arrow.from), layout.centre(arrow.to));
+  let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+  if length == 0.0 {
+    return;
+  }
+  let (ux, uy) = ((x2 - x1) / length, (y2 - y1) / length);
+  let head = layout.square * 0.4;
+  let half_width = layout.square * 0.2;
+  let (base_x, base_y) = (x2 - ux * head, y2 - uy * head);
+  let _ = writeln!(svg, r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="{:.1}" stroke-linecap="round" opacity="0.8"/>"#,
+    x1, y1, base_x, base_y, color, layout.square * 0.15);
+  let _ = writeln!(svg, r#"<polygon points="{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}" fill="{}" opacity="0.8"/>"#,
+    x2, y2, base_x - uy * half_width, base_y + ux * half_width, base_x + uy * half_width, base_y - ux * half_width, color);
+}
+
+/// Draws the board as an SVG document.
+pub fn render_svg(board: &Board, options: &SvgOptions) -> String {
+  let theme = &options.theme;
+  let square = options.square_size.max(1) as f64;
+  let layout = Layout {
+    margin: if options.coordinates { square / 2.0 } else { 0.0 },
+    square,
+    width: board.get_width(),
+    height: board.get_height(),
+    flipped: options.flipped,
+  };
+  let total_width = layout.margin * 2.0 + square * layout.width as f64;
+  let total_height = layout.margin * 2.0 + square * layout.height as f64;
+
+  let mut svg = String::new();
+  let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}">"#,
+    total_width, total_height, total_width, total_height);
+
+  svg += "<defs>\n";
+  for color in [Color::White, Color::Black].iter() {
+    let fill = if *color == Color::White { &theme.white_piece } else { &theme.black_piece };
+    for kind in [PieceKind::Pawn, PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen, PieceKind::King].iter() {
+      let _ = writeln!(svg, r#"<g id="{}" fill="{}" stroke="{}" stroke-width="1.5" stroke-linejoin="round">{}</g>"#,
+        piece_id(*kind, *color), fill, theme.piece_outline, piece_shape(*kind));
+    }
+  }
+  svg += "</defs>\n";
+
+  if options.coordinates {
+    let _ = writeln!(svg, r#"<rect x="0" y="0" width="{:.0}" height="{:.0}" fill="{}"/>"#, total_width, total_height, theme.dark_square);
+  }
+
+  let active = board.get_active_color();
+  let checked_king = if options.highlight_check && board.is_in_check(&active) { board.find_king(&active) } else { None };
+  for square_ref in board.get_squares().iter() {
+    let coord = *square_ref.get_coord();
+    let (left, top) = layout.coord_corner(coord);
+    let fill = if *square_ref.get_color() == SquareColor::Light { &theme.light_square } else { &theme.dark_square };
+    let _ = writeln!(svg, r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#, left, top, square, square, fill);
+    if checked_king == Some(coord) {
+      let _ = writeln!(svg, r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" opacity="0.7"/>"#, left, top, square, square, theme.check);
+    }
+    if options.highlights.contains(&coord) {
+      let _ = writeln!(svg, r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" opacity="0.5"/>"#, left, top, square, square, theme.highlight);
+    }
+  }
+
+  if options.coordinates {
+    let font_size = square * 0.3;
+    for x in 0..layout.width {
+      let (left, _) = layout.corner(x, 0);
+      let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="{:.1}" text-anchor="middle" fill="{}">{}</text>"#,
+        left + square / 2.0, total_height - layout.margin / 3.0, font_size, theme.light_square, (b'a' + x as u8) as char);
+    }
+    for y in 0..layout.height {
+      let (_, top) = layout.corner(0, y);
+      let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="{:.1}" text-anchor="middle" fill="{}">{}</text>"#,
+        layout.margin / 2.0, top + square / 2.0 + font_size / 3.0, font_size, theme.light_square, y + 1);
+    }
+  }
+
+  let scale = square / PIECE_BOX;
+  for square_ref in board.get_squares().iter() {
+    let piece = match square_ref.get_piece() {
+      Some(piece) => piece,
+      None => continue,
+    };
+    let (left, top) = layout.coord_corner(*square_ref.get_coord());
+    match piece.get_kind() {
+      Some(kind) => {
+        let _ = writeln!(svg, r##"<use xlink:href="#{}" transform="translate({:.1},{:.1}) scale({:.4})"/>"##,
+          piece_id(kind, *piece.get_color()), left, top, scale);
+      }
+      // Fairy pieces are drawn as their letter on a disc
+      None => {
+        let (fill, text) = if *piece.get_color() == Color::White { (&theme.white_piece, &theme.black_piece) } else { (&theme.black_piece, &theme.white_piece) };
+        let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}" stroke="{}" stroke-width="{:.1}"/>"#,
+          left + square / 2.0, top + square / 2.0, square * 0.38, fill, theme.piece_outline, scale * 1.5);
+        let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-weight="bold" font-size="{:.1}" text-anchor="middle" fill="{}">{}</text>"#,
+          left + square / 2.0, top + square * 0.65, square * 0.45, text, piece.get_short_name());
+      }
+    }
+  }
+
+  for arrow in options.arrows.iter() {
+    write_arrow(&mut svg, &layout, arrow, arrow.color.as_deref().unwrap_or(&theme.arrow));
+  }
+
+  svg += "</svg>\n";
+  svg
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::board::moves::Move;
+
+  fn coord(name: &str) -> Coordinate {
+    Coordinate::get_coordinate(name).unwrap()
+  }
+
+  #[test]
+  fn test_svg_starting_position() {
+    let svg = render_svg(&Board::new(), &SvgOptions::default());
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" width="405" height="405" viewBox="0 0 405 405">"#));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<use ").count(), 32);
+    assert_eq!(svg.matches(r##"xlink:href="#white-pawn""##).count(), 8);
+    // a1 is dark and in the bottom left corner, inside the margin
+    assert!(svg.contains(r##"<rect x="22.5" y="337.5" width="45.0" height="45.0" fill="#b58863"/>"##));
+    assert!(svg.contains(r#">h</text>"#) && svg.contains(r#">8</text>"#));
+  }
+
+  #[test]
+  fn test_svg_matches_golden_file() {
+    // Scholar's mate, with the last move highlighted and the bishop's support drawn
+    let board = Board::from_fen_string("r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4").unwrap();
+    let options = SvgOptions {
+      highlights: vec![coord("h5"), coord("f7")],
+      arrows: vec![Arrow::new(coord("c4"), coord("f7"))],
+      ..Default::default()
+    };
+    assert_eq!(render_svg(&board, &options), include_str!("scholars_mate.svg"));
+  }
+
+  #[test]
+  fn test_svg_options() {
+    let mut board = Board::from_fen_string("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let mv = Move::from_uci("a1a8").unwrap();
+    board.make_move(mv).unwrap();
+
+    let options = SvgOptions {
+      square_size: 10,
+      coordinates: false,
+      flipped: true,
+      highlights: vec![mv.from, mv.to],
+      arrows: vec![Arrow::new(coord("a8"), coord("e8")), Arrow { color: Some(String::from("red")), ..Arrow::new(coord("e1"), coord("e2")) }],
+      theme: SvgTheme::blue(),
+      ..Default::default()
+    };
+    let svg = render_svg(&board, &options);
+    assert!(svg.contains(r#"width="80" height="80""#));
+    // Flipped, a1 is in the top right corner
+    assert!(svg.contains(r##"<rect x="70.0" y="0.0" width="10.0" height="10.0" fill="#9bc7e0" opacity="0.5"/>"##));
+    assert_eq!(svg.matches(r#"opacity="0.5""#).count(), 2);
+    assert_eq!(svg.matches(r##"fill="#e05050""##).count(), 1);
+    assert_eq!(svg.matches("<polygon").count(), 2);
+    assert!(svg.contains(r#"stroke="red""#));
+    assert!(!svg.contains("</text>"));
+  }
+
+  #[test]
+  fn test_svg_fairy_pieces() {
+    let archbishop = std::sync::Arc::new(crate::piece::betza::PieceDefinition::new('A', "BN").unwrap());
+    let board = Board::from_fen_string_with_pieces("4k/5/2A2/5/K4 w - - 0 1", &[archbishop]).unwrap();
+    let svg = render_svg(&board, &SvgOptions::default());
+    assert!(svg.contains(r#"width="270" height="270""#));
+    assert!(svg.contains(">A</text>"));
+  }
+}
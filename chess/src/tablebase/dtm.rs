@@ -0,0 +1,719 @@
+//! Distance-to-mate tables generated by retrograde analysis, for endgames of up to four pieces.
+//!
+//! Every position of a material balance is set up as a [`Board`](`crate::board::Board`) and its
+//! legal moves are followed once. Starting from the checkmates, results are then worked
+//! backwards a ply at a time until nothing changes, and whatever is left is a draw. Since the
+//! results only rest on the board's own move generation, the tables double as a check of it.
+//! Distances ignore the fifty-move rule.
+//!
+//! Tables are saved in the crate's own format. All values are little endian:
+//!
+//! | Field    | Type          | Notes                                                        |
+//! |----------|---------------|--------------------------------------------------------------|
+//! | magic    | 4             | `RCTB`                                                       |
+//! | version  | u32           | currently 1                                                  |
+//! | material | u8, then text | the length, then a name such as `KQvK`                       |
+//! | count    | u32           | number of positions                                          |
+//! | values   | u16 \* count  | 0 for draws and impossible positions, else the plies to mate plus one |
+//!
+//! Positions are indexed by side to move, the white king's square and then every other
+//! piece's square. The board is mirrored so the white king is on the queen side, and without
+//! pawns also in the a1-d1-d4 triangle.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::errors::Error;
+use crate::piece::{Color, PieceKind};
+use crate::tablebase::{material_signature, Tablebase, Wdl};
+
+const MAGIC: &[u8; 4] = b"RCTB";
+const VERSION: u32 = 1;
+/// Extension of saved tables.
+pub const EXTENSION: &str = "rdtm";
+/// The most pieces, kings included, a table can have.
+pub const MAX_PIECES: usize = 4;
+
+/// Non-king pieces in the order material names list them.
+const KIND_ORDER: [PieceKind; 5] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight, PieceKind::Pawn];
+const PROMOTION_KINDS: [PieceKind; 4] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+/// The a1-d1-d4 triangle the white king is brought into when there are no pawns.
+const TRIANGLE: [usize; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+
+/// Result of a position for the side to move, with the number of plies until mate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dtm {
+  Win(u32),
+  Loss(u32),
+  Draw,
+}
+
+impl Dtm {
+  /// Wins take an odd number of plies and losses an even number, so the stored value only
+  /// needs the distance.
+  fn encode(self) -> u16 {
+    match self {
+      Dtm::Win(plies) | Dtm::Loss(plies) => plies as u16 + 1,
+      Dtm::Draw => 0,
+    }
+  }
+
+  fn decode(value: u16) -> Dtm {
+    match value {
+      0 => Dtm::Draw,
+      value if value.is_multiple_of(2) => Dtm::Win(value as u32 - 1),
+      value => Dtm::Loss(value as u32 - 1),
+    }
+  }
+
+  /// The result for the side that moved into a position with this result.
+  fn before(self) -> Dtm {
+    match self {
+      Dtm::Win(plies) => Dtm::Loss(plies + 1),
+      Dtm::Loss(plies) => Dtm::Win(plies + 1),
+      Dtm::Draw => Dtm::Draw,
+    }
+  }
+
+  /// Orders results from the side to move's point of view: quick wins first, slow losses last.
+  fn preference(self) -> i64 {
+    match self {
+      Dtm::Win(plies) => i64::MAX / 2 - plies as i64,
+      Dtm::Loss(plies) => i64::MIN / 2 + plies as i64,
+      Dtm::Draw => 0,
+    }
+  }
+}
+
+fn file_of(square: usize) -> usize {
+  square & 7
+}
+
+fn rank_of(square: usize) -> usize {
+  square >> 3
+}
+
+/// The pieces of a table besides the kings.
+#[derive(Clone, Debug, PartialEq)]
+struct Material {
+  white: Vec<PieceKind>,
+  black: Vec<PieceKind>,
+}
+
+impl Material {
+  fn new(mut white: Vec<PieceKind>, mut black: Vec<PieceKind>) -> Material {
+    let order = |kind: &PieceKind| KIND_ORDER.iter().position(|other| other == kind);
+    white.sort_by_key(order);
+    black.sort_by_key(order);
+    Material { white, black }
+  }
+
+  /// Parses a name such as `KBNvK`. Pawns may only be on one side, since en passant rights
+  /// aren't indexed.
+  fn parse(name: &str) -> Result<Material, Error> {
+    let (white, black) = name.split_once('v').ok_or(Error::InvalidTablebase)?;
+    let parse_side = |side: &str| -> Result<Vec<PieceKind>, Error> {
+      let pieces = side.strip_prefix('K').ok_or(Error::InvalidTablebase)?;
+      pieces.chars().map(|c| {
+        match PieceKind::from_short_name(&c.to_string()) {
+          Some(kind) if c.is_ascii_uppercase() && kind != PieceKind::King => Ok(kind),
+          _ => Err(Error::InvalidTablebase),
+        }
+      }).collect()
+    };
+    let material = Material::new(parse_side(white)?, parse_side(black)?);
+    let pawns = |side: &[PieceKind]| side.contains(&PieceKind::Pawn);
+    if material.get_piece_count() > MAX_PIECES || (pawns(&material.white) && pawns(&material.black)) {
+      return Err(Error::InvalidTablebase);
+    }
+    Ok(material)
+  }
+
+  fn get_name(&self) -> String {
+    let side = |pieces: &[PieceKind]| pieces.iter().map(|kind| kind.get_short_name()).collect::<String>();
+    format!("K{}vK{}", side(&self.white), side(&self.black))
+  }
+
+  fn swapped(&self) -> Material {
+    Material { white: self.black.clone(), black: self.white.clone() }
+  }
+
+  fn get_piece_count(&self) -> usize {
+    2 + self.white.len() + self.black.len()
+  }
+
+  fn has_pawns(&self) -> bool {
+    self.white.contains(&PieceKind::Pawn) || self.black.contains(&PieceKind::Pawn)
+  }
+
+  /// Whether either side could ever mate, by the same rule as
+  /// [`Board::has_mating_material`](`crate::board::Board::has_mating_material`).
+  fn can_mate(&self) -> bool {
+    let side_can_mate = |own: &[PieceKind], other: &[PieceKind]| {
+      let minors = own.iter().filter(|kind| matches!(kind, PieceKind::Knight | PieceKind::Bishop)).count();
+      own.len() > minors || minors >= 2 || (minors == 1 && !other.is_empty())
+    };
+    side_can_mate(&self.white, &self.black) || side_can_mate(&self.black, &self.white)
+  }
+
+  /// Every material one capture or promotion away.
+  fn get_successors(&self) -> Vec<Material> {
+    let mut successors = vec![];
+    for (own, other, white) in [(&self.white, &self.black, true), (&self.black, &self.white, false)] {
+      for (i, kind) in own.iter().enumerate() {
+        let mut replacements = vec![None];
+        if *kind == PieceKind::Pawn {
+          replacements.extend(PROMOTION_KINDS.iter().map(|kind| Some(*kind)));
+        }
+        for replacement in replacements {
+          let mut changed = own.clone();
+          changed.remove(i);
+          changed.extend(replacement);
+          successors.push(if white { Material::new(changed, other.clone()) } else { Material::new(other.clone(), changed) });
+        }
+      }
+    }
+    successors
+  }
+
+  /// Kings first, then the other white pieces and then the black ones.
+  fn get_pieces(&self) -> Vec<(Color, PieceKind)> {
+    let mut pieces = vec![(Color::White, PieceKind::King), (Color::Black, PieceKind::King)];
+    pieces.extend(self.white.iter().map(|kind| (Color::White, *kind)));
+    pieces.extend(self.black.iter().map(|kind| (Color::Black, *kind)));
+    pieces
+  }
+
+  fn get_king_squares(&self) -> usize {
+    if self.has_pawns() { 32 } else { TRIANGLE.len() }
+  }
+
+  fn get_size(&self) -> usize {
+    2 * self.get_king_squares() * 64usize.pow(self.get_piece_count() as u32 - 1)
+  }
+
+  /// Mirrors the squares so the white king is where the index expects it.
+  fn normalize(&self, squares: &mut [usize]) {
+    if file_of(squares[0]) > 3 {
+      squares.iter_mut().for_each(|square| *square ^= 7);
+    }
+    if self.has_pawns() {
+      return;
+    }
+    if rank_of(squares[0]) > 3 {
+      squares.iter_mut().for_each(|square| *square ^= 56);
+    }
+    if rank_of(squares[0]) > file_of(squares[0]) {
+      squares.iter_mut().for_each(|square| *square = (*square >> 3) | ((*square & 7) << 3));
+    }
+  }
+
+  fn encode(&self, squares: &[usize], black_to_move: bool) -> usize {
+    let king = if self.has_pawns() {
+      rank_of(squares[0]) * 4 + file_of(squares[0])
+    } else {
+      TRIANGLE.iter().position(|square| *square == squares[0]).expect("king is in the triangle")
+    };
+    let mut index = black_to_move as usize * self.get_king_squares() + king;
+    for square in squares[1..].iter() {
+      index = index * 64 + square;
+    }
+    index
+  }
+
+  fn decode(&self, mut index: usize) -> (Vec<usize>, bool) {
+    let mut squares = vec![0; self.get_piece_count()];
+    for square in squares[1..].iter_mut().rev() {
+      *square = index % 64;
+      index /= 64;
+    }
+    let king = index % self.get_king_squares();
+    squares[0] = if self.has_pawns() { (king / 4) * 8 + king % 4 } else { TRIANGLE[king] };
+    (squares, index >= self.get_king_squares())
+  }
+
+  /// The index of a position with this material, or its colours swapped.
+  fn index_of(&self, board: &Board) -> Option<usize> {
+    if !board.get_castling_availability().is_empty() {
+      return None;
+    }
+    let signature = material_signature(board);
+    let flip = if signature == self.get_name() {
+      false
+    } else if signature == self.swapped().get_name() {
+      true
+    } else {
+      return None;
+    };
+
+    let mut found: Vec<(Color, PieceKind, usize)> = vec![];
+    for (index, square) in board.get_squares().iter().enumerate() {
+      if let Some(piece) = square.get_piece() {
+        let color = if flip { piece.get_color().opposite() } else { *piece.get_color() };
+        found.push((color, piece.get_kind()?, if flip { index ^ 56 } else { index }));
+      }
+    }
+    let mut squares = vec![];
+    for (color, kind) in self.get_pieces() {
+      let at = found.iter().position(|(other_color, other_kind, _)| *other_color == color && *other_kind == kind)?;
+      squares.push(found.remove(at).2);
+    }
+
+    self.normalize(&mut squares);
+    Some(self.encode(&squares, (board.get_active_color() == Color::Black) != flip))
+  }
+
+  /// Sets up the position at an index, unless it's impossible.
+  fn to_board(&self, squares: &[usize], black_to_move: bool) -> Option<Board> {
+    let mut grid: [Option<(Color, PieceKind)>; 64] = [None; 64];
+    for ((color, kind), square) in self.get_pieces().into_iter().zip(squares.iter()) {
+      let back_rank = rank_of(*square) == 0 || rank_of(*square) == 7;
+      if grid[*square].is_some() || (kind == PieceKind::Pawn && back_rank) {
+        return None;
+      }
+      grid[*square] = Some((color, kind));
+    }
+    let (white_king, black_king) = (squares[0], squares[1]);
+    if (rank_of(white_king) as i32 - rank_of(black_king) as i32).abs() <= 1 && (file_of(white_king) as i32 - file_of(black_king) as i32).abs() <= 1 {
+      return None;
+    }
+
+    let mut fen = String::new();
+    for rank in (0..8).rev() {
+      let mut empty = 0;
+      for file in 0..8 {
+        match grid[rank * 8 + file] {
+          Some((color, kind)) => {
+            if empty > 0 {
+              fen += &empty.to_string();
+              empty = 0;
+            }
+            let name = kind.get_short_name();
+            fen += &if color == Color::White { name.to_string() } else { name.to_lowercase() };
+          }
+          None => empty += 1,
+        }
+      }
+      if empty > 0 {
+        fen += &empty.to_string();
+      }
+      if rank > 0 {
+        fen += "/";
+      }
+    }
+    fen += if black_to_move { " b - - 0 1" } else { " w - - 0 1" };
+
+    let board = Board::from_fen_string(&fen).ok()?;
+    // The side that just moved can't have left its king in check
+    if board.is_in_check(&board.get_active_color().opposite()) {
+      return None;
+    }
+    Some(board)
+  }
+}
+
+/// The results of every position of one material balance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DtmTable {
+  material: Material,
+  values: Vec<u16>,
+}
+
+impl DtmTable {
+  /// Works out every position, looking up the results after captures and promotions in
+  /// `tablebase`, which must already hold those tables.
+  fn generate(material: Material, tablebase: &DtmTablebase) -> DtmTable {
+    let size = material.get_size();
+    // Moves as a graph: the positions each position leads to, one list after another. Moves
+    // that change the material lead past the end, to a node standing for their result.
+    let mut offsets = Vec::with_capacity(size + 1);
+    let mut children: Vec<u32> = vec![];
+    let mut values = vec![0u16; size];
+    let mut done = vec![false; size];
+    offsets.push(0);
+    for index in 0..size {
+      let (squares, black_to_move) = material.decode(index);
+      match material.to_board(&squares, black_to_move) {
+        None => done[index] = true,
+        Some(board) => {
+          let moves = board.get_legal_moves();
+          if moves.is_empty() {
+            done[index] = true;
+            if board.is_in_check(&board.get_active_color()) {
+              values[index] = Dtm::Loss(0).encode();
+            }
+          }
+          for mv in moves {
+            let mut after = board.clone();
+            after.make_move(mv).expect("legal moves can be made");
+            let child = match material.index_of(&after) {
+              Some(child) => child,
+              None => size + tablebase.probe(&after).unwrap_or(Dtm::Draw).encode() as usize,
+            };
+            children.push(child as u32);
+          }
+        }
+      }
+      offsets.push(children.len());
+    }
+
+    let value_of = |values: &[u16], child: u32| {
+      let child = child as usize;
+      Dtm::decode(if child < size { values[child] } else { (child - size) as u16 })
+    };
+
+    // A position is won in n plies once a move reaches a loss in n - 1, and lost once every
+    // move reaches a win, in one more ply than the slowest of them
+    let mut plies = 1;
+    let mut longest = 0;
+    let mut last_change = 0;
+    while plies <= last_change + 2 || plies <= longest + 1 {
+      for index in 0..size {
+        if done[index] {
+          continue;
+        }
+        let moves = &children[offsets[index]..offsets[index + 1]];
+        let result = if plies % 2 == 1 {
+          moves.iter().any(|child| value_of(&values, *child) == Dtm::Loss(plies - 1)).then_some(Dtm::Win(plies))
+        } else {
+          moves.iter().try_fold(0, |slowest, child| {
+            match value_of(&values, *child) {
+              Dtm::Win(child_plies) => Some(slowest.max(child_plies + 1)),
+              _ => None,
+            }
+          }).map(Dtm::Loss)
+        };
+        if let Some(result) = result {
+          values[index] = result.encode();
+          done[index] = true;
+          last_change = plies;
+          if let Dtm::Win(result_plies) | Dtm::Loss(result_plies) = result {
+            longest = longest.max(result_plies);
+          }
+        }
+      }
+      plies += 1;
+    }
+
+    DtmTable { material, values }
+  }
+
+  /// The material as named in tablebase files, e.g. `KQvK`.
+  pub fn get_material(&self) -> String {
+    self.material.get_name()
+  }
+
+  /// The result of a position with this material, or with the colours swapped.
+  pub fn probe(&self, board: &Board) -> Option<Dtm> {
+    let index = self.material.index_of(board)?;
+    Some(Dtm::decode(self.values[index]))
+  }
+
+  /// The most plies it takes to mate from any position in the table.
+  pub fn get_longest_mate(&self) -> u32 {
+    self.values.iter().map(|value| *value as u32).max().unwrap_or(0).saturating_sub(1)
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Result<DtmTable, Error> {
+    let read_u32 = |at: usize| bytes.get(at..at + 4).map(|slice| u32::from_le_bytes(slice.try_into().unwrap()));
+    if bytes.len() < 9 || &bytes[0..4] != MAGIC || read_u32(4) != Some(VERSION) {
+      return Err(Error::InvalidTablebase);
+    }
+    let name_end = 9 + bytes[8] as usize;
+    let name = bytes.get(9..name_end).and_then(|name| std::str::from_utf8(name).ok()).ok_or(Error::InvalidTablebase)?;
+    let material = Material::parse(name)?;
+    let count = read_u32(name_end).ok_or(Error::InvalidTablebase)? as usize;
+    let values_start = name_end + 4;
+    if count != material.get_size() || bytes.len() != values_start + count * 2 {
+      return Err(Error::InvalidTablebase);
+    }
+
+    let values = bytes[values_start..].chunks_exact(2).map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]])).collect();
+    Ok(DtmTable { material, values })
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let name = self.material.get_name();
+    let mut bytes = Vec::with_capacity(13 + name.len() + self.values.len() * 2);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.push(name.len() as u8);
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.extend_from_slice(&(self.values.len() as u32).to_le_bytes());
+    for value in self.values.iter() {
+      bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<DtmTable, Error> {
+    let bytes = fs::read(path).map_err(|_| Error::IoError)?;
+    DtmTable::from_bytes(&bytes)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+    fs::write(path, self.to_bytes()).map_err(|_| Error::IoError)
+  }
+}
+
+/// A set of generated tables, answering for any position one of them covers.
+#[derive(Clone, Debug, Default)]
+pub struct DtmTablebase {
+  tables: HashMap<String, DtmTable>,
+}
+
+impl DtmTablebase {
+  pub fn new() -> DtmTablebase {
+    DtmTablebase::default()
+  }
+
+  /// Generates the table for a material such as `KRvK`, first generating any table its
+  /// captures and promotions lead to. Tables already held aren't generated again.
+  ///
+  /// Returns [`Error::InvalidTablebase`](`crate::errors::Error::InvalidTablebase`) if the
+  /// material can't be made into a table.
+  pub fn generate(&mut self, material: &str) -> Result<(), Error> {
+    let material = Material::parse(material)?;
+    self.generate_material(material);
+    Ok(())
+  }
+
+  fn generate_material(&mut self, material: Material) {
+    if self.get_table(&material.get_name()).is_some() {
+      return;
+    }
+    for successor in material.get_successors().into_iter().filter(|successor| successor.can_mate()) {
+      self.generate_material(successor);
+    }
+    let table = DtmTable::generate(material, self);
+    self.add_table(table);
+  }
+
+  pub fn add_table(&mut self, table: DtmTable) {
+    self.tables.insert(table.get_material(), table);
+  }
+
+  /// Finds the table for a material, held under either colouring.
+  pub fn get_table(&self, material: &str) -> Option<&DtmTable> {
+    self.tables.get(material).or_else(|| {
+      let swapped = Material::parse(material).ok()?.swapped().get_name();
+      self.tables.get(&swapped)
+    })
+  }
+
+  pub fn get_tables(&self) -> impl Iterator<Item = &DtmTable> {
+    self.tables.values()
+  }
+
+  /// Loads every table saved in a directory.
+  pub fn open<P: AsRef<Path>>(directory: P) -> Result<DtmTablebase, Error> {
+    let mut tablebase = DtmTablebase::new();
+    for entry in fs::read_dir(directory).map_err(|_| Error::IoError)? {
+      let path = entry.map_err(|_| Error::IoError)?.path();
+      if path.extension().and_then(|ext| ext.to_str()) == Some(EXTENSION) {
+        tablebase.add_table(DtmTable::load(&path)?);
+      }
+    }
+    Ok(tablebase)
+  }
+
+  /// Saves every table in a directory, named after its material.
+  pub fn save<P: AsRef<Path>>(&self, directory: P) -> Result<(), Error> {
+    for table in self.tables.values() {
+      table.save(directory.as_ref().join(format!("{}.{}", table.get_material(), EXTENSION)))?;
+    }
+    Ok(())
+  }
+
+  /// The result of a position, if a table covers it. Positions where neither side can mate
+  /// are draws without one.
+  pub fn probe(&self, board: &Board) -> Option<Dtm> {
+    if !board.get_castling_availability().is_empty() {
+      return None;
+    }
+    match self.get_table(&material_signature(board)) {
+      Some(table) => table.probe(board),
+      None if !board.has_mating_material(&Color::White) && !board.has_mating_material(&Color::Black) => Some(Dtm::Draw),
+      None => None,
+    }
+  }
+
+  /// The quickest mate, the draw, or the slowest loss, along with the position's result.
+  pub fn best_move(&self, board: &Board) -> Option<(Move, Dtm)> {
+    let result = self.probe(board)?;
+    let mut best: Option<(Move, Dtm)> = None;
+    for mv in board.get_legal_moves() {
+      let mut after = board.clone();
+      after.make_move(mv).ok()?;
+      let outcome = self.probe(&after)?.before();
+      if best.is_none_or(|(_, best)| outcome.preference() > best.preference()) {
+        best = Some((mv, outcome));
+      }
+    }
+    best.map(|(mv, _)| (mv, result))
+  }
+}
+
+/// Generated tables can stand in for Syzygy ones. They know nothing of the fifty-move rule, and
+/// answer for the distance to zeroing with the distance to mate, which is never shorter.
+impl Tablebase for DtmTablebase {
+  fn get_max_pieces(&self) -> usize {
+    self.tables.values().map(|table| table.material.get_piece_count()).max().unwrap_or(0)
+  }
+
+  fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+    match self.probe(board)? {
+      Dtm::Win(_) => Some(Wdl::Win),
+      Dtm::Loss(_) => Some(Wdl::Loss),
+      Dtm::Draw => Some(Wdl::Draw),
+    }
+  }
+
+  fn probe_dtz(&self, board: &Board) -> Option<i32> {
+    match self.probe(board)? {
+      Dtm::Win(plies) => Some(plies as i32),
+      Dtm::Loss(plies) => Some(-(plies.max(1) as i32)),
+      Dtm::Draw => Some(0),
+    }
+  }
+}
+
+/// KPvK and the tables it leads to, KQvK and KRvK among them, generated once for every test
+/// that needs them.
+#[cfg(test)]
+pub(crate) fn test_tablebase() -> &'static DtmTablebase {
+  static TABLEBASE: std::sync::OnceLock<DtmTablebase> = std::sync::OnceLock::new();
+  TABLEBASE.get_or_init(|| {
+    let mut tablebase = DtmTablebase::new();
+    tablebase.generate("KPvK").unwrap();
+    tablebase
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_material_names() {
+    let material = Material::parse("KNBvK").unwrap();
+    assert_eq!(material.get_name(), "KBNvK");
+    assert_eq!(material.get_size(), 2 * 10 * 64 * 64 * 64);
+    assert!(material.can_mate());
+    assert!(!Material::parse("KBvK").unwrap().can_mate());
+    let successors: Vec<String> = Material::parse("KPvK").unwrap().get_successors().iter().map(Material::get_name).collect();
+    assert_eq!(successors, ["KvK", "KQvK", "KRvK", "KBvK", "KNvK"]);
+    assert_eq!(Material::parse("KPvKP"), Err(Error::InvalidTablebase));
+    assert_eq!(Material::parse("KQRvKR"), Err(Error::InvalidTablebase));
+    assert_eq!(Material::parse("QvK"), Err(Error::InvalidTablebase));
+
+    let board = Board::from_fen_string("8/8/8/8/8/5k2/8/5K1R b - - 0 1").unwrap();
+    let material = Material::parse("KRvK").unwrap();
+    let (squares, black_to_move) = material.decode(material.index_of(&board).unwrap());
+    assert_eq!(material.to_board(&squares, black_to_move).unwrap().to_fen_string(), "8/8/8/8/8/2k5/8/R1K5 b - - 0 1");
+  }
+
+  #[test]
+  fn test_king_and_queen_against_king() {
+    let tablebase = test_tablebase();
+    let table = tablebase.get_table("KvKQ").unwrap();
+    // The longest mate with a queen takes ten moves, counted from the defender's move
+    assert_eq!(table.get_longest_mate(), 20);
+
+    let probe = |fen: &str| tablebase.probe(&Board::from_fen_string(fen).unwrap());
+    assert_eq!(probe("8/8/8/8/8/1K6/7Q/k7 w - - 0 1"), Some(Dtm::Win(1)));
+    assert_eq!(probe("8/8/8/8/8/1K6/Q7/k7 b - - 0 1"), Some(Dtm::Loss(0)));
+    assert_eq!(probe("K7/q7/1k6/8/8/8/8/8 w - - 0 1"), Some(Dtm::Loss(0)));
+    assert_eq!(probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Draw));
+    // Black takes the undefended queen
+    assert_eq!(probe("8/8/8/8/8/8/1Qk5/6K1 b - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(probe("8/8/8/8/8/8/2k5/6K1 b - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(probe("8/8/8/8/8/2k5/8/R3K3 w Q - 0 1"), None);
+
+    let board = Board::from_fen_string("8/8/8/8/8/1K6/7Q/k7 w - - 0 1").unwrap();
+    let (mv, result) = tablebase.best_move(&board).unwrap();
+    assert_eq!(result, Dtm::Win(1));
+    let mut after = board.clone();
+    after.make_move(mv).unwrap();
+    assert!(after.get_legal_moves().is_empty() && after.is_in_check(&Color::Black));
+    assert_eq!(tablebase.probe_wdl(&board), Some(Wdl::Win));
+
+    let bytes = table.to_bytes();
+    assert_eq!(DtmTable::from_bytes(&bytes).as_ref(), Ok(table));
+    assert_eq!(DtmTable::from_bytes(&bytes[..bytes.len() - 2]), Err(Error::InvalidTablebase));
+  }
+
+  #[test]
+  fn test_king_and_rook_against_king() {
+    let tablebase = test_tablebase();
+    // Sixteen moves with a rook
+    assert_eq!(tablebase.get_table("KRvK").unwrap().get_longest_mate(), 32);
+
+    let probe = |fen: &str| tablebase.probe(&Board::from_fen_string(fen).unwrap());
+    assert_eq!(probe("1k6/8/1K6/8/8/8/8/7R w - - 0 1"), Some(Dtm::Win(1)));
+    assert_eq!(probe("8/8/8/3k4/8/8/8/R3K3 w - - 0 1"), Some(Dtm::Win(27)));
+    assert_eq!(probe("8/8/8/8/8/8/2kR4/6K1 b - - 0 1"), Some(Dtm::Draw));
+  }
+
+  #[test]
+  fn test_king_and_pawn_against_king() {
+    let tablebase = test_tablebase();
+    // Twenty-eight moves, most of them after promoting
+    assert_eq!(tablebase.get_table("KPvK").unwrap().get_longest_mate(), 56);
+
+    let probe = |fen: &str| tablebase.probe(&Board::from_fen_string(fen).unwrap());
+    // A king on the sixth rank in front of its pawn wins whoever is to move
+    assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(Dtm::Win(21)));
+    assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(Dtm::Loss(24)));
+    assert_eq!(probe("8/8/8/8/8/4k3/4P3/4K3 w - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(probe("k7/8/K7/P7/8/8/8/8 w - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(probe("8/8/8/8/8/8/4P3/4K2k w - - 0 1"), Some(Dtm::Win(23)));
+  }
+
+  /// Plays the best move for both sides until mate, checking every move brings it one ply
+  /// closer.
+  fn play_out(tablebase: &DtmTablebase, fen: &str) {
+    let mut board = Board::from_fen_string(fen).unwrap();
+    let mut result = tablebase.probe(&board).unwrap();
+    while result != Dtm::Loss(0) {
+      let (mv, current) = tablebase.best_move(&board).unwrap();
+      assert_eq!(current, result);
+      board.make_move(mv).unwrap();
+      let next = match result {
+        Dtm::Win(plies) => Dtm::Loss(plies - 1),
+        Dtm::Loss(plies) => Dtm::Win(plies - 1),
+        Dtm::Draw => panic!("{} is a draw", fen),
+      };
+      result = tablebase.probe(&board).unwrap();
+      assert_eq!(result, next, "{}", board.to_fen_string());
+    }
+    assert!(board.get_legal_moves().is_empty() && board.is_in_check(&board.get_active_color()));
+  }
+
+  #[test]
+  fn test_best_moves_lower_the_distance_to_mate() {
+    let tablebase = test_tablebase();
+    play_out(tablebase, "8/8/8/3k4/8/8/8/R3K3 w - - 0 1");
+    play_out(tablebase, "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1");
+    // Through the promotion into KQvK
+    play_out(tablebase, "8/8/8/8/8/8/4P3/4K2k w - - 0 1");
+  }
+
+  #[test]
+  #[ignore = "generates the five million positions of KBNvK, which takes minutes"]
+  fn test_king_bishop_and_knight_against_king() {
+    let mut tablebase = DtmTablebase::new();
+    tablebase.generate("KBNvK").unwrap();
+    // Thirty-three moves with bishop and knight
+    assert_eq!(tablebase.get_table("KBNvK").unwrap().get_longest_mate(), 66);
+
+    // The king has to be driven from the corner the bishop can't reach
+    let fen = "k7/8/1K6/8/8/8/8/2B1N3 w - - 0 1";
+    assert_eq!(tablebase.probe(&Board::from_fen_string(fen).unwrap()), Some(Dtm::Win(47)));
+    play_out(&tablebase, fen);
+  }
+}
@@ -0,0 +1,203 @@
+//! Endgame tablebases: perfect play for positions with few pieces left.
+//!
+//! [`Tablebase`](`crate::tablebase::Tablebase`) is what the search asks; the Syzygy files read by
+//! [`SyzygyTablebase`](`crate::tablebase::syzygy::SyzygyTablebase`) are one source of answers,
+//! and tables generated by [`DtmTablebase`](`crate::tablebase::dtm::DtmTablebase`) another.
+
+pub mod dtm;
+pub mod syzygy;
+
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::piece::{Color, PieceKind};
+use crate::variant::{Standard, Variant};
+
+pub use crate::tablebase::syzygy::SyzygyTablebase;
+
+/// Rank given to a root move that wins at once; slower wins rank lower.
+const MAX_DTZ: i32 = 1 << 18;
+
+/// Result of a position for the side to move, with perfect play. Cursed wins and blessed
+/// losses would be wins and losses without the fifty-move rule.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Wdl {
+  Loss,
+  BlessedLoss,
+  Draw,
+  CursedWin,
+  Win,
+}
+
+impl Wdl {
+  /// The result as a number from -2 for a loss to 2 for a win.
+  pub fn value(self) -> i32 {
+    self as i32 - 2
+  }
+
+  pub fn from_value(value: i32) -> Option<Wdl> {
+    match value {
+      -2 => Some(Wdl::Loss),
+      -1 => Some(Wdl::BlessedLoss),
+      0 => Some(Wdl::Draw),
+      1 => Some(Wdl::CursedWin),
+      2 => Some(Wdl::Win),
+      _ => None,
+    }
+  }
+
+  /// The same result from the other side's point of view.
+  pub fn negate(self) -> Wdl {
+    Wdl::from_value(-self.value()).unwrap()
+  }
+}
+
+/// Something that knows the result of small endgames.
+pub trait Tablebase: Send + Sync {
+  /// The most pieces, kings included, of any position this tablebase can answer for.
+  fn get_max_pieces(&self) -> usize;
+
+  /// The result of the position for the side to move, or `None` if it isn't covered.
+  fn probe_wdl(&self, board: &Board) -> Option<Wdl>;
+
+  /// Distance to zeroing: plies until the winning side can capture or push a pawn on its way
+  /// to the win, positive when the side to move wins and negative when it loses. Cursed wins
+  /// and blessed losses are 100 further away. Draws are 0.
+  fn probe_dtz(&self, board: &Board) -> Option<i32>;
+}
+
+/// The material of a position in tablebase file naming, e.g. `KQvKR` for a white king and
+/// queen against a black king and rook.
+pub fn material_signature(board: &Board) -> String {
+  let mut counts = [[0; 6]; 2];
+  for piece in board.get_squares().iter().filter_map(|square| square.get_piece().as_ref()) {
+    if let Some(kind) = piece.get_kind() {
+      counts[(*piece.get_color() == Color::Black) as usize][kind.index()] += 1;
+    }
+  }
+
+  let kinds = [PieceKind::King, PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight, PieceKind::Pawn];
+  let side = |counts: &[usize; 6]| kinds.iter().map(|kind| kind.get_short_name().repeat(counts[kind.index()])).collect::<String>();
+  format!("{}v{}", side(&counts[0]), side(&counts[1]))
+}
+
+/// Whether a tablebase can be asked about the position: it must be chess on an 8x8 board with both kings on the
+/// board, not have too many pieces, and castling rights aren't stored in tablebases.
+pub fn is_probeable(tablebase: &dyn Tablebase, board: &Board) -> bool {
+  board.get_variant().get_name() == Standard.get_name()
+    && board.is_standard_size()
+    && board.find_king(&Color::White).is_some()
+    && board.find_king(&Color::Black).is_some()
+    && board.get_piece_count() <= tablebase.get_max_pieces()
+    && board.get_castling_availability().is_empty()
+}
+
+/// The distance to zeroing right after a zeroing move into a position with the given result.
+pub(crate) fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+  match wdl {
+    Wdl::Win => 1,
+    Wdl::CursedWin => 101,
+    Wdl::BlessedLoss => -101,
+    Wdl::Loss => -1,
+    Wdl::Draw => 0,
+  }
+}
+
+/// Finds the root moves that keep the best result the tablebase allows, taking the fifty-move
+/// counter into account. Among winning moves only the quickest to zero are kept, so a search
+/// restricted to them always makes progress. Returns `None` if the position can't be probed.
+pub fn filter_root_moves(tablebase: &dyn Tablebase, board: &Board) -> Option<Vec<Move>> {
+  if !is_probeable(tablebase, board) {
+    return None;
+  }
+
+  let half_moves = board.get_half_move_clock();
+  let mut ranked = vec![];
+  for mv in board.get_legal_moves() {
+    let zeroing = board.is_capture(&mv) || board.piece_at(mv.from).and_then(|piece| piece.get_kind()) == Some(PieceKind::Pawn);
+    let mut after = board.clone();
+    after.make_move(mv).ok()?;
+
+    let mut dtz = if zeroing {
+      dtz_before_zeroing(tablebase.probe_wdl(&after)?.negate())
+    } else {
+      let dtz = -tablebase.probe_dtz(&after)?;
+      dtz + dtz.signum()
+    };
+    // A mate is as quick as zeroing gets
+    if dtz == 2 && after.is_in_check(&after.get_active_color()) && after.get_legal_moves().is_empty() {
+      dtz = 1;
+    }
+
+    let rank = if dtz > 0 && dtz + half_moves <= 99 {
+      MAX_DTZ - dtz
+    } else if dtz > 0 {
+      // Wins that the fifty-move rule turns into draws still beat real draws
+      MAX_DTZ / 2 - (dtz + half_moves)
+    } else if dtz < 0 && -dtz * 2 + half_moves < 100 {
+      -MAX_DTZ - dtz
+    } else if dtz < 0 {
+      -MAX_DTZ / 2 - dtz
+    } else {
+      0
+    };
+    ranked.push((mv, rank));
+  }
+
+  let best = ranked.iter().map(|(_, rank)| *rank).max()?;
+  Some(ranked.into_iter().filter(|(_, rank)| *rank == best).map(|(mv, _)| mv).collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  /// Says the side with the queen wins in a few moves, and anything else is a draw.
+  struct QueenWins;
+
+  impl Tablebase for QueenWins {
+    fn get_max_pieces(&self) -> usize {
+      4
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+      let signature = material_signature(board);
+      let owner = match (signature.find('Q'), signature.find('v')) {
+        (Some(queen), Some(split)) if queen < split => Color::White,
+        (Some(_), _) => Color::Black,
+        _ => return Some(Wdl::Draw),
+      };
+      Some(if owner == board.get_active_color() { Wdl::Win } else { Wdl::Loss })
+    }
+
+    fn probe_dtz(&self, board: &Board) -> Option<i32> {
+      Some(self.probe_wdl(board)?.value() * 5 / 2)
+    }
+  }
+
+  #[test]
+  fn test_wdl_values() {
+    assert_eq!(Wdl::Win.negate(), Wdl::Loss);
+    assert_eq!(Wdl::CursedWin.negate(), Wdl::BlessedLoss);
+    assert_eq!(Wdl::Draw.value(), 0);
+    assert!(Wdl::CursedWin > Wdl::Draw);
+    assert_eq!(Wdl::from_value(3), None);
+  }
+
+  #[test]
+  fn test_material_signature() {
+    let board = Board::from_fen_string("3r2k1/8/8/8/3Q4/8/8/K7 w - - 0 1").unwrap();
+    assert_eq!(material_signature(&board), "KQvKR");
+    assert_eq!(material_signature(&Board::new()), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+  }
+
+  #[test]
+  fn test_root_moves_keep_the_quickest_win() {
+    // Taking the rook zeroes at once; every other move wins too, but more slowly
+    let board = Board::from_fen_string("3r2k1/8/8/8/3Q4/8/8/K7 w - - 0 1").unwrap();
+    assert_eq!(filter_root_moves(&QueenWins, &board), Some(vec![Move::from_uci("d4d8").unwrap()]));
+
+    assert_eq!(filter_root_moves(&QueenWins, &Board::new()), None);
+    let castling = Board::from_fen_string("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert_eq!(filter_root_moves(&QueenWins, &castling), None);
+  }
+}
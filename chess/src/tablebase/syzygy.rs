@@ -0,0 +1,1314 @@
+//! Probing Syzygy tablebases, following the reference probing code.
+//!
+//! Each material balance has a WDL file (`.rtbw`) giving the result of every position and a
+//! DTZ file (`.rtbz`) giving the distance to zeroing for one side to move. Positions are
+//! turned into an index, and the value at that index is found by decompressing a block of
+//! Huffman-coded symbol pairs. Files are read the first time they are needed and kept in memory.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::board::Board;
+use crate::errors::Error;
+use crate::piece::{Color, PieceKind};
+use crate::tablebase::{dtz_before_zeroing, is_probeable, material_signature, Tablebase, Wdl};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+/// The most pieces any Syzygy table has.
+const TB_PIECES: usize = 7;
+
+// Flags in the first byte of a file
+const SPLIT: u8 = 1;
+const HAS_PAWNS: u8 = 2;
+
+// Flags of each compressed table
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+fn rank_of(square: usize) -> usize {
+  square >> 3
+}
+
+fn file_of(square: usize) -> usize {
+  square & 7
+}
+
+/// Which side of the a1-h8 diagonal a square is on; 0 for squares on it.
+fn off_a1h8(square: usize) -> i32 {
+  rank_of(square) as i32 - file_of(square) as i32
+}
+
+/// Lookup tables shared by every table's indexing scheme.
+struct Indices {
+  map_b1h1h7: [u64; 64],
+  map_a1d1d4: [u64; 64],
+  /// Index of the two kings, by the white king's triangle code and the black king's square.
+  map_kk: [[u64; 64]; 10],
+  /// `binomial[k][n]` is n choose k.
+  binomial: [[u64; 64]; 7],
+  map_pawns: [u64; 64],
+  lead_pawn_idx: [[u64; 64]; 6],
+  lead_pawns_size: [[u64; 4]; 6],
+}
+
+impl Indices {
+  fn new() -> Indices {
+    let mut indices = Indices {
+      map_b1h1h7: [0; 64],
+      map_a1d1d4: [0; 64],
+      map_kk: [[0; 64]; 10],
+      binomial: [[0; 64]; 7],
+      map_pawns: [0; 64],
+      lead_pawn_idx: [[0; 64]; 6],
+      lead_pawns_size: [[0; 4]; 6],
+    };
+
+    for (code, square) in (0..64).filter(|square| off_a1h8(*square) < 0).enumerate() {
+      indices.map_b1h1h7[square] = code as u64;
+    }
+
+    // The a1-d1-d4 triangle, with the squares on the diagonal last
+    let mut code = 0;
+    let mut diagonal = vec![];
+    for square in (0..28).filter(|square| file_of(*square) <= 3) {
+      if off_a1h8(square) < 0 {
+        indices.map_a1d1d4[square] = code;
+        code += 1;
+      } else if off_a1h8(square) == 0 {
+        diagonal.push(square);
+      }
+    }
+    for square in diagonal {
+      indices.map_a1d1d4[square] = code;
+      code += 1;
+    }
+
+    // Kings can't touch, and with the white king on the diagonal the black king is kept
+    // below it. Both kings on the diagonal come last.
+    let mut code = 0;
+    let mut both_on_diagonal = vec![];
+    let map_a1d1d4 = indices.map_a1d1d4;
+    for idx in 0..10 {
+      for s1 in (0..28).filter(|s1| map_a1d1d4[*s1] == idx && (idx != 0 || *s1 == 1)) {
+        for s2 in 0..64 {
+          let touching = (rank_of(s1) as i32 - rank_of(s2) as i32).abs() <= 1 && (file_of(s1) as i32 - file_of(s2) as i32).abs() <= 1;
+          if touching || (off_a1h8(s1) == 0 && off_a1h8(s2) > 0) {
+            continue;
+          }
+          if off_a1h8(s1) == 0 && off_a1h8(s2) == 0 {
+            both_on_diagonal.push((idx as usize, s2));
+          } else {
+            indices.map_kk[idx as usize][s2] = code;
+            code += 1;
+          }
+        }
+      }
+    }
+    for (idx, s2) in both_on_diagonal {
+      indices.map_kk[idx][s2] = code;
+      code += 1;
+    }
+
+    indices.binomial[0][0] = 1;
+    for n in 1..64 {
+      for k in 0..7.min(n + 1) {
+        indices.binomial[k][n] = if k > 0 { indices.binomial[k - 1][n - 1] } else { 0 }
+          + if k < n { indices.binomial[k][n - 1] } else { 0 };
+      }
+    }
+
+    // Lead pawns are indexed file by file, from the second rank up
+    for lead_pawns in 1..=5 {
+      for file in 0..4 {
+        let mut available_squares = 47;
+        let mut idx = 0;
+        for rank in 1..7 {
+          let square = rank * 8 + file;
+          if lead_pawns == 1 {
+            indices.map_pawns[square] = available_squares;
+            indices.map_pawns[square ^ 7] = available_squares - 1;
+            available_squares -= 2;
+          }
+          indices.lead_pawn_idx[lead_pawns][square] = idx;
+          idx += indices.binomial[lead_pawns - 1][indices.map_pawns[square] as usize];
+        }
+        indices.lead_pawns_size[lead_pawns][file] = idx;
+      }
+    }
+
+    indices
+  }
+}
+
+fn indices() -> &'static Indices {
+  static INDICES: OnceLock<Indices> = OnceLock::new();
+  INDICES.get_or_init(Indices::new)
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+  Some(u16::from_le_bytes(bytes.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+  Some(u32::from_le_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn read_u32_be(bytes: &[u8], pos: usize) -> Option<u32> {
+  Some(u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn read_u64_be(bytes: &[u8], pos: usize) -> Option<u64> {
+  Some(u64::from_be_bytes(bytes.get(pos..pos + 8)?.try_into().ok()?))
+}
+
+/// Reads a file front to back while its layout is worked out.
+struct Cursor<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+  fn u8(&mut self) -> Option<u8> {
+    let value = *self.bytes.get(self.pos)?;
+    self.pos += 1;
+    Some(value)
+  }
+
+  fn u16(&mut self) -> Option<u16> {
+    let value = read_u16(self.bytes, self.pos)?;
+    self.pos += 2;
+    Some(value)
+  }
+
+  fn u32(&mut self) -> Option<u32> {
+    let value = read_u32(self.bytes, self.pos)?;
+    self.pos += 4;
+    Some(value)
+  }
+
+  fn align(&mut self, alignment: usize) {
+    self.pos = self.pos.div_ceil(alignment) * alignment;
+  }
+}
+
+/// What a table file's name says about it.
+#[derive(Clone, Debug, PartialEq)]
+struct TableInfo {
+  /// The name, with the stronger side as white.
+  key: String,
+  /// The name with the colours swapped.
+  key2: String,
+  piece_count: usize,
+  has_pawns: bool,
+  has_unique_pieces: bool,
+  /// Pawns of the side whose pawns lead the index, then of the other side.
+  pawn_count: [usize; 2],
+}
+
+impl TableInfo {
+  fn from_name(name: &str) -> Option<TableInfo> {
+    let (white, black) = name.split_once('v')?;
+    let mut counts = [[0; 6]; 2];
+    for (side, pieces) in [white, black].iter().enumerate() {
+      for c in pieces.chars() {
+        if !c.is_ascii_uppercase() {
+          return None;
+        }
+        counts[side][PieceKind::from_short_name(&c.to_string())?.index()] += 1;
+      }
+    }
+    let king = PieceKind::King.index();
+    let piece_count = white.len() + black.len();
+    if counts[0][king] != 1 || counts[1][king] != 1 || piece_count > TB_PIECES {
+      return None;
+    }
+
+    let pawn = PieceKind::Pawn.index();
+    let white_leads = counts[1][pawn] == 0 || (counts[0][pawn] > 0 && counts[1][pawn] >= counts[0][pawn]);
+    let pawn_count = if white_leads { [counts[0][pawn], counts[1][pawn]] } else { [counts[1][pawn], counts[0][pawn]] };
+    Some(TableInfo {
+      key: name.to_string(),
+      key2: format!("{}v{}", black, white),
+      piece_count,
+      has_pawns: pawn_count[0] > 0,
+      has_unique_pieces: counts.iter().any(|side| side[..king].contains(&1)),
+      pawn_count,
+    })
+  }
+}
+
+/// One compressed table: the values for one side to move and, with pawns, one lead pawn file.
+#[derive(Clone, Debug, Default)]
+struct PairsData {
+  flags: u8,
+  /// Piece codes in index order: 1 to 6 for a white pawn to king, 9 to 14 for black.
+  pieces: [u8; TB_PIECES],
+  /// Sizes of the groups of pieces indexed together, ending with 0.
+  group_len: [usize; TB_PIECES + 1],
+  /// What each group's index is multiplied by; the entry after the last group is the table size.
+  group_idx: [u64; TB_PIECES + 1],
+  sizeof_block: u64,
+  span: u64,
+  sparse_index_size: usize,
+  blocks_num: u64,
+  block_length_size: usize,
+  /// The only value in the table, if it has [`SINGLE_VALUE`] set.
+  min_sym_len: u8,
+  base64: Vec<u64>,
+  /// How many values each symbol expands to, less one.
+  symlen: Vec<u8>,
+  // Offsets into the file
+  lowest_sym: usize,
+  btree: usize,
+  sparse_index: usize,
+  block_length: usize,
+  data: usize,
+  /// Where the DTZ value maps for each kind of result start.
+  map_idx: [usize; 4],
+}
+
+/// The two children of a symbol in the symbol tree.
+fn read_lr(bytes: &[u8], btree: usize, sym: usize) -> Option<(usize, usize)> {
+  let lr = bytes.get(btree + 3 * sym..btree + 3 * sym + 3)?;
+  let left = ((lr[1] as usize & 0xF) << 8) | lr[0] as usize;
+  let right = ((lr[2] as usize) << 4) | (lr[1] as usize >> 4);
+  Some((left, right))
+}
+
+fn set_groups(info: &TableInfo, d: &mut PairsData, order: [u8; 2], file: usize) {
+  let indices = indices();
+  let mut n = 0;
+  let mut first_len: i32 = if info.has_pawns { 0 } else if info.has_unique_pieces { 3 } else { 2 };
+  d.group_len[0] = 1;
+  for i in 1..info.piece_count {
+    first_len -= 1;
+    if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+      d.group_len[n] += 1;
+    } else {
+      n += 1;
+      d.group_len[n] = 1;
+    }
+  }
+  n += 1;
+  d.group_len[n] = 0;
+
+  // The leading group, the other side's pawns and the remaining groups may be encoded in any
+  // order; `order` says where the first two go
+  let pp = info.has_pawns && info.pawn_count[1] > 0;
+  let mut next = if pp { 2 } else { 1 };
+  let mut free_squares = 64 - d.group_len[0] - if pp { d.group_len[1] } else { 0 };
+  let mut idx = 1;
+  let mut k = 0;
+  while next < n || k == order[0] as usize || k == order[1] as usize {
+    if k == order[0] as usize {
+      d.group_idx[0] = idx;
+      idx *= if info.has_pawns {
+        indices.lead_pawns_size[d.group_len[0]][file]
+      } else if info.has_unique_pieces {
+        31332
+      } else {
+        462
+      };
+    } else if k == order[1] as usize {
+      d.group_idx[1] = idx;
+      idx *= indices.binomial[d.group_len[1]][48 - d.group_len[0]];
+    } else {
+      d.group_idx[next] = idx;
+      idx *= indices.binomial[d.group_len[next]][free_squares];
+      free_squares -= d.group_len[next];
+      next += 1;
+    }
+    k += 1;
+  }
+  d.group_idx[n] = idx;
+}
+
+fn set_symlen(bytes: &[u8], d: &mut PairsData, sym: usize, visited: &mut [bool]) -> Option<u8> {
+  visited[sym] = true;
+  let (left, right) = read_lr(bytes, d.btree, sym)?;
+  if right == 0xFFF {
+    return Some(0);
+  }
+  if left >= visited.len() || right >= visited.len() {
+    return None;
+  }
+  for child in [left, right] {
+    if !visited[child] {
+      d.symlen[child] = set_symlen(bytes, d, child, visited)?;
+    }
+  }
+  Some(d.symlen[left].wrapping_add(d.symlen[right]).wrapping_add(1))
+}
+
+/// Reads the Huffman code description of a table.
+fn set_sizes(cursor: &mut Cursor, d: &mut PairsData) -> Option<()> {
+  d.flags = cursor.u8()?;
+  if d.flags & SINGLE_VALUE != 0 {
+    d.min_sym_len = cursor.u8()?;
+    return Some(());
+  }
+
+  let groups = d.group_len.iter().position(|len| *len == 0)?;
+  let table_size = d.group_idx[groups];
+  d.sizeof_block = 1u64.checked_shl(cursor.u8()? as u32)?;
+  d.span = 1u64.checked_shl(cursor.u8()? as u32)?;
+  d.sparse_index_size = table_size.div_ceil(d.span) as usize;
+  let padding = cursor.u8()? as usize;
+  d.blocks_num = cursor.u32()? as u64;
+  // Padded so the sparse index can't point past the end
+  d.block_length_size = d.blocks_num as usize + padding;
+  let max_sym_len = cursor.u8()?;
+  d.min_sym_len = cursor.u8()?;
+  if max_sym_len < d.min_sym_len || d.min_sym_len == 0 {
+    return None;
+  }
+
+  // base64[i] is the lowest code of length min_sym_len + i, left aligned to 64 bits
+  d.lowest_sym = cursor.pos;
+  let lengths = (max_sym_len - d.min_sym_len) as usize + 1;
+  d.base64 = vec![0; lengths];
+  for i in (0..lengths - 1).rev() {
+    let lowest = read_u16(cursor.bytes, d.lowest_sym + 2 * i)? as i64;
+    let next_lowest = read_u16(cursor.bytes, d.lowest_sym + 2 * i + 2)? as i64;
+    d.base64[i] = d.base64[i + 1].wrapping_add((lowest - next_lowest) as u64) / 2;
+  }
+  for (i, base) in d.base64.iter_mut().enumerate() {
+    *base = base.checked_shl(64 - i as u32 - d.min_sym_len as u32).unwrap_or(0);
+  }
+  cursor.pos += lengths * 2;
+
+  let symbols = cursor.u16()? as usize;
+  d.btree = cursor.pos;
+  d.symlen = vec![0; symbols];
+  let mut visited = vec![false; symbols];
+  for sym in 0..symbols {
+    if !visited[sym] {
+      d.symlen[sym] = set_symlen(cursor.bytes, d, sym, &mut visited)?;
+    }
+  }
+  cursor.pos += symbols * 3 + (symbols & 1);
+  Some(())
+}
+
+/// Finds the value at `idx` in a compressed table.
+fn decompress_pairs(bytes: &[u8], d: &PairsData, idx: u64) -> Option<u16> {
+  if d.flags & SINGLE_VALUE != 0 {
+    return Some(d.min_sym_len as u16);
+  }
+
+  // The sparse index gives the block and offset of every span-th value, counted from the
+  // middle of the span
+  let entry = d.sparse_index + 6 * (idx / d.span) as usize;
+  let mut block = read_u32(bytes, entry)? as usize;
+  let mut offset = read_u16(bytes, entry + 4)? as i64 + (idx % d.span) as i64 - (d.span / 2) as i64;
+  let block_length = |block: usize| read_u16(bytes, d.block_length + 2 * block).map(|length| length as i64);
+  while offset < 0 {
+    block = block.checked_sub(1)?;
+    offset += block_length(block)? + 1;
+  }
+  while offset > block_length(block)? {
+    offset -= block_length(block)? + 1;
+    block += 1;
+  }
+
+  let mut ptr = d.data + block * d.sizeof_block as usize;
+  let mut buf64 = read_u64_be(bytes, ptr)?;
+  ptr += 8;
+  let mut buf64_size = 64;
+  let symlen = |sym: usize| d.symlen.get(sym).map(|len| *len as i64);
+  let mut sym;
+  loop {
+    let mut len = 0;
+    while buf64 < *d.base64.get(len)? {
+      len += 1;
+    }
+    let code = (buf64 - d.base64[len]).checked_shr(64 - len as u32 - d.min_sym_len as u32).unwrap_or(0);
+    sym = (code as u16).wrapping_add(read_u16(bytes, d.lowest_sym + 2 * len)?) as usize;
+    if offset < symlen(sym)? + 1 {
+      break;
+    }
+    offset -= symlen(sym)? + 1;
+    len += d.min_sym_len as usize;
+    buf64 = buf64.checked_shl(len as u32).unwrap_or(0);
+    buf64_size -= len as i32;
+    if buf64_size <= 32 {
+      buf64_size += 32;
+      buf64 |= (read_u32_be(bytes, ptr)? as u64) << (64 - buf64_size);
+      ptr += 4;
+    }
+  }
+
+  // Walk down the pair tree to the value the offset falls on
+  while symlen(sym)? != 0 {
+    let (left, right) = read_lr(bytes, d.btree, sym)?;
+    if offset < symlen(left)? + 1 {
+      sym = left;
+    } else {
+      offset -= symlen(left)? + 1;
+      sym = right;
+    }
+  }
+  Some(read_lr(bytes, d.btree, sym)?.0 as u16)
+}
+
+/// A table file's contents and the layout found in them.
+struct TableData {
+  bytes: Vec<u8>,
+  /// Compressed tables by side to move, then by lead pawn file.
+  items: Vec<Vec<PairsData>>,
+}
+
+impl TableData {
+  fn parse(bytes: Vec<u8>, info: &TableInfo, is_dtz: bool) -> Option<TableData> {
+    let magic = if is_dtz { DTZ_MAGIC } else { WDL_MAGIC };
+    if bytes.len() < 5 || bytes[..4] != magic {
+      return None;
+    }
+    let flags = bytes[4];
+    if (flags & HAS_PAWNS != 0) != info.has_pawns || (flags & SPLIT != 0) != (info.key != info.key2) {
+      return None;
+    }
+
+    let mut cursor = Cursor { bytes: &bytes, pos: 5 };
+    let sides = if !is_dtz && info.key != info.key2 { 2 } else { 1 };
+    let files = if info.has_pawns { 4 } else { 1 };
+    let pp = info.has_pawns && info.pawn_count[1] > 0;
+    let mut items = vec![vec![PairsData::default(); files]; sides];
+    for file in 0..files {
+      let first = cursor.u8()?;
+      let second = if pp { cursor.u8()? } else { 0xFF };
+      let order = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+      for k in 0..info.piece_count {
+        let pieces = cursor.u8()?;
+        for (side, item) in items.iter_mut().enumerate() {
+          item[file].pieces[k] = if side == 1 { pieces >> 4 } else { pieces & 0xF };
+        }
+      }
+      for (side, item) in items.iter_mut().enumerate() {
+        set_groups(info, &mut item[file], order[side], file);
+      }
+    }
+    cursor.align(2);
+
+    for file in 0..files {
+      for item in items.iter_mut() {
+        set_sizes(&mut cursor, &mut item[file])?;
+      }
+    }
+
+    if is_dtz {
+      for d in items[0].iter_mut().filter(|d| d.flags & MAPPED != 0) {
+        if d.flags & WIDE != 0 {
+          cursor.align(2);
+          for map_idx in d.map_idx.iter_mut() {
+            let len = cursor.u16()? as usize;
+            *map_idx = cursor.pos;
+            cursor.pos += 2 * len;
+          }
+        } else {
+          for map_idx in d.map_idx.iter_mut() {
+            let len = cursor.u8()? as usize;
+            *map_idx = cursor.pos;
+            cursor.pos += len;
+          }
+        }
+      }
+      cursor.align(2);
+    }
+
+    for file in 0..files {
+      for item in items.iter_mut() {
+        item[file].sparse_index = cursor.pos;
+        cursor.pos += item[file].sparse_index_size * 6;
+      }
+    }
+    for file in 0..files {
+      for item in items.iter_mut() {
+        item[file].block_length = cursor.pos;
+        cursor.pos += item[file].block_length_size * 2;
+      }
+    }
+    for file in 0..files {
+      for item in items.iter_mut() {
+        cursor.align(64);
+        item[file].data = cursor.pos;
+        cursor.pos += (item[file].blocks_num * item[file].sizeof_block) as usize;
+      }
+    }
+    if cursor.pos > bytes.len() {
+      return None;
+    }
+
+    Some(TableData { bytes, items })
+  }
+
+  /// Turns a stored DTZ value into plies, given the position's result.
+  fn map_dtz(&self, file: usize, value: u16, wdl: Wdl) -> Option<i32> {
+    let d = &self.items[0][file];
+    let mut value = value as i32;
+    if d.flags & MAPPED != 0 {
+      let start = d.map_idx[[1, 3, 0, 2, 0][(wdl.value() + 2) as usize]];
+      value = if d.flags & WIDE != 0 {
+        read_u16(&self.bytes, start + 2 * value as usize)? as i32
+      } else {
+        *self.bytes.get(start + value as usize)? as i32
+      };
+    }
+    // Tables store moves rather than plies unless told otherwise
+    let in_moves = match wdl {
+      Wdl::Win => d.flags & WIN_PLIES == 0,
+      Wdl::Loss => d.flags & LOSS_PLIES == 0,
+      Wdl::CursedWin | Wdl::BlessedLoss => true,
+      Wdl::Draw => false,
+    };
+    Some(if in_moves { value * 2 + 1 } else { value + 1 })
+  }
+}
+
+/// One table file, read on first use.
+struct Table {
+  info: TableInfo,
+  path: PathBuf,
+  is_dtz: bool,
+  data: OnceLock<Option<TableData>>,
+}
+
+impl Table {
+  fn get_data(&self) -> Option<&TableData> {
+    self.data.get_or_init(|| {
+      let bytes = fs::read(&self.path).ok()?;
+      TableData::parse(bytes, &self.info, self.is_dtz)
+    }).as_ref()
+  }
+}
+
+enum Probe {
+  Value(i32),
+  /// The DTZ table only has the other side to move.
+  ChangeStm,
+}
+
+/// Where a position's value is kept, or why no table needs to be read.
+enum Location<'a> {
+  Entry { data: &'a TableData, side: usize, file: usize, idx: u64 },
+  Known(Probe),
+}
+
+/// Syzygy tablebases read from local directories.
+#[derive(Default)]
+pub struct SyzygyTablebase {
+  /// WDL tables by material, under both colourings.
+  wdl: HashMap<String, Arc<Table>>,
+  dtz: HashMap<String, Arc<Table>>,
+  max_pieces: usize,
+}
+
+impl SyzygyTablebase {
+  /// Finds the `.rtbw` and `.rtbz` files in a list of directories, separated as in the `PATH`
+  /// environment variable. Files are only read when first probed.
+  ///
+  /// Returns [`Error::IoError`](`crate::errors::Error::IoError`) if a directory can't be read.
+  pub fn open(paths: &str) -> Result<SyzygyTablebase, Error> {
+    let mut tablebase = SyzygyTablebase::default();
+    for directory in env::split_paths(paths) {
+      for entry in fs::read_dir(&directory).map_err(|_| Error::IoError)? {
+        let path = entry.map_err(|_| Error::IoError)?.path();
+        tablebase.add_file(&path);
+      }
+    }
+    Ok(tablebase)
+  }
+
+  fn add_file(&mut self, path: &Path) {
+    let (name, is_dtz) = match (path.file_stem().and_then(|stem| stem.to_str()), path.extension().and_then(|ext| ext.to_str())) {
+      (Some(name), Some("rtbw")) => (name, false),
+      (Some(name), Some("rtbz")) => (name, true),
+      _ => return,
+    };
+    let info = match TableInfo::from_name(name) {
+      Some(info) => info,
+      None => return,
+    };
+
+    self.max_pieces = self.max_pieces.max(info.piece_count);
+    let tables = if is_dtz { &mut self.dtz } else { &mut self.wdl };
+    let table = Arc::new(Table { info: info.clone(), path: path.to_path_buf(), is_dtz, data: OnceLock::new() });
+    tables.insert(info.key, Arc::clone(&table));
+    tables.insert(info.key2, table);
+  }
+
+  /// The number of WDL tables found.
+  pub fn get_table_count(&self) -> usize {
+    let mut names: Vec<&str> = self.wdl.values().map(|table| table.info.key.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    names.len()
+  }
+
+  /// Looks a position up in its WDL table, or with `wdl` given, its DTZ table.
+  fn probe_table(&self, board: &Board, wdl: Option<Wdl>) -> Option<Probe> {
+    let (data, side, file, idx) = match self.locate(board, wdl.is_some())? {
+      Location::Entry { data, side, file, idx } => (data, side, file, idx),
+      Location::Known(probe) => return Some(probe),
+    };
+    let value = decompress_pairs(&data.bytes, &data.items[side][file], idx)?;
+    match wdl {
+      Some(wdl) => Some(Probe::Value(data.map_dtz(file, value, wdl)?)),
+      None => Some(Probe::Value(value as i32 - 2)),
+    }
+  }
+
+  /// Finds the table, side to move, lead pawn file and index a position is stored under.
+  fn locate(&self, board: &Board, dtz: bool) -> Option<Location<'_>> {
+    let mut pieces = vec![];
+    for (index, square) in board.get_squares().iter().enumerate() {
+      if let Some(piece) = square.get_piece() {
+        let black = if *piece.get_color() == Color::Black { 8 } else { 0 };
+        pieces.push((index, piece.get_kind()?.index() as u8 + 1 + black));
+      }
+    }
+    // There are no tables for two kings alone
+    if pieces.len() == 2 {
+      return Some(Location::Known(Probe::Value(0)));
+    }
+
+    let signature = material_signature(board);
+    let table = if dtz { self.dtz.get(&signature)? } else { self.wdl.get(&signature)? };
+    let info = &table.info;
+    let data = table.get_data()?;
+    let indices = indices();
+
+    // Tables are stored with the stronger side as white, and symmetric ones with white to move
+    let black_to_move = board.get_active_color() == Color::Black;
+    let flip = (info.key == info.key2 && black_to_move) || signature != info.key;
+    let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+    let stm = (flip ^ black_to_move) as usize;
+
+    let mut squares = vec![];
+    let mut codes = vec![];
+    let mut tb_file = 0;
+    let lead_pawn = if info.has_pawns { Some(data.items[0][0].pieces[0] ^ flip_color) } else { None };
+    if let Some(lead_pawn) = lead_pawn {
+      for (square, _) in pieces.iter().filter(|(_, code)| *code == lead_pawn) {
+        squares.push(square ^ flip_squares);
+        codes.push(lead_pawn ^ flip_color);
+      }
+      let furthest = (0..squares.len()).max_by_key(|i| indices.map_pawns[squares[*i]])?;
+      squares.swap(0, furthest);
+      tb_file = file_of(squares[0]).min(7 - file_of(squares[0]));
+    }
+    let lead_pawns = squares.len();
+
+    if dtz {
+      let d = &data.items[0][tb_file];
+      if (d.flags & STM) as usize != stm && (info.key != info.key2 || info.has_pawns) {
+        return Some(Location::Known(Probe::ChangeStm));
+      }
+    }
+
+    for (square, code) in pieces.iter().filter(|(_, code)| Some(*code) != lead_pawn) {
+      squares.push(square ^ flip_squares);
+      codes.push(code ^ flip_color);
+    }
+    if squares.len() != info.piece_count {
+      return None;
+    }
+
+    let side = stm % data.items.len();
+    let d = &data.items[side][tb_file];
+    // Put the pieces in the order the table indexes them
+    for i in lead_pawns..squares.len() - 1 {
+      if let Some(j) = (i + 1..squares.len()).find(|j| d.pieces[i] == codes[*j]) {
+        codes.swap(i, j);
+        squares.swap(i, j);
+      }
+    }
+
+    // Mirror so the leading piece is on the queen side
+    if file_of(squares[0]) > 3 {
+      squares.iter_mut().for_each(|square| *square ^= 7);
+    }
+
+    let mut idx;
+    if info.has_pawns {
+      idx = indices.lead_pawn_idx[lead_pawns][squares[0]];
+      squares[1..lead_pawns].sort_by_key(|square| indices.map_pawns[*square]);
+      for (i, square) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+        idx += indices.binomial[i][indices.map_pawns[*square] as usize];
+      }
+    } else {
+      // Bring the leading pieces into the a1-d1-d4 triangle, below the diagonal if they can be
+      if rank_of(squares[0]) > 3 {
+        squares.iter_mut().for_each(|square| *square ^= 56);
+      }
+      for i in 0..d.group_len[0] {
+        if off_a1h8(squares[i]) == 0 {
+          continue;
+        }
+        if off_a1h8(squares[i]) > 0 {
+          for square in squares[i..].iter_mut() {
+            *square = ((*square >> 3) | (*square << 3)) & 63;
+          }
+        }
+        break;
+      }
+
+      if info.has_unique_pieces {
+        let (s0, s1, s2) = (squares[0], squares[1], squares[2]);
+        let adjust1 = (s1 > s0) as usize;
+        let adjust2 = (s2 > s0) as usize + (s2 > s1) as usize;
+        idx = if off_a1h8(s0) != 0 {
+          (indices.map_a1d1d4[s0] * 63 + (s1 - adjust1) as u64) * 62 + (s2 - adjust2) as u64
+        } else if off_a1h8(s1) != 0 {
+          (6 * 63 + rank_of(s0) as u64 * 28 + indices.map_b1h1h7[s1]) * 62 + (s2 - adjust2) as u64
+        } else if off_a1h8(s2) != 0 {
+          6 * 63 * 62 + 4 * 28 * 62 + rank_of(s0) as u64 * 7 * 28 + (rank_of(s1) - adjust1) as u64 * 28 + indices.map_b1h1h7[s2]
+        } else {
+          6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank_of(s0) as u64 * 7 * 6 + (rank_of(s1) - adjust1) as u64 * 6 + (rank_of(s2) - adjust2) as u64
+        };
+      } else {
+        idx = indices.map_kk[indices.map_a1d1d4[squares[0]] as usize][squares[1]];
+      }
+    }
+
+    // The other groups are combinations of the squares the earlier groups left free
+    idx *= d.group_idx[0];
+    let mut group_start = d.group_len[0];
+    let mut remaining_pawns = info.has_pawns && info.pawn_count[1] > 0;
+    let mut next = 1;
+    while d.group_len[next] != 0 {
+      let len = d.group_len[next];
+      squares[group_start..group_start + len].sort_unstable();
+      let mut n = 0;
+      for i in 0..len {
+        let square = squares[group_start + i];
+        let adjust = squares[..group_start].iter().filter(|earlier| square > **earlier).count();
+        let free = (square - adjust).checked_sub(if remaining_pawns { 8 } else { 0 })?;
+        n += indices.binomial[i + 1][free];
+      }
+      remaining_pawns = false;
+      idx += n * d.group_idx[next];
+      group_start += len;
+      next += 1;
+    }
+
+    Some(Location::Entry { data, side, file: tb_file, idx })
+  }
+
+  fn probe_wdl_table(&self, board: &Board) -> Option<Wdl> {
+    match self.probe_table(board, None)? {
+      Probe::Value(value) => Wdl::from_value(value),
+      Probe::ChangeStm => None,
+    }
+  }
+
+  /// Tables assume captures are never best, so captures (and with `check_zeroing`, pawn moves)
+  /// are searched first. Also returns whether a zeroing move is best.
+  fn search(&self, board: &Board, check_zeroing: bool) -> Option<(Wdl, bool)> {
+    let moves = board.get_legal_moves();
+    let mut best = Wdl::Loss;
+    let mut move_count = 0;
+    for mv in moves.iter() {
+      let is_pawn = board.piece_at(mv.from).and_then(|piece| piece.get_kind()) == Some(PieceKind::Pawn);
+      if !board.is_capture(mv) && (!check_zeroing || !is_pawn) {
+        continue;
+      }
+      move_count += 1;
+      let mut after = board.clone();
+      after.make_move(*mv).ok()?;
+      let value = self.search(&after, false)?.0.negate();
+      if value > best {
+        best = value;
+        if value == Wdl::Win {
+          return Some((value, true));
+        }
+      }
+    }
+
+    let no_more_moves = move_count > 0 && move_count == moves.len();
+    let value = if no_more_moves { best } else { self.probe_wdl_table(board)? };
+    if best >= value {
+      return Some((best, best > Wdl::Draw || no_more_moves));
+    }
+    Some((value, false))
+  }
+
+  fn dtz(&self, board: &Board) -> Option<i32> {
+    let (wdl, zeroing_is_best) = self.search(board, true)?;
+    if wdl == Wdl::Draw {
+      return Some(0);
+    }
+    if zeroing_is_best {
+      return Some(dtz_before_zeroing(wdl));
+    }
+
+    match self.probe_table(board, Some(wdl))? {
+      Probe::Value(dtz) => {
+        let dtz = if wdl == Wdl::BlessedLoss || wdl == Wdl::CursedWin { dtz + 100 } else { dtz };
+        Some(dtz * wdl.value().signum())
+      }
+      Probe::ChangeStm => {
+        // One ply further on the table has the answer
+        let mut min_dtz = None;
+        for mv in board.get_legal_moves() {
+          let zeroing = board.is_capture(&mv) || board.piece_at(mv.from).and_then(|piece| piece.get_kind()) == Some(PieceKind::Pawn);
+          let mut after = board.clone();
+          after.make_move(mv).ok()?;
+          let mut dtz = if zeroing { -dtz_before_zeroing(self.search(&after, false)?.0) } else { -self.dtz(&after)? };
+          if dtz == 1 && after.is_in_check(&after.get_active_color()) && after.get_legal_moves().is_empty() {
+            min_dtz = Some(1);
+          }
+          if !zeroing {
+            dtz += dtz.signum();
+          }
+          if dtz.signum() == wdl.value().signum() && min_dtz.is_none_or(|min| dtz < min) {
+            min_dtz = Some(dtz);
+          }
+        }
+        Some(min_dtz.unwrap_or(-1))
+      }
+    }
+  }
+}
+
+impl Tablebase for SyzygyTablebase {
+  fn get_max_pieces(&self) -> usize {
+    self.max_pieces
+  }
+
+  fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+    if !is_probeable(self, board) {
+      return None;
+    }
+    self.search(board, false).map(|(wdl, _)| wdl)
+  }
+
+  fn probe_dtz(&self, board: &Board) -> Option<i32> {
+    if !is_probeable(self, board) {
+      return None;
+    }
+    self.dtz(board)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use crate::tablebase::dtm::{test_tablebase, Dtm};
+
+  /// A table file whose tables each hold a single value, one per side to move.
+  fn single_value_file(magic: [u8; 4], pieces: &[u8], values: &[u8]) -> Vec<u8> {
+    let mut bytes = magic.to_vec();
+    bytes.push(SPLIT);
+    bytes.push(0);
+    bytes.extend(pieces.iter().map(|piece| piece | piece << 4));
+    if bytes.len() % 2 == 1 {
+      bytes.push(0);
+    }
+    for value in values {
+      bytes.extend_from_slice(&[SINGLE_VALUE, *value]);
+    }
+    bytes.resize(64, 0);
+    bytes
+  }
+
+  /// One table to write: its flags, a value for every index and, for DTZ tables, the maps
+  /// from stored values to distances.
+  struct Contents {
+    flags: u8,
+    values: Vec<u16>,
+    maps: Vec<Vec<u8>>,
+  }
+
+  /// A table file laid out the way real ones are, but with every value given a code of the
+  /// same length. Tables are listed by lead pawn file, then by side to move.
+  fn compressed_file(magic: [u8; 4], flags: u8, pieces: &[u8], tables: &[Vec<Contents>]) -> Vec<u8> {
+    // Blocks of 64 bytes, and a sparse index entry every 256 values
+    let (block_bits, span): (u8, usize) = (6, 256);
+    let mut bytes = magic.to_vec();
+    bytes.push(flags);
+    for _ in tables {
+      bytes.push(0);
+      bytes.extend(pieces.iter().map(|piece| piece | piece << 4));
+    }
+    if bytes.len() % 2 == 1 {
+      bytes.push(0);
+    }
+
+    // Code length, values per block and blocks of each table
+    let mut layouts = vec![];
+    for table in tables.iter().flatten() {
+      bytes.push(table.flags);
+      if table.flags & SINGLE_VALUE != 0 {
+        bytes.push(table.values[0] as u8);
+        layouts.push((0, 0, 0));
+        continue;
+      }
+      let symbols = table.values.iter().max().unwrap() + 1;
+      let len = (u16::BITS - (symbols - 1).leading_zeros()).max(1) as usize;
+      let per_block = 512 / len;
+      let blocks = table.values.len().div_ceil(per_block);
+      bytes.extend_from_slice(&[block_bits, span.trailing_zeros() as u8, 0]);
+      bytes.extend_from_slice(&(blocks as u32).to_le_bytes());
+      // The longest and shortest code lengths, then the lowest symbol of the one length
+      bytes.extend_from_slice(&[len as u8, len as u8, 0, 0]);
+      bytes.extend_from_slice(&symbols.to_le_bytes());
+      for value in 0..symbols {
+        bytes.extend_from_slice(&[value as u8, 0xF0 | (value >> 8) as u8, 0xFF]);
+      }
+      if symbols % 2 == 1 {
+        bytes.push(0);
+      }
+      layouts.push((len, per_block, blocks));
+    }
+    if magic == DTZ_MAGIC {
+      for table in tables.iter().map(|sides| &sides[0]).filter(|table| table.flags & MAPPED != 0) {
+        for map in table.maps.iter() {
+          bytes.push(map.len() as u8);
+          bytes.extend_from_slice(map);
+        }
+      }
+      if bytes.len() % 2 == 1 {
+        bytes.push(0);
+      }
+    }
+
+    let compressed = || tables.iter().flatten().zip(layouts.iter()).filter(|(table, _)| table.flags & SINGLE_VALUE == 0);
+    for (table, (_, per_block, blocks)) in compressed() {
+      // Each entry gives the block and offset of the value in the middle of its span
+      for k in 0..table.values.len().div_ceil(span) {
+        let middle = k * span + span / 2;
+        let block = (middle / per_block).min(blocks - 1);
+        bytes.extend_from_slice(&(block as u32).to_le_bytes());
+        bytes.extend_from_slice(&((middle - block * per_block) as u16).to_le_bytes());
+      }
+    }
+    for (table, (_, per_block, blocks)) in compressed() {
+      for block in 0..*blocks {
+        let count = (table.values.len() - block * per_block).min(*per_block);
+        bytes.extend_from_slice(&(count as u16 - 1).to_le_bytes());
+      }
+    }
+    for (table, (len, per_block, _)) in tables.iter().flatten().zip(layouts.iter()) {
+      bytes.resize(bytes.len().div_ceil(64) * 64, 0);
+      if table.flags & SINGLE_VALUE != 0 {
+        continue;
+      }
+      for chunk in table.values.chunks(*per_block) {
+        let mut block = [0u8; 64];
+        for (i, value) in chunk.iter().enumerate() {
+          for bit in 0..*len {
+            if value >> (len - 1 - bit) & 1 != 0 {
+              block[(i * len + bit) / 8] |= 0x80 >> ((i * len + bit) % 8);
+            }
+          }
+        }
+        bytes.extend_from_slice(&block);
+      }
+    }
+    // The decoder reads a little past the block it is in
+    bytes.extend_from_slice(&[0; 8]);
+    bytes
+  }
+
+  /// Sets up a position, unless pieces overlap, a pawn is on a back rank, the kings touch or
+  /// the side that just moved is in check.
+  fn position(pieces: &[(char, usize)], black_to_move: bool) -> Option<Board> {
+    let mut grid = [None; 64];
+    for (letter, square) in pieces.iter() {
+      if grid[*square].is_some() || (*letter == 'P' && (rank_of(*square) == 0 || rank_of(*square) == 7)) {
+        return None;
+      }
+      grid[*square] = Some(*letter);
+    }
+    let kings: Vec<usize> = pieces.iter().filter(|(letter, _)| letter.eq_ignore_ascii_case(&'k')).map(|(_, square)| *square).collect();
+    if rank_of(kings[0]).abs_diff(rank_of(kings[1])) <= 1 && file_of(kings[0]).abs_diff(file_of(kings[1])) <= 1 {
+      return None;
+    }
+
+    let mut fen = String::new();
+    for rank in (0..8).rev() {
+      let mut empty = 0;
+      for file in 0..8 {
+        match grid[rank * 8 + file] {
+          Some(letter) => {
+            if empty > 0 {
+              fen += &empty.to_string();
+              empty = 0;
+            }
+            fen.push(letter);
+          }
+          None => empty += 1,
+        }
+      }
+      if empty > 0 {
+        fen += &empty.to_string();
+      }
+      fen.push(if rank > 0 { '/' } else { ' ' });
+    }
+    fen += if black_to_move { "b - - 0 1" } else { "w - - 0 1" };
+    let board = Board::from_fen_string(&fen).ok()?;
+    (!board.is_in_check(&board.get_active_color().opposite())).then_some(board)
+  }
+
+  /// Where a position is stored in its WDL table: side to move, lead pawn file and index.
+  type Key = (usize, usize, u64);
+
+  fn key(tablebase: &SyzygyTablebase, board: &Board) -> Key {
+    match tablebase.locate(board, false) {
+      Some(Location::Entry { side, file, idx, .. }) => (side, file, idx),
+      _ => panic!("{} has no entry", board.to_fen_string()),
+    }
+  }
+
+  /// Writes the WDL and DTZ files of a three piece material, with the stronger side as white
+  /// and the pieces in the order given, filled in from the generated distance-to-mate tables.
+  /// Returns the distance to zeroing of every position the white side wins or loses.
+  fn write_tables(directory: &Path, name: &str, pieces: &[(u8, char)]) -> HashMap<Key, u32> {
+    let has_pawns = pieces[0].1 == 'P';
+    let files = if has_pawns { 4 } else { 1 };
+    let flags = SPLIT | if has_pawns { HAS_PAWNS } else { 0 };
+    let codes: Vec<u8> = pieces.iter().map(|(code, _)| *code).collect();
+    let placeholder = |sides: usize| (0..files).map(|_| (0..sides).map(|_| Contents { flags: SINGLE_VALUE, values: vec![0], maps: vec![] }).collect()).collect::<Vec<_>>();
+    fs::write(directory.join(format!("{}.rtbw", name)), compressed_file(WDL_MAGIC, flags, &codes, &placeholder(2))).unwrap();
+    fs::write(directory.join(format!("{}.rtbz", name)), compressed_file(DTZ_MAGIC, flags, &codes, &placeholder(1))).unwrap();
+    let tablebase = SyzygyTablebase::open(directory.to_str().unwrap()).unwrap();
+    let oracle = test_tablebase();
+    let sizes: Vec<Vec<usize>> = tablebase.wdl[name].get_data().unwrap().items.iter()
+      .map(|files| files.iter().map(|d| d.group_idx[d.group_len.iter().position(|len| *len == 0).unwrap()] as usize).collect())
+      .collect();
+
+    // Every index has a position with the leading piece on the queen side, and without pawns
+    // also in the a1-d1-d4 triangle. Those that share an index must share a result.
+    let leading: Vec<usize> = if has_pawns {
+      (8..56).filter(|square| file_of(*square) < 4).collect()
+    } else {
+      (0..28).filter(|square| file_of(*square) < 4 && off_a1h8(*square) <= 0).collect()
+    };
+    let mut wdl_values: Vec<Vec<Vec<Option<u16>>>> = sizes.iter().map(|sizes| sizes.iter().map(|size| vec![None; *size]).collect()).collect();
+    // Wins with white to move and losses with black to move, with the positions each move
+    // leads to, and whether a pawn move wins at once
+    let mut nodes: HashMap<Key, (Vec<Key>, bool)> = HashMap::new();
+    for lead in leading.iter() {
+      for (s1, s2, black_to_move) in (0..64 * 64 * 2).map(|i| (i % 64, i / 64 % 64, i >= 64 * 64)) {
+        let placed: Vec<(char, usize)> = pieces.iter().map(|(_, letter)| *letter).zip([*lead, s1, s2]).collect();
+        let board = match position(&placed, black_to_move) {
+          Some(board) => board,
+          None => continue,
+        };
+        let (side, file, idx) = key(&tablebase, &board);
+        let result = oracle.probe(&board).unwrap();
+        let value = match result {
+          Dtm::Win(_) => 4,
+          Dtm::Loss(_) => 0,
+          Dtm::Draw => 2,
+        };
+        let stored = wdl_values[side][file][idx as usize].get_or_insert(value);
+        assert_eq!(*stored, value, "{}", board.to_fen_string());
+
+        if !matches!((result, black_to_move), (Dtm::Win(_), false) | (Dtm::Loss(_), true)) || nodes.contains_key(&(side, file, idx)) {
+          continue;
+        }
+        let mut children = vec![];
+        let mut pawn_move_wins = false;
+        for mv in board.get_legal_moves() {
+          let mut after = board.clone();
+          after.make_move(mv).unwrap();
+          let after_result = oracle.probe(&after).unwrap();
+          if board.piece_at(mv.from).and_then(|piece| piece.get_kind()) == Some(PieceKind::Pawn) {
+            pawn_move_wins |= matches!(after_result, Dtm::Loss(_));
+          } else if matches!(after_result, Dtm::Win(_) | Dtm::Loss(_)) {
+            children.push(key(&tablebase, &after));
+          }
+        }
+        nodes.insert((side, file, idx), (children, pawn_move_wins));
+      }
+    }
+
+    // Distances to zeroing, worked backwards from the winning pawn moves and the mates
+    let mut distances: HashMap<Key, u32> = HashMap::new();
+    for (key, (children, pawn_move_wins)) in nodes.iter() {
+      if *pawn_move_wins {
+        distances.insert(*key, 1);
+      } else if key.0 == 1 && children.is_empty() {
+        distances.insert(*key, 0);
+      }
+    }
+    for plies in 1..200 {
+      let mut found = vec![];
+      for (key, (children, _)) in nodes.iter().filter(|(key, _)| !distances.contains_key(key)) {
+        let reached = if key.0 == 0 {
+          children.iter().any(|child| distances.get(child) == Some(&(plies - 1)))
+        } else {
+          children.iter().all(|child| distances.get(child).is_some_and(|distance| *distance < plies))
+            && children.iter().any(|child| distances.get(child) == Some(&(plies - 1)))
+        };
+        if reached {
+          found.push(*key);
+        }
+      }
+      distances.extend(found.into_iter().map(|key| (key, plies)));
+    }
+    assert_eq!(distances.len(), nodes.len());
+
+    let wdl_tables = (0..files).map(|file| {
+      wdl_values.iter().map(|files| Contents { flags: 0, values: files[file].iter().map(|value| value.unwrap_or(2)).collect(), maps: vec![] }).collect()
+    }).collect::<Vec<Vec<Contents>>>();
+    // DTZ tables have white to move, and store distances in plies through a map
+    let dtz_tables = (0..files).map(|file| {
+      let mut map: Vec<u8> = distances.iter().filter(|(key, _)| key.0 == 0 && key.1 == file).map(|(_, distance)| (distance - 1) as u8).collect();
+      map.sort_unstable();
+      map.dedup();
+      let values = (0..sizes[0][file] as u64).map(|idx| {
+        distances.get(&(0, file, idx)).map_or(0, |distance| map.binary_search(&((distance - 1) as u8)).unwrap() as u16)
+      }).collect();
+      vec![Contents { flags: MAPPED | WIN_PLIES | LOSS_PLIES, values, maps: vec![map, vec![0], vec![0], vec![0]] }]
+    }).collect::<Vec<Vec<Contents>>>();
+    fs::write(directory.join(format!("{}.rtbw", name)), compressed_file(WDL_MAGIC, flags, &codes, &wdl_tables)).unwrap();
+    fs::write(directory.join(format!("{}.rtbz", name)), compressed_file(DTZ_MAGIC, flags, &codes, &dtz_tables)).unwrap();
+    distances
+  }
+
+  #[test]
+  fn test_index_tables() {
+    let indices = indices();
+    assert_eq!(indices.map_kk.iter().flatten().max(), Some(&461));
+    assert_eq!(indices.binomial[2][5], 10);
+    assert_eq!(indices.binomial[5][63], 7028847);
+    assert_eq!(indices.lead_pawns_size[1], [6; 4]);
+    assert_eq!(indices.map_pawns[8], 47);
+    assert_eq!(indices.map_pawns[15], 46);
+  }
+
+  #[test]
+  fn test_table_names() {
+    let info = TableInfo::from_name("KRPvKP").unwrap();
+    assert_eq!(info.key2, "KPvKRP");
+    assert_eq!(info.pawn_count, [1, 1]);
+    assert!(info.has_pawns && info.has_unique_pieces);
+    assert!(!TableInfo::from_name("KNNvK").unwrap().has_unique_pieces);
+    assert_eq!(TableInfo::from_name("KQvQ"), None);
+    assert_eq!(TableInfo::from_name("KQQQQvKQQ"), None);
+  }
+
+  #[test]
+  fn test_decompress_pairs() {
+    // Two leaf symbols of one bit each: 0 stands for 3 and 1 for 7. Forty values to a block.
+    let value = |i: u64| if i.is_multiple_of(3) { 7 } else { 3 };
+    let mut bytes = vec![0, 3, 4, 0, 5, 0, 0, 0, 1, 1, 0, 0, 2, 0];
+    bytes.extend_from_slice(&[3, 0xF0, 0xFF, 7, 0xF0, 0xFF]);
+    let mut d = PairsData { group_len: [1, 0, 0, 0, 0, 0, 0, 0], group_idx: [1, 200, 0, 0, 0, 0, 0, 0], ..PairsData::default() };
+    set_sizes(&mut Cursor { bytes: &bytes, pos: 0 }, &mut d).unwrap();
+    assert_eq!((d.sizeof_block, d.span, d.sparse_index_size, d.symlen.len()), (8, 16, 13, 2));
+
+    d.sparse_index = bytes.len();
+    for k in 0..13u64 {
+      let middle = 16 * k + 8;
+      bytes.extend_from_slice(&((middle / 40) as u32).to_le_bytes());
+      bytes.extend_from_slice(&((middle % 40) as u16).to_le_bytes());
+    }
+    d.block_length = bytes.len();
+    for _ in 0..5 {
+      bytes.extend_from_slice(&39u16.to_le_bytes());
+    }
+    d.data = bytes.len();
+    for block in 0..5 {
+      let bits = (0..40).fold(0u64, |bits, j| bits | ((value(40 * block + j) == 7) as u64) << (63 - j));
+      bytes.extend_from_slice(&bits.to_be_bytes());
+    }
+    bytes.extend_from_slice(&[0; 4]);
+
+    for idx in 0..200 {
+      assert_eq!(decompress_pairs(&bytes, &d, idx), Some(value(idx)), "index {}", idx);
+    }
+  }
+
+  #[test]
+  fn test_probe_compressed_tables() {
+    let directory = env::temp_dir().join(format!("chess-syzygy-compressed-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let materials = [("KQvK", [(5, 'Q'), (6, 'K'), (14, 'k')]), ("KRvK", [(4, 'R'), (6, 'K'), (14, 'k')]), ("KPvK", [(1, 'P'), (6, 'K'), (14, 'k')])];
+    let distances: Vec<HashMap<Key, u32>> = materials.iter().map(|(name, pieces)| write_tables(&directory, name, pieces)).collect();
+    // Underpromotions lead to the drawn minor piece tables
+    for (name, piece) in [("KBvK", 3), ("KNvK", 2)] {
+      fs::write(directory.join(format!("{}.rtbw", name)), single_value_file(WDL_MAGIC, &[piece, 6, 14], &[2, 2])).unwrap();
+      fs::write(directory.join(format!("{}.rtbz", name)), single_value_file(DTZ_MAGIC, &[piece, 6, 14], &[0])).unwrap();
+    }
+    let tablebase = SyzygyTablebase::open(directory.to_str().unwrap()).unwrap();
+    assert_eq!(tablebase.get_table_count(), 5);
+
+    // Positions from all over the board, so every mirroring is looked up
+    let oracle = test_tablebase();
+    for ((_, pieces), distances) in materials.iter().zip(distances.iter()) {
+      for i in (0..2 * 64 * 64 * 64).step_by(97) {
+        let placed: Vec<(char, usize)> = pieces.iter().map(|(_, letter)| *letter).zip([i % 64, i / 64 % 64, i / 4096 % 64]).collect();
+        let board = match position(&placed, i >= 64 * 64 * 64) {
+          Some(board) => board,
+          None => continue,
+        };
+        let fen = board.to_fen_string();
+        let result = oracle.probe(&board).unwrap();
+        let (wdl, dtz) = match result {
+          Dtm::Win(_) => (Wdl::Win, distances[&key(&tablebase, &board)] as i32),
+          Dtm::Loss(_) => (Wdl::Loss, -(distances[&key(&tablebase, &board)].max(1) as i32)),
+          Dtm::Draw => (Wdl::Draw, 0),
+        };
+        // Without pawns only mate zeroes the winner's count, so DTZ is the distance to mate
+        if let (Dtm::Win(plies) | Dtm::Loss(plies), false) = (result, pieces[0].1 == 'P') {
+          assert_eq!(dtz.unsigned_abs(), plies.max(1), "{}", fen);
+        }
+        assert_eq!((tablebase.probe_wdl(&board), tablebase.probe_dtz(&board)), (Some(wdl), Some(dtz)), "{}", fen);
+      }
+    }
+
+    let probe = |fen: &str| {
+      let board = Board::from_fen_string(fen).unwrap();
+      (tablebase.probe_wdl(&board), tablebase.probe_dtz(&board))
+    };
+    assert_eq!(probe("8/8/8/8/8/1K6/7Q/k7 w - - 0 1"), (Some(Wdl::Win), Some(1)));
+    assert_eq!(probe("1k6/8/1K6/8/8/8/8/7R w - - 0 1"), (Some(Wdl::Win), Some(1)));
+    assert_eq!(probe("8/8/8/8/8/8/1Qk5/6K1 b - - 0 1"), (Some(Wdl::Draw), Some(0)));
+    // A king on the sixth rank in front of its pawn wins; the defender's king in front of the
+    // pawn, or in the corner against a rook pawn, draws
+    assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), (Some(Wdl::Win), Some(3)));
+    assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), (Some(Wdl::Loss), Some(-4)));
+    assert_eq!(probe("8/8/8/8/8/4k3/4P3/4K3 w - - 0 1"), (Some(Wdl::Draw), Some(0)));
+    assert_eq!(probe("k7/8/K7/P7/8/8/8/8 w - - 0 1"), (Some(Wdl::Draw), Some(0)));
+    fs::remove_dir_all(&directory).unwrap();
+  }
+
+  #[test]
+  fn test_probe_single_value_tables() {
+    let directory = env::temp_dir().join(format!("chess-syzygy-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    // White queen, white king, black king; the side with the queen wins, zeroing in 5 moves
+    fs::write(directory.join("KQvK.rtbw"), single_value_file(WDL_MAGIC, &[5, 6, 14], &[4, 0])).unwrap();
+    fs::write(directory.join("KQvK.rtbz"), single_value_file(DTZ_MAGIC, &[5, 6, 14], &[5])).unwrap();
+    fs::write(directory.join("notes.txt"), "not a table").unwrap();
+    let tablebase = SyzygyTablebase::open(directory.to_str().unwrap()).unwrap();
+    assert_eq!(tablebase.get_table_count(), 1);
+    assert_eq!(tablebase.get_max_pieces(), 3);
+
+    let probe = |fen: &str| {
+      let board = Board::from_fen_string(fen).unwrap();
+      (tablebase.probe_wdl(&board), tablebase.probe_dtz(&board))
+    };
+    assert_eq!(probe("7k/8/8/8/8/8/8/K2Q4 w - - 0 1"), (Some(Wdl::Win), Some(11)));
+    // The DTZ table only has white to move, so black's moves are looked at
+    assert_eq!(probe("7k/8/8/8/8/8/8/K2Q4 b - - 0 1"), (Some(Wdl::Loss), Some(-12)));
+    assert_eq!(probe("k2q4/8/8/8/8/8/8/7K w - - 0 1"), (Some(Wdl::Loss), Some(-12)));
+    // Taking the queen leaves two kings, which is a draw
+    assert_eq!(probe("7k/6Q1/8/8/8/8/8/K7 b - - 0 1"), (Some(Wdl::Draw), Some(0)));
+    assert_eq!(probe("7k/8/8/8/8/8/8/K2R4 w - - 0 1"), (None, None));
+    fs::remove_dir_all(&directory).unwrap();
+  }
+}
@@ -0,0 +1,516 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::book::PolyglotBook;
+use crate::errors::Error;
+use crate::eval::EvalParams;
+use crate::piece::Color;
+use crate::search::{SearchInfo, SearchLimits, SearchOptions, SearchResult, SearchSignals, Searcher, MATE_BOUND, MATE_SCORE, MAX_PLY};
+use crate::search::time::{ClockState, SystemTimeSource, TimeManager, TimeSource};
+use crate::tablebase::{SyzygyTablebase, Tablebase};
+
+const ENGINE_NAME: &str = "rust-chess";
+const DEFAULT_DEPTH: u32 = 6;
+const MAX_THREADS: usize = 256;
+const MAX_HASH_SIZE_MB: usize = 4096;
+const MAX_MULTI_PV: usize = 256;
+
+/// Formats a search score the way UCI expects: `cp <centipawns>` or `mate <moves>`,
+/// negative when the side to move is getting mated.
+pub fn format_score(score: i32) -> String {
+  if score >= MATE_BOUND {
+    format!("mate {}", (MATE_SCORE - score + 1) / 2)
+  } else if score <= -MATE_BOUND {
+    format!("mate -{}", (MATE_SCORE + score) / 2)
+  } else {
+    format!("cp {}", score)
+  }
+}
+
+/// Reads the number following `name` in a `go` command, if there is one.
+fn go_arg<T: std::str::FromStr>(args: &[&str], name: &str) -> Option<T> {
+  args.iter().position(|arg| *arg == name)
+    .and_then(|i| args.get(i + 1))
+    .and_then(|value| value.parse().ok())
+}
+
+/// Builds a time manager from the `go` arguments for the side to move, if the GUI sent a clock
+/// or a fixed move time.
+pub fn parse_time_limits(args: &[&str], color: Color, source: Arc<dyn TimeSource>) -> Option<TimeManager> {
+  if let Some(movetime) = go_arg::<u64>(args, "movetime") {
+    return Some(TimeManager::fixed(Duration::from_millis(movetime), source));
+  }
+
+  let (time_name, increment_name) = if color == Color::White { ("wtime", "winc") } else { ("btime", "binc") };
+  // Some GUIs send negative times once the flag has fallen
+  let time_left = go_arg::<i64>(args, time_name)?.max(0) as u64;
+  let clock = ClockState {
+    time_left: Duration::from_millis(time_left),
+    increment: Duration::from_millis(go_arg::<i64>(args, increment_name).unwrap_or(0).max(0) as u64),
+    moves_to_go: go_arg(args, "movestogo"),
+  };
+  Some(TimeManager::new(&clock, source))
+}
+
+/// Parses the arguments of a `position` command, e.g. `startpos moves e2e4 e7e5`
+/// or `fen <fen> moves ...`. With `chess960`, castling moves are read as the king taking its
+/// own rook.
+pub fn parse_position(args: &[&str], chess960: bool) -> Result<Board, Error> {
+  let moves_at = args.iter().position(|arg| *arg == "moves").unwrap_or(args.len());
+  let mut board = match args.first() {
+    Some(&"startpos") => Board::new(),
+    Some(&"fen") => Board::from_fen_string(&args[1..moves_at].join(" "))?,
+    _ => return Err(Error::InvalidFENString),
+  };
+  board.set_chess960(chess960);
+
+  for move_str in args.iter().skip(moves_at + 1) {
+    let mv = Move::from_uci(move_str)?;
+    if !board.is_legal_move(&mv) {
+      return Err(Error::InvalidMove);
+    }
+    board.make_move(mv)?;
+  }
+
+  Ok(board)
+}
+
+/// Speaks the Universal Chess Interface protocol, one command at a time.
+///
+/// Searches run on their own thread, so `stop`, `ponderhit` and `isready` are answered while
+/// the engine thinks. Everything the engine says goes to `out`, including the `info` lines and
+/// `bestmove` written from the search thread.
+pub struct UciEngine<W: Write + Send + 'static> {
+  board: Board,
+  options: SearchOptions,
+  /// The searcher, while no search is running.
+  searcher: Option<Searcher>,
+  /// The running search, which hands the searcher back when it's done.
+  search_thread: Option<JoinHandle<Searcher>>,
+  signals: SearchSignals,
+  time_source: Arc<dyn TimeSource>,
+  /// The opening book set with the `BookFile` option, consulted before searching.
+  book: Option<PolyglotBook>,
+  /// The Syzygy tablebases found in the `SyzygyPath` directories.
+  tablebase: Option<Arc<dyn Tablebase>>,
+  /// Whether the GUI plays Chess960, set with the `UCI_Chess960` option.
+  chess960: bool,
+  out: Arc<Mutex<W>>,
+}
+
+impl<W: Write + Send + 'static> UciEngine<W> {
+  pub fn new(out: W) -> UciEngine<W> {
+    UciEngine::with_time_source(out, Arc::new(SystemTimeSource::default()))
+  }
+
+  pub fn with_time_source(out: W, time_source: Arc<dyn TimeSource>) -> UciEngine<W> {
+    let mut searcher = Searcher::new(EvalParams::default());
+    searcher.set_time_source(Arc::clone(&time_source));
+    UciEngine {
+      board: Board::new(),
+      options: searcher.get_options().clone(),
+      signals: searcher.get_signals(),
+      searcher: Some(searcher),
+      search_thread: None,
+      time_source,
+      book: None,
+      tablebase: None,
+      chess960: false,
+      out: Arc::new(Mutex::new(out)),
+    }
+  }
+
+  pub fn get_board(&self) -> &Board {
+    &self.board
+  }
+
+  pub fn get_options(&self) -> &SearchOptions {
+    &self.options
+  }
+
+  pub fn is_searching(&self) -> bool {
+    self.search_thread.is_some()
+  }
+
+  /// Waits for the running search, if any, to finish and print its best move.
+  pub fn wait(&mut self) {
+    if let Some(handle) = self.search_thread.take() {
+      self.searcher = Some(handle.join().expect("search thread panicked"));
+    }
+  }
+
+  fn get_searcher(&mut self) -> &mut Searcher {
+    self.wait();
+    self.searcher.as_mut().expect("searcher is returned when the search thread ends")
+  }
+
+  /// Handles one line from the GUI. Returns `false` once the GUI has asked the engine to quit.
+  pub fn handle_command(&mut self, line: &str) -> io::Result<bool> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    match tokens.first() {
+      Some(&"uci") => {
+        let mut out = self.out.lock().expect("output lock poisoned");
+        writeln!(out, "id name {}", ENGINE_NAME)?;
+        writeln!(out, "id author {}", env!("CARGO_PKG_AUTHORS"))?;
+        let defaults = SearchOptions::default();
+        writeln!(out, "option name Threads type spin default {} min 1 max {}", defaults.threads, MAX_THREADS)?;
+        writeln!(out, "option name Hash type spin default {} min 1 max {}", defaults.hash_size_mb, MAX_HASH_SIZE_MB)?;
+        writeln!(out, "option name MultiPV type spin default {} min 1 max {}", defaults.multi_pv, MAX_MULTI_PV)?;
+        writeln!(out, "option name Ponder type check default false")?;
+        writeln!(out, "option name BookFile type string default <empty>")?;
+        writeln!(out, "option name SyzygyPath type string default <empty>")?;
+        writeln!(out, "option name UCI_Chess960 type check default false")?;
+        writeln!(out, "uciok")?;
+        out.flush()?;
+      }
+      Some(&"isready") => {
+        let mut out = self.out.lock().expect("output lock poisoned");
+        writeln!(out, "readyok")?;
+        out.flush()?;
+      }
+      Some(&"ucinewgame") => {
+        self.board = Board::new();
+        self.board.set_chess960(self.chess960);
+        self.get_searcher().clear();
+      }
+      Some(&"setoption") => self.set_option(&tokens[1..]),
+      Some(&"position") => {
+        self.wait();
+        // A position the engine can't follow is ignored rather than guessed at
+        if let Ok(board) = parse_position(&tokens[1..], self.chess960) {
+          self.board = board;
+        }
+      }
+      Some(&"go") => self.go(&tokens[1..])?,
+      Some(&"stop") => {
+        self.signals.stop();
+        self.wait();
+      }
+      Some(&"ponderhit") => self.signals.ponderhit(),
+      Some(&"quit") => {
+        self.signals.stop();
+        self.wait();
+        return Ok(false);
+      }
+      _ => {}
+    }
+    Ok(true)
+  }
+
+  /// Applies `setoption name <name> value <value>`. Unknown options and bad values are ignored.
+  fn set_option(&mut self, args: &[&str]) {
+    let value_at = args.iter().position(|arg| *arg == "value").unwrap_or(args.len());
+    if args.first() != Some(&"name") {
+      return;
+    }
+    let name = args[1..value_at].join(" ").to_lowercase();
+    let text = args.get(value_at + 1..).map(|words| words.join(" ")).unwrap_or_default();
+    if name == "bookfile" {
+      // A book that can't be read leaves the engine without one rather than failing later
+      self.book = PolyglotBook::load(&text).ok();
+      return;
+    }
+    if name == "syzygypath" {
+      self.tablebase = match SyzygyTablebase::open(&text) {
+        Ok(tablebase) if tablebase.get_table_count() > 0 && text != "<empty>" => Some(Arc::new(tablebase)),
+        _ => None,
+      };
+      return;
+    }
+    if name == "uci_chess960" {
+      self.chess960 = text == "true";
+      self.board.set_chess960(self.chess960);
+      return;
+    }
+    let value = text.parse::<usize>().ok();
+
+    match (name.as_str(), value) {
+      ("threads", Some(threads)) => self.options.threads = threads.clamp(1, MAX_THREADS),
+      ("hash", Some(size)) => self.options.hash_size_mb = size.clamp(1, MAX_HASH_SIZE_MB),
+      ("multipv", Some(lines)) => self.options.multi_pv = lines.clamp(1, MAX_MULTI_PV),
+      _ => {}
+    }
+  }
+
+  /// Starts a search on its own thread for `go [ponder] [infinite] [depth] [movetime] [wtime ...]`.
+  /// Positions in the opening book are answered straight away with a book move instead, unless
+  /// the GUI asked for analysis.
+  fn go(&mut self, args: &[&str]) -> io::Result<()> {
+    if !args.contains(&"ponder") && !args.contains(&"infinite") {
+      self.wait();
+      let random = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or(0);
+      if let Some(mv) = self.book.as_ref().and_then(|book| book.weighted_move(&self.board, random)) {
+        let mut out = self.out.lock().expect("output lock poisoned");
+        writeln!(out, "bestmove {}", mv)?;
+        return out.flush();
+      }
+    }
+
+    let options = self.options.clone();
+    let tablebase = self.tablebase.clone();
+    let searcher = self.get_searcher();
+    searcher.set_options(options);
+    searcher.set_tablebase(tablebase);
+    let mut searcher = self.searcher.take().expect("no search is running");
+
+    let time = parse_time_limits(args, self.board.get_active_color(), Arc::clone(&self.time_source));
+    let limits = if args.contains(&"infinite") {
+      SearchLimits::infinite()
+    } else {
+      let default_depth = if time.is_some() { MAX_PLY as u32 / 2 } else { DEFAULT_DEPTH };
+      SearchLimits { depth: go_arg(args, "depth").unwrap_or(default_depth), time, infinite: false }
+    };
+    self.signals.reset(args.contains(&"ponder"));
+
+    let board = self.board.clone();
+    let out = Arc::clone(&self.out);
+    self.search_thread = Some(thread::spawn(move || {
+      // There's no one to report a broken pipe to, so write errors are dropped
+      let result = searcher.search_with_listener(&board, limits, |info| {
+        let _ = write_info(info, &mut *out.lock().expect("output lock poisoned"));
+      });
+      let _ = write_best_move(&result, &mut *out.lock().expect("output lock poisoned"));
+      searcher
+    }));
+    Ok(())
+  }
+}
+
+impl<W: Write + Send + 'static> Drop for UciEngine<W> {
+  fn drop(&mut self) {
+    self.signals.stop();
+    self.wait();
+  }
+}
+
+/// Writes one `info` line per principal variation of a completed iteration.
+fn write_info<W: Write>(info: &SearchInfo, out: &mut W) -> io::Result<()> {
+  for (index, line) in info.lines.iter().enumerate() {
+    let pv: Vec<String> = line.pv.iter().map(|mv| mv.to_string()).collect();
+    writeln!(out, "info depth {} seldepth {} multipv {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+      line.depth, info.seldepth, index + 1, format_score(line.score), info.nodes, info.nps, info.hashfull,
+      info.elapsed.as_millis(), pv.join(" "))?;
+  }
+  out.flush()
+}
+
+/// Writes `bestmove`, with the expected reply to ponder on when the principal variation has one.
+fn write_best_move<W: Write>(result: &SearchResult, out: &mut W) -> io::Result<()> {
+  match (result.best_move, result.pv.get(1)) {
+    (Some(mv), Some(reply)) => writeln!(out, "bestmove {} ponder {}", mv, reply)?,
+    (Some(mv), None) => writeln!(out, "bestmove {}", mv)?,
+    (None, _) => writeln!(out, "bestmove 0000")?,
+  }
+  out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  /// Output that tests can read while the engine holds on to it.
+  #[derive(Clone, Default)]
+  struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+  impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  impl SharedBuffer {
+    fn take(&self) -> String {
+      String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+    }
+  }
+
+  fn engine() -> (UciEngine<SharedBuffer>, SharedBuffer) {
+    let buffer = SharedBuffer::default();
+    (UciEngine::new(buffer.clone()), buffer)
+  }
+
+  /// Sends a command and returns everything the engine says until any search it started is done.
+  fn run(engine: &mut UciEngine<SharedBuffer>, buffer: &SharedBuffer, line: &str) -> String {
+    engine.handle_command(line).unwrap();
+    engine.wait();
+    buffer.take()
+  }
+
+  #[test]
+  fn test_handshake_lists_options() {
+    let (mut engine, buffer) = engine();
+    let reply = run(&mut engine, &buffer, "uci");
+    assert!(reply.contains("option name Threads type spin default 1"));
+    assert!(reply.contains("option name Hash type spin"));
+    assert!(reply.ends_with("uciok\n"));
+    assert_eq!(run(&mut engine, &buffer, "isready"), "readyok\n");
+  }
+
+  #[test]
+  fn test_setoption_threads() {
+    let (mut engine, buffer) = engine();
+    run(&mut engine, &buffer, "setoption name Threads value 3");
+    assert_eq!(engine.get_options().threads, 3);
+    run(&mut engine, &buffer, "setoption name Threads value banana");
+    assert_eq!(engine.get_options().threads, 3);
+    run(&mut engine, &buffer, "setoption name Hash value 2");
+    assert_eq!(engine.get_options().hash_size_mb, 2);
+  }
+
+  #[test]
+  fn test_position_and_go() {
+    let (mut engine, buffer) = engine();
+    run(&mut engine, &buffer, "position startpos moves e2e4 e7e5 g1f3");
+    assert_eq!(engine.get_board().to_fen_string(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+    run(&mut engine, &buffer, "position fen 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+    let reply = run(&mut engine, &buffer, "go depth 2");
+    assert!(reply.contains("score mate 1"), "{}", reply);
+    assert!(reply.ends_with("bestmove a1a8\n"), "{}", reply);
+    assert!(reply.contains(" seldepth ") && reply.contains(" nps ") && reply.contains(" hashfull "), "{}", reply);
+  }
+
+  #[test]
+  fn test_time_limits_follow_side_to_move() {
+    let source: Arc<dyn TimeSource> = Arc::new(SystemTimeSource::default());
+    let args = ["wtime", "60030", "btime", "30030", "winc", "1000", "binc", "0"];
+    let white = parse_time_limits(&args, Color::White, Arc::clone(&source)).unwrap();
+    assert_eq!(white.get_soft_limit(), Duration::from_millis(2_750));
+    let black = parse_time_limits(&args, Color::Black, Arc::clone(&source)).unwrap();
+    assert_eq!(black.get_soft_limit(), Duration::from_millis(1_000));
+
+    let fixed = parse_time_limits(&["movetime", "500"], Color::White, Arc::clone(&source)).unwrap();
+    assert_eq!(fixed.get_hard_limit(), Duration::from_millis(500));
+    assert!(parse_time_limits(&["depth", "5"], Color::White, source).is_none());
+  }
+
+  #[test]
+  fn test_go_with_clock_returns_a_move() {
+    let (mut engine, buffer) = engine();
+    run(&mut engine, &buffer, "position startpos");
+    let reply = run(&mut engine, &buffer, "go wtime 1000 btime 1000 movestogo 10");
+    assert!(reply.contains("bestmove "), "{}", reply);
+    assert!(!reply.contains("bestmove 0000"), "{}", reply);
+  }
+
+  #[test]
+  fn test_multi_pv_reports_every_line_each_iteration() {
+    let (mut engine, buffer) = engine();
+    run(&mut engine, &buffer, "setoption name MultiPV value 3");
+    assert_eq!(engine.get_options().multi_pv, 3);
+
+    let reply = run(&mut engine, &buffer, "go depth 2");
+    for depth in 1..=2 {
+      for line in 1..=3 {
+        let prefix = format!("info depth {} ", depth);
+        let multipv = format!(" multipv {} ", line);
+        assert!(reply.lines().any(|l| l.starts_with(&prefix) && l.contains(&multipv)), "{}", reply);
+      }
+    }
+    assert!(!reply.contains("multipv 4"));
+  }
+
+  #[test]
+  fn test_infinite_search_waits_for_stop() {
+    let (mut engine, buffer) = engine();
+    engine.handle_command("position fen 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+    engine.handle_command("go infinite depth 1").unwrap();
+    assert!(engine.is_searching());
+    engine.handle_command("isready").unwrap();
+
+    // Even the mate is held back until the GUI asks for it
+    thread::sleep(Duration::from_millis(50));
+    assert!(!buffer.take().contains("bestmove"));
+    engine.handle_command("stop").unwrap();
+    assert!(!engine.is_searching());
+    assert!(buffer.take().contains("bestmove a1a8"));
+  }
+
+  #[test]
+  fn test_ponderhit_switches_to_normal_time() {
+    let (mut engine, buffer) = engine();
+    engine.handle_command("position startpos moves e2e4").unwrap();
+    engine.handle_command("go ponder wtime 1000 btime 1000 movestogo 40").unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert!(!buffer.take().contains("bestmove"));
+
+    engine.handle_command("ponderhit").unwrap();
+    engine.wait();
+    let reply = buffer.take();
+    assert!(reply.contains("bestmove "), "{}", reply);
+  }
+
+  #[test]
+  fn test_format_score() {
+    assert_eq!(format_score(35), "cp 35");
+    assert_eq!(format_score(MATE_SCORE - 3), "mate 2");
+    assert_eq!(format_score(-MATE_SCORE + 2), "mate -1");
+  }
+
+  #[test]
+  fn test_illegal_moves_are_rejected() {
+    assert_eq!(parse_position(&["startpos", "moves", "e2e5"], false).err(), Some(Error::InvalidMove));
+  }
+
+  #[test]
+  fn test_chess960_castling_is_king_takes_rook() {
+    let moves = ["moves", "e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"];
+    let standard = parse_position(&[&["startpos"][..], &moves[..], &["e1g1"][..]].concat(), false).unwrap();
+    let chess960 = parse_position(&[&["startpos"][..], &moves[..], &["e1h1"][..]].concat(), true).unwrap();
+    assert_eq!(standard.to_fen_string(), chess960.to_fen_string());
+    assert!(parse_position(&[&["startpos"][..], &moves[..], &["e1g1"][..]].concat(), true).is_err());
+
+    let buffer = SharedBuffer::default();
+    let mut engine = UciEngine::new(buffer.clone());
+    assert!(run(&mut engine, &buffer, "uci").contains("option name UCI_Chess960 type check default false"));
+    run(&mut engine, &buffer, "setoption name UCI_Chess960 value true");
+    run(&mut engine, &buffer, "position fen 4k3/8/8/8/8/8/8/RK5R w KQ - 0 1 moves b1a1");
+    assert_eq!(engine.get_board().to_fen_string(), "4k3/8/8/8/8/8/8/2KR3R b - - 1 1");
+  }
+
+  #[test]
+  fn test_book_moves_are_played_without_searching() {
+    use crate::book::{encode_move, polyglot_key, BookEntry};
+
+    let start = Board::new();
+    let mv = Move::from_uci("d2d4").unwrap();
+    let book = PolyglotBook::from_entries(vec![
+      BookEntry { key: polyglot_key(&start).unwrap(), raw_move: encode_move(&start, &mv), weight: 1, learn: 0 },
+    ]);
+    let path = std::env::temp_dir().join(format!("rust-chess-uci-book-{}.bin", std::process::id()));
+    book.save(&path).unwrap();
+
+    let (mut engine, buffer) = engine();
+    run(&mut engine, &buffer, &format!("setoption name BookFile value {}", path.display()));
+    std::fs::remove_file(&path).unwrap();
+    run(&mut engine, &buffer, "position startpos");
+    assert_eq!(run(&mut engine, &buffer, "go depth 3"), "bestmove d2d4\n");
+
+    run(&mut engine, &buffer, "position startpos moves d2d4");
+    let reply = run(&mut engine, &buffer, "go depth 1");
+    assert!(reply.starts_with("info depth 1"), "{}", reply);
+  }
+
+  #[test]
+  fn test_syzygy_path_finds_tables() {
+    let directory = std::env::temp_dir().join(format!("rust-chess-uci-syzygy-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("KQvK.rtbw"), []).unwrap();
+
+    let (mut engine, buffer) = engine();
+    assert!(run(&mut engine, &buffer, "uci").contains("option name SyzygyPath type string default <empty>"));
+    run(&mut engine, &buffer, &format!("setoption name SyzygyPath value {}", directory.display()));
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert!(engine.tablebase.is_some());
+    run(&mut engine, &buffer, "setoption name SyzygyPath value <empty>");
+    assert!(engine.tablebase.is_none());
+  }
+}
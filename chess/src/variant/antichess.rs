@@ -0,0 +1,96 @@
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::game::GameResult;
+use crate::piece::{Color, PieceKind};
+use crate::variant::Variant;
+
+/// Pawns may also promote to a king.
+const PROMOTION_KINDS: [PieceKind; 5] = [PieceKind::King, PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+
+/// Losing chess: captures are compulsory, the king is an ordinary piece that can be captured
+/// or promoted to, and there is no castling. A player wins by losing every piece or by being
+/// stalemated.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Antichess;
+
+impl Variant for Antichess {
+  fn get_name(&self) -> &'static str {
+    "antichess"
+  }
+
+  fn get_start_fen(&self) -> &'static str {
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+  }
+
+  fn get_pseudo_legal_moves(&self, board: &Board) -> Vec<Move> {
+    let mut moves = board.get_standard_pseudo_legal_moves();
+    moves.retain(|mv| !board.is_castle(mv));
+
+    if moves.iter().any(|mv| board.is_capture(mv)) {
+      moves.retain(|mv| board.is_capture(mv));
+    }
+    moves
+  }
+
+  fn get_promotion_kinds(&self) -> &[PieceKind] {
+    &PROMOTION_KINDS
+  }
+
+  fn is_pseudo_legal_move(&self, board: &Board, mv: &Move) -> bool {
+    self.get_pseudo_legal_moves(board).contains(mv)
+  }
+
+  /// Kings may be left attacked, so every move is legal.
+  fn is_legal_position(&self, _board: &Board, _mover: Color) -> bool {
+    true
+  }
+
+  /// Having no moves, whether every piece is gone or the rest are stuck, wins.
+  fn get_result_without_moves(&self, board: &Board) -> GameResult {
+    GameResult::win_for(board.get_active_color())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+
+  fn board(fen: &str) -> Board {
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(Antichess));
+    board
+  }
+
+  #[test]
+  fn test_antichess_perft() {
+    let start = Board::new_variant(Arc::new(Antichess)).unwrap();
+    assert_eq!(start.perft(1), 20);
+    assert_eq!(start.perft(2), 400);
+    assert_eq!(start.perft(3), 8067);
+    assert_eq!(start.perft(4), 153299);
+  }
+
+  #[test]
+  fn test_captures_are_compulsory() {
+    let board = board("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+    assert_eq!(board.get_legal_moves(), vec![Move::from_uci("e4d5").unwrap()]);
+
+    // Kings can be captured, and promoted to
+    let board = self::board("8/P7/8/8/8/8/8/1k2K3 w - - 0 1");
+    assert!(board.get_legal_moves().contains(&Move::from_uci("a7a8k").unwrap()));
+    let board = self::board("8/8/8/8/8/8/8/kR6 b - - 0 1");
+    assert_eq!(board.get_legal_moves(), vec![Move::from_uci("a1b1").unwrap()]);
+  }
+
+  #[test]
+  fn test_having_no_moves_wins() {
+    let lost_everything = board("8/8/8/8/8/8/8/1R6 b - - 0 1");
+    assert!(lost_everything.get_legal_moves().is_empty());
+    assert_eq!(lost_everything.get_variant().get_result_without_moves(&lost_everything), GameResult::BlackWins);
+
+    let stalemated = board("8/8/8/8/8/p7/P7/8 b - - 0 1");
+    assert!(stalemated.get_legal_moves().is_empty());
+    assert_eq!(stalemated.get_variant().get_result_without_moves(&stalemated), GameResult::BlackWins);
+  }
+}
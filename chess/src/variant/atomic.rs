@@ -0,0 +1,151 @@
+use crate::board::Board;
+use crate::board::coord::Coordinate;
+use crate::board::moves::{Move, MoveUndo};
+use crate::game::GameResult;
+use crate::piece::{Color, PieceKind};
+use crate::variant::Variant;
+
+/// Chess where every capture sets off an explosion, taking the capturing piece and every piece
+/// next to the capture square off the board along with the captured one. Pawns are only caught
+/// by a capture of or by themselves. Blowing up the enemy king wins, so kings can't capture and
+/// may stand side by side, where neither can be checked.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Atomic;
+
+const NEIGHBOURS: [(i8, i8); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+fn kings_touch(board: &Board) -> bool {
+  match (board.find_king(&Color::White), board.find_king(&Color::Black)) {
+    (Some(white), Some(black)) => NEIGHBOURS.iter().any(|offset| white + *offset == black),
+    _ => false,
+  }
+}
+
+fn is_king_capture(board: &Board, mv: &Move) -> bool {
+  let is_king = board.get_square(mv.from).ok()
+    .and_then(|square| square.get_piece().as_ref().and_then(|piece| piece.get_kind()))
+    == Some(PieceKind::King);
+  is_king && board.is_capture(mv)
+}
+
+/// Whether the color is in check, which touching kings rule out.
+fn is_in_atomic_check(board: &Board, color: Color) -> bool {
+  !kings_touch(board) && board.is_in_check(&color)
+}
+
+impl Variant for Atomic {
+  fn get_name(&self) -> &'static str {
+    "atomic"
+  }
+
+  fn get_pseudo_legal_moves(&self, board: &Board) -> Vec<Move> {
+    board.get_standard_pseudo_legal_moves().into_iter()
+      .filter(|mv| !is_king_capture(board, mv))
+      .collect()
+  }
+
+  fn is_pseudo_legal_move(&self, board: &Board, mv: &Move) -> bool {
+    board.is_standard_pseudo_legal_move(mv) && !is_king_capture(board, mv)
+  }
+
+  /// The mover's king must survive, and unless the enemy king has just been blown up, it must
+  /// not be left in check.
+  fn is_legal_position(&self, board: &Board, mover: Color) -> bool {
+    match (board.find_king(&mover), board.find_king(&mover.opposite())) {
+      (None, _) => false,
+      (Some(_), None) => true,
+      (Some(_), Some(_)) => !is_in_atomic_check(board, mover),
+    }
+  }
+
+  fn after_move(&self, board: &mut Board, undo: &mut MoveUndo) {
+    if undo.get_captured().is_none() {
+      return;
+    }
+
+    let center = undo.get_destination();
+    board.remove_piece(center, undo);
+    for offset in NEIGHBOURS.iter() {
+      let coord: Coordinate = center + *offset;
+      if !coord.is_valid() {
+        continue;
+      }
+      let is_pawn = board.get_square(coord).ok()
+        .and_then(|square| square.get_piece().as_ref().map(|piece| piece.get_kind() == Some(PieceKind::Pawn)));
+      if is_pawn == Some(false) {
+        board.remove_piece(coord, undo);
+      }
+    }
+  }
+
+  fn get_result(&self, board: &Board) -> Option<GameResult> {
+    [Color::White, Color::Black].iter()
+      .find(|color| board.find_king(color).is_none())
+      .map(|color| GameResult::win_for(color.opposite()))
+  }
+
+  fn get_result_without_moves(&self, board: &Board) -> GameResult {
+    let color = board.get_active_color();
+    if is_in_atomic_check(board, color) { GameResult::win_for(color.opposite()) } else { GameResult::Draw }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+
+  fn board(fen: &str) -> Board {
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(Atomic));
+    board
+  }
+
+  #[test]
+  fn test_atomic_perft() {
+    // Captures first come at ply three, so explosions only show up at ply four: 45 nodes over standard chess
+    let start = Board::new_variant(Arc::new(Atomic)).unwrap();
+    assert_eq!(start.perft(4), 197326);
+
+    // Middlegames with captures next to both kings
+    let black = board("rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1");
+    assert_eq!(black.perft(1), 40);
+    assert_eq!(black.perft(2), 1238);
+    assert_eq!(black.perft(3), 45237);
+    let white = board("rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1");
+    assert_eq!(white.perft(1), 28);
+    assert_eq!(white.perft(2), 833);
+    assert_eq!(white.perft(3), 23353);
+  }
+
+  #[test]
+  fn test_captures_explode() {
+    // Nxb7 blows up the knight and the pieces behind b7, but not the pawns beside it
+    let mut board = board("rnbqkbnr/pppppppp/8/2N5/8/8/PPPPPPPP/RNBQKB1R w KQkq - 0 1");
+    let before = board.to_fen_string();
+    let hash = board.get_hash();
+    let capture = Move::from_uci("c5b7").unwrap();
+    let undo = board.make_move(capture).unwrap();
+    assert_eq!(board.to_fen_string(), "3qkbnr/p1pppppp/8/8/8/8/PPPPPPPP/RNBQKB1R b KQk - 0 1");
+    assert_eq!(board.get_variant_result(), None);
+    board.unmake_move(capture, undo);
+    assert_eq!(board.to_fen_string(), before);
+    assert_eq!(board.get_hash(), hash);
+
+    // Taking on f7 blows up the king beside it, which wins
+    let mut board = self::board("rnbqkbnr/pppppppp/8/4N3/8/8/8/4K3 w kq - 0 1");
+    board.make_move(Move::from_uci("e5f7").unwrap()).unwrap();
+    assert_eq!(board.get_variant_result(), Some(GameResult::WhiteWins));
+    assert_eq!(board.to_fen_string(), "rnbq3r/ppppp1pp/8/8/8/8/8/4K3 b - - 0 1");
+  }
+
+  #[test]
+  fn test_kings_never_capture_and_may_touch() {
+    let board = board("8/8/8/8/3k4/4K3/8/7q w - - 0 1");
+    let legal = board.get_legal_moves();
+    // Touching kings can't be checked, so the queen on h1 doesn't matter there
+    assert!(!legal.contains(&Move::from_uci("e3d4").unwrap()));
+    assert!(legal.contains(&Move::from_uci("e3e4").unwrap()));
+    assert!(!legal.contains(&Move::from_uci("e3f3").unwrap()));
+  }
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::errors::Error;
+use crate::game::GameResult;
+use crate::piece::{Color, PieceKind};
+use crate::variant::Variant;
+use crate::variant::crazyhouse;
+
+/// Crazyhouse for two teams of two on two boards. A captured piece goes to the capturer's
+/// partner, who plays the other color on the other board, so it keeps its color.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bughouse;
+
+impl Variant for Bughouse {
+  fn get_name(&self) -> &'static str {
+    "bughouse"
+  }
+
+  fn get_start_fen(&self) -> &'static str {
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+  }
+
+  fn has_pockets(&self) -> bool {
+    true
+  }
+
+  fn get_pseudo_legal_moves(&self, board: &Board) -> Vec<Move> {
+    crazyhouse::get_moves_with_drops(board)
+  }
+}
+
+/// The two boards of a bughouse game. White on one board and black on the other are partners.
+#[derive(Clone)]
+pub struct BughouseGame {
+  boards: [Board; 2],
+}
+
+impl BughouseGame {
+  pub fn new() -> BughouseGame {
+    let board = Board::new_variant(Arc::new(Bughouse)).expect("the starting position is valid");
+    BughouseGame { boards: [board.clone(), board] }
+  }
+
+  /// Returns board 0 or 1.
+  pub fn get_board(&self, index: usize) -> Option<&Board> {
+    self.boards.get(index)
+  }
+
+  /// Plays a legal move on one board, passing any captured piece to the pocket of the same
+  /// color on the other board.
+  pub fn make_move(&mut self, index: usize, mv: Move) -> Result<(), Error> {
+    let board = self.boards.get_mut(index).ok_or(Error::InvalidMove)?;
+    if !board.is_legal_move(&mv) {
+      return Err(Error::InvalidMove);
+    }
+
+    let undo = board.make_move(mv)?;
+    let captured = undo.get_captured().and_then(|(_, piece)| {
+      let kind = if undo.is_captured_promoted() { Some(PieceKind::Pawn) } else { piece.get_kind() };
+      kind.map(|kind| (*piece.get_color(), kind))
+    });
+    if let Some((color, kind)) = captured {
+      if let Some(pocket) = self.boards[1 - index].get_pocket_mut(color) {
+        pocket.add(kind);
+      }
+    }
+    Ok(())
+  }
+
+  /// The result once either board is checkmated, reported for the team playing white on
+  /// board 0.
+  pub fn get_result(&self) -> Option<GameResult> {
+    for (index, board) in self.boards.iter().enumerate() {
+      let color = board.get_active_color();
+      if board.get_legal_moves().is_empty() && board.is_in_check(&color) {
+        let loser_is_first_team = (index == 0) == (color == Color::White);
+        return Some(if loser_is_first_team { GameResult::BlackWins } else { GameResult::WhiteWins });
+      }
+    }
+    None
+  }
+}
+
+impl Default for BughouseGame {
+  fn default() -> Self {
+    BughouseGame::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_captures_go_to_the_partner() {
+    let mut game = BughouseGame::new();
+    for mv in ["e2e4", "d7d5", "e4d5"].iter() {
+      game.make_move(0, Move::from_uci(mv).unwrap()).unwrap();
+    }
+    assert!(game.get_board(0).unwrap().get_pocket(Color::White).unwrap().is_empty());
+    let partner = game.get_board(1).unwrap();
+    assert_eq!(partner.get_pocket(Color::Black).unwrap().get_count(PieceKind::Pawn), 1);
+    assert_eq!(partner.to_fen_string(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[p] w KQkq - 0 1");
+
+    // Board 1's black player drops the pawn once it's their turn
+    game.make_move(1, Move::from_uci("e2e4").unwrap()).unwrap();
+    assert!(game.make_move(1, Move::from_uci("P@e5").unwrap()).is_ok());
+    assert!(game.make_move(2, Move::from_uci("e2e4").unwrap()).is_err());
+    assert_eq!(game.get_result(), None);
+  }
+
+  #[test]
+  fn test_bughouse_perft() {
+    // Nxe5's pawn goes to the partner, so unlike crazyhouse nothing can be dropped here
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[] w KQkq - 2 3";
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(Bughouse));
+    assert_eq!(board.perft(3), 24079);
+
+    // Pawns passed over from the other board can be dropped
+    let mut board = Board::from_fen_string("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[Pp] w KQkq - 2 3").unwrap();
+    board.set_variant(Arc::new(Bughouse));
+    assert_eq!(board.perft(1), 57);
+    assert_eq!(board.perft(3), 141848);
+
+    // Captures on the board itself don't fill its own pockets
+    let mut board = Board::from_fen_string("4k3/8/8/3p4/4P3/8/8/4K3[] w - - 0 1").unwrap();
+    board.set_variant(Arc::new(Bughouse));
+    board.make_move(Move::from_uci("e4d5").unwrap()).unwrap();
+    assert_eq!(board.perft(1), 5);
+  }
+}
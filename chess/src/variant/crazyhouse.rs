@@ -0,0 +1,114 @@
+use crate::board::Board;
+use crate::board::moves::{Move, MoveUndo};
+use crate::piece::PieceKind;
+use crate::variant::Variant;
+
+/// Chess where captured pieces change sides and go into the capturer's pocket, to be dropped
+/// back onto the board instead of moving.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crazyhouse;
+
+/// The standard moves plus drops from the pocket, for Crazyhouse and Bughouse.
+pub(crate) fn get_moves_with_drops(board: &Board) -> Vec<Move> {
+  let mut moves = board.get_standard_pseudo_legal_moves();
+  moves.append(&mut board.get_drop_moves());
+  moves
+}
+
+impl Variant for Crazyhouse {
+  fn get_name(&self) -> &'static str {
+    "crazyhouse"
+  }
+
+  fn get_start_fen(&self) -> &'static str {
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+  }
+
+  fn has_pockets(&self) -> bool {
+    true
+  }
+
+  fn get_pseudo_legal_moves(&self, board: &Board) -> Vec<Move> {
+    get_moves_with_drops(board)
+  }
+
+  fn after_move(&self, board: &mut Board, undo: &mut MoveUndo) {
+    let captured = undo.get_captured().and_then(|(_, piece)| piece.get_kind());
+    if let Some(kind) = captured {
+      // Promoted pieces turn back into pawns when captured
+      let kind = if undo.is_captured_promoted() { PieceKind::Pawn } else { kind };
+      let capturer = board.get_active_color().opposite();
+      if let Some(pocket) = board.get_pocket_mut(capturer) {
+        pocket.add(kind);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+  use crate::piece::Color;
+
+  fn board(fen: &str) -> Board {
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(Crazyhouse));
+    board
+  }
+
+  #[test]
+  fn test_crazyhouse_perft() {
+    // Nxe5 is white's only capture, and dropping the pawn it wins is what sets crazyhouse apart by ply three
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[] w KQkq - 2 3";
+    assert_eq!(board(fen).perft(2), 835);
+    assert_eq!(board(fen).perft(3), 25060);
+    assert_eq!(Board::from_fen_string("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap().perft(3), 24079);
+
+    // Taking the promoted queen puts a pawn in black's pocket, not a queen
+    let promoted = board("4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1");
+    assert_eq!(promoted.perft(2), 360);
+    assert_eq!(promoted.perft(3), 5445);
+
+    // Every piece in both pockets
+    let full = board("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1");
+    assert_eq!(full.perft(1), 301);
+    assert_eq!(full.perft(2), 75353);
+  }
+
+  #[test]
+  fn test_captures_fill_the_pocket() {
+    let mut board = board("4k3/8/8/3p4/4P3/8/8/4K3[] w - - 0 1");
+    let capture = Move::from_uci("e4d5").unwrap();
+    let hash = board.get_hash();
+    let undo = board.make_move(capture).unwrap();
+    assert_eq!(board.to_fen_string(), "4k3/8/8/3P4/8/8/8/4K3[P] b - - 0 1");
+    board.unmake_move(capture, undo);
+    assert_eq!(board.get_hash(), hash);
+    assert!(board.get_pocket(Color::White).unwrap().is_empty());
+
+    let mut board = self::board("4k3/8/8/8/8/8/5p2/7K[] b - - 0 1");
+    board.make_move(Move::from_uci("f2f1q").unwrap()).unwrap();
+    assert_eq!(board.to_fen_string(), "4k3/8/8/8/8/8/8/5q~1K[] w - - 0 2");
+
+    // A promoted queen goes back in the pocket as a pawn
+    let mut board = self::board("4k3/8/8/8/8/8/8/r4Q~1K[] b - - 0 1");
+    board.make_move(Move::from_uci("a1f1").unwrap()).unwrap();
+    assert_eq!(board.to_fen_string(), "4k3/8/8/8/8/8/8/5r1K[p] w - - 0 2");
+  }
+
+  #[test]
+  fn test_drops_block_checks() {
+    let board = board("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1");
+    let legal = board.get_legal_moves();
+    for to in ["b1", "c1", "d1"].iter() {
+      assert!(legal.contains(&Move::from_uci(&format!("N@{}", to)).unwrap()));
+    }
+    assert!(!legal.contains(&Move::from_uci("N@e3").unwrap()));
+    assert_eq!(board.move_from_san("N@d1").unwrap(), Move::from_uci("N@d1").unwrap());
+
+    let mut after = board.clone();
+    after.make_move(Move::from_uci("N@d1").unwrap()).unwrap();
+    assert_eq!(after.to_fen_string(), "4k3/8/8/8/8/8/8/r2NK3[] b - - 1 1");
+  }
+}
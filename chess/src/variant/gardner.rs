@@ -0,0 +1,58 @@
+use crate::board::Board;
+use crate::board::rank::Rank;
+use crate::piece::Color;
+use crate::variant::Variant;
+
+/// Gardner's minichess, on a 5x5 board with one of every piece a side. Pawns only ever move
+/// one square, so there is no en passant, and there is no castling.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Gardner;
+
+impl Variant for Gardner {
+  fn get_name(&self) -> &'static str {
+    "gardner"
+  }
+
+  fn get_start_fen(&self) -> &'static str {
+    "rnbqk/ppppp/5/PPPPP/RNBQK w - - 0 1"
+  }
+
+  fn can_double_push(&self, _board: &Board, _color: Color, _rank: Rank) -> bool {
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+  use crate::board::coord::Coordinate;
+  use crate::board::moves::Move;
+  use crate::board::square::SquareColor;
+
+  #[test]
+  fn test_gardner_perft() {
+    let start = Board::new_variant(Arc::new(Gardner)).unwrap();
+    assert_eq!((start.get_width(), start.get_height()), (5, 5));
+    // Each white move gives black its seven usual replies, less any push it blocks, plus any
+    // capture it offers
+    assert_eq!(start.perft(1), 7);
+    assert_eq!(start.perft(2), 53);
+  }
+
+  #[test]
+  fn test_gardner_board() {
+    let board = Board::new_variant(Arc::new(Gardner)).unwrap();
+    let square = |name: &str| *board.get_square(Coordinate::get_coordinate(name).unwrap()).unwrap().get_color();
+    assert_eq!(square("a1"), SquareColor::Dark);
+    assert_eq!(square("e5"), SquareColor::Dark);
+    assert_eq!(square("e1"), SquareColor::Dark);
+    assert_eq!(square("b1"), SquareColor::Light);
+
+    // Pawns promote on the fifth rank
+    let mut board = Board::from_fen_string("4k/P4/5/5/K4 w - - 0 1").unwrap();
+    board.set_variant(Arc::new(Gardner));
+    assert!(board.get_legal_moves().contains(&Move::from_uci("a4a5q").unwrap()));
+    assert!(!board.get_legal_moves().contains(&Move::from_uci("a4a5").unwrap()));
+  }
+}
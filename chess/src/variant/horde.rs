@@ -0,0 +1,96 @@
+use crate::board::Board;
+use crate::board::rank::Rank;
+use crate::game::GameResult;
+use crate::piece::Color;
+use crate::variant::Variant;
+
+/// Thirty-six white pawns and no king against black's usual army. The pawns on white's first
+/// rank may also move two squares. White wins by checkmate, black by capturing every white
+/// piece.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Horde;
+
+impl Variant for Horde {
+  fn get_name(&self) -> &'static str {
+    "horde"
+  }
+
+  fn get_start_fen(&self) -> &'static str {
+    "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+  }
+
+  fn can_double_push(&self, _board: &Board, color: Color, rank: Rank) -> bool {
+    match color {
+      Color::White => rank == Rank::One || rank == Rank::Two,
+      Color::Black => rank == Rank::Seven,
+    }
+  }
+
+  fn get_result(&self, board: &Board) -> Option<GameResult> {
+    let white_left = board.get_squares().iter()
+      .any(|square| square.get_piece().as_ref().is_some_and(|piece| *piece.get_color() == Color::White));
+    if white_left { None } else { Some(GameResult::BlackWins) }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+  use crate::board::moves::Move;
+  use crate::game::Game;
+  use crate::search::{Searcher, MATE_SCORE};
+
+  fn board(fen: &str) -> Board {
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(Horde));
+    board
+  }
+
+  #[test]
+  fn test_horde_perft() {
+    let start = Board::new_variant(Arc::new(Horde)).unwrap();
+    assert_eq!(start.perft(1), 8);
+    assert_eq!(start.perft(2), 128);
+    assert_eq!(start.perft(3), 1274);
+    assert_eq!(start.perft(4), 23310);
+  }
+
+  #[test]
+  fn test_first_rank_pawns_double_push() {
+    let mut board = board("4k3/8/8/8/8/8/8/P7 w - - 0 1");
+    let legal = board.get_legal_moves();
+    assert_eq!(legal.len(), 2);
+    let double_push = Move::from_uci("a1a3").unwrap();
+    assert!(legal.contains(&double_push));
+    // The pawn skips its second rank, not its third, so it can't be taken en passant
+    board.make_move(double_push).unwrap();
+    assert_eq!(board.to_fen_string(), "4k3/8/8/8/8/P7/8/8 b - - 0 1");
+
+    // Double pushes from the second rank work as usual
+    let mut board = self::board("4k3/8/8/8/1p6/8/P7/8 w - - 0 1");
+    board.make_move(Move::from_uci("a2a4").unwrap()).unwrap();
+    assert!(board.get_legal_moves().contains(&Move::from_uci("b4a3").unwrap()));
+  }
+
+  #[test]
+  fn test_horde_results() {
+    // Black wins once every white piece is gone
+    assert_eq!(board("4k3/8/8/8/8/8/8/8 w - - 0 1").get_variant_result(), Some(GameResult::BlackWins));
+
+    // A stuck horde is stalemated, a draw, and the king-less side is never in check
+    let stuck = board("4k3/8/8/8/8/p7/P7/8 w - - 0 1");
+    assert!(stuck.get_legal_moves().is_empty());
+    assert!(!stuck.is_in_check(&Color::White));
+    assert_eq!(stuck.get_variant().get_result_without_moves(&stuck), GameResult::Draw);
+
+    // The search takes the last pawn like it would mate
+    let result = Searcher::default().search(&board("4k3/8/8/8/8/8/3p4/4P3 b - - 0 1"), 2);
+    assert_eq!(result.score, MATE_SCORE - 1);
+    assert_eq!(result.best_move.map(|mv| mv.to), Move::from_uci("d2e1q").ok().map(|mv| mv.to));
+
+    let mut game = Game::with_variant(Arc::new(Horde)).unwrap();
+    game.make_move(Move::from_uci("b5b6").unwrap()).unwrap();
+    assert_eq!(game.get_result(), None);
+  }
+}
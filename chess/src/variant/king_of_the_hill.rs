@@ -0,0 +1,96 @@
+use crate::board::Board;
+use crate::board::coord::Coordinate;
+use crate::game::GameResult;
+use crate::piece::Color;
+use crate::variant::Variant;
+
+/// Chess where bringing your king to one of the four centre squares also wins.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KingOfTheHill;
+
+/// Whether the coordinate is in the centre of the board: the middle two files and ranks, or the
+/// middle one along a side with an odd number of squares.
+fn is_on_hill(board: &Board, coord: Coordinate) -> bool {
+  let centre = |size: i8| (size + 1) / 2..=size / 2 + 1;
+  let (file, rank): (i8, i8) = (coord.file.into(), coord.rank.into());
+  centre(board.get_width()).contains(&file) && centre(board.get_height()).contains(&rank)
+}
+
+impl Variant for KingOfTheHill {
+  fn get_name(&self) -> &'static str {
+    "kingofthehill"
+  }
+
+  fn get_result(&self, board: &Board) -> Option<GameResult> {
+    [Color::White, Color::Black].iter()
+      .find(|color| board.find_king(color).is_some_and(|coord| is_on_hill(board, coord)))
+      .map(|color| GameResult::win_for(*color))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+  use crate::board::moves::Move;
+
+  fn board(fen: &str) -> Board {
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(KingOfTheHill));
+    board
+  }
+
+  /// Perft over standard chess that stops wherever a king stands on the hill.
+  fn hill_perft(board: &mut Board, depth: u32) -> u64 {
+    let on_hill = [Color::White, Color::Black].iter().any(|color| board.find_king(color).is_some_and(|coord| is_on_hill(board, coord)));
+    if depth == 0 {
+      return 1;
+    }
+    if on_hill {
+      return 0;
+    }
+    let mut nodes = 0;
+    for mv in board.get_legal_moves() {
+      let undo = board.make_move(mv).unwrap();
+      nodes += hill_perft(board, depth - 1);
+      board.unmake_move(mv, undo);
+    }
+    nodes
+  }
+
+  #[test]
+  fn test_king_of_the_hill_perft() {
+    // Kd4 and Ke4 end the game; every other king move leaves black three replies
+    let near = board("8/8/8/8/8/4K3/8/k7 w - - 0 1");
+    assert_eq!(near.perft(1), 8);
+    assert_eq!(near.perft(2), 18);
+    assert_eq!(Board::from_fen_string("8/8/8/8/8/4K3/8/k7 w - - 0 1").unwrap().perft(2), 24);
+
+    // Both kings a step from the hill, with pawns guarding and contesting d4, d5 and e5
+    let fen = "8/p4pp1/3k4/2p5/4P3/2K5/PP4PP/8 w - - 0 1";
+    let mut standard = Board::from_fen_string(fen).unwrap();
+    let expected = hill_perft(&mut standard, 4);
+    assert_eq!(board(fen).perft(4), expected);
+    assert!(expected < standard.perft(4));
+  }
+
+  #[test]
+  fn test_reaching_the_hill_wins() {
+    let mut board = board("8/8/8/8/8/4K3/8/k7 w - - 0 1");
+    assert_eq!(board.get_variant_result(), None);
+    board.make_move(Move::from_uci("e3e4").unwrap()).unwrap();
+    assert_eq!(board.get_variant_result(), Some(GameResult::WhiteWins));
+    assert!(board.get_legal_moves().is_empty());
+  }
+
+  #[test]
+  fn test_hill_follows_the_board_size() {
+    // On 10x10 the hill is e5 to f6, so d4 and e4 aren't on it
+    assert_eq!(board("9k/10/10/10/5K4/10/10/10/10/10 w - - 0 1").get_variant_result(), Some(GameResult::WhiteWins));
+    assert_eq!(board("9k/10/10/10/10/10/4K5/10/10/10 w - - 0 1").get_variant_result(), None);
+    assert_eq!(board("9k/10/10/10/10/10/3K6/10/10/10 w - - 0 1").get_variant_result(), None);
+    // On 5x5 it's c3 alone
+    assert_eq!(board("4k/5/2K2/5/5 w - - 0 1").get_variant_result(), Some(GameResult::WhiteWins));
+    assert_eq!(board("4k/5/3K1/5/5 w - - 0 1").get_variant_result(), None);
+  }
+}
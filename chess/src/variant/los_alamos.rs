@@ -0,0 +1,53 @@
+use crate::board::Board;
+use crate::board::rank::Rank;
+use crate::piece::{Color, PieceKind};
+use crate::variant::Variant;
+
+const PROMOTION_KINDS: [PieceKind; 3] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Knight];
+
+/// Los Alamos chess, the first chess played by a computer: a 6x6 board without bishops.
+/// Pawns only move one square and can't promote to a bishop, and there is no castling.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LosAlamos;
+
+impl Variant for LosAlamos {
+  fn get_name(&self) -> &'static str {
+    "losalamos"
+  }
+
+  fn get_start_fen(&self) -> &'static str {
+    "rnqknr/pppppp/6/6/PPPPPP/RNQKNR w - - 0 1"
+  }
+
+  fn can_double_push(&self, _board: &Board, _color: Color, _rank: Rank) -> bool {
+    false
+  }
+
+  fn get_promotion_kinds(&self) -> &[PieceKind] {
+    &PROMOTION_KINDS
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+  use crate::board::moves::Move;
+
+  #[test]
+  fn test_los_alamos_perft() {
+    // Neither side can reach the other in one move, so every reply is available after each move
+    let start = Board::new_variant(Arc::new(LosAlamos)).unwrap();
+    assert_eq!(start.perft(1), 10);
+    assert_eq!(start.perft(2), 100);
+  }
+
+  #[test]
+  fn test_no_bishop_promotions() {
+    let mut board = Board::from_fen_string("5k/1P4/6/6/6/K5 w - - 0 1").unwrap();
+    board.set_variant(Arc::new(LosAlamos));
+    let promotions: Vec<Move> = board.get_legal_moves().into_iter().filter(|mv| mv.promotion.is_some()).collect();
+    assert_eq!(promotions.len(), 3);
+    assert!(!promotions.contains(&Move::from_uci("b5b6b").unwrap()));
+  }
+}
@@ -0,0 +1,192 @@
+//! Variants: chess with some of its rules changed.
+//!
+//! A [`Board`](`crate::board::Board`) plays by the [`Variant`](`crate::variant::Variant`) it was
+//! given, so move generation, the search and [`Game`](`crate::game::Game`) all follow the
+//! variant's rules without knowing which one it is. New variants implement the trait, only
+//! overriding the hooks whose rules differ from chess.
+
+pub mod antichess;
+pub mod atomic;
+pub mod bughouse;
+pub mod crazyhouse;
+pub mod gardner;
+pub mod horde;
+pub mod king_of_the_hill;
+pub mod los_alamos;
+pub mod racing_kings;
+pub mod three_check;
+
+use std::sync::Arc;
+
+use crate::board::Board;
+use crate::board::rank::Rank;
+use crate::board::moves::{Move, MoveUndo};
+use crate::game::GameResult;
+use crate::piece::{Color, PieceKind};
+use crate::piece::betza::PieceDefinition;
+
+pub use crate::variant::antichess::Antichess;
+pub use crate::variant::atomic::Atomic;
+pub use crate::variant::bughouse::{Bughouse, BughouseGame};
+pub use crate::variant::crazyhouse::Crazyhouse;
+pub use crate::variant::gardner::Gardner;
+pub use crate::variant::horde::Horde;
+pub use crate::variant::king_of_the_hill::KingOfTheHill;
+pub use crate::variant::los_alamos::LosAlamos;
+pub use crate::variant::racing_kings::RacingKings;
+pub use crate::variant::three_check::ThreeCheck;
+
+pub const STANDARD_START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The pieces a pawn may promote to in chess.
+pub const STANDARD_PROMOTION_KINDS: [PieceKind; 4] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+
+/// State some variants keep besides the pieces, carried by the board and restored when a move
+/// is taken back.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct VariantState {
+  checks: [u32; 2],
+}
+
+impl VariantState {
+  /// How many times the color has given check, for variants that count them.
+  pub fn get_checks(&self, color: Color) -> u32 {
+    self.checks[color.index()]
+  }
+
+  pub fn set_checks(&mut self, color: Color, checks: u32) {
+    self.checks[color.index()] = checks;
+  }
+}
+
+/// The rules a board is played by. Every hook defaults to the rules of chess.
+pub trait Variant: Send + Sync {
+  /// The variant's name, as GUIs know it, e.g. `kingofthehill`.
+  fn get_name(&self) -> &'static str;
+
+  /// The starting position, which also sets the size of the board.
+  fn get_start_fen(&self) -> &'static str {
+    STANDARD_START_FEN
+  }
+
+  /// Fairy pieces the variant adds to chess, which its FEN strings may use.
+  fn get_fairy_pieces(&self) -> Vec<Arc<PieceDefinition>> {
+    vec![]
+  }
+
+  /// Whether captured pieces can be dropped back, so boards need
+  /// [pockets](`crate::board::pocket::Pocket`).
+  fn has_pockets(&self) -> bool {
+    false
+  }
+
+  /// Every move the side to move could make, before checking that it leaves a
+  /// [legal position](`crate::variant::Variant::is_legal_position`).
+  fn get_pseudo_legal_moves(&self, board: &Board) -> Vec<Move> {
+    board.get_standard_pseudo_legal_moves()
+  }
+
+  /// Whether a pawn of the color on the rank may move two squares; in chess, only from its
+  /// second rank.
+  fn can_double_push(&self, board: &Board, color: Color, rank: Rank) -> bool {
+    rank == if color == Color::White { Rank::Two } else { Rank::from(board.get_height() - 1) }
+  }
+
+  /// The pieces a pawn may promote to, in the order moves are generated.
+  fn get_promotion_kinds(&self) -> &[PieceKind] {
+    &STANDARD_PROMOTION_KINDS
+  }
+
+  /// Whether the move is one [`get_pseudo_legal_moves`](`crate::variant::Variant::get_pseudo_legal_moves`)
+  /// would generate, ideally without generating them all.
+  fn is_pseudo_legal_move(&self, board: &Board, mv: &Move) -> bool {
+    board.is_standard_pseudo_legal_move(mv)
+  }
+
+  /// Whether the position reached after `mover` moved is allowed. In chess, the mover's king
+  /// may not be left in check.
+  fn is_legal_position(&self, board: &Board, mover: Color) -> bool {
+    !board.is_in_check(&mover)
+  }
+
+  /// Called once a move has been made, to update the board's
+  /// [`VariantState`](`crate::variant::VariantState`) and pockets, or to take further pieces off
+  /// with [`Board::remove_piece`](`crate::board::Board::remove_piece`).
+  fn after_move(&self, _board: &mut Board, _undo: &mut MoveUndo) {}
+
+  /// The result, if the variant's own rules have ended the game. Checkmate and stalemate are
+  /// found from the legal moves as usual, so variants only report what chess wouldn't.
+  fn get_result(&self, _board: &Board) -> Option<GameResult> {
+    None
+  }
+
+  /// The result when the side to move has no legal moves: in chess, checkmate loses and
+  /// stalemate draws.
+  fn get_result_without_moves(&self, board: &Board) -> GameResult {
+    let color = board.get_active_color();
+    if board.is_in_check(&color) { GameResult::win_for(color.opposite()) } else { GameResult::Draw }
+  }
+}
+
+/// Chess as usual.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Standard;
+
+impl Variant for Standard {
+  fn get_name(&self) -> &'static str {
+    "chess"
+  }
+}
+
+/// Every variant this crate provides.
+pub fn get_variants() -> Vec<Arc<dyn Variant>> {
+  vec![
+    Arc::new(Standard),
+    Arc::new(KingOfTheHill),
+    Arc::new(ThreeCheck),
+    Arc::new(RacingKings),
+    Arc::new(Crazyhouse),
+    Arc::new(Bughouse),
+    Arc::new(Atomic),
+    Arc::new(Antichess),
+    Arc::new(Horde),
+    Arc::new(Gardner),
+    Arc::new(LosAlamos),
+  ]
+}
+
+/// Finds a variant by its [name](`crate::variant::Variant::get_name`).
+pub fn from_name(name: &str) -> Option<Arc<dyn Variant>> {
+  get_variants().into_iter().find(|variant| variant.get_name() == name)
+}
+
+/// Finds a variant from the `Variant` tag of a PGN game, which GUIs write in their own ways,
+/// e.g. `King of the Hill` or `Three-check`. Chess960 is not a variant here, since any board can
+/// follow its castling rules.
+pub fn from_pgn_tag(tag: &str) -> Option<Arc<dyn Variant>> {
+  let name: String = tag.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase();
+  match name.as_str() {
+    "standard" => from_name("chess"),
+    "threecheck" => from_name("3check"),
+    name => from_name(name),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+
+  #[test]
+  fn test_variants_by_name() {
+    assert_eq!(from_name("3check").map(|variant| variant.get_name()), Some("3check"));
+    assert!(from_name("chess").is_some());
+    assert!(from_name("bughouse").is_some());
+    assert!(from_name("shogi").is_none());
+    assert_eq!(from_pgn_tag("King of the Hill").map(|variant| variant.get_name()), Some("kingofthehill"));
+    assert_eq!(from_pgn_tag("Three-check").map(|variant| variant.get_name()), Some("3check"));
+    assert!(from_pgn_tag("Shogi").is_none());
+    let board = Board::new_variant(from_name("racingkings").unwrap()).unwrap();
+    assert_eq!(board.get_variant().get_name(), "racingkings");
+    assert_eq!(board.to_fen_string(), "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1");
+  }
+}
@@ -0,0 +1,87 @@
+use crate::board::Board;
+use crate::board::rank::Rank;
+use crate::game::GameResult;
+use crate::piece::Color;
+use crate::variant::Variant;
+
+/// Both kings race to the eighth rank, and no one may give check. If white gets there first,
+/// black has one move to draw by getting there too.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RacingKings;
+
+fn has_arrived(board: &Board, color: Color) -> bool {
+  board.find_king(&color).is_some_and(|king| king.rank == Rank::Eight)
+}
+
+impl Variant for RacingKings {
+  fn get_name(&self) -> &'static str {
+    "racingkings"
+  }
+
+  fn get_start_fen(&self) -> &'static str {
+    "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"
+  }
+
+  fn is_legal_position(&self, board: &Board, _mover: Color) -> bool {
+    !board.is_in_check(&Color::White) && !board.is_in_check(&Color::Black)
+  }
+
+  fn get_result(&self, board: &Board) -> Option<GameResult> {
+    match (has_arrived(board, Color::White), has_arrived(board, Color::Black)) {
+      (true, true) => Some(GameResult::Draw),
+      (false, true) => Some(GameResult::BlackWins),
+      (true, false) => {
+        // Black may still catch up with its next move
+        let catches_up = board.get_active_color() == Color::Black
+          && board.get_legal_moves_ignoring_result().iter().any(|mv| {
+            let mut after = board.clone();
+            after.make_move(*mv).is_ok() && has_arrived(&after, Color::Black)
+          });
+        if catches_up { None } else { Some(GameResult::WhiteWins) }
+      }
+      (false, false) => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+  use crate::board::moves::Move;
+
+  fn board(fen: &str) -> Board {
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(RacingKings));
+    board
+  }
+
+  #[test]
+  fn test_racing_kings_perft() {
+    let start = Board::new_variant(Arc::new(RacingKings)).unwrap();
+    assert_eq!(start.perft(1), 21);
+    assert_eq!(start.perft(2), 421);
+    assert_eq!(start.perft(3), 11264);
+  }
+
+  #[test]
+  fn test_giving_check_is_illegal() {
+    // Rb1 would check the black king on b7
+    let board = board("8/1k6/8/8/8/8/8/R5K1 w - - 0 1");
+    assert!(!board.get_legal_moves().contains(&Move::from_uci("a1b1").unwrap()));
+    assert!(board.get_legal_moves().contains(&Move::from_uci("a1a2").unwrap()));
+  }
+
+  #[test]
+  fn test_race_results() {
+    // Black reached the last rank first
+    assert_eq!(board("1k6/8/8/8/8/8/8/6K1 w - - 0 1").get_variant_result(), Some(GameResult::BlackWins));
+    // White is there, and black can follow
+    assert_eq!(board("6K1/1k6/8/8/8/8/8/8 b - - 0 1").get_variant_result(), None);
+    assert_eq!(board("6K1/8/1k6/8/8/8/8/8 b - - 0 1").get_variant_result(), Some(GameResult::WhiteWins));
+
+    let mut board = board("6K1/1k6/8/8/8/8/8/8 b - - 0 1");
+    board.make_move(Move::from_uci("b7b8").unwrap()).unwrap();
+    assert_eq!(board.get_variant_result(), Some(GameResult::Draw));
+  }
+}
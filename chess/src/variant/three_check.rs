@@ -0,0 +1,77 @@
+use crate::board::Board;
+use crate::board::moves::MoveUndo;
+use crate::game::GameResult;
+use crate::piece::Color;
+use crate::variant::Variant;
+
+/// Checks needed to win.
+pub const CHECKS_TO_WIN: u32 = 3;
+
+/// Chess where giving check for the third time also wins.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreeCheck;
+
+impl Variant for ThreeCheck {
+  fn get_name(&self) -> &'static str {
+    "3check"
+  }
+
+  fn after_move(&self, board: &mut Board, _undo: &mut MoveUndo) {
+    let checked = board.get_active_color();
+    if board.is_in_check(&checked) {
+      let state = board.get_variant_state_mut();
+      state.set_checks(checked.opposite(), state.get_checks(checked.opposite()) + 1);
+    }
+  }
+
+  fn get_result(&self, board: &Board) -> Option<GameResult> {
+    [Color::White, Color::Black].iter()
+      .find(|color| board.get_variant_state().get_checks(**color) >= CHECKS_TO_WIN)
+      .map(|color| GameResult::win_for(*color))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{*};
+  use std::sync::Arc;
+  use crate::board::moves::Move;
+
+  #[test]
+  fn test_three_check_perft() {
+    // Kiwipete with a check to go on each side: the three checks black can give at ply two end the game
+    let mut kiwipete = Board::from_fen_string("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 +2+2").unwrap();
+    kiwipete.set_variant(Arc::new(ThreeCheck));
+    assert_eq!(kiwipete.perft(2), 2039);
+    assert_eq!(kiwipete.perft(3), 97848);
+
+    // With two checks given, Ra8+ wins at once and takes away black's three replies to it
+    let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0";
+    let mut board = Board::from_fen_string(fen).unwrap();
+    board.set_variant(Arc::new(ThreeCheck));
+    let standard = Board::from_fen_string(fen).unwrap();
+    assert_eq!(board.perft(1), standard.perft(1));
+    assert_eq!(board.perft(2), standard.perft(2) - 3);
+  }
+
+  #[test]
+  fn test_checks_are_counted_and_taken_back() {
+    let mut board = Board::new_variant(Arc::new(ThreeCheck)).unwrap();
+    for mv in ["e2e4", "e7e5", "f1b5", "h7h6"].iter() {
+      board.make_move(Move::from_uci(mv).unwrap()).unwrap();
+    }
+    assert_eq!(board.get_variant_state().get_checks(Color::White), 0);
+
+    let check = Move::from_uci("b5d7").unwrap();
+    let hash = board.get_hash();
+    let undo = board.make_move(check).unwrap();
+    assert_eq!(board.get_variant_state().get_checks(Color::White), 1);
+    assert!(board.to_fen_string().ends_with(" +1+0"));
+    board.unmake_move(check, undo);
+    assert_eq!(board.get_variant_state().get_checks(Color::White), 0);
+    assert_eq!(board.get_hash(), hash);
+
+    board.get_variant_state_mut().set_checks(Color::Black, 3);
+    assert_eq!(board.get_variant_result(), Some(GameResult::BlackWins));
+  }
+}